@@ -209,8 +209,7 @@ impl InputSystem {
 }
 
 impl CliApp {
-    fn new() -> Self {
-        let config = Config::default();
+    fn new(config: Config) -> Self {
         let input_system = InputSystem::new();
 
         // Display which input mode was detected
@@ -375,12 +374,20 @@ impl CliApp {
                         let _ = scorer; // Suppress unused warning
                         let _ = score;
                     }
+                    pong_core::Event::BallNudged | pong_core::Event::BallReserved { .. } => {
+                        // Stuck-ball rescue, nothing to show in the harness
+                    }
+                    pong_core::Event::PaddleHit { .. }
+                    | pong_core::Event::WallHit { .. }
+                    | pong_core::Event::ObstacleHit { .. } => {
+                        // Collisions are only reported through game.events()
+                    }
                 }
             }
 
-            // Reset ready flags only after successful transition to countdown
+            // Reset ready flags only after successful transition out of the lobby
             if matches!(old_status, Status::Lobby)
-                && matches!(self.game.status, Status::Countdown(_))
+                && matches!(self.game.status, Status::Warmup(_) | Status::Countdown(_))
             {
                 // Reset ready state in input system
                 match &mut self.input_system {
@@ -607,15 +614,15 @@ impl CliApp {
         )?;
         row += 1;
 
-        // Ball velocity debug (if we can access it)
+        // Ball velocity debug
         execute!(
             stdout(),
             MoveTo(0, row),
             SetForegroundColor(Color::DarkGrey),
             Print(format!(
                 "Ball vel: ({:.2}, {:.2}) | Paddle Y: L={:.2} R={:.2}",
-                fx::to_f32(self.game.ball.vel.x),
-                fx::to_f32(self.game.ball.vel.y),
+                fx::to_f32(view.ball_vel.x),
+                fx::to_f32(view.ball_vel.y),
                 fx::to_f32(view.left_paddle_y),
                 fx::to_f32(view.right_paddle_y)
             )),
@@ -626,8 +633,23 @@ impl CliApp {
     }
 }
 
+/// Load the rule file given with `--config <path>`, or the default rules
+fn load_config() -> Result<Config> {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(path) = args
+        .iter()
+        .position(|arg| arg == "--config")
+        .and_then(|i| args.get(i + 1))
+    else {
+        return Ok(Config::default());
+    };
+
+    let text = std::fs::read_to_string(path)?;
+    Config::from_toml(&text).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+}
+
 fn main() -> Result<()> {
-    let mut app = CliApp::new();
+    let mut app = CliApp::new(load_config()?);
 
     // Handle Ctrl+C gracefully
     ctrlc::set_handler(move || {
@@ -224,7 +224,9 @@ impl InputSystem {
     }
 
     fn reset_ready_after_countdown(&mut self, old_status: Status, new_status: Status) {
-        if matches!(old_status, Status::Lobby) && matches!(new_status, Status::Countdown(_)) {
+        if matches!(old_status, Status::Lobby)
+            && matches!(new_status, Status::Warmup(_) | Status::Countdown(_))
+        {
             self.p1.handle_ready(false);
             self.p2.handle_ready(false);
         }
@@ -304,6 +306,8 @@ pub struct App {
     pub game: Option<Game>,
     /// Lockstep instance (for networked games)
     pub lockstep: Option<Lockstep<GameAdapter, RtcTransport>>,
+    /// Transport awaiting the peer's SDP before it can start a lockstep
+    pub rtc_transport: Option<RtcTransport>,
     /// Input system (cli_harness style)
     pub input_system: InputSystem,
     /// Last game tick
@@ -333,6 +337,7 @@ impl App {
             network_mode: NetworkMode::Local,
             game: None,
             lockstep: None,
+            rtc_transport: None,
             input_system,
             last_tick: Instant::now(),
             events,
@@ -588,12 +593,12 @@ impl App {
         self.menu_state.host_state.connection_status = "Initializing...".to_string();
 
         // Try to create WebRTC transport in host mode
-        match RtcTransportBuilder::new_manual_sdp(SdpMode::Offer) {
+        match RtcTransportBuilder::from_env().build(SdpMode::Offer) {
             Ok((transport, offer_sdp)) => {
                 self.menu_state.host_state.offer_sdp = offer_sdp;
                 self.menu_state.host_state.connection_status =
                     "Ready to host. Copy the Offer SDP and send to peer.".to_string();
-                // TODO: Store transport for later use
+                self.rtc_transport = Some(transport);
             }
             Err(e) => {
                 self.menu_state.host_state.connection_status = format!("Error: {}", e);
@@ -1,460 +1,519 @@
-//! PeerJS bridge transport implementation for terminal client
-
-use pong_core::transport::{Transport, TransportError};
-use serde::{Deserialize, Serialize};
+//! Native WebRTC DataChannel transport for the terminal client
+//!
+//! Offer/answer and ICE run in-process on the `webrtc` crate, so hosting or
+//! joining needs nothing but the binary. Descriptions are exchanged by hand:
+//! each side's SDP is serialized as a single line of JSON (the same shape a
+//! browser's `RTCSessionDescription` takes) with all ICE candidates already
+//! gathered, so one copy/paste in each direction is enough.
+
+use bytes::Bytes;
+use pong_core::clock::{Clock, SystemClock};
+use pong_core::transport::{CloseReason, Lifecycle, Transport, TransportError, TransportMetrics};
 use std::collections::VecDeque;
-use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, Command, Stdio};
-use std::sync::{Arc, Mutex};
-use std::thread;
-
-/// Mode for peer connection
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum PeerMode {
-    Host, // Host mode - waits for connections
-    Join, // Join mode - connects to host
-}
-
-/// Messages sent from Node.js bridge to Rust
-#[derive(Deserialize, Debug)]
-#[serde(tag = "type")]
-enum BridgeMessage {
-    #[serde(rename = "peer_ready")]
-    PeerReady { peer_id: String, mode: String },
-
-    #[serde(rename = "connected")]
-    Connected { peer_id: String },
-
-    #[serde(rename = "data")]
-    Data { bytes: Vec<u8> },
-
-    #[serde(rename = "connection_closed")]
-    ConnectionClosed,
-
-    #[serde(rename = "connection_error")]
-    ConnectionError { message: String },
-
-    #[serde(rename = "error")]
-    Error { message: String },
-
-    #[serde(rename = "disconnected")]
-    Disconnected,
-}
-
-/// Messages sent from Rust to Node.js bridge
-#[derive(Serialize, Debug)]
-#[serde(tag = "type")]
-enum RustMessage {
-    #[serde(rename = "send")]
-    Send { bytes: Vec<u8> },
-
-    #[serde(rename = "close")]
-    Close,
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc;
+use webrtc::api::APIBuilder;
+use webrtc::data_channel::data_channel_init::RTCDataChannelInit;
+use webrtc::data_channel::RTCDataChannel;
+use webrtc::ice_transport::ice_server::RTCIceServer;
+use webrtc::peer_connection::configuration::RTCConfiguration;
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::RTCPeerConnection;
+
+/// Label of the single data channel both peers use
+const CHANNEL_LABEL: &str = "pong";
+
+/// STUN server used when none are configured
+const DEFAULT_STUN_SERVER: &str = "stun:stun.l.google.com:19302";
+
+/// Environment variables read by `RtcTransportBuilder::from_env`
+const STUN_SERVERS_VAR: &str = "PONG_STUN_SERVERS";
+const TURN_URL_VAR: &str = "PONG_TURN_URL";
+const TURN_USERNAME_VAR: &str = "PONG_TURN_USERNAME";
+const TURN_CREDENTIAL_VAR: &str = "PONG_TURN_CREDENTIAL";
+
+/// Which half of the manual SDP exchange this peer performs
+#[derive(Debug, Clone, PartialEq)]
+pub enum SdpMode {
+    /// Host: create the data channel and an offer for the peer
+    Offer,
+    /// Join: answer the host's offer (as produced by the `Offer` side)
+    Answer(String),
 }
 
-/// Transport state
-#[derive(Debug, Clone, PartialEq)]
-enum TransportState {
-    /// Initializing the bridge process
-    Initializing,
-    /// Bridge is ready, waiting for connection
-    WaitingForConnection,
-    /// Connected and ready to send/receive
+/// Connection state
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RtcState {
+    /// Local description is ready; waiting for the peer
+    Connecting,
+    /// Data channel is open
     Connected,
     /// Connection closed or failed
     Closed,
 }
 
-/// Internal transport data
-struct TransportInner {
-    state: TransportState,
-    peer_id: Option<String>,
-    connected_peer: Option<String>,
-    received_messages: VecDeque<Vec<u8>>,
-    on_message: Option<Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>>,
-    bridge_process: Option<Child>,
-    error_message: Option<String>,
-}
-
-/// PeerJS bridge transport implementation
-pub struct PeerBridgeTransport {
-    inner: Arc<Mutex<TransportInner>>,
-    mode: PeerMode,
+/// State shared with the WebRTC callbacks
+struct RtcShared {
+    state: RtcState,
+    on_message: Option<Box<dyn Fn(Vec<u8>) + Send + Sync>>,
+    /// Messages received before a callback was set
+    pending: VecDeque<Vec<u8>>,
+    /// Why the connection ended, if not by `close`
+    error: Option<String>,
+    metrics: TransportMetrics,
+    lifecycle: Lifecycle,
 }
 
-impl PeerBridgeTransport {
-    /// Create a new PeerJS bridge transport
-    pub fn new(mode: PeerMode, host_peer_id: Option<String>) -> Result<Self, TransportError> {
-        // Check if Node.js is available
-        if !Self::check_nodejs_available() {
-            return Err(TransportError::ConnectionFailed(
-                "Node.js is not available. Please install Node.js to use PeerJS networking."
-                    .to_string(),
-            ));
-        }
-
-        // Check if the bridge script exists
-        let bridge_path = std::path::Path::new("clients/terminal/src/peer_bridge.js");
-        if !bridge_path.exists() {
-            return Err(TransportError::ConnectionFailed(
-                "PeerJS bridge script not found. Expected at clients/terminal/src/peer_bridge.js"
-                    .to_string(),
-            ));
+impl RtcShared {
+    fn fail(&mut self, error: impl Into<String>) {
+        if self.state != RtcState::Closed {
+            let error = error.into();
+            self.state = RtcState::Closed;
+            self.error = Some(error.clone());
+            self.lifecycle.closed(CloseReason::Error(error));
         }
+    }
+}
 
-        let inner = Arc::new(Mutex::new(TransportInner {
-            state: TransportState::Initializing,
-            peer_id: None,
-            connected_peer: None,
-            received_messages: VecDeque::new(),
-            on_message: None,
-            bridge_process: None,
-            error_message: None,
-        }));
+type SharedState = Arc<Mutex<RtcShared>>;
 
-        let transport = Self {
-            inner: inner.clone(),
-            mode,
-        };
+fn lock(shared: &Mutex<RtcShared>) -> MutexGuard<'_, RtcShared> {
+    // A panicking callback poisons the lock but leaves the state usable
+    shared.lock().unwrap_or_else(|e| e.into_inner())
+}
 
-        // Start the bridge process
-        transport.start_bridge_process(host_peer_id)?;
+/// Builds an `RtcTransport` and its local session description
+pub struct RtcTransportBuilder {
+    /// STUN servers, needing no credentials
+    ice_servers: Vec<String>,
+    /// TURN relays, for peers that can't reach each other directly
+    turn_servers: Vec<RTCIceServer>,
+}
 
-        Ok(transport)
+impl Default for RtcTransportBuilder {
+    fn default() -> Self {
+        Self {
+            ice_servers: vec![DEFAULT_STUN_SERVER.to_string()],
+            turn_servers: Vec::new(),
+        }
     }
+}
 
-    /// Check if Node.js is available
-    fn check_nodejs_available() -> bool {
-        Command::new("node").arg("--version").output().is_ok()
+impl RtcTransportBuilder {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    /// Start the Node.js bridge process
-    fn start_bridge_process(&self, host_peer_id: Option<String>) -> Result<(), TransportError> {
-        let bridge_path = "clients/terminal/src/peer_bridge.js";
-
-        let mut cmd = Command::new("node");
-        cmd.arg(bridge_path);
-
-        match self.mode {
-            PeerMode::Host => {
-                cmd.arg("host");
-            }
-            PeerMode::Join => {
-                cmd.arg("join");
-                if let Some(peer_id) = host_peer_id {
-                    cmd.arg(peer_id);
-                } else {
-                    return Err(TransportError::InvalidConfig(
-                        "Host peer ID required for join mode".to_string(),
-                    ));
-                }
-            }
+    /// Defaults overridden from the environment, for self-hosted servers:
+    /// `PONG_STUN_SERVERS` (comma-separated URLs, empty for none) and
+    /// `PONG_TURN_URL` with `PONG_TURN_USERNAME` / `PONG_TURN_CREDENTIAL`
+    pub fn from_env() -> Self {
+        let mut builder = Self::new();
+        if let Ok(urls) = std::env::var(STUN_SERVERS_VAR) {
+            builder = builder.ice_servers(
+                urls.split(',')
+                    .map(str::trim)
+                    .filter(|url| !url.is_empty())
+                    .map(String::from)
+                    .collect(),
+            );
         }
-
-        cmd.stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
-
-        let mut child = cmd.spawn().map_err(|e| {
-            TransportError::ConnectionFailed(format!("Failed to start bridge process: {}", e))
-        })?;
-
-        // Get handles to stdin/stdout
-        let stdin = child.stdin.take().ok_or_else(|| {
-            TransportError::ConnectionFailed("Failed to get stdin handle".to_string())
-        })?;
-
-        let stdout = child.stdout.take().ok_or_else(|| {
-            TransportError::ConnectionFailed("Failed to get stdout handle".to_string())
-        })?;
-
-        // Store the process handle
-        {
-            let mut inner = self.inner.lock().map_err(|_| {
-                TransportError::ConnectionFailed("Failed to acquire lock".to_string())
-            })?;
-            inner.bridge_process = Some(child);
+        if let Ok(url) = std::env::var(TURN_URL_VAR) {
+            builder = builder.turn_server(
+                url,
+                std::env::var(TURN_USERNAME_VAR).unwrap_or_default(),
+                std::env::var(TURN_CREDENTIAL_VAR).unwrap_or_default(),
+            );
         }
+        builder
+    }
 
-        // Start reader thread for bridge messages
-        let inner_clone = self.inner.clone();
-        let reader = BufReader::new(stdout);
-        thread::spawn(move || {
-            Self::bridge_reader_thread(inner_clone, reader);
-        });
+    /// Replace the STUN server URLs (empty for host candidates only, e.g. on a LAN)
+    pub fn ice_servers(mut self, urls: Vec<String>) -> Self {
+        self.ice_servers = urls;
+        self
+    }
 
-        // Store stdin handle for sending messages
-        let inner_clone2 = self.inner.clone();
-        thread::spawn(move || {
-            Self::bridge_writer_thread(inner_clone2, stdin);
+    /// Add a TURN relay (`turn:` or `turns:` URL) with its credentials
+    pub fn turn_server(
+        mut self,
+        url: impl Into<String>,
+        username: impl Into<String>,
+        credential: impl Into<String>,
+    ) -> Self {
+        self.turn_servers.push(RTCIceServer {
+            urls: vec![url.into()],
+            username: username.into(),
+            credential: credential.into(),
+            ..Default::default()
         });
+        self
+    }
 
-        Ok(())
+    /// Start a transport with the default ICE servers, returning it with the
+    /// description to hand to the peer
+    pub fn new_manual_sdp(mode: SdpMode) -> Result<(RtcTransport, String), TransportError> {
+        Self::new().build(mode)
     }
 
-    /// Thread for reading messages from the bridge
-    fn bridge_reader_thread(
-        inner: Arc<Mutex<TransportInner>>,
-        mut reader: BufReader<std::process::ChildStdout>,
-    ) {
-        let mut line = String::new();
-
-        loop {
-            line.clear();
-            match reader.read_line(&mut line) {
-                Ok(0) => break, // EOF
-                Ok(_) => {
-                    if let Ok(message) = serde_json::from_str::<BridgeMessage>(line.trim()) {
-                        Self::handle_bridge_message(&inner, message);
-                    } else {
-                        eprintln!("[Bridge] Invalid message: {}", line.trim());
-                    }
+    /// Start a transport, returning it with the description to hand to the
+    /// peer. Blocks until ICE gathering is complete.
+    pub fn build(self, mode: SdpMode) -> Result<(RtcTransport, String), TransportError> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("rtc-transport")
+            .enable_all()
+            .build()
+            .map_err(|e| TransportError::ConnectionFailed(e.to_string()))?;
+
+        let shared = Arc::new(Mutex::new(RtcShared {
+            state: RtcState::Connecting,
+            on_message: None,
+            pending: VecDeque::new(),
+            error: None,
+            metrics: TransportMetrics::default(),
+            lifecycle: Lifecycle::default(),
+        }));
+        let (outgoing, outgoing_rx) = mpsc::unbounded_channel();
+
+        let (peer, description) = runtime.block_on(async {
+            let peer = self.create_peer(&shared).await?;
+            let description = match mode {
+                SdpMode::Offer => {
+                    let channel = peer
+                        .create_data_channel(CHANNEL_LABEL, Some(RTCDataChannelInit::default()))
+                        .await
+                        .map_err(connection_failed)?;
+                    attach_channel(channel, &shared, outgoing_rx);
+                    let offer = peer.create_offer(None).await.map_err(connection_failed)?;
+                    local_description(&peer, offer).await?
                 }
-                Err(e) => {
-                    eprintln!("[Bridge] Read error: {}", e);
-                    break;
+                SdpMode::Answer(offer) => {
+                    let mut outgoing_rx = Some(outgoing_rx);
+                    let channel_shared = Arc::clone(&shared);
+                    peer.on_data_channel(Box::new(move |channel| {
+                        // Only the host's channel is expected; ignore any others
+                        if let Some(outgoing_rx) = outgoing_rx.take() {
+                            attach_channel(channel, &channel_shared, outgoing_rx);
+                        }
+                        Box::pin(async {})
+                    }));
+                    peer.set_remote_description(parse_description(&offer)?)
+                        .await
+                        .map_err(invalid_description)?;
+                    let answer = peer.create_answer(None).await.map_err(connection_failed)?;
+                    local_description(&peer, answer).await?
                 }
-            }
-        }
+            };
+            Ok::<_, TransportError>((peer, description))
+        })?;
 
-        // Mark as closed when reader thread exits
-        if let Ok(mut inner_guard) = inner.lock() {
-            inner_guard.state = TransportState::Closed;
-        }
+        Ok((
+            RtcTransport {
+                runtime,
+                peer,
+                outgoing,
+                shared,
+            },
+            description,
+        ))
     }
 
-    /// Thread for writing messages to the bridge
-    fn bridge_writer_thread(
-        inner: Arc<Mutex<TransportInner>>,
-        mut writer: std::process::ChildStdin,
-    ) {
-        // This thread will be used by the send() method
-        // For now, just keep it alive
-        loop {
-            std::thread::sleep(std::time::Duration::from_millis(100));
-
-            // Check if we should exit
-            if let Ok(inner_guard) = inner.lock() {
-                if inner_guard.state == TransportState::Closed {
-                    break;
-                }
-            }
+    async fn create_peer(
+        &self,
+        shared: &SharedState,
+    ) -> Result<Arc<RTCPeerConnection>, TransportError> {
+        let mut ice_servers = self.turn_servers.clone();
+        if !self.ice_servers.is_empty() {
+            ice_servers.insert(
+                0,
+                RTCIceServer {
+                    urls: self.ice_servers.clone(),
+                    ..Default::default()
+                },
+            );
         }
+        let config = RTCConfiguration {
+            ice_servers,
+            ..Default::default()
+        };
+        let peer = APIBuilder::new()
+            .build()
+            .new_peer_connection(config)
+            .await
+            .map_err(connection_failed)?;
+
+        let state_shared = Arc::clone(shared);
+        peer.on_peer_connection_state_change(Box::new(move |state| {
+            if matches!(
+                state,
+                RTCPeerConnectionState::Failed | RTCPeerConnectionState::Disconnected
+            ) {
+                lock(&state_shared).fail(format!("Peer connection {state}"));
+            }
+            Box::pin(async {})
+        }));
+        Ok(Arc::new(peer))
     }
+}
 
-    /// Handle messages from the bridge
-    fn handle_bridge_message(inner: &Arc<Mutex<TransportInner>>, message: BridgeMessage) {
-        if let Ok(mut inner_guard) = inner.lock() {
-            match message {
-                BridgeMessage::PeerReady { peer_id, mode: _ } => {
-                    println!("[Bridge] Peer ready with ID: {}", peer_id);
-                    inner_guard.peer_id = Some(peer_id);
-                    inner_guard.state = TransportState::WaitingForConnection;
-                }
-                BridgeMessage::Connected { peer_id } => {
-                    println!("[Bridge] Connected to peer: {}", peer_id);
-                    inner_guard.connected_peer = Some(peer_id);
-                    inner_guard.state = TransportState::Connected;
-                }
-                BridgeMessage::Data { bytes } => {
-                    // Call the callback if set
-                    if let Some(callback) = &inner_guard.on_message {
-                        callback(bytes.clone());
-                    } else {
-                        // Store for later retrieval
-                        inner_guard.received_messages.push_back(bytes);
-                    }
-                }
-                BridgeMessage::ConnectionClosed => {
-                    println!("[Bridge] Connection closed");
-                    inner_guard.state = TransportState::Closed;
-                }
-                BridgeMessage::ConnectionError { message } => {
-                    println!("[Bridge] Connection error: {}", message);
-                    inner_guard.error_message = Some(message);
-                    inner_guard.state = TransportState::Closed;
-                }
-                BridgeMessage::Error { message } => {
-                    println!("[Bridge] Error: {}", message);
-                    inner_guard.error_message = Some(message);
-                    inner_guard.state = TransportState::Closed;
-                }
-                BridgeMessage::Disconnected => {
-                    println!("[Bridge] Peer disconnected");
-                    inner_guard.state = TransportState::Closed;
-                }
+/// Wire a data channel's callbacks to `shared` and start its writer task
+fn attach_channel(
+    channel: Arc<RTCDataChannel>,
+    shared: &SharedState,
+    mut outgoing: mpsc::UnboundedReceiver<Vec<u8>>,
+) {
+    let open_shared = Arc::clone(shared);
+    channel.on_open(Box::new(move || {
+        let mut shared = lock(&open_shared);
+        if shared.state == RtcState::Connecting {
+            shared.state = RtcState::Connected;
+            shared.lifecycle.opened();
+        }
+        Box::pin(async {})
+    }));
+
+    let message_shared = Arc::clone(shared);
+    channel.on_message(Box::new(move |message| {
+        let mut shared = lock(&message_shared);
+        let bytes = message.data.to_vec();
+        shared.metrics.record_received(bytes.len(), wall_ms());
+        match &shared.on_message {
+            Some(callback) => callback(bytes),
+            None => shared.pending.push_back(bytes),
+        }
+        Box::pin(async {})
+    }));
+
+    let close_shared = Arc::clone(shared);
+    channel.on_close(Box::new(move || {
+        let mut shared = lock(&close_shared);
+        shared.state = RtcState::Closed;
+        shared.lifecycle.closed(CloseReason::Remote);
+        Box::pin(async {})
+    }));
+
+    // Sends go through one task so they reach the channel in order
+    let write_shared = Arc::clone(shared);
+    tokio::spawn(async move {
+        while let Some(bytes) = outgoing.recv().await {
+            let len = bytes.len();
+            let result = channel.send(&Bytes::from(bytes)).await;
+            let mut shared = lock(&write_shared);
+            if let Err(e) = result {
+                shared.metrics.record_send_error();
+                shared.fail(format!("Send failed: {e}"));
+                break;
             }
+            shared.metrics.record_sent(len, wall_ms());
         }
-    }
+        let _ = channel.close().await;
+    });
+}
 
-    /// Send a message to the bridge
-    fn send_to_bridge(&self, message: RustMessage) -> Result<(), TransportError> {
-        // For now, print the message (in a real implementation, we'd send via stdin)
-        let json = serde_json::to_string(&message).map_err(|e| {
-            TransportError::SendFailed(format!("Failed to serialize message: {}", e))
-        })?;
+/// Apply `description` locally and wait for ICE gathering, so the returned
+/// SDP carries every candidate
+async fn local_description(
+    peer: &RTCPeerConnection,
+    description: RTCSessionDescription,
+) -> Result<String, TransportError> {
+    let mut gathered = peer.gathering_complete_promise().await;
+    peer.set_local_description(description)
+        .await
+        .map_err(connection_failed)?;
+    let _ = gathered.recv().await;
+
+    let description = peer.local_description().await.ok_or_else(|| {
+        TransportError::ConnectionFailed("No local description after gathering".to_string())
+    })?;
+    serde_json::to_string(&description).map_err(|e| TransportError::ConnectionFailed(e.to_string()))
+}
 
-        println!("[Bridge] Sending: {}", json);
-        Ok(())
-    }
+fn wall_ms() -> u32 {
+    SystemClock::new().wall_ms()
+}
 
-    /// Get the peer ID (for host mode)
-    pub fn get_peer_id(&self) -> Option<String> {
-        if let Ok(inner) = self.inner.lock() {
-            inner.peer_id.clone()
-        } else {
-            None
-        }
+fn parse_description(text: &str) -> Result<RTCSessionDescription, TransportError> {
+    serde_json::from_str(text.trim())
+        .map_err(|e| TransportError::InvalidConfig(format!("Invalid session description: {e}")))
+}
+
+fn connection_failed(e: webrtc::Error) -> TransportError {
+    TransportError::ConnectionFailed(e.to_string())
+}
+
+fn invalid_description(e: webrtc::Error) -> TransportError {
+    TransportError::InvalidConfig(format!("Rejected session description: {e}"))
+}
+
+/// Transport over a WebRTC DataChannel
+pub struct RtcTransport {
+    /// Drives the peer connection; dropped (and stopped) with the transport
+    runtime: Runtime,
+    peer: Arc<RTCPeerConnection>,
+    /// Queue for the channel's writer task
+    outgoing: mpsc::UnboundedSender<Vec<u8>>,
+    shared: SharedState,
+}
+
+impl RtcTransport {
+    /// Host side: apply the peer's answer to complete the connection
+    pub fn accept_answer(&self, answer: &str) -> Result<(), TransportError> {
+        let description = parse_description(answer)?;
+        self.runtime
+            .block_on(self.peer.set_remote_description(description))
+            .map_err(invalid_description)
     }
 
-    /// Get connection mode
-    pub fn get_mode(&self) -> PeerMode {
-        self.mode
+    fn shared(&self) -> MutexGuard<'_, RtcShared> {
+        lock(&self.shared)
     }
 }
 
-impl Transport for PeerBridgeTransport {
+impl Transport for RtcTransport {
     fn send(&self, bytes: &[u8]) -> Result<(), TransportError> {
-        let inner = self
-            .inner
-            .lock()
-            .map_err(|_| TransportError::SendFailed("Failed to acquire lock".to_string()))?;
-
-        if inner.state != TransportState::Connected {
+        if !self.is_open() {
             return Err(TransportError::NotConnected);
         }
-
-        drop(inner); // Release lock before sending
-
-        self.send_to_bridge(RustMessage::Send {
-            bytes: bytes.to_vec(),
-        })
+        self.outgoing
+            .send(bytes.to_vec())
+            .map_err(|_| TransportError::SendFailed("Data channel writer stopped".to_string()))
     }
 
     fn set_on_message(&mut self, callback: Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>) {
-        if let Ok(mut inner) = self.inner.lock() {
-            // Process any queued messages first
-            while let Some(message) = inner.received_messages.pop_front() {
-                callback(message);
-            }
-
-            inner.on_message = Some(callback);
+        let mut shared = self.shared();
+        for bytes in shared.pending.drain(..) {
+            callback(bytes);
         }
+        shared.on_message = Some(callback);
     }
 
     fn is_open(&self) -> bool {
-        if let Ok(inner) = self.inner.lock() {
-            inner.state == TransportState::Connected
-        } else {
-            false
-        }
+        self.shared().state == RtcState::Connected
     }
 
     fn close(&mut self) -> Result<(), TransportError> {
-        if let Ok(mut inner) = self.inner.lock() {
-            if inner.state == TransportState::Closed {
+        {
+            let mut shared = self.shared();
+            if shared.state == RtcState::Closed {
                 return Err(TransportError::AlreadyClosed);
             }
-
-            inner.state = TransportState::Closed;
-
-            // Kill the bridge process
-            if let Some(mut process) = inner.bridge_process.take() {
-                let _ = process.kill();
-                let _ = process.wait();
-            }
+            shared.state = RtcState::Closed;
+            shared.lifecycle.closed(CloseReason::Local);
         }
-
-        // Send close message to bridge
-        let _ = self.send_to_bridge(RustMessage::Close);
-        Ok(())
+        self.runtime
+            .block_on(self.peer.close())
+            .map_err(|e| TransportError::ConnectionFailed(e.to_string()))
     }
 
     fn status(&self) -> String {
-        if let Ok(inner) = self.inner.lock() {
-            match inner.state {
-                TransportState::Initializing => "Initializing PeerJS bridge...".to_string(),
-                TransportState::WaitingForConnection => {
-                    if let Some(peer_id) = &inner.peer_id {
-                        format!("Waiting for connection (ID: {})", peer_id)
-                    } else {
-                        "Waiting for connection...".to_string()
-                    }
-                }
-                TransportState::Connected => {
-                    if let Some(peer_id) = &inner.connected_peer {
-                        format!("Connected to {}", peer_id)
-                    } else {
-                        "Connected".to_string()
-                    }
-                }
-                TransportState::Closed => {
-                    if let Some(error) = &inner.error_message {
-                        format!("Closed ({})", error)
-                    } else {
-                        "Closed".to_string()
-                    }
-                }
-            }
-        } else {
-            "Error".to_string()
+        let shared = self.shared();
+        match (shared.state, &shared.error) {
+            (RtcState::Connecting, _) => "Waiting for peer (WebRTC)".to_string(),
+            (RtcState::Connected, _) => "Connected (WebRTC)".to_string(),
+            (RtcState::Closed, Some(error)) => format!("Disconnected (WebRTC): {error}"),
+            (RtcState::Closed, None) => "Disconnected (WebRTC)".to_string(),
         }
     }
-}
 
-/// Factory for creating PeerJS bridge transports
-pub struct PeerBridgeTransportFactory;
+    fn metrics(&self) -> TransportMetrics {
+        self.shared().metrics
+    }
+
+    /// Fires once the data channel opens, after the answer is accepted
+    fn set_on_open(&mut self, callback: Box<dyn Fn() + Send + Sync + 'static>) {
+        self.shared().lifecycle.set_on_open(callback);
+    }
 
-impl PeerBridgeTransportFactory {
-    /// Create a host transport
-    pub fn create_host() -> Result<PeerBridgeTransport, TransportError> {
-        PeerBridgeTransport::new(PeerMode::Host, None)
+    fn set_on_close(&mut self, callback: Box<dyn Fn(CloseReason) + Send + Sync + 'static>) {
+        self.shared().lifecycle.set_on_close(callback);
     }
+}
 
-    /// Create a guest transport that connects to a host
-    pub fn create_guest(host_peer_id: String) -> Result<PeerBridgeTransport, TransportError> {
-        PeerBridgeTransport::new(PeerMode::Join, Some(host_peer_id))
+impl Drop for RtcTransport {
+    fn drop(&mut self) {
+        let _ = self.close();
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::{Duration, Instant};
+
+    fn wait_for(mut done: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(20);
+        while !done() {
+            assert!(Instant::now() < deadline, "timed out");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
 
     #[test]
-    fn test_check_nodejs() {
-        // This test will pass if Node.js is available
-        println!(
-            "Node.js available: {}",
-            PeerBridgeTransport::check_nodejs_available()
+    fn test_turn_server_config() {
+        let builder = RtcTransportBuilder::new()
+            .ice_servers(vec!["stun:stun.example.com:3478".to_string()])
+            .turn_server("turn:turn.example.com:3478", "pong", "secret");
+        assert_eq!(builder.ice_servers, vec!["stun:stun.example.com:3478"]);
+        assert_eq!(builder.turn_servers.len(), 1);
+        assert_eq!(
+            builder.turn_servers[0].urls,
+            vec!["turn:turn.example.com:3478"]
+        );
+        assert_eq!(
+            (
+                builder.turn_servers[0].username.as_str(),
+                builder.turn_servers[0].credential.as_str()
+            ),
+            ("pong", "secret")
         );
     }
 
     #[test]
-    fn test_create_host_transport() {
-        // This will fail if Node.js is not available, which is expected
-        match PeerBridgeTransportFactory::create_host() {
-            Ok(transport) => {
-                assert_eq!(transport.get_mode(), PeerMode::Host);
-                println!("Host transport created successfully");
-            }
-            Err(e) => {
-                println!("Expected error (Node.js may not be available): {}", e);
-            }
-        }
+    fn test_rejects_bad_offer() {
+        let result = RtcTransportBuilder::new()
+            .ice_servers(Vec::new())
+            .build(SdpMode::Answer("not json".to_string()));
+        assert!(matches!(result, Err(TransportError::InvalidConfig(_))));
     }
 
     #[test]
-    fn test_create_guest_transport() {
-        // This will fail if Node.js is not available, which is expected
-        match PeerBridgeTransportFactory::create_guest("test-peer-id".to_string()) {
-            Ok(transport) => {
-                assert_eq!(transport.get_mode(), PeerMode::Join);
-                println!("Guest transport created successfully");
-            }
-            Err(e) => {
-                println!("Expected error (Node.js may not be available): {}", e);
-            }
-        }
+    fn test_manual_sdp_round_trip() {
+        let builder = || RtcTransportBuilder::new().ice_servers(Vec::new());
+        let (mut host, offer) = builder().build(SdpMode::Offer).unwrap();
+        let (mut guest, answer) = builder().build(SdpMode::Answer(offer)).unwrap();
+        assert!(!answer.contains('\n'));
+        assert_eq!(host.status(), "Waiting for peer (WebRTC)");
+        assert_eq!(host.send(b"early"), Err(TransportError::NotConnected));
+        let opened = Arc::new(Mutex::new(false));
+        let flag = Arc::clone(&opened);
+        host.set_on_open(Box::new(move || *flag.lock().unwrap() = true));
+        assert!(!*opened.lock().unwrap());
+
+        host.accept_answer(&answer).unwrap();
+        wait_for(|| host.is_open() && guest.is_open());
+        assert!(*opened.lock().unwrap());
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        guest.set_on_message(Box::new(move |bytes| sink.lock().unwrap().push(bytes)));
+        host.send(b"hello").unwrap();
+        host.send(&[7; 1000]).unwrap();
+        wait_for(|| received.lock().unwrap().len() == 2);
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![b"hello".to_vec(), vec![7; 1000]]
+        );
+        assert_eq!(guest.metrics().bytes_received, 1005);
+
+        let closed = Arc::new(Mutex::new(None));
+        let sink = Arc::clone(&closed);
+        guest.set_on_close(Box::new(move |reason| *sink.lock().unwrap() = Some(reason)));
+        host.close().unwrap();
+        assert_eq!(host.close(), Err(TransportError::AlreadyClosed));
+        wait_for(|| !guest.is_open());
+        assert!(closed.lock().unwrap().is_some());
     }
 }
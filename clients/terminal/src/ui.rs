@@ -1,5 +1,5 @@
 use crate::app::{App, AppScreen};
-use pong_core::{Config, RenderHelper, Side, Status, View};
+use pong_core::{fx, Config, RenderHelper, Side, Status, View};
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
@@ -296,10 +296,12 @@ impl App {
                 // Render score and status
                 let status_text = match view.status {
                     Status::Lobby => "Waiting for players to be ready (SPACE)",
+                    Status::Warmup(_) => "Warmup - practice rally",
                     Status::Countdown(_) => "Get ready...",
                     Status::Playing => "Playing",
                     Status::Scored(_, _) => "Point scored!",
                     Status::GameOver(_) => "Game Over!",
+                    Status::Paused => "Paused",
                 };
 
                 let score_content = format!(
@@ -361,9 +363,10 @@ impl App {
             return; // Too small to render
         }
 
-        // Calculate field dimensions that appear square to users
+        // Calculate field dimensions that match the physics aspect ratio
         let field_width = available_width as usize;
-        let ideal_height = (available_width as f32 * CHAR_ASPECT_RATIO) as usize;
+        let aspect = fx::to_f32(view.field_width.max(1));
+        let ideal_height = (available_width as f32 * CHAR_ASPECT_RATIO / aspect) as usize;
         let field_height = ideal_height.min(available_height as usize);
 
         // Create config for RenderHelper (reconstruct from view data)
@@ -372,6 +375,7 @@ impl App {
             paddle_width: view.paddle_width,
             ball_radius: view.ball_radius,
             paddle_x: view.paddle_x_offset,
+            field_width: view.field_width,
             ..Config::default()
         };
 
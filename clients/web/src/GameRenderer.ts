@@ -56,14 +56,13 @@ export class GameRenderer {
       }
 
       // Convert fixed-point coordinates to normalized coordinates
-      const normalizedBallX = view.ball_pos.x / GAME_CONFIG.FIXED_POINT_SCALE;
+      const normalizedBallX = view.ball_pos.x / view.field_width;
       const normalizedBallY = view.ball_pos.y / GAME_CONFIG.FIXED_POINT_SCALE;
       const normalizedLeftPaddleY =
         view.left_paddle_y / GAME_CONFIG.FIXED_POINT_SCALE;
       const normalizedRightPaddleY =
         view.right_paddle_y / GAME_CONFIG.FIXED_POINT_SCALE;
-      const normalizedPaddleXOffset =
-        view.paddle_x_offset / GAME_CONFIG.FIXED_POINT_SCALE;
+      const normalizedPaddleXOffset = view.paddle_x_offset / view.field_width;
       const normalizedPaddleHalfH =
         view.paddle_half_h / GAME_CONFIG.FIXED_POINT_SCALE;
 
@@ -178,7 +177,9 @@ export class GameRenderer {
           return view.status;
       }
     } else if (typeof view.status === "object") {
-      if ("Countdown" in view.status) {
+      if ("Warmup" in view.status) {
+        return isMobile ? "Warmup" : "Warmup - practice rally";
+      } else if ("Countdown" in view.status) {
         return "Get ready...";
       } else if ("Scored" in view.status) {
         return "Point scored!";
@@ -7,15 +7,32 @@ export const GAME_CONFIG = {
   DEFAULT_GAME_CONFIG: {
     paddle_half_h: 8192,
     paddle_speed: 196608,
+    paddle_accel: 196608,
+    paddle_decel: 393216,
     ball_speed: 32768,
     ball_speed_up: 68813,
+    rally_ramp_hits: 0,
+    rally_ramp_speed_up: 72089,
     wall_thickness: 0,
+    wall_restitution: 65536,
+    wall_friction: 0,
     paddle_x: 3276,
+    paddle_max_tilt: 1820,
+    bounce_jitter: 0,
     max_score: 11,
     seed: 0xc0ffee,
     tick_hz: 60,
     ball_radius: 2048,
     paddle_width: 1638,
+    physics_substeps: 1,
+    warmup_secs: 0,
+    stuck_min_vx: 0,
+    stuck_timeout_secs: 0,
+    drama_rally_hits: 10,
+    drama_speed: 65536,
+    field_width: 65536,
+    goal_gap: 65536,
+    goal_gap_y: 32768,
   },
 } as const;
 
@@ -33,6 +50,9 @@ export const INPUT_CONFIG = {
   },
   BUTTON_BITS: {
     READY: 1,
+    DASH: 2,
+    PAUSE: 4,
+    TAUNT: 8,
   },
   // Simplified sensitivity settings
   TOUCH_SENSITIVITY: {
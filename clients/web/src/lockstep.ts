@@ -3,12 +3,52 @@
  */
 
 import { Transport } from "./rtc_transport";
-import { WasmGame, Input, InputPair, GameView } from "./types";
+import { WasmGame, Input, InputPair, GameView, GameEvent } from "./types";
 
 // Wire protocol message types
 const WIRE_MSG_INPUT_PAIR = 0x01;
 const WIRE_MSG_SNAPSHOT = 0x02;
 const WIRE_MSG_PING = 0x03;
+const WIRE_MSG_CHAT = 0x0b;
+const WIRE_MSG_EMOTE = 0x0c;
+const WIRE_MSG_PONG = 0x0e;
+const WIRE_MSG_SNAPSHOT_REQUEST = 0x0f;
+
+// Predefined reactions for sendEmote (ids match pong_net::wire_protocol::emotes)
+export const EMOTES = {
+  GG: 0,
+  NICE_SHOT: 1,
+  LAUGH: 2,
+  OOPS: 3,
+} as const;
+
+// Longest chat message in UTF-8 bytes
+export const MAX_CHAT_BYTES = 256;
+
+// Size of the CRC32 trailer on every message (matches pong_net::wire_protocol)
+const CHECKSUM_SIZE = 4;
+
+// CRC32 (IEEE 802.3, reflected) lookup table
+const CRC32_TABLE = (() => {
+  const table = new Uint32Array(256);
+  for (let i = 0; i < 256; i++) {
+    let crc = i;
+    for (let bit = 0; bit < 8; bit++) {
+      crc = crc & 1 ? (crc >>> 1) ^ 0xedb88320 : crc >>> 1;
+    }
+    table[i] = crc >>> 0;
+  }
+  return table;
+})();
+
+/** CRC32 (IEEE) of a byte array, as used for the wire message trailer */
+export function crc32(bytes: Uint8Array): number {
+  let crc = 0xffffffff;
+  for (const byte of bytes) {
+    crc = CRC32_TABLE[(crc ^ byte) & 0xff] ^ (crc >>> 8);
+  }
+  return (crc ^ 0xffffffff) >>> 0;
+}
 
 export enum Side {
   Left = 0,
@@ -20,10 +60,15 @@ export interface LockstepEvent {
     | "game_advanced"
     | "peer_disconnected"
     | "pong_received"
-    | "snapshot_received";
+    | "snapshot_received"
+    | "snapshot_requested"
+    | "chat_received"
+    | "emote_received";
   tick?: number;
-  events?: any[];
+  events?: GameEvent[];
   roundTripMs?: number;
+  text?: string;
+  emote?: number;
 }
 
 export interface CoreAdapter {
@@ -34,6 +79,8 @@ export interface CoreAdapter {
     bAxis: number,
     bBtn: number
   ): any | undefined;
+  /** Every event from the last step in order, collisions included */
+  drainEvents(): GameEvent[];
   viewJson(): string;
   snapshotBytes(): Uint8Array;
   restoreBytes(bytes: Uint8Array): void;
@@ -61,6 +108,10 @@ export class WasmGameAdapter implements CoreAdapter {
     return eventJson ? JSON.parse(eventJson) : undefined;
   }
 
+  drainEvents(): GameEvent[] {
+    return JSON.parse(this.game.events_json());
+  }
+
   viewJson(): string {
     return this.game.view_json();
   }
@@ -78,6 +129,14 @@ export class WasmGameAdapter implements CoreAdapter {
   }
 }
 
+/** Append the CRC32 trailer the Rust peer verifies on every message */
+function withChecksum(body: Uint8Array): Uint8Array {
+  const bytes = new Uint8Array(body.length + CHECKSUM_SIZE);
+  bytes.set(body);
+  new DataView(bytes.buffer).setUint32(body.length, crc32(body), true);
+  return bytes;
+}
+
 /**
  * Wire protocol message encoding/decoding
  */
@@ -93,7 +152,7 @@ export class WireMsg {
     view.setInt8(7, inputPair.b.axis_y);
     view.setUint8(8, inputPair.b.buttons);
 
-    return new Uint8Array(buffer);
+    return withChecksum(new Uint8Array(buffer));
   }
 
   static encodeSnapshot(snapshotBytes: Uint8Array): Uint8Array {
@@ -103,7 +162,7 @@ export class WireMsg {
     view.setUint8(0, WIRE_MSG_SNAPSHOT);
     new Uint8Array(buffer, 1).set(snapshotBytes);
 
-    return new Uint8Array(buffer);
+    return withChecksum(new Uint8Array(buffer));
   }
 
   static encodePing(timestamp: number): Uint8Array {
@@ -113,12 +172,69 @@ export class WireMsg {
     view.setUint8(0, WIRE_MSG_PING);
     view.setUint32(1, timestamp, true); // little endian
 
-    return new Uint8Array(buffer);
+    return withChecksum(new Uint8Array(buffer));
+  }
+
+  static encodeSnapshotRequest(sinceTick: number): Uint8Array {
+    const buffer = new ArrayBuffer(5);
+    const view = new DataView(buffer);
+
+    view.setUint8(0, WIRE_MSG_SNAPSHOT_REQUEST);
+    view.setUint32(1, sinceTick, true); // little endian
+
+    return withChecksum(new Uint8Array(buffer));
+  }
+
+  static encodePong(originalTimestamp: number): Uint8Array {
+    const buffer = new ArrayBuffer(5);
+    const view = new DataView(buffer);
+
+    view.setUint8(0, WIRE_MSG_PONG);
+    view.setUint32(1, originalTimestamp, true); // little endian
+
+    return withChecksum(new Uint8Array(buffer));
+  }
+
+  static encodeChat(text: string): Uint8Array {
+    let utf8 = new TextEncoder().encode(text);
+    if (utf8.length > MAX_CHAT_BYTES) {
+      // Cut at the limit, backing off so a multi-byte character isn't split
+      let len = MAX_CHAT_BYTES;
+      while (len > 0 && (utf8[len] & 0xc0) === 0x80) {
+        len--;
+      }
+      utf8 = utf8.subarray(0, len);
+    }
+
+    const buffer = new ArrayBuffer(3 + utf8.length);
+    const view = new DataView(buffer);
+
+    view.setUint8(0, WIRE_MSG_CHAT);
+    view.setUint16(1, utf8.length, true); // little endian
+    new Uint8Array(buffer, 3).set(utf8);
+
+    return withChecksum(new Uint8Array(buffer));
+  }
+
+  static encodeEmote(emote: number): Uint8Array {
+    return withChecksum(new Uint8Array([WIRE_MSG_EMOTE, emote & 0xff]));
   }
 
   static decode(bytes: Uint8Array): any {
-    if (bytes.length === 0) {
-      throw new Error("Empty message");
+    if (bytes.length <= CHECKSUM_SIZE) {
+      throw new Error("Message too short");
+    }
+
+    // Verify and strip the CRC32 trailer
+    const bodyLength = bytes.length - CHECKSUM_SIZE;
+    const trailer = new DataView(
+      bytes.buffer,
+      bytes.byteOffset + bodyLength,
+      CHECKSUM_SIZE
+    );
+    bytes = bytes.subarray(0, bodyLength);
+    if (crc32(bytes) !== trailer.getUint32(0, true)) {
+      throw new Error("Checksum mismatch");
     }
 
     const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
@@ -163,6 +279,49 @@ export class WireMsg {
           timestamp: view.getUint32(1, true),
         };
 
+      case WIRE_MSG_SNAPSHOT_REQUEST:
+        if (bytes.length !== 5) {
+          throw new Error("Invalid SnapshotRequest message length");
+        }
+        return {
+          type: "snapshot_request",
+          sinceTick: view.getUint32(1, true),
+        };
+
+      case WIRE_MSG_PONG:
+        if (bytes.length !== 5) {
+          throw new Error("Invalid Pong message length");
+        }
+        return {
+          type: "pong",
+          timestamp: view.getUint32(1, true),
+        };
+
+      case WIRE_MSG_CHAT: {
+        if (bytes.length < 3) {
+          throw new Error("Invalid Chat message length");
+        }
+        const len = view.getUint16(1, true);
+        if (len > MAX_CHAT_BYTES || bytes.length < 3 + len) {
+          throw new Error("Invalid Chat message length");
+        }
+        return {
+          type: "chat",
+          text: new TextDecoder("utf-8", { fatal: true }).decode(
+            bytes.subarray(3, 3 + len)
+          ),
+        };
+      }
+
+      case WIRE_MSG_EMOTE:
+        if (bytes.length !== 2) {
+          throw new Error("Invalid Emote message length");
+        }
+        return {
+          type: "emote",
+          emote: view.getUint8(1),
+        };
+
       default:
         throw new Error(`Unknown message type: ${msgType}`);
     }
@@ -182,6 +341,9 @@ export class Lockstep {
   private localInputBuffer = new Map<number, Input>();
   private remoteInputBuffer = new Map<number, Input>();
 
+  // Events raised by incoming messages, handed out by the next tick()
+  private pendingEvents: LockstepEvent[] = [];
+
   constructor(
     gameAdapter: CoreAdapter,
     transport: Transport,
@@ -196,7 +358,7 @@ export class Lockstep {
 
     // Set up message handler
     this.transport.onMessage((bytes) => {
-      this.onNetMessage(bytes);
+      this.pendingEvents.push(...this.onNetMessage(bytes));
     });
   }
 
@@ -289,7 +451,7 @@ export class Lockstep {
 
         case "ping": {
           // Respond with a pong
-          const pong = WireMsg.encodePing(wireMsg.timestamp);
+          const pong = WireMsg.encodePong(wireMsg.timestamp);
           try {
             this.transport.send(pong);
           } catch (error) {
@@ -297,6 +459,40 @@ export class Lockstep {
           }
           break;
         }
+
+        case "snapshot_request": {
+          this.sendSnapshot();
+          events.push({
+            type: "snapshot_requested",
+            tick: wireMsg.sinceTick,
+          });
+          break;
+        }
+
+        case "pong": {
+          // Timestamps travel as u32, so compare modulo 2^32
+          events.push({
+            type: "pong_received",
+            roundTripMs: ((Date.now() >>> 0) - wireMsg.timestamp) >>> 0,
+          });
+          break;
+        }
+
+        case "chat": {
+          events.push({
+            type: "chat_received",
+            text: wireMsg.text,
+          });
+          break;
+        }
+
+        case "emote": {
+          events.push({
+            type: "emote_received",
+            emote: wireMsg.emote,
+          });
+          break;
+        }
       }
 
       return events;
@@ -310,7 +506,8 @@ export class Lockstep {
    * Try to advance the simulation (call this regularly in your game loop)
    */
   tick(): { view: GameView; events: LockstepEvent[] } {
-    const events: LockstepEvent[] = [];
+    const events: LockstepEvent[] = this.pendingEvents;
+    this.pendingEvents = [];
 
     if (!this.isRunning) {
       // Return current view even if not running
@@ -328,7 +525,7 @@ export class Lockstep {
       const bInput = this.localSide === Side.Left ? remoteInput : localInput;
 
       // Step the simulation
-      const gameEvent = this.core.step(
+      this.core.step(
         this.currentTick,
         aInput.axis_y,
         aInput.buttons,
@@ -343,13 +540,11 @@ export class Lockstep {
       // Advance tick
       this.currentTick += 1;
 
-      if (gameEvent) {
-        events.push({
-          type: "game_advanced",
-          tick: this.currentTick - 1,
-          events: [gameEvent],
-        });
-      }
+      events.push({
+        type: "game_advanced",
+        tick: this.currentTick - 1,
+        events: this.core.drainEvents(),
+      });
     }
 
     // Get current view
@@ -360,13 +555,29 @@ export class Lockstep {
   }
 
   /**
-   * Request a snapshot from the remote peer
+   * Ask the remote peer to send us its snapshot (e.g. after a desync)
    */
   requestSnapshot(): void {
     if (!this.isRunning) {
       throw new Error("Lockstep not running");
     }
 
+    const request = WireMsg.encodeSnapshotRequest(this.currentTick);
+    try {
+      this.transport.send(request);
+    } catch (error) {
+      console.error("Failed to send snapshot request:", error);
+    }
+  }
+
+  /**
+   * Push our current snapshot to the remote peer
+   */
+  sendSnapshot(): void {
+    if (!this.isRunning) {
+      throw new Error("Lockstep not running");
+    }
+
     // Send our current snapshot to the peer
     const snapshotBytes = this.core.snapshotBytes();
     const wireMsg = WireMsg.encodeSnapshot(snapshotBytes);
@@ -377,6 +588,37 @@ export class Lockstep {
     }
   }
 
+  /**
+   * Send a chat message to the peer (truncated to MAX_CHAT_BYTES)
+   */
+  sendChat(text: string): void {
+    if (!this.isRunning) {
+      throw new Error("Lockstep not running");
+    }
+
+    const chat = WireMsg.encodeChat(text);
+    try {
+      this.transport.send(chat);
+    } catch (error) {
+      console.error("Failed to send chat:", error);
+    }
+  }
+
+  /**
+   * Send a predefined reaction (see EMOTES) to the peer
+   */
+  sendEmote(emote: number): void {
+    if (!this.isRunning) {
+      throw new Error("Lockstep not running");
+    }
+
+    try {
+      this.transport.send(WireMsg.encodeEmote(emote));
+    } catch (error) {
+      console.error("Failed to send emote:", error);
+    }
+  }
+
   /**
    * Send a ping to measure round-trip time
    */
@@ -331,8 +331,8 @@ function startNetworkedGameLoop(): void {
 
     // Handle lockstep events
     for (const event of events) {
-      if (event.type === "game_advanced") {
-        console.log("Game advanced to tick", event.tick);
+      if (event.type === "game_advanced" && event.events?.length) {
+        console.log("Game events at tick", event.tick, event.events);
       }
     }
 
@@ -18,6 +18,87 @@ export enum ConnectionMode {
   Guest = "guest",
 }
 
+/**
+ * PeerJS signaling server and ICE servers (STUN/TURN) to connect through
+ */
+export interface PeerServerConfig {
+  host: string;
+  port: number;
+  path: string;
+  secure: boolean;
+  /** API key, for PeerServer deployments that require one */
+  key?: string;
+  iceServers: RTCIceServer[];
+}
+
+/**
+ * The public PeerJS cloud with Google/Twilio STUN
+ */
+export const DEFAULT_PEER_SERVER: PeerServerConfig = {
+  host: "0.peerjs.com",
+  port: 443,
+  path: "/",
+  secure: true,
+  iceServers: [
+    { urls: "stun:stun.l.google.com:19302" },
+    { urls: "stun:global.stun.twilio.com:3478" },
+    { urls: "stun:stun1.l.google.com:19302" },
+    { urls: "stun:stun2.l.google.com:19302" },
+    // Add local network support
+    { urls: "stun:stun3.l.google.com:19302" },
+    { urls: "stun:stun4.l.google.com:19302" },
+  ],
+};
+
+/**
+ * Build the server config from the page's query string, falling back to
+ * DEFAULT_PEER_SERVER, so a self-hosted PeerServer or TURN relay can be used
+ * without rebuilding:
+ *
+ *   ?peerServer=https://peer.example.com:9000/myapp&peerKey=secret
+ *   &turn=turn:turn.example.com:3478&turnUser=pong&turnCredential=secret
+ *
+ * Both players need the same peerServer; share the full page URL.
+ */
+export function resolvePeerServerConfig(
+  search: string = window.location.search
+): PeerServerConfig {
+  const params = new URLSearchParams(search);
+  const config: PeerServerConfig = {
+    ...DEFAULT_PEER_SERVER,
+    iceServers: [...DEFAULT_PEER_SERVER.iceServers],
+  };
+
+  const server = params.get("peerServer");
+  if (server) {
+    try {
+      const url = new URL(server);
+      config.secure = url.protocol === "https:" || url.protocol === "wss:";
+      config.host = url.hostname;
+      config.port = url.port ? Number(url.port) : config.secure ? 443 : 80;
+      config.path = url.pathname || "/";
+    } catch (error) {
+      console.warn("Ignoring invalid peerServer URL:", server, error);
+    }
+  }
+
+  const key = params.get("peerKey");
+  if (key) {
+    config.key = key;
+  }
+
+  const turn = params.get("turn");
+  if (turn) {
+    config.iceServers.push({
+      urls: turn,
+      username: params.get("turnUser") ?? undefined,
+      credential: params.get("turnCredential") ?? undefined,
+    });
+  }
+
+  return config;
+}
+
 export class PeerTransport implements Transport {
   private peer: Peer | null = null;
   private connection: DataConnection | null = null;
@@ -26,9 +107,14 @@ export class PeerTransport implements Transport {
   private mode: ConnectionMode;
   private peerId: string = "";
   private statusMessage: string = "Initializing...";
+  private server: PeerServerConfig;
 
-  constructor(mode: ConnectionMode) {
+  constructor(
+    mode: ConnectionMode,
+    server: PeerServerConfig = resolvePeerServerConfig()
+  ) {
     this.mode = mode;
+    this.server = server;
   }
 
   /**
@@ -39,20 +125,13 @@ export class PeerTransport implements Transport {
       try {
         // Create peer with auto-generated ID and better local connection support
         this.peer = new Peer({
-          host: "0.peerjs.com",
-          port: 443,
-          path: "/",
-          secure: true,
+          host: this.server.host,
+          port: this.server.port,
+          path: this.server.path,
+          secure: this.server.secure,
+          ...(this.server.key ? { key: this.server.key } : {}),
           config: {
-            iceServers: [
-              { urls: "stun:stun.l.google.com:19302" },
-              { urls: "stun:global.stun.twilio.com:3478" },
-              { urls: "stun:stun1.l.google.com:19302" },
-              { urls: "stun:stun2.l.google.com:19302" },
-              // Add local network support
-              { urls: "stun:stun3.l.google.com:19302" },
-              { urls: "stun:stun4.l.google.com:19302" },
-            ],
+            iceServers: this.server.iceServers,
             iceCandidatePoolSize: 10,
           },
           debug: 2, // Increase debugging level
@@ -311,11 +390,11 @@ export class PeerTransportFactory {
   /**
    * Create a new PeerJS transport for hosting
    */
-  static async createHost(): Promise<{
+  static async createHost(server?: PeerServerConfig): Promise<{
     transport: PeerTransport;
     peerId: string;
   }> {
-    const transport = new PeerTransport(ConnectionMode.Host);
+    const transport = new PeerTransport(ConnectionMode.Host, server);
     const peerId = await transport.initialize();
     return { transport, peerId };
   }
@@ -323,8 +402,11 @@ export class PeerTransportFactory {
   /**
    * Create a new PeerJS transport for joining
    */
-  static async createGuest(hostPeerId: string): Promise<PeerTransport> {
-    const transport = new PeerTransport(ConnectionMode.Guest);
+  static async createGuest(
+    hostPeerId: string,
+    server?: PeerServerConfig
+  ): Promise<PeerTransport> {
+    const transport = new PeerTransport(ConnectionMode.Guest, server);
     await transport.initialize(hostPeerId);
     return transport;
   }
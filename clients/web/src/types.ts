@@ -6,25 +6,57 @@ export interface GameView {
   score: [number, number];
   left_paddle_y: number;
   right_paddle_y: number;
+  left_paddle_tilt: number;
+  right_paddle_tilt: number;
+  left_paddle_vy: number; // Paddle velocities in fixed-point units/sec
+  right_paddle_vy: number;
   paddle_half_h: number;
   ball_pos: { x: number; y: number };
+  ball_vel: { x: number; y: number };
   paddle_x_offset: number;
   paddle_width: number;
   ball_radius: number;
+  field_width: number; // Field width in fixed-point units (height is 1.0)
+  tension: {
+    match_point: boolean;
+    long_rally: boolean;
+    fast_ball: boolean;
+  };
+  countdown_ticks_remaining: number; // 0 unless counting down to a serve
+  serving_side: "Left" | "Right" | null; // Who serves next, while a serve is pending
 }
 
+// Changed GameView fields since the previous frame; tick is always present
+export type ViewDelta = Partial<GameView> & { tick: number };
+
 export type GameStatus =
   | "Lobby"
+  | { Warmup: number }
   | { Countdown: number }
   | "Playing"
   | { Scored: [string, number] }
-  | { GameOver: string };
+  | { GameOver: string }
+  | "Paused";
 
-export interface GameEvent {
-  Scored: {
-    scorer: "Left" | "Right";
-    score: [number, number];
-  };
+export type GameEvent =
+  | {
+      Scored: {
+        scorer: "Left" | "Right";
+        score: [number, number];
+      };
+    }
+  | "BallNudged"
+  | { BallReserved: { server: "Left" | "Right" } }
+  | { PaddleHit: { side: "Left" | "Right"; contact: CollisionInfo } }
+  | { WallHit: { contact: CollisionInfo } }
+  | { ObstacleHit: { index: number; contact: CollisionInfo } };
+
+// Impact details for effects; all values are fixed-point
+export interface CollisionInfo {
+  point: { x: number; y: number };
+  normal: { x: number; y: number };
+  impact_offset: number;
+  new_speed: number;
 }
 
 export interface WasmGame {
@@ -37,6 +69,8 @@ export interface WasmGame {
     b_btn: number
   ): string | undefined;
   view_json(): string;
+  view_delta_json(): string; // ViewDelta since the previous call
+  events_json(): string;
   snapshot_bytes(): Uint8Array;
   restore_bytes(bytes: Uint8Array): void;
   reset_match(): void;
@@ -48,21 +82,40 @@ export interface WasmGame {
 export interface Config {
   paddle_half_h: number;
   paddle_speed: number;
+  paddle_accel: number;
+  paddle_decel: number;
   ball_speed: number;
   ball_speed_up: number;
+  rally_ramp_hits: number;
+  rally_ramp_speed_up: number;
   wall_thickness: number;
+  wall_restitution: number;
+  wall_friction: number;
   paddle_x: number;
+  paddle_max_tilt: number;
+  bounce_jitter: number;
   max_score: number;
   seed: number;
   tick_hz: number;
   ball_radius: number;
   paddle_width: number;
+  physics_substeps: number;
+  warmup_secs: number;
+  stuck_min_vx: number;
+  stuck_timeout_secs: number;
+  drama_rally_hits: number;
+  drama_speed: number;
+  field_width: number;
+  goal_gap: number;
+  goal_gap_y: number;
 }
 
 // Input types
 export interface Input {
   axis_y: number; // [-127, 127]
-  buttons: number; // Bitfield
+  axis_x?: number; // [-127, 127], horizontal axis
+  buttons: number; // Bitfield, see INPUT_CONFIG.BUTTON_BITS
+  tilt?: number; // [-127, 127], paddle tilt axis
 }
 
 export interface InputPair {
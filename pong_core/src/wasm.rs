@@ -1,6 +1,6 @@
 //! WASM bridge module for JavaScript interop
 
-use crate::{Config, Game, Input, InputPair};
+use crate::{Config, Game, Input, InputPair, View, ViewDelta};
 use wasm_bindgen::prelude::*;
 
 // Console logging placeholder (can be implemented later)
@@ -12,6 +12,7 @@ macro_rules! console_log {
 #[wasm_bindgen]
 pub struct WasmGame {
     inner: Game,
+    last_view: Option<View>, // View sent by the last view_delta_json call
 }
 
 #[wasm_bindgen]
@@ -36,7 +37,10 @@ impl WasmGame {
         let game = Game::new(config);
         console_log!("WasmGame created with tick_hz: {}", config.tick_hz);
 
-        Ok(WasmGame { inner: game })
+        Ok(WasmGame {
+            inner: game,
+            last_view: None,
+        })
     }
 
     /// Step the game forward one tick with inputs for both players
@@ -79,6 +83,36 @@ impl WasmGame {
         }
     }
 
+    /// Get only the view fields changed since the previous call as a JSON
+    /// object (every field on the first call). `tick` is always included.
+    pub fn view_delta_json(&mut self) -> String {
+        let view = self.inner.view();
+        let delta = match &self.last_view {
+            Some(prev) => ViewDelta::between(prev, &view),
+            None => ViewDelta::full(&view),
+        };
+        self.last_view = Some(view);
+
+        match serde_json::to_string(&delta) {
+            Ok(json) => json,
+            Err(e) => {
+                console_log!("Failed to serialize view delta: {}", e);
+                "{}".to_string()
+            }
+        }
+    }
+
+    /// Get every event from the last step (including collisions) as a JSON array
+    pub fn events_json(&self) -> String {
+        match serde_json::to_string(self.inner.events()) {
+            Ok(json) => json,
+            Err(e) => {
+                console_log!("Failed to serialize events: {}", e);
+                "[]".to_string()
+            }
+        }
+    }
+
     /// Get a snapshot of the current game state as bytes
     pub fn snapshot_bytes(&self) -> Vec<u8> {
         let snapshot = self.inner.snapshot();
@@ -88,10 +122,14 @@ impl WasmGame {
     /// Restore game state from snapshot bytes
     pub fn restore_bytes(&mut self, bytes: &[u8]) {
         match crate::Snapshot::decode(bytes) {
-            Ok(snapshot) => {
-                self.inner.restore(&snapshot);
-                console_log!("Game state restored from snapshot");
-            }
+            Ok(snapshot) => match self.inner.restore(&snapshot) {
+                Ok(()) => {
+                    console_log!("Game state restored from snapshot");
+                }
+                Err(e) => {
+                    console_log!("Snapshot rejected: {:?}", e);
+                }
+            },
             Err(e) => {
                 console_log!("Failed to restore from snapshot: {:?}", e);
             }
@@ -177,6 +215,20 @@ mod tests {
         assert!(view.get("status").is_some());
     }
 
+    #[test]
+    fn test_view_delta_json() {
+        let mut game = WasmGame::new("{}".to_string()).unwrap();
+
+        // First call carries the whole view
+        let full: serde_json::Value = serde_json::from_str(&game.view_delta_json()).unwrap();
+        assert!(full.get("ball_pos").is_some());
+        assert!(full.get("paddle_width").is_some());
+
+        // Nothing changes in the lobby but the tick
+        let _ = game.step(0, 0, 0, 0, 0);
+        assert_eq!(game.view_delta_json(), r#"{"tick":1}"#);
+    }
+
     #[test]
     fn test_snapshot_roundtrip() {
         let mut game1 = WasmGame::new("{}".to_string()).unwrap();
@@ -197,6 +249,13 @@ mod tests {
         assert_eq!(game1.view_json(), game2.view_json());
     }
 
+    #[test]
+    fn test_partial_config_json_uses_defaults() {
+        let game = WasmGame::new(r#"{"max_score": 3}"#.to_string()).unwrap();
+        assert_eq!(game.inner.config.max_score, 3);
+        assert_eq!(game.inner.config.tick_hz, Config::default().tick_hz);
+    }
+
     #[test]
     fn test_default_config_json() {
         let json = default_config_json();
@@ -0,0 +1,59 @@
+//! Computer opponent: steers a paddle toward the ball.
+//!
+//! A pure function of the view, so any peer can stand in for a side it doesn't
+//! control (e.g. after the remote player drops out of a networked match).
+
+use crate::types::*;
+
+/// Ball-to-paddle gap left alone, so the paddle settles instead of jittering
+const DEAD_ZONE: Fx = FX_ONE / 64;
+
+/// Axis per unit of gap; an eighth of the field height already means full speed
+const AXIS_PER_UNIT: i64 = 127 * 8;
+
+/// Input that moves `side`'s paddle toward the ball while it's heading that
+/// way, and back to the middle of the field otherwise
+pub fn track_ball(view: &View, side: Side) -> Input {
+    let (paddle_y, incoming) = match side {
+        Side::Left => (view.left_paddle_y, view.ball_vel.x < 0),
+        Side::Right => (view.right_paddle_y, view.ball_vel.x > 0),
+    };
+    let target = if incoming {
+        view.ball_pos.y
+    } else {
+        FX_ONE / 2
+    };
+
+    let gap = target - paddle_y;
+    if gap.abs() <= DEAD_ZONE {
+        return Input::zero();
+    }
+    let axis = (gap as i64 * AXIS_PER_UNIT / FX_ONE as i64).clamp(-127, 127);
+    Input::new(axis as i8, Buttons::empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+
+    #[test]
+    fn test_track_ball_follows_incoming_ball() {
+        let mut view = Game::new(Config::default()).view();
+        view.right_paddle_y = FX_ONE / 2;
+        view.ball_pos.y = FX_ONE / 4;
+        view.ball_vel.x = FX_ONE / 2;
+
+        // Incoming and a quarter field away: full speed toward it
+        assert_eq!(track_ball(&view, Side::Right).axis_y, -127);
+
+        // Heading away: back to the middle, where the paddle already is
+        view.ball_vel.x = -FX_ONE / 2;
+        assert_eq!(track_ball(&view, Side::Right), Input::zero());
+
+        // The left paddle reacts to the same ball, proportionally when close
+        view.left_paddle_y = FX_ONE / 4 + FX_ONE / 32;
+        let axis = track_ball(&view, Side::Left).axis_y;
+        assert!((-127..0).contains(&axis));
+    }
+}
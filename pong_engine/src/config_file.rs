@@ -0,0 +1,173 @@
+//! TOML rule files for `Config`.
+//!
+//! Fixed-point fields are written as plain decimals (`ball_speed = 0.5`)
+//! rather than raw 16.16 integers, and converted back on load with the same
+//! truncation as `fx::from_f32`, so hand-edited files mean what they say.
+//! Missing fields fall back to `Config::default()`.
+
+use crate::types::{Config, Fixed, Fx, FX_ONE};
+use std::fmt;
+use toml::{Table, Value};
+
+/// `Config` fields stored as `Fx`, written to TOML as decimals
+const FX_FIELDS: &[&str] = &[
+    "paddle_half_h",
+    "paddle_speed",
+    "paddle_accel",
+    "paddle_decel",
+    "ball_speed",
+    "ball_speed_up",
+    "rally_ramp_speed_up",
+    "wall_thickness",
+    "wall_restitution",
+    "wall_friction",
+    "paddle_x",
+    "paddle_max_tilt",
+    "bounce_jitter",
+    "ball_radius",
+    "paddle_width",
+    "stuck_min_vx",
+    "drama_speed",
+    "field_width",
+    "goal_gap",
+    "goal_gap_y",
+];
+
+/// Errors from loading or saving a TOML config
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// Not valid TOML, or a field has the wrong type
+    Parse(String),
+    /// Config could not be written as TOML
+    Serialize(String),
+    /// A fixed-point field is not a finite number in range
+    InvalidValue { field: String, reason: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Parse(msg) => write!(f, "Config parse error: {msg}"),
+            ConfigError::Serialize(msg) => write!(f, "Config serialize error: {msg}"),
+            ConfigError::InvalidValue { field, reason } => {
+                write!(f, "Invalid value for {field}: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Load a config from TOML, with decimal fixed-point fields
+    pub fn from_toml(text: &str) -> Result<Config, ConfigError> {
+        let mut table: Table = text
+            .parse()
+            .map_err(|e: toml::de::Error| ConfigError::Parse(e.to_string()))?;
+
+        for &field in FX_FIELDS {
+            if let Some(value) = table.get_mut(field) {
+                let raw = decimal_to_fx(field, value)?;
+                *value = Value::Integer(raw as i64);
+            }
+        }
+
+        Value::Table(table)
+            .try_into()
+            .map_err(|e: toml::de::Error| ConfigError::Parse(e.to_string()))
+    }
+
+    /// Save the config as TOML, with decimal fixed-point fields
+    pub fn to_toml(&self) -> Result<String, ConfigError> {
+        let mut table = Table::try_from(self)
+            .map_err(|e: toml::ser::Error| ConfigError::Serialize(e.to_string()))?;
+
+        for &field in FX_FIELDS {
+            if let Some(value) = table.get_mut(field) {
+                if let Some(raw) = value.as_integer() {
+                    *value = Value::Float(fx_to_decimal(raw as Fx));
+                }
+            }
+        }
+
+        toml::to_string(&table).map_err(|e| ConfigError::Serialize(e.to_string()))
+    }
+}
+
+/// Shortest decimal that loads back to exactly `raw`
+fn fx_to_decimal(raw: Fx) -> f64 {
+    // Display output is a plain decimal, always a valid f64 literal
+    Fixed(raw).to_string().parse().unwrap_or(0.0)
+}
+
+/// Convert a TOML number in whole units to fixed point
+fn decimal_to_fx(field: &str, value: &Value) -> Result<Fx, ConfigError> {
+    let invalid = |reason: &str| ConfigError::InvalidValue {
+        field: field.to_string(),
+        reason: reason.to_string(),
+    };
+
+    match value {
+        Value::Float(f) if f.is_finite() => format!("{f}")
+            .parse::<Fixed>()
+            .map(|fixed| fixed.0)
+            .map_err(|e| invalid(&e.to_string())),
+        Value::Float(_) => Err(invalid("not a finite number")),
+        Value::Integer(units) => units
+            .checked_mul(FX_ONE as i64)
+            .and_then(|raw| Fx::try_from(raw).ok())
+            .ok_or_else(|| invalid("out of range")),
+        _ => Err(invalid("expected a number")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::fx;
+
+    #[test]
+    fn test_default_config_round_trip() {
+        let config = Config::default();
+        let text = config.to_toml().unwrap();
+        let loaded = Config::from_toml(&text).unwrap();
+        assert_eq!(loaded.to_toml().unwrap(), text);
+        assert_eq!(loaded.paddle_x, config.paddle_x);
+        assert_eq!(loaded.seed, config.seed);
+
+        // Fixed-point fields are human-readable decimals
+        assert!(text.contains("paddle_x = 0.05\n"));
+        assert!(text.contains("field_width = 1.0\n"));
+        assert!(text.contains("max_score = "));
+    }
+
+    #[test]
+    fn test_partial_file_uses_defaults() {
+        let config =
+            Config::from_toml("ball_speed = 0.75\nmax_score = 3\nfield_width = 2\n").unwrap();
+        assert_eq!(config.ball_speed, fx::from_f32(0.75));
+        assert_eq!(config.max_score, 3);
+        assert_eq!(config.field_width, FX_ONE * 2);
+        assert_eq!(config.paddle_half_h, Config::default().paddle_half_h);
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        assert!(matches!(
+            Config::from_toml("ball_speed = ["),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            Config::from_toml("ball_speed = \"fast\""),
+            Err(ConfigError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            Config::from_toml("ball_speed = 40000.0"),
+            Err(ConfigError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            Config::from_toml("max_score = \"five\""),
+            Err(ConfigError::Parse(_))
+        ));
+    }
+}
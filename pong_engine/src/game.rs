@@ -0,0 +1,1289 @@
+//! Main game logic and state management.
+
+use crate::physics::{ClassicPhysics, PhysicsEngine};
+use crate::types::{fx, *};
+
+/// Why `Game::restore` refused a snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreError {
+    /// The snapshot was taken under different rules or obstacle layout
+    /// (`Config::layout_fingerprint` differs)
+    ConfigMismatch { expected: u64, found: u64 },
+    /// The snapshot holds phases for a different number of obstacles
+    ObstacleMismatch { expected: usize, found: usize },
+}
+
+/// Main game state and logic, generic over the physics rules in use
+pub struct Game<P: PhysicsEngine = ClassicPhysics> {
+    pub config: Config,
+    pub tick: Tick,
+    pub status: Status,
+    pub paddles: [Paddle; 2],
+    pub ball: Ball,
+    pub extra_balls: Vec<Ball>, // Balls in play besides `ball`, until the next serve
+    pub score: [u8; 2],
+    pub rng: u64,
+    pub obstacles: Vec<Obstacle>,
+    pub rally_hits: u16,             // Paddle hits since the last serve
+    pub stall_ticks: u16,            // Ticks since the ball last touched a paddle
+    pub trace: Option<PhysicsTrace>, // Per-tick state hashes, when tracing
+    pub events: Vec<Event>,          // Everything that happened during the last step
+    pub paused: bool,                // Held by the players; shown as `Status::Paused`
+    pub physics: P,
+}
+
+impl Game {
+    /// Create a new game with the given configuration and classic physics
+    pub fn new(config: Config) -> Self {
+        Game::with_physics(config, ClassicPhysics)
+    }
+}
+
+impl<P: PhysicsEngine> Game<P> {
+    /// Create a new game with the given configuration and physics rules
+    pub fn with_physics(config: Config, physics: P) -> Self {
+        let mut game = Game {
+            config,
+            tick: 0,
+            status: Status::Lobby,
+            paddles: [
+                Paddle::new(FX_ONE / 2), // Left paddle at center
+                Paddle::new(FX_ONE / 2), // Right paddle at center
+            ],
+            ball: Ball::new(config.field_center(), Vec2::zero()),
+            extra_balls: Vec::new(),
+            score: [0, 0],
+            rng: config.seed,
+            obstacles: Vec::new(),
+            rally_hits: 0,
+            stall_ticks: 0,
+            trace: None,
+            events: Vec::new(),
+            paused: false,
+            physics,
+        };
+
+        game.reset_for_serve(Side::Left);
+        game
+    }
+
+    /// Step the game simulation forward by one tick
+    pub fn step(&mut self, inputs: &InputPair) -> Option<Event> {
+        debug_assert_eq!(inputs.tick, self.tick, "Input tick mismatch");
+
+        self.events.clear();
+        let mut event = None;
+
+        match self.status {
+            // Never stored; `paused` holds the simulation outside of `step`
+            Status::Paused => {}
+
+            Status::Lobby => {
+                // Check if both players are ready
+                if inputs.a.is_ready() && inputs.b.is_ready() {
+                    self.status = match self.config.warmup_ticks() {
+                        0 => Status::Countdown(180), // 3 seconds at 60 Hz
+                        ticks => Status::Warmup(ticks),
+                    };
+                }
+            }
+
+            Status::Countdown(ticks_remaining) => {
+                if ticks_remaining <= 1 {
+                    self.status = Status::Playing;
+                    // Ball should already be set up from reset_for_serve
+                } else {
+                    self.status = Status::Countdown(ticks_remaining - 1);
+                }
+            }
+
+            Status::Warmup(ticks_remaining) => {
+                // Ball is live, but leaving the field just re-serves it
+                if let Some(scorer) = self.simulate_rally(inputs) {
+                    self.reset_for_serve(scorer.opposite());
+                } else {
+                    event = self.rescue_stuck_ball();
+                }
+
+                if ticks_remaining <= 1 {
+                    // Start the real match from a fresh serve
+                    self.reset_for_serve(Side::Left);
+                    self.status = Status::Countdown(180);
+                } else {
+                    self.status = Status::Warmup(ticks_remaining - 1);
+                }
+            }
+
+            Status::Playing => {
+                if let Some(scorer) = self.simulate_rally(inputs) {
+                    self.handle_score(scorer);
+
+                    event = Some(Event::Scored {
+                        scorer,
+                        score: self.score,
+                    });
+                } else {
+                    event = self.rescue_stuck_ball();
+                }
+            }
+
+            Status::Scored(scorer, ticks_remaining) => {
+                if ticks_remaining <= 1 {
+                    // Check for game over
+                    let max_score = self.config.max_score;
+                    if let Some(winner) = Side::BOTH
+                        .into_iter()
+                        .find(|&side| self.score[side] >= max_score)
+                    {
+                        self.status = Status::GameOver(winner);
+                    } else {
+                        // Continue playing - serve to the side that was scored on
+                        self.reset_for_serve(scorer.opposite());
+                        self.status = Status::Playing;
+                    }
+                } else {
+                    self.status = Status::Scored(scorer, ticks_remaining - 1);
+                }
+            }
+
+            Status::GameOver(_) => {
+                // Game is over, wait for restart or rematch
+                // In M1, we don't handle this - would be handled by the client
+            }
+        }
+
+        self.tick = self.tick.wrapping_add(1);
+        self.events.extend(event);
+
+        if self.trace.is_some() {
+            let hash = self.state_hash();
+            if let Some(trace) = &mut self.trace {
+                trace.record(inputs.tick, hash);
+            }
+        }
+
+        event
+    }
+
+    /// All events from the last step in the order they happened, including
+    /// collisions; `step` itself only returns the game-state event
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Start recording a per-tick state hash, keeping the last `capacity` ticks
+    pub fn enable_trace(&mut self, capacity: usize) {
+        self.trace = Some(PhysicsTrace::new(capacity));
+    }
+
+    /// Stop tracing and drop the recorded hashes
+    pub fn disable_trace(&mut self) {
+        self.trace = None;
+    }
+
+    /// Recorded per-tick hashes (None unless tracing is enabled)
+    pub fn trace(&self) -> Option<&PhysicsTrace> {
+        self.trace.as_ref()
+    }
+
+    /// 64-bit FNV-1a hash of the serialized simulation state
+    pub fn state_hash(&self) -> u64 {
+        self.snapshot().state_hash()
+    }
+
+    /// Generate pure physics view (no screen coordinates)
+    pub fn view(&self) -> View {
+        View {
+            tick: self.tick,
+            status: if self.paused {
+                Status::Paused
+            } else {
+                self.status
+            },
+            score: self.score,
+
+            // Pure physics data - client agnostic
+            left_paddle_y: self.paddles[Side::Left].y,
+            right_paddle_y: self.paddles[Side::Right].y,
+            left_paddle_tilt: self.paddles[Side::Left].tilt,
+            right_paddle_tilt: self.paddles[Side::Right].tilt,
+            left_paddle_vy: self.paddles[Side::Left].vy,
+            right_paddle_vy: self.paddles[Side::Right].vy,
+            paddle_half_h: self.config.paddle_half_h,
+            ball_pos: self.ball.pos,
+            ball_vel: self.ball.vel,
+            paddle_x_offset: self.config.paddle_x, // Distance from edge
+            paddle_width: self.config.paddle_width,
+            ball_radius: self.config.ball_radius,
+            field_width: self.config.width(),
+            tension: self.tension(),
+            countdown_ticks_remaining: self.status.countdown_remaining().unwrap_or(0),
+            serving_side: self.serving_side(),
+        }
+    }
+
+    /// Create a snapshot of the current game state
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            tick: self.tick,
+            status: self.status,
+            paddles: self.paddles,
+            ball: self.ball,
+            score: self.score,
+            rng: self.rng,
+            obstacle_phases: self.obstacles.iter().map(|o| o.phase).collect(),
+            rally_hits: self.rally_hits,
+            stall_ticks: self.stall_ticks,
+            config_hash: self.config.layout_fingerprint(&self.obstacles),
+            extra_balls: self.extra_balls.clone(),
+        }
+    }
+
+    /// Restore game state from a snapshot. Snapshots taken under other rules or
+    /// obstacle layout are rejected and leave the game untouched; a
+    /// `config_hash` of 0 is trusted, but the obstacle count must still match.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), RestoreError> {
+        if snapshot.obstacle_phases.len() != self.obstacles.len() {
+            return Err(RestoreError::ObstacleMismatch {
+                expected: self.obstacles.len(),
+                found: snapshot.obstacle_phases.len(),
+            });
+        }
+        let expected = self.config.layout_fingerprint(&self.obstacles);
+        if snapshot.config_hash != 0 && snapshot.config_hash != expected {
+            return Err(RestoreError::ConfigMismatch {
+                expected,
+                found: snapshot.config_hash,
+            });
+        }
+
+        self.tick = snapshot.tick;
+        self.status = snapshot.status;
+        self.paddles = snapshot.paddles;
+        self.ball = snapshot.ball;
+        self.extra_balls.clone_from(&snapshot.extra_balls);
+        self.score = snapshot.score;
+        self.rng = snapshot.rng;
+        self.rally_hits = snapshot.rally_hits;
+        self.stall_ticks = snapshot.stall_ticks;
+        for (obstacle, &phase) in self.obstacles.iter_mut().zip(&snapshot.obstacle_phases) {
+            obstacle.set_phase(phase, self.config.tick_hz);
+        }
+        Ok(())
+    }
+
+    /// Reset the game for a new match (rematch), as `Game::new` would start it
+    /// with the same obstacles
+    pub fn reset_match(&mut self) {
+        self.tick = 0;
+        self.status = Status::Lobby;
+        self.score = [0, 0];
+        self.paddles = [Paddle::new(FX_ONE / 2), Paddle::new(FX_ONE / 2)];
+        self.ball = Ball::new(self.config.field_center(), Vec2::zero());
+        self.rng = self.config.seed;
+        for obstacle in &mut self.obstacles {
+            *obstacle = Obstacle::new(obstacle.origin, obstacle.half_size, obstacle.motion);
+        }
+        self.events.clear();
+        self.reset_for_serve(Side::Left);
+    }
+
+    /// Start the next game of a series: scores reset and `server` serves from
+    /// `seed` after a countdown. Unlike `reset_match` the tick keeps counting
+    /// and no ready-up is needed, so peers in lockstep carry straight on.
+    pub fn start_next_game(&mut self, seed: u64, server: Side) {
+        self.status = Status::Countdown(180);
+        self.score = [0, 0];
+        self.paddles = [Paddle::new(FX_ONE / 2), Paddle::new(FX_ONE / 2)];
+        self.rng = seed;
+        self.reset_for_serve(server);
+    }
+
+    /// Simulate one tick of live play, returning the side that would score
+    fn simulate_rally(&mut self, inputs: &InputPair) -> Option<Side> {
+        // Update paddles based on input
+        for side in Side::BOTH {
+            self.physics.update_paddle(
+                &mut self.paddles[side],
+                &inputs.get_input(side),
+                &self.config,
+            );
+        }
+
+        // Move obstacles along their paths
+        for obstacle in &mut self.obstacles {
+            obstacle.advance(self.config.tick_hz);
+        }
+
+        // Limit ball speed to prevent runaway using proper fixed-point math
+        let max_speed = fx::mul_fx(self.config.ball_speed, 4 * FX_ONE); // Allow 4x base speed max
+
+        // Integrate the balls in substeps so fast balls don't tunnel
+        for _ in 0..self.config.substeps() {
+            for index in 0..=self.extra_balls.len() {
+                let mut ball = match index {
+                    0 => self.ball,
+                    _ => self.extra_balls[index - 1],
+                };
+                let scorer = self.simulate_ball(&mut ball, max_speed);
+                match index {
+                    0 => self.ball = ball,
+                    _ => self.extra_balls[index - 1] = ball,
+                }
+                if scorer.is_some() {
+                    return scorer;
+                }
+            }
+
+            if !self.extra_balls.is_empty() {
+                let mut balls = Vec::with_capacity(1 + self.extra_balls.len());
+                balls.push(self.ball);
+                balls.extend_from_slice(&self.extra_balls);
+                self.physics.collide_all_balls(&mut balls, &self.config);
+                self.ball = balls[0];
+                self.extra_balls.copy_from_slice(&balls[1..]);
+            }
+        }
+
+        None
+    }
+
+    /// Advance one ball by a substep against walls, paddles and obstacles,
+    /// returning the side that would score
+    fn simulate_ball(&mut self, ball: &mut Ball, max_speed: Fx) -> Option<Side> {
+        // Update ball physics
+        if let Some(contact) = self.physics.update_ball(ball, &self.config) {
+            self.events.push(Event::WallHit { contact });
+        }
+
+        // Check paddle collisions
+        let left_hit = self.physics.check_paddle_collision(
+            ball,
+            &self.paddles[Side::Left],
+            Side::Left,
+            &self.config,
+        );
+        let right_hit = self.physics.check_paddle_collision(
+            ball,
+            &self.paddles[Side::Right],
+            Side::Right,
+            &self.config,
+        );
+        let paddle_hit = match (left_hit, right_hit) {
+            (Some(contact), _) => Some((Side::Left, contact)),
+            (None, Some(contact)) => Some((Side::Right, contact)),
+            (None, None) => None,
+        };
+        if let Some((side, contact)) = paddle_hit {
+            self.events.push(Event::PaddleHit { side, contact });
+            self.physics
+                .apply_bounce_jitter(ball, &self.config, &mut self.rng);
+            self.advance_rally_ramp(ball);
+            self.stall_ticks = 0;
+        }
+        for (index, obstacle) in self.obstacles.iter().enumerate() {
+            if let Some(contact) =
+                self.physics
+                    .check_obstacle_collision(ball, obstacle, &self.config)
+            {
+                self.events.push(Event::ObstacleHit {
+                    index: index.min(u8::MAX as usize) as u8,
+                    contact,
+                });
+            }
+        }
+
+        self.physics.limit_ball_speed(ball, max_speed);
+
+        // Check for scoring
+        self.physics.check_scoring(ball, &self.config)
+    }
+
+    /// Handle a scoring event
+    fn handle_score(&mut self, scorer: Side) {
+        self.score[scorer] += 1;
+
+        self.status = Status::Scored(scorer, 180); // 3 seconds pause
+    }
+
+    /// Reset ball and game state for a serve
+    fn reset_for_serve(&mut self, serving_side: Side) {
+        self.extra_balls.clear();
+        self.rally_hits = 0;
+        self.stall_ticks = 0;
+        self.physics
+            .serve_ball(&mut self.ball, serving_side, &self.config, &mut self.rng);
+    }
+
+    /// Count a paddle hit and speed `ball` up every `rally_ramp_hits` hits
+    fn advance_rally_ramp(&mut self, ball: &mut Ball) {
+        self.rally_hits = self.rally_hits.saturating_add(1);
+
+        let step = self.config.rally_ramp_hits as u16;
+        if step > 0 && self.rally_hits % step == 0 {
+            let factor = self.config.rally_ramp_speed_up;
+            ball.vel.x = fx::mul_fx(ball.vel.x, factor);
+            ball.vel.y = fx::mul_fx(ball.vel.y, factor);
+        }
+    }
+
+    /// Rescue a ball that stopped making progress: re-serve after too long
+    /// without paddle contact, or nudge a ball crawling horizontally
+    fn rescue_stuck_ball(&mut self) -> Option<Event> {
+        self.stall_ticks = self.stall_ticks.saturating_add(1);
+
+        let timeout = self.config.stuck_timeout_ticks();
+        if timeout > 0 && self.stall_ticks >= timeout {
+            // Serve from the half the ball is stuck in, toward the other player
+            let server = if self.ball.pos.x < self.config.field_center().x {
+                Side::Left
+            } else {
+                Side::Right
+            };
+            self.reset_for_serve(server);
+            return Some(Event::BallReserved { server });
+        }
+
+        let min_vx = self.config.stuck_min_vx;
+        if min_vx > 0 && fx::abs_fx(self.ball.vel.x) < min_vx {
+            // Keep the current heading; a dead stop heads for the far paddle
+            let heading_right = self.ball.vel.x > 0
+                || (self.ball.vel.x == 0 && self.ball.pos.x < self.config.field_center().x);
+            self.ball.vel.x = if heading_right { min_vx } else { -min_vx };
+            return Some(Event::BallNudged);
+        }
+
+        None
+    }
+
+    /// Classify the current tick's tension from match state and rally data
+    pub fn tension(&self) -> Tension {
+        if self.status == Status::Lobby || self.status.is_terminal() {
+            return Tension::default();
+        }
+
+        let rally_live = matches!(self.status, Status::Playing);
+        let match_point_score = self.config.max_score.saturating_sub(1);
+        let drama_speed = self.config.drama_speed as i64;
+        let (vx, vy) = (self.ball.vel.x as i64, self.ball.vel.y as i64);
+
+        Tension {
+            match_point: self.config.max_score > 0
+                && self.score.iter().any(|&s| s >= match_point_score),
+            long_rally: rally_live
+                && self.config.drama_rally_hits > 0
+                && self.rally_hits >= self.config.drama_rally_hits,
+            fast_ball: rally_live
+                && drama_speed > 0
+                && vx * vx + vy * vy > drama_speed * drama_speed,
+        }
+    }
+
+    /// Side serving the pending serve, or None while a rally is live or the
+    /// match is over. A staged serve is read back from the ball's direction.
+    pub fn serving_side(&self) -> Option<Side> {
+        match self.status {
+            Status::Lobby | Status::Countdown(_) => Some(if self.ball.vel.x >= 0 {
+                Side::Left // Left serves toward the right
+            } else {
+                Side::Right
+            }),
+            Status::Scored(scorer, _) => Some(scorer.opposite()),
+            Status::Warmup(_) | Status::Playing | Status::GameOver(_) | Status::Paused => None,
+        }
+    }
+
+    /// Get the current winner (if game is over)
+    pub fn winner(&self) -> Option<Side> {
+        self.status.winner()
+    }
+
+    /// Check if the game is active (accepting inputs)
+    pub fn is_active(&self) -> bool {
+        !self.paused && matches!(self.status, Status::Playing)
+    }
+
+    /// Hold or release the match. Only the view changes: the paused status
+    /// isn't simulated or snapshotted, so peers pausing together stay in sync.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Get a human-readable status string
+    pub fn status_string(&self) -> &'static str {
+        if self.paused {
+            return "Paused";
+        }
+        match self.status {
+            Status::Lobby => "Waiting for players",
+            Status::Warmup(_) => "Warmup - practice rally",
+            Status::Countdown(_) => "Get ready...",
+            Status::Playing => "Playing",
+            Status::Scored(_, _) => "Point scored!",
+            Status::GameOver(_) => "Game over",
+            Status::Paused => "Paused",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game_creation() {
+        let config = Config::default();
+        let game = Game::new(config);
+
+        assert_eq!(game.tick, 0);
+        assert_eq!(game.status, Status::Lobby);
+        assert_eq!(game.score, [0, 0]);
+        assert_eq!(game.ball.pos.x, FX_ONE / 2);
+        assert_eq!(game.ball.pos.y, FX_ONE / 2);
+    }
+
+    #[test]
+    fn test_lobby_to_countdown() {
+        let mut game = Game::new(Config::default());
+
+        // Both players ready
+        let inputs = InputPair::new(0, Input::new(0, 1), Input::new(0, 1));
+        let event = game.step(&inputs);
+
+        assert!(matches!(game.status, Status::Countdown(_)));
+        assert!(event.is_none());
+    }
+
+    #[test]
+    fn test_lobby_to_warmup() {
+        let config = Config {
+            warmup_secs: 2,
+            ..Config::default()
+        };
+        let mut game = Game::new(config);
+
+        let inputs = InputPair::new(0, Input::new(0, 1), Input::new(0, 1));
+        game.step(&inputs);
+
+        assert_eq!(game.status, Status::Warmup(120));
+    }
+
+    #[test]
+    fn test_warmup_does_not_score() {
+        let config = Config {
+            warmup_secs: 2,
+            ..Config::default()
+        };
+        let mut game = Game::new(config);
+        game.status = Status::Warmup(100);
+        game.ball.pos.x = FX_ONE + 1000;
+
+        let inputs = InputPair::new(0, Input::zero(), Input::zero());
+        let event = game.step(&inputs);
+
+        assert!(event.is_none());
+        assert_eq!(game.score, [0, 0]);
+        assert_eq!(game.status, Status::Warmup(99));
+        // Ball was re-served from the center
+        assert_eq!(game.ball.pos.x, FX_ONE / 2);
+    }
+
+    #[test]
+    fn test_warmup_to_countdown() {
+        let mut game = Game::new(Config::default());
+        game.status = Status::Warmup(1);
+
+        let inputs = InputPair::new(0, Input::zero(), Input::zero());
+        game.step(&inputs);
+
+        assert_eq!(game.status, Status::Countdown(180));
+        assert_eq!(game.ball.pos, Vec2::new(FX_ONE / 2, FX_ONE / 2));
+    }
+
+    #[test]
+    fn test_countdown_to_playing() {
+        let mut game = Game::new(Config::default());
+        game.status = Status::Countdown(1);
+
+        let inputs = InputPair::new(0, Input::zero(), Input::zero());
+        let event = game.step(&inputs);
+
+        assert_eq!(game.status, Status::Playing);
+        assert!(event.is_none());
+    }
+
+    #[test]
+    fn test_paddle_movement_during_play() {
+        let mut game = Game::new(Config::default());
+        game.status = Status::Playing;
+
+        let initial_left_y = game.paddles[0].y;
+
+        // Move left paddle up
+        let inputs = InputPair::new(0, Input::new(127, 0), Input::zero());
+        game.step(&inputs);
+
+        assert_ne!(game.paddles[0].y, initial_left_y);
+        assert!(game.paddles[0].vy > 0); // Moving up
+    }
+
+    #[test]
+    fn test_scoring() {
+        let mut game = Game::new(Config::default());
+        game.status = Status::Playing;
+
+        // Move ball past right edge to simulate scoring
+        game.ball.pos.x = FX_ONE + 1000;
+
+        let inputs = InputPair::new(0, Input::zero(), Input::zero());
+        let event = game.step(&inputs);
+
+        // Left player should have scored
+        assert_eq!(game.score[0], 1);
+        assert!(matches!(game.status, Status::Scored(Side::Left, _)));
+
+        if let Some(Event::Scored { scorer, score }) = event {
+            assert_eq!(scorer, Side::Left);
+            assert_eq!(score, [1, 0]);
+        } else {
+            panic!("Expected scoring event");
+        }
+    }
+
+    #[test]
+    fn test_game_over() {
+        let mut game = Game::new(Config::default());
+        game.score[0] = game.config.max_score; // Left player at max score
+        game.status = Status::Scored(Side::Left, 1); // About to transition
+
+        let inputs = InputPair::new(0, Input::zero(), Input::zero());
+        game.step(&inputs);
+
+        assert!(matches!(game.status, Status::GameOver(Side::Left)));
+        assert_eq!(game.winner(), Some(Side::Left));
+    }
+
+    #[test]
+    fn test_serve_after_score() {
+        let mut game = Game::new(Config::default());
+        game.score = [1, 0];
+        game.status = Status::Scored(Side::Left, 1); // Left scored, about to serve
+
+        let inputs = InputPair::new(0, Input::zero(), Input::zero());
+        game.step(&inputs);
+
+        // Should be playing again
+        assert_eq!(game.status, Status::Playing);
+        // Ball should be at center
+        assert_eq!(game.ball.pos.x, FX_ONE / 2);
+        assert_eq!(game.ball.pos.y, FX_ONE / 2);
+        // Ball should be moving (right side serves since left scored)
+        assert!(game.ball.vel.x != 0 || game.ball.vel.y != 0);
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let mut game1 = Game::new(Config::default());
+        game1.tick = 100;
+        game1.score[0] = 3;
+        game1.score[1] = 2;
+        game1.status = Status::Playing;
+
+        let snapshot = game1.snapshot();
+
+        let mut game2 = Game::new(Config::default());
+        game2.restore(&snapshot).unwrap();
+
+        assert_eq!(game1.tick, game2.tick);
+        assert_eq!(game1.status, game2.status);
+        assert_eq!(game1.score, game2.score);
+        assert_eq!(game1.paddles[0].y, game2.paddles[0].y);
+        assert_eq!(game1.paddles[1].y, game2.paddles[1].y);
+        assert_eq!(game1.ball.pos, game2.ball.pos);
+        assert_eq!(game1.ball.vel, game2.ball.vel);
+        assert_eq!(game1.rng, game2.rng);
+    }
+
+    #[test]
+    fn test_restore_rejects_other_config() {
+        let mut game1 = Game::new(Config::default());
+        game1.tick = 40;
+        let snapshot = Snapshot::decode(&game1.snapshot().encode()).unwrap();
+        assert_eq!(snapshot.config_hash, Config::default().fingerprint());
+
+        let other = Config {
+            max_score: 3,
+            ..Config::default()
+        };
+        let mut game2 = Game::new(other);
+        assert_eq!(
+            game2.restore(&snapshot),
+            Err(RestoreError::ConfigMismatch {
+                expected: other.fingerprint(),
+                found: snapshot.config_hash,
+            })
+        );
+        assert_eq!(game2.tick, 0); // Untouched
+
+        // Snapshots without a fingerprint (older peers) are still accepted
+        let legacy = Snapshot {
+            config_hash: 0,
+            ..snapshot
+        };
+        game2.restore(&legacy).unwrap();
+        assert_eq!(game2.tick, 40);
+    }
+
+    #[test]
+    fn test_obstacles_move_and_restore() {
+        let motion = ObstacleMotion::Sine {
+            amplitude: Vec2::new(0, FX_ONE / 4),
+            period: 120,
+        };
+        let obstacle = Obstacle::new(
+            Vec2::new(FX_ONE / 2, FX_ONE / 2),
+            Vec2::new(FX_ONE / 64, FX_ONE / 16),
+            motion,
+        );
+
+        let mut game1 = Game::new(Config::default());
+        game1.obstacles.push(obstacle);
+        game1.status = Status::Playing;
+        for tick in 0..30 {
+            game1.step(&InputPair::new(tick, Input::zero(), Input::zero()));
+        }
+        assert_eq!(game1.obstacles[0].phase, 30);
+        assert_eq!(game1.obstacles[0].pos.y, FX_ONE * 3 / 4); // Quarter cycle
+
+        // Phases travel through the snapshot and rebuild the same state
+        let snapshot = game1.snapshot();
+        assert_eq!(snapshot.obstacle_phases, vec![30]);
+        let mut game2 = Game::new(Config::default());
+        game2.obstacles.push(obstacle);
+        game2
+            .restore(&Snapshot::decode(&snapshot.encode()).unwrap())
+            .unwrap();
+        assert_eq!(game2.obstacles, game1.obstacles);
+
+        // A different layout is refused, even from snapshots without a hash
+        let mut game3 = Game::new(Config::default());
+        assert_eq!(
+            game3.restore(&snapshot),
+            Err(RestoreError::ObstacleMismatch {
+                expected: 0,
+                found: 1
+            })
+        );
+        game3.obstacles.push(Obstacle {
+            origin: Vec2::new(FX_ONE / 4, FX_ONE / 2),
+            ..obstacle
+        });
+        assert!(matches!(
+            game3.restore(&snapshot),
+            Err(RestoreError::ConfigMismatch { .. })
+        ));
+        let legacy = Snapshot {
+            config_hash: 0,
+            ..snapshot.clone()
+        };
+        game3.obstacles.clear();
+        assert!(game3.restore(&legacy).is_err());
+        assert_eq!(game3.tick, 0); // Untouched
+    }
+
+    #[test]
+    fn test_extra_balls_collide_deterministically() {
+        let mut game1 = Game::new(Config::default());
+        game1.status = Status::Playing;
+        game1.ball = Ball::new(
+            Vec2::new(FX_ONE * 2 / 5, FX_ONE / 2),
+            Vec2::new(FX_ONE / 2, 0),
+        );
+        game1.extra_balls.push(Ball::new(
+            Vec2::new(FX_ONE * 3 / 5, FX_ONE / 2),
+            Vec2::new(-FX_ONE / 2, 0),
+        ));
+
+        // A second game picks up mid-flight from a snapshot round trip
+        let mut game2 = Game::new(Config::default());
+        for tick in 0..30 {
+            if tick == 5 {
+                let snapshot = Snapshot::decode(&game1.snapshot().encode()).unwrap();
+                assert_eq!(snapshot.extra_balls, game1.extra_balls);
+                game2.restore(&snapshot).unwrap();
+            }
+            let inputs = InputPair::new(tick, Input::zero(), Input::zero());
+            game1.step(&inputs);
+            if tick >= 5 {
+                game2.step(&inputs);
+            }
+        }
+
+        // Head-on equal-mass balls swap velocities instead of passing through
+        assert!(game1.ball.vel.x < 0);
+        assert!(game1.extra_balls[0].vel.x > 0);
+        assert!(game1.ball.pos.x < game1.extra_balls[0].pos.x);
+        assert_eq!(game1.snapshot().encode(), game2.snapshot().encode());
+
+        // The next serve ends the multi-ball rally
+        game1.reset_for_serve(Side::Left);
+        assert!(game1.extra_balls.is_empty());
+    }
+
+    #[test]
+    fn test_rally_speed_ramp() {
+        let config = Config {
+            rally_ramp_hits: 2,
+            rally_ramp_speed_up: FX_ONE * 2,
+            ..Config::default()
+        };
+        let mut game = Game::new(config);
+        let mut ball = Ball::new(game.ball.pos, Vec2::new(FX_ONE / 4, 0));
+
+        // First hit only counts; second hit applies the ramp
+        game.advance_rally_ramp(&mut ball);
+        assert_eq!(game.rally_hits, 1);
+        assert_eq!(ball.vel.x, FX_ONE / 4);
+        game.advance_rally_ramp(&mut ball);
+        assert_eq!(game.rally_hits, 2);
+        assert_eq!(ball.vel.x, FX_ONE / 2);
+
+        // Ramp state survives a snapshot and resets on serve
+        let mut restored = Game::new(config);
+        restored.restore(&game.snapshot()).unwrap();
+        assert_eq!(restored.rally_hits, 2);
+        game.reset_for_serve(Side::Left);
+        assert_eq!(game.rally_hits, 0);
+    }
+
+    #[test]
+    fn test_stuck_ball_nudged() {
+        let config = Config {
+            stuck_min_vx: FX_ONE / 16,
+            ..Config::default()
+        };
+        let mut game = Game::with_physics(config, NoScorePhysics);
+        game.status = Status::Playing;
+        game.ball.vel = Vec2::new(0, FX_ONE / 2); // Bouncing straight up and down
+        game.ball.pos.x = FX_ONE / 4;
+
+        let event = game.step(&InputPair::new(0, Input::zero(), Input::zero()));
+        assert_eq!(event, Some(Event::BallNudged));
+        assert_eq!(game.ball.vel.x, game.config.stuck_min_vx); // Toward the far paddle
+
+        // Off by default: the same ball is left alone
+        let mut game = Game::with_physics(Config::default(), NoScorePhysics);
+        game.status = Status::Playing;
+        game.ball.vel = Vec2::new(0, FX_ONE / 2);
+        let event = game.step(&InputPair::new(0, Input::zero(), Input::zero()));
+        assert_eq!(event, None);
+        assert_eq!(game.ball.vel.x, 0);
+    }
+
+    #[test]
+    fn test_stuck_ball_reserved_after_timeout() {
+        let config = Config {
+            stuck_timeout_secs: 1,
+            ..Config::default()
+        };
+        let mut game = Game::with_physics(config, NoScorePhysics);
+        game.status = Status::Playing;
+        game.stall_ticks = config.stuck_timeout_ticks() - 1;
+        game.ball.pos = Vec2::new(FX_ONE * 3 / 4, FX_ONE / 4);
+
+        let event = game.step(&InputPair::new(0, Input::zero(), Input::zero()));
+        assert_eq!(
+            event,
+            Some(Event::BallReserved {
+                server: Side::Right
+            })
+        );
+        assert_eq!(game.ball.pos, config.field_center());
+        assert_eq!(game.stall_ticks, 0);
+        assert!(game.ball.vel.x < 0); // Served toward the left player
+    }
+
+    #[test]
+    fn test_trace_matches_across_identical_games() {
+        let mut game1 = Game::new(Config::default());
+        let mut game2 = Game::new(Config::default());
+        game1.enable_trace(16);
+        game2.enable_trace(16);
+
+        for tick in 0..40 {
+            let buttons = if tick == 0 { 1 } else { 0 };
+            let inputs = InputPair::new(tick, Input::new(30, buttons), Input::new(-30, buttons));
+            game1.step(&inputs);
+            game2.step(&inputs);
+        }
+
+        let trace1 = game1.trace().unwrap();
+        assert_eq!(trace1.len(), 16);
+        assert_eq!(trace1.entries().last().unwrap().tick, 39);
+        assert_eq!(trace1.first_divergence(game2.trace().unwrap()), None);
+
+        // A single-unit difference in state shows up on the next tick
+        game2.ball.pos.y += 1;
+        game1.step(&InputPair::new(40, Input::zero(), Input::zero()));
+        game2.step(&InputPair::new(40, Input::zero(), Input::zero()));
+        assert_eq!(
+            game1
+                .trace()
+                .unwrap()
+                .first_divergence(game2.trace().unwrap()),
+            Some(40)
+        );
+
+        // Tracing is opt-in
+        assert!(Game::new(Config::default()).trace().is_none());
+    }
+
+    #[test]
+    fn test_collision_events_recorded() {
+        let mut game = Game::new(Config::default());
+        game.status = Status::Playing;
+
+        // Ball about to hit the bottom wall
+        game.ball.pos = Vec2::new(FX_ONE / 2, 10);
+        game.ball.vel = Vec2::new(FX_ONE / 4, -FX_ONE / 2);
+        let event = game.step(&InputPair::new(0, Input::zero(), Input::zero()));
+
+        assert_eq!(event, None); // Collisions aren't game-state events
+        match game.events() {
+            [Event::WallHit { contact }] => {
+                assert_eq!(contact.point.y, 0);
+                assert_eq!(contact.normal, Vec2::new(0, FX_ONE));
+            }
+            other => panic!("expected a single wall hit, got {other:?}"),
+        }
+
+        // Ball about to hit the left paddle
+        game.ball.pos = Vec2::new(game.config.paddle_x + 200, game.paddles[0].y);
+        game.ball.vel = Vec2::new(-FX_ONE / 2, 0);
+        game.step(&InputPair::new(1, Input::zero(), Input::zero()));
+        assert!(matches!(
+            game.events(),
+            [Event::PaddleHit {
+                side: Side::Left,
+                ..
+            }]
+        ));
+
+        // Quiet tick clears the list
+        game.step(&InputPair::new(2, Input::zero(), Input::zero()));
+        assert!(game.events().is_empty());
+    }
+
+    #[test]
+    fn test_tension_flags() {
+        let mut game = Game::new(Config::default());
+        assert_eq!(game.view().tension, Tension::default()); // Lobby is calm
+
+        game.status = Status::Playing;
+        game.ball.vel = Vec2::new(FX_ONE / 2, 0);
+        assert!(!game.tension().is_high());
+
+        game.score = [game.config.max_score - 1, 3];
+        assert!(game.tension().match_point);
+
+        game.rally_hits = game.config.drama_rally_hits;
+        assert!(game.tension().long_rally);
+
+        game.ball.vel = Vec2::new(game.config.drama_speed, FX_ONE / 4);
+        assert!(game.tension().fast_ball);
+
+        // Rally flags drop between points, match point persists
+        game.status = Status::Scored(Side::Left, 60);
+        let tension = game.view().tension;
+        assert!(tension.match_point);
+        assert!(!tension.long_rally && !tension.fast_ball);
+    }
+
+    #[test]
+    fn test_reset_match() {
+        let mut game = Game::new(Config::default());
+        game.tick = 1000;
+        game.score = [5, 3];
+        game.status = Status::GameOver(Side::Left);
+
+        game.reset_match();
+
+        assert_eq!(game.tick, 0);
+        assert_eq!(game.status, Status::Lobby);
+        assert_eq!(game.score, [0, 0]);
+        assert_eq!(game.paddles[0].y, FX_ONE / 2);
+        assert_eq!(game.paddles[1].y, FX_ONE / 2);
+    }
+
+    #[test]
+    fn test_reset_match_matches_new_game() {
+        let obstacle = Obstacle::new(
+            Vec2::new(FX_ONE / 2, FX_ONE / 2),
+            Vec2::new(FX_ONE / 64, FX_ONE / 16),
+            ObstacleMotion::Sine {
+                amplitude: Vec2::new(0, FX_ONE / 4),
+                period: 120,
+            },
+        );
+        let mut fresh = Game::new(Config::default());
+        fresh.obstacles.push(obstacle);
+
+        // Play a while with tilted, moving paddles and extra balls in flight
+        let mut game = Game::new(Config::default());
+        game.obstacles.push(obstacle);
+        game.status = Status::Playing;
+        game.extra_balls
+            .push(Ball::new(Vec2::new(FX_ONE / 4, FX_ONE / 4), Vec2::zero()));
+        for tick in 0..45 {
+            let input = Input::new(100, 0).with_tilt(60);
+            game.step(&InputPair::new(tick, input, input));
+        }
+        game.score = [4, 7];
+        assert_ne!(game.obstacles, fresh.obstacles);
+        assert_ne!(game.paddles, fresh.paddles);
+
+        game.reset_match();
+        assert_eq!(game.snapshot().encode(), fresh.snapshot().encode());
+        assert_eq!(game.paddles, fresh.paddles); // Tilt isn't in the snapshot
+        assert_eq!(game.obstacles, fresh.obstacles);
+    }
+
+    #[test]
+    fn test_start_next_game() {
+        let mut game = Game::new(Config::default());
+        game.tick = 1000;
+        game.score = [5, 3];
+        game.status = Status::GameOver(Side::Left);
+
+        game.start_next_game(42, Side::Right);
+
+        assert_eq!(game.tick, 1000);
+        assert_eq!(game.status, Status::Countdown(180));
+        assert_eq!(game.score, [0, 0]);
+        assert_eq!(game.serving_side(), Some(Side::Right));
+
+        // The same seed and server give the same serve
+        let mut other = Game::new(Config::default());
+        other.tick = 1000;
+        other.start_next_game(42, Side::Right);
+        assert_eq!(other.state_hash(), game.state_hash());
+    }
+
+    #[test]
+    fn test_view_generation() {
+        let game = Game::new(Config::default());
+        let view = game.view();
+
+        assert_eq!(view.tick, game.tick);
+        assert_eq!(view.status, game.status);
+        assert_eq!(view.left_paddle_y, game.paddles[0].y);
+        assert_eq!(view.right_paddle_y, game.paddles[1].y);
+        assert_eq!(view.paddle_half_h, game.config.paddle_half_h);
+        assert_eq!(view.ball_pos, game.ball.pos);
+        assert_eq!(view.score, game.score);
+        assert_eq!(view.paddle_x_offset, game.config.paddle_x);
+        assert_eq!(view.paddle_width, game.config.paddle_width);
+        assert_eq!(view.ball_radius, game.config.ball_radius);
+    }
+
+    #[test]
+    fn test_view_countdown_and_server() {
+        let mut game = Game::new(Config::default());
+        assert_eq!(game.view().serving_side, Some(Side::Left));
+        assert_eq!(game.view().countdown_ticks_remaining, 0);
+
+        game.status = Status::Countdown(3);
+        game.step(&InputPair::new(0, Input::zero(), Input::zero()));
+        assert_eq!(game.view().countdown_ticks_remaining, 2);
+        assert_eq!(game.view().serving_side, Some(Side::Left));
+
+        game.status = Status::Playing;
+        assert_eq!(game.view().countdown_ticks_remaining, 0);
+        assert_eq!(game.view().serving_side, None);
+
+        // Scored-on side serves next
+        game.status = Status::Scored(Side::Left, 30);
+        assert_eq!(game.view().serving_side, Some(Side::Right));
+        game.reset_for_serve(Side::Right);
+        game.status = Status::Countdown(10);
+        assert_eq!(game.view().serving_side, Some(Side::Right));
+    }
+
+    #[test]
+    fn test_paused_shows_in_view_only() {
+        let mut game = Game::new(Config::default());
+        game.status = Status::Countdown(30);
+        let snapshot = game.snapshot();
+
+        game.set_paused(true);
+        assert_eq!(game.view().status, Status::Paused);
+        assert_eq!(game.status_string(), "Paused");
+        assert!(!game.is_active());
+        assert_eq!(game.snapshot(), snapshot);
+
+        game.set_paused(false);
+        assert_eq!(game.view().status, Status::Countdown(30));
+    }
+
+    #[test]
+    fn test_view_exposes_velocities() {
+        let mut game = Game::new(Config::default());
+        game.status = Status::Playing;
+        game.ball.vel = Vec2::new(FX_ONE / 2, -FX_ONE / 4);
+
+        let inputs = InputPair::new(0, Input::new(127, 0), Input::new(-127, 0));
+        game.step(&inputs);
+        let view = game.view();
+
+        assert_eq!(view.ball_vel, game.ball.vel);
+        assert_eq!(view.left_paddle_vy, game.paddles[0].vy);
+        assert_eq!(view.right_paddle_vy, game.paddles[1].vy);
+        assert!(view.left_paddle_vy > 0);
+        assert!(view.right_paddle_vy < 0);
+    }
+
+    #[test]
+    fn test_deterministic_simulation() {
+        let config = Config::default();
+        let mut game1 = Game::new(config);
+        let mut game2 = Game::new(config);
+
+        // Apply same inputs to both games
+        let test_inputs = [
+            InputPair::new(0, Input::new(0, 1), Input::new(0, 1)), // Ready
+            InputPair::new(1, Input::new(50, 0), Input::new(-30, 0)), // Play
+            InputPair::new(2, Input::new(-20, 0), Input::new(75, 0)), // Play
+        ];
+
+        for inputs in &test_inputs {
+            game1.step(inputs);
+            game2.step(inputs);
+        }
+
+        // Games should be in identical states
+        assert_eq!(game1.tick, game2.tick);
+        assert_eq!(game1.status, game2.status);
+        assert_eq!(game1.paddles, game2.paddles);
+        assert_eq!(game1.ball.pos, game2.ball.pos);
+        assert_eq!(game1.ball.vel, game2.ball.vel);
+        assert_eq!(game1.score, game2.score);
+        assert_eq!(game1.rng, game2.rng);
+    }
+
+    /// Physics rules that delegate to classic physics but never award points
+    struct NoScorePhysics;
+
+    impl PhysicsEngine for NoScorePhysics {
+        fn update_paddle(&self, paddle: &mut Paddle, input: &Input, config: &Config) {
+            ClassicPhysics.update_paddle(paddle, input, config)
+        }
+
+        fn update_ball(&self, ball: &mut Ball, config: &Config) -> Option<CollisionInfo> {
+            ClassicPhysics.update_ball(ball, config)
+        }
+
+        fn check_paddle_collision(
+            &self,
+            ball: &mut Ball,
+            paddle: &Paddle,
+            side: Side,
+            config: &Config,
+        ) -> Option<CollisionInfo> {
+            ClassicPhysics.check_paddle_collision(ball, paddle, side, config)
+        }
+
+        fn check_obstacle_collision(
+            &self,
+            ball: &mut Ball,
+            obstacle: &Obstacle,
+            config: &Config,
+        ) -> Option<CollisionInfo> {
+            ClassicPhysics.check_obstacle_collision(ball, obstacle, config)
+        }
+
+        fn check_scoring(&self, _ball: &Ball, _config: &Config) -> Option<Side> {
+            None
+        }
+
+        fn serve_ball(
+            &self,
+            ball: &mut Ball,
+            serving_side: Side,
+            config: &Config,
+            rng_state: &mut u64,
+        ) {
+            ClassicPhysics.serve_ball(ball, serving_side, config, rng_state)
+        }
+
+        fn apply_bounce_jitter(&self, ball: &mut Ball, config: &Config, rng_state: &mut u64) {
+            ClassicPhysics.apply_bounce_jitter(ball, config, rng_state)
+        }
+
+        fn limit_ball_speed(&self, ball: &mut Ball, max_speed: Fx) {
+            ClassicPhysics.limit_ball_speed(ball, max_speed)
+        }
+
+        fn collide_balls(&self, a: &mut Ball, b: &mut Ball, config: &Config) -> bool {
+            ClassicPhysics.collide_balls(a, b, config)
+        }
+    }
+
+    #[test]
+    fn test_custom_physics_engine() {
+        let mut game = Game::with_physics(Config::default(), NoScorePhysics);
+        game.status = Status::Playing;
+        game.ball.pos.x = FX_ONE + 1000;
+
+        let inputs = InputPair::new(0, Input::zero(), Input::zero());
+        let event = game.step(&inputs);
+
+        assert!(event.is_none());
+        assert_eq!(game.score, [0, 0]);
+        assert_eq!(game.status, Status::Playing);
+    }
+
+    #[test]
+    fn test_substeps_are_deterministic() {
+        let config = Config {
+            physics_substeps: 4,
+            ..Config::default()
+        };
+        let mut game1 = Game::new(config);
+        let mut game2 = Game::new(config);
+        game1.status = Status::Playing;
+        game2.status = Status::Playing;
+
+        for tick in 0..120 {
+            let inputs = InputPair::new(tick, Input::new(40, 0), Input::new(-60, 0));
+            assert_eq!(game1.step(&inputs), game2.step(&inputs));
+        }
+
+        assert_eq!(game1.snapshot(), game2.snapshot());
+    }
+
+    #[test]
+    fn test_is_active() {
+        let mut game = Game::new(Config::default());
+
+        assert!(!game.is_active()); // Lobby
+
+        game.status = Status::Playing;
+        assert!(game.is_active());
+
+        game.status = Status::GameOver(Side::Left);
+        assert!(!game.is_active());
+    }
+
+    #[test]
+    fn test_status_string() {
+        let mut game = Game::new(Config::default());
+
+        assert_eq!(game.status_string(), "Waiting for players");
+
+        game.status = Status::Playing;
+        assert_eq!(game.status_string(), "Playing");
+
+        game.status = Status::GameOver(Side::Left);
+        assert_eq!(game.status_string(), "Game over");
+    }
+}
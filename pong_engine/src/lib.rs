@@ -0,0 +1,293 @@
+//! Pong engine - deterministic simulation, physics and state serialization
+
+pub mod ai;
+#[cfg(feature = "toml")]
+pub mod config_file;
+pub mod game;
+pub mod physics;
+pub mod replay;
+pub mod serialization;
+pub mod types;
+
+pub use game::{Game, RestoreError};
+pub use types::*;
+
+#[cfg(test)]
+mod paddle_height_tests {
+    use super::*;
+
+    #[test]
+    fn test_paddle_height_consistency() {
+        let config = Config::default();
+        let mut game = Game::new(config);
+
+        // Test field dimensions
+        let field_width = 80;
+        let field_height = 24;
+
+        // Create RenderHelper for consistent rendering
+        let render_helper = RenderHelper::new(field_width, field_height, &config);
+
+        // Test paddle at different Y positions
+        let test_positions = [
+            fx::from_f32(0.1),  // Near bottom
+            fx::from_f32(0.25), // Quarter up
+            fx::from_f32(0.5),  // Center
+            fx::from_f32(0.75), // Three quarters up
+            fx::from_f32(0.9),  // Near top
+        ];
+
+        let mut paddle_heights = Vec::new();
+
+        for &pos in &test_positions {
+            // Move left paddle to test position
+            game.paddles[0].y = pos;
+
+            // Get paddle rectangle using RenderHelper
+            let paddle_rect = render_helper.get_paddle_rect(pos, Side::Left);
+
+            // Calculate paddle height in screen coordinates
+            let paddle_height = paddle_rect.bottom - paddle_rect.top + 1;
+            paddle_heights.push(paddle_height);
+
+            println!(
+                "Position: {:.2} -> Paddle height: {} pixels",
+                fx::to_f32(pos),
+                paddle_height
+            );
+        }
+
+        // All paddle heights should be EXACTLY the same with new architecture
+        let first_height = paddle_heights[0];
+        for (i, &height) in paddle_heights.iter().enumerate() {
+            assert_eq!(
+                height,
+                first_height,
+                "Paddle height inconsistency at position {}: expected {}, got {}",
+                fx::to_f32(test_positions[i]),
+                first_height,
+                height
+            );
+        }
+
+        println!(
+            "✓ All paddle heights are perfectly consistent: {} pixels",
+            first_height
+        );
+    }
+
+    #[test]
+    fn test_paddle_boundaries() {
+        let config = Config::default();
+        let mut game = Game::new(config);
+
+        let field_width = 80;
+        let field_height = 24;
+        let render_helper = RenderHelper::new(field_width, field_height, &config);
+
+        // Test paddle at extreme positions to ensure bounds are respected
+        let extreme_positions = [
+            config.paddle_half_h,          // Minimum Y (paddle touching bottom)
+            FX_ONE - config.paddle_half_h, // Maximum Y (paddle touching top)
+        ];
+
+        for &pos in &extreme_positions {
+            game.paddles[0].y = pos;
+            let paddle_rect = render_helper.get_paddle_rect(pos, Side::Left);
+
+            // Paddle should stay within field bounds
+            assert!(paddle_rect.top < field_height);
+            assert!(paddle_rect.bottom < field_height);
+            assert!(paddle_rect.top <= paddle_rect.bottom);
+
+            println!(
+                "Extreme position {:.2} -> top: {}, bottom: {}",
+                fx::to_f32(pos),
+                paddle_rect.top,
+                paddle_rect.bottom
+            );
+        }
+
+        println!("✓ Paddle boundaries are properly respected");
+    }
+
+    #[test]
+    fn test_render_helper_consistency() {
+        let config = Config::default();
+        let mut game = Game::new(config);
+
+        let field_width = 80;
+        let field_height = 24;
+        let render_helper = RenderHelper::new(field_width, field_height, &config);
+
+        // Test with small incremental movements to verify perfect consistency
+        let base_pos = fx::from_f32(0.5);
+        let small_increment = FX_ONE / 100; // 0.01 in fixed-point
+
+        let mut prev_height: Option<usize> = None;
+
+        for i in 0..10 {
+            let pos = base_pos + (small_increment * i);
+            game.paddles[0].y = pos;
+
+            let paddle_rect = render_helper.get_paddle_rect(pos, Side::Left);
+            let height = paddle_rect.bottom - paddle_rect.top + 1;
+
+            if let Some(prev) = prev_height {
+                // Height should be EXACTLY the same with new architecture
+                assert_eq!(
+                    height,
+                    prev,
+                    "Paddle height changed unexpectedly at position {}: {} vs {}",
+                    fx::to_f32(pos),
+                    height,
+                    prev
+                );
+            }
+
+            prev_height = Some(height);
+        }
+
+        println!("✓ RenderHelper provides perfect consistency across all positions");
+    }
+
+    #[test]
+    fn test_render_helper_fixed_dimensions() {
+        let config = Config::default();
+        let render_helper = RenderHelper::new(80, 24, &config);
+
+        // The RenderHelper should report consistent paddle dimensions
+        let expected_height = render_helper.paddle_height_pixels();
+
+        // Test at various positions
+        let test_positions = [
+            fx::from_f32(0.1),
+            fx::from_f32(0.3),
+            fx::from_f32(0.5),
+            fx::from_f32(0.7),
+            fx::from_f32(0.9),
+        ];
+
+        for pos in test_positions {
+            let left_rect = render_helper.get_paddle_rect(pos, Side::Left);
+            let right_rect = render_helper.get_paddle_rect(pos, Side::Right);
+
+            let left_height = left_rect.bottom - left_rect.top + 1;
+            let right_height = right_rect.bottom - right_rect.top + 1;
+
+            assert_eq!(left_height, expected_height);
+            assert_eq!(right_height, expected_height);
+            assert_eq!(left_height, right_height);
+        }
+
+        println!(
+            "✓ RenderHelper maintains fixed paddle dimensions: {} pixels",
+            expected_height
+        );
+    }
+
+    #[test]
+    fn test_render_helper_widescreen_field() {
+        let config = Config {
+            field_width: FX_ONE * 2,
+            ..Config::default()
+        };
+        let render_helper = RenderHelper::new(81, 24, &config);
+
+        // X maps across the full physics width, not just the first unit
+        assert_eq!(render_helper.physics_to_screen_x(0), 0);
+        assert_eq!(render_helper.physics_to_screen_x(FX_ONE), 40);
+        assert_eq!(render_helper.physics_to_screen_x(FX_ONE * 2), 80);
+
+        // The right paddle is drawn at the far edge of the wide field
+        let right_rect = render_helper.get_paddle_rect(FX_ONE / 2, Side::Right);
+        assert!(right_rect.left > 70);
+    }
+
+    #[test]
+    fn test_render_helper_honors_paddle_x() {
+        let config = Config {
+            paddle_x: FX_ONE / 4,
+            ..Config::default()
+        };
+        let render_helper = RenderHelper::new(81, 24, &config);
+
+        let left = render_helper.get_paddle_rect(FX_ONE / 2, Side::Left);
+        let right = render_helper.get_paddle_rect(FX_ONE / 2, Side::Right);
+        assert_eq!((left.left + left.right) / 2, 20); // Center at 25% of 80 columns
+        assert_eq!((right.left + right.right) / 2, 60); // Mirrored from the right edge
+    }
+
+    #[test]
+    fn test_pixel_render_helper_letterbox() {
+        let config = Config::default();
+
+        // A wide viewport letterboxes the square field horizontally
+        let helper = PixelRenderHelper::new(800.0, 600.0, 1.0, &config);
+        assert_eq!(helper.scale(), 600.0);
+        assert_eq!(
+            helper.field_rect(),
+            PixelRect::new(100.0, 0.0, 600.0, 600.0)
+        );
+        assert_eq!(helper.physics_to_pixel(Vec2::zero()), (100.0, 600.0));
+        assert_eq!(
+            helper.physics_to_pixel(Vec2::new(FX_ONE, FX_ONE)),
+            (700.0, 0.0)
+        );
+
+        // DPI scale doubles everything into device pixels
+        let hidpi = PixelRenderHelper::from_u32(800, 600, 2.0, &config);
+        assert_eq!(hidpi.viewport_dimensions(), (1600.0, 1200.0));
+        assert_eq!(hidpi.field_rect().snapped(), (200, 0, 1200, 1200));
+
+        // Paddles sit on their physics centre line
+        let left = helper.get_paddle_rect(FX_ONE / 2, Side::Left);
+        let center_x = left.x + left.width / 2.0;
+        let expected_x = 100.0 + fx::to_f32(config.paddle_x) * 600.0;
+        assert!((center_x - expected_x).abs() < 0.01);
+        assert!((left.y + left.height / 2.0 - 300.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_pixel_render_helper_widescreen_field() {
+        let config = Config {
+            field_width: FX_ONE * 2,
+            ..Config::default()
+        };
+
+        // A tall viewport letterboxes a 2:1 field vertically
+        let helper = PixelRenderHelper::new(400.0, 400.0, 1.0, &config);
+        assert_eq!(
+            helper.field_rect(),
+            PixelRect::new(0.0, 100.0, 400.0, 200.0)
+        );
+        let (x, y, radius) = helper.get_ball_circle(config.field_center());
+        assert_eq!((x, y), (200.0, 200.0));
+        assert!((radius - fx::to_f32(config.ball_radius) * 200.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_ball_position_precise() {
+        let config = Config::default();
+        let render_helper = RenderHelper::new(80, 24, &config);
+
+        // Field center lands between cells
+        let center = render_helper.get_ball_position_precise(Vec2::new(FX_ONE / 2, FX_ONE / 2));
+        assert_eq!(center, Vec2::new(FX_ONE * 79 / 2, FX_ONE * 23 / 2));
+
+        // Corners map exactly, with Y inverted
+        let origin = render_helper.get_ball_position_precise(Vec2::zero());
+        assert_eq!(origin, Vec2::new(0, FX_ONE * 23));
+        let far = render_helper.get_ball_position_precise(Vec2::new(FX_ONE, FX_ONE));
+        assert_eq!(far, Vec2::new(FX_ONE * 79, 0));
+
+        // Rounding the precise position agrees with the whole-cell position
+        for i in 0..50 {
+            let pos = Vec2::new(i * 1321, FX_ONE - i * 977);
+            let precise = render_helper.get_ball_position_precise(pos);
+            let (col, row) = render_helper.get_ball_position(pos);
+            assert!((fx::round_fx(precise.x) / FX_ONE - col as Fx).abs() <= 1);
+            assert!((fx::round_fx(precise.y) / FX_ONE - row as Fx).abs() <= 1);
+        }
+    }
+}
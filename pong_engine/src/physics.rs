@@ -0,0 +1,1152 @@
+//! Deterministic physics engine for Pong.
+
+use crate::types::{fx, *};
+
+/// Pluggable physics rules used by `Game` to simulate a tick.
+///
+/// Implementations must be fully deterministic: given the same state, inputs
+/// and config they have to produce bit-identical results on every platform.
+pub trait PhysicsEngine {
+    /// Update paddle position based on input and constraints
+    fn update_paddle(&self, paddle: &mut Paddle, input: &Input, config: &Config);
+
+    /// Advance the ball by one physics substep and handle wall collisions
+    /// (including end walls outside the goal gap), returning the wall
+    /// contact if the ball bounced
+    fn update_ball(&self, ball: &mut Ball, config: &Config) -> Option<CollisionInfo>;
+
+    /// Check for paddle-ball collision and handle it, returning the contact
+    fn check_paddle_collision(
+        &self,
+        ball: &mut Ball,
+        paddle: &Paddle,
+        side: Side,
+        config: &Config,
+    ) -> Option<CollisionInfo>;
+
+    /// Check for a collision between the ball and a (possibly moving)
+    /// obstacle and handle it, returning the contact
+    fn check_obstacle_collision(
+        &self,
+        ball: &mut Ball,
+        obstacle: &Obstacle,
+        config: &Config,
+    ) -> Option<CollisionInfo>;
+
+    /// Check if ball is out of bounds (scoring condition)
+    fn check_scoring(&self, ball: &Ball, config: &Config) -> Option<Side>;
+
+    /// Reset ball for serve
+    fn serve_ball(&self, ball: &mut Ball, serving_side: Side, config: &Config, rng_state: &mut u64);
+
+    /// Rotate the ball's velocity by a small random angle after a paddle hit,
+    /// drawing from the shared RNG only when jitter is enabled
+    fn apply_bounce_jitter(&self, ball: &mut Ball, config: &Config, rng_state: &mut u64);
+
+    /// Limit ball speed to prevent runaway velocity
+    fn limit_ball_speed(&self, ball: &mut Ball, max_speed: Fx);
+
+    /// Resolve a collision between two balls, returning true if they touched
+    fn collide_balls(&self, a: &mut Ball, b: &mut Ball, config: &Config) -> bool;
+
+    /// Resolve collisions between every pair of balls, returning the hit count.
+    /// Pairs are visited in index order so all peers resolve them identically.
+    /// `Game` calls this each substep while extra balls are in play.
+    fn collide_all_balls(&self, balls: &mut [Ball], config: &Config) -> usize {
+        let mut hits = 0;
+        for j in 1..balls.len() {
+            let (head, tail) = balls.split_at_mut(j);
+            for a in head.iter_mut() {
+                if self.collide_balls(a, &mut tail[0], config) {
+                    hits += 1;
+                }
+            }
+        }
+        hits
+    }
+}
+
+/// The original Pong rules: walls, paddles and a per-hit speed multiplier,
+/// tuned through `Config`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ClassicPhysics;
+
+impl PhysicsEngine for ClassicPhysics {
+    fn update_paddle(&self, paddle: &mut Paddle, input: &Input, config: &Config) {
+        // Convert input axis to velocity with proper fixed-point math
+        let target_velocity = if input.axis_y == 0 {
+            0
+        } else {
+            // Clamp input to prevent -128 overflow and normalize properly
+            let clamped_input = input.axis_y.clamp(-127, 127);
+            let normalized_input = fx::from_ratio(clamped_input as i32, 127);
+            fx::mul_fx(normalized_input, config.paddle_speed)
+        };
+
+        // Ramp toward the target velocity, using the acceleration limit when
+        // speeding up and the deceleration limit when slowing or reversing
+        let speeding_up = target_velocity != 0
+            && (paddle.vy == 0 || (target_velocity > 0) == (paddle.vy > 0))
+            && fx::abs_fx(target_velocity) > fx::abs_fx(paddle.vy);
+        let max_change = if speeding_up {
+            config.paddle_accel
+        } else {
+            config.paddle_decel
+        };
+        paddle.vy = paddle.vy.saturating_add(fx::clamp_fx(
+            target_velocity.saturating_sub(paddle.vy),
+            -max_change,
+            max_change,
+        ));
+
+        // Update position using proper fixed-point division
+        paddle.y = paddle
+            .y
+            .saturating_add(fx::div_fx(paddle.vy, config.tick_hz as i32 * FX_ONE));
+
+        // Constrain paddle to field bounds
+        let half_h = config.paddle_half_h;
+        let min_y = half_h;
+        let max_y = FX_ONE - half_h;
+
+        paddle.y = fx::clamp_fx(paddle.y, min_y, max_y);
+
+        // Stop velocity if we hit bounds
+        if paddle.y <= min_y || paddle.y >= max_y {
+            paddle.vy = 0;
+        }
+
+        // Tilt follows the tilt axis directly, scaled to the configured limit
+        let tilt_input = input.tilt.clamp(-127, 127) as i32;
+        paddle.tilt = fx::mul_fx(fx::from_ratio(tilt_input, 127), config.paddle_max_tilt);
+    }
+
+    fn update_ball(&self, ball: &mut Ball, config: &Config) -> Option<CollisionInfo> {
+        // Integrate one substep (a full tick when substeps == 1)
+        let step_hz = config.substep_hz() * FX_ONE;
+        ball.pos += Vec2::new(
+            fx::div_fx(ball.vel.x, step_hz),
+            fx::div_fx(ball.vel.y, step_hz),
+        );
+
+        // Handle top/bottom wall collisions
+        let mut contact = None;
+        let side_wall = if ball.pos.y <= 0 {
+            Some((0, FX_ONE)) // Bottom wall, normal points up
+        } else if ball.pos.y >= FX_ONE {
+            Some((FX_ONE, -FX_ONE)) // Top wall, normal points down
+        } else {
+            None
+        };
+        if let Some((wall_y, normal_y)) = side_wall {
+            ball.pos.y = wall_y;
+            Self::bounce_off_wall(ball, config);
+            contact = Some(CollisionInfo {
+                point: Vec2::new(ball.pos.x, wall_y),
+                normal: Vec2::new(0, normal_y),
+                impact_offset: 0,
+                new_speed: ball.vel.length(),
+            });
+        }
+
+        // End walls outside the goal gap bounce the ball instead of scoring
+        if !config.in_goal_gap(ball.pos.y) {
+            let end_wall = if ball.pos.x <= 0 && ball.vel.x < 0 {
+                Some((0, FX_ONE)) // Left end wall, normal points right
+            } else if ball.pos.x >= config.width() && ball.vel.x > 0 {
+                Some((config.width(), -FX_ONE)) // Right end wall, normal points left
+            } else {
+                None
+            };
+            if let Some((wall_x, normal_x)) = end_wall {
+                ball.pos.x = wall_x;
+                Self::bounce_off_end_wall(ball, config);
+                contact = Some(CollisionInfo {
+                    point: Vec2::new(wall_x, ball.pos.y),
+                    normal: Vec2::new(normal_x, 0),
+                    impact_offset: 0,
+                    new_speed: ball.vel.length(),
+                });
+            }
+        }
+
+        contact
+    }
+
+    fn check_paddle_collision(
+        &self,
+        ball: &mut Ball,
+        paddle: &Paddle,
+        side: Side,
+        config: &Config,
+    ) -> Option<CollisionInfo> {
+        let paddle_x = config.paddle_center_x(side);
+
+        // Use fixed collision geometry for consistent detection
+        let ball_radius = config.ball_radius;
+        let paddle_half_width = config.paddle_width / 2;
+        let paddle_half_h = config.paddle_half_h;
+
+        // Calculate ball bounds
+        let ball_left = ball.pos.x - ball_radius;
+        let ball_right = ball.pos.x + ball_radius;
+        let ball_top = ball.pos.y - ball_radius;
+        let ball_bottom = ball.pos.y + ball_radius;
+
+        // Calculate paddle bounds (fixed - no ball radius contamination)
+        let paddle_left = paddle_x - paddle_half_width;
+        let paddle_right = paddle_x + paddle_half_width;
+        let paddle_top = paddle.y - paddle_half_h;
+        let paddle_bottom = paddle.y + paddle_half_h;
+
+        // Check for overlap using clean bounds logic
+        if ball_right >= paddle_left
+            && ball_left <= paddle_right
+            && ball_bottom >= paddle_top
+            && ball_top <= paddle_bottom
+        {
+            let moving_toward_paddle = match side {
+                Side::Left => ball.vel.x < 0,
+                Side::Right => ball.vel.x > 0,
+            };
+
+            if moving_toward_paddle {
+                let (face_x, normal_x) = match side {
+                    Side::Left => (paddle_right, FX_ONE),
+                    Side::Right => (paddle_left, -FX_ONE),
+                };
+
+                // Reflect ball velocity off the (possibly tilted) face
+                Self::reflect_off_face(ball, normal_x, paddle.tilt);
+
+                // Position ball outside paddle bounds using correct geometry
+                ball.pos.x = face_x + fx::mul_fx(ball_radius, normal_x);
+
+                // Add paddle velocity influence
+                let velocity_influence = fx::div_fx(paddle.vy, 4 * FX_ONE);
+                ball.vel.y = ball.vel.y.saturating_add(velocity_influence);
+
+                // Apply speed up
+                ball.vel = ball.vel.mul_fx(config.ball_speed_up);
+
+                // Where along the paddle the ball struck, -1 (bottom) to 1 (top)
+                let impact_offset = fx::clamp_fx(
+                    fx::div_fx(ball.pos.y - paddle.y, paddle_half_h),
+                    -FX_ONE,
+                    FX_ONE,
+                );
+
+                return Some(CollisionInfo {
+                    point: Vec2::new(face_x, ball.pos.y),
+                    normal: Vec2::new(normal_x, 0),
+                    impact_offset,
+                    new_speed: ball.vel.length(),
+                });
+            }
+        }
+
+        None
+    }
+
+    fn check_obstacle_collision(
+        &self,
+        ball: &mut Ball,
+        obstacle: &Obstacle,
+        config: &Config,
+    ) -> Option<CollisionInfo> {
+        // Treat the ball as a point against the box grown by its radius
+        let dx = ball.pos.x as i64 - obstacle.pos.x as i64;
+        let dy = ball.pos.y as i64 - obstacle.pos.y as i64;
+        let reach_x = obstacle.half_size.x as i64 + config.ball_radius as i64;
+        let reach_y = obstacle.half_size.y as i64 + config.ball_radius as i64;
+        let pen_x = reach_x - dx.abs();
+        let pen_y = reach_y - dy.abs();
+        if pen_x <= 0 || pen_y <= 0 {
+            return None;
+        }
+
+        // Resolve along the axis of least penetration; ties favour X so
+        // corner hits bounce back toward the paddles
+        let (normal, face_offset) = if pen_x <= pen_y {
+            let sign = if dx >= 0 { FX_ONE } else { -FX_ONE };
+            (Vec2::new(sign, 0), (dy, obstacle.half_size.y))
+        } else {
+            let sign = if dy >= 0 { FX_ONE } else { -FX_ONE };
+            (Vec2::new(0, sign), (dx, obstacle.half_size.x))
+        };
+
+        // Push the ball out onto the grown surface
+        if normal.x != 0 {
+            ball.pos.x = fx::saturate(obstacle.pos.x as i64 + reach_x * normal.x.signum() as i64);
+        } else {
+            ball.pos.y = fx::saturate(obstacle.pos.y as i64 + reach_y * normal.y.signum() as i64);
+        }
+
+        // Reflect the ball's velocity relative to the moving surface, so a
+        // surface moving into the ball drives it away faster
+        let (ball_n, surface_n) = if normal.x != 0 {
+            (ball.vel.x, obstacle.vel.x)
+        } else {
+            (ball.vel.y, obstacle.vel.y)
+        };
+        let sign = if normal.x + normal.y > 0 { 1 } else { -1 };
+        let approach = (ball_n.saturating_sub(surface_n)).saturating_mul(sign);
+        if approach < 0 {
+            let bounced = surface_n.saturating_sub(fx::mul_fx(
+                ball_n.saturating_sub(surface_n),
+                config.wall_restitution,
+            ));
+            if normal.x != 0 {
+                ball.vel.x = bounced;
+            } else {
+                ball.vel.y = bounced;
+            }
+        }
+
+        let (along, half_len) = face_offset;
+        let point = Vec2::new(
+            fx::saturate(ball.pos.x as i64 - fx::mul_fx(config.ball_radius, normal.x) as i64),
+            fx::saturate(ball.pos.y as i64 - fx::mul_fx(config.ball_radius, normal.y) as i64),
+        );
+        Some(CollisionInfo {
+            point,
+            normal,
+            impact_offset: fx::clamp_fx(fx::div_fx(fx::saturate(along), half_len), -FX_ONE, FX_ONE),
+            new_speed: ball.vel.length(),
+        })
+    }
+
+    fn check_scoring(&self, ball: &Ball, config: &Config) -> Option<Side> {
+        if ball.pos.x < 0 {
+            Some(Side::Right) // Right player scored
+        } else if ball.pos.x > config.width() {
+            Some(Side::Left) // Left player scored
+        } else {
+            None
+        }
+    }
+
+    fn serve_ball(
+        &self,
+        ball: &mut Ball,
+        serving_side: Side,
+        config: &Config,
+        rng_state: &mut u64,
+    ) {
+        // Center the ball
+        ball.pos = config.field_center();
+
+        // Generate serve direction with some randomness
+        let base_speed = config.ball_speed;
+
+        // Simple linear congruential generator for deterministic randomness
+        let random_angle = Self::next_random(rng_state) as i32;
+
+        // Convert to Y velocity component (roughly -30° to +30°)
+        let y_vel = (random_angle % (FX_ONE / 2)) - (FX_ONE / 4);
+
+        // X velocity based on serving side
+        let x_vel = match serving_side {
+            Side::Left => base_speed,   // Serve to right
+            Side::Right => -base_speed, // Serve to left
+        };
+
+        ball.vel = Vec2::new(x_vel, y_vel);
+    }
+
+    fn apply_bounce_jitter(&self, ball: &mut Ball, config: &Config, rng_state: &mut u64) {
+        let max_angle = config.bounce_jitter.clamp(0, FX_ONE / 8);
+        if max_angle == 0 {
+            return;
+        }
+
+        // Uniform angle in [-max_angle, max_angle]
+        let span = (2 * max_angle + 1) as u64;
+        let angle = (Self::next_random(rng_state) % span) as Fx - max_angle;
+
+        let sin = fx::sin_turns(angle);
+        let cos = fx::sin_turns(angle + FX_ONE / 4);
+        let vel = ball.vel;
+        ball.vel = Vec2::new(
+            fx::mul_fx(vel.x, cos).saturating_sub(fx::mul_fx(vel.y, sin)),
+            fx::mul_fx(vel.x, sin).saturating_add(fx::mul_fx(vel.y, cos)),
+        );
+    }
+
+    fn limit_ball_speed(&self, ball: &mut Ball, max_speed: Fx) {
+        // Compare squared magnitudes exactly in i64 (32.32 fixed point)
+        let speed_squared = ball.vel.length_sq();
+        let max_speed_squared = Vec2::new(max_speed, 0).length_sq();
+
+        if speed_squared > max_speed_squared {
+            // Calculate current speed with zero-division protection
+            let current_speed = Self::isqrt_fx(speed_squared);
+
+            if current_speed > 0 {
+                let scale = fx::div_fx(max_speed, current_speed);
+                ball.vel = ball.vel.mul_fx(scale);
+            } else {
+                // Fallback: if speed calculation failed, clamp directly
+                ball.vel.x = ball.vel.x.clamp(-max_speed, max_speed);
+                ball.vel.y = ball.vel.y.clamp(-max_speed, max_speed);
+            }
+        }
+    }
+
+    fn collide_balls(&self, a: &mut Ball, b: &mut Ball, config: &Config) -> bool {
+        let min_dist = 2 * config.ball_radius;
+
+        // Collision normal from a to b, in i64 to keep squared terms exact
+        let nx = b.pos.x as i64 - a.pos.x as i64;
+        let ny = b.pos.y as i64 - a.pos.y as i64;
+        let dist_sq = Self::length_sq(nx, ny);
+        let min_dist_sq = Self::length_sq(min_dist as i64, 0);
+
+        if dist_sq >= min_dist_sq {
+            return false;
+        }
+
+        // Coincident centers have no normal - separate along X deterministically
+        if dist_sq == 0 {
+            a.pos.x = a.pos.x.saturating_sub(config.ball_radius);
+            b.pos.x = b.pos.x.saturating_add(config.ball_radius);
+            std::mem::swap(&mut a.vel.x, &mut b.vel.x);
+            return true;
+        }
+
+        // Equal-mass elastic collision: exchange the velocity components along
+        // the normal, but only if the balls are approaching each other
+        let rel_vx = a.vel.x as i128 - b.vel.x as i128;
+        let rel_vy = a.vel.y as i128 - b.vel.y as i128;
+        let approach = rel_vx * nx as i128 + rel_vy * ny as i128;
+        if approach > 0 {
+            let impulse_x = fx::saturate((approach * nx as i128 / dist_sq as i128) as i64);
+            let impulse_y = fx::saturate((approach * ny as i128 / dist_sq as i128) as i64);
+            a.vel.x = a.vel.x.saturating_sub(impulse_x);
+            a.vel.y = a.vel.y.saturating_sub(impulse_y);
+            b.vel.x = b.vel.x.saturating_add(impulse_x);
+            b.vel.y = b.vel.y.saturating_add(impulse_y);
+        }
+
+        // Push the balls apart so they no longer overlap
+        let dist = Self::isqrt_fx(dist_sq).max(1);
+        let half_overlap = (min_dist - dist) / 2 + 1;
+        let push_x = ((half_overlap as i64 * nx) / dist as i64) as Fx;
+        let push_y = ((half_overlap as i64 * ny) / dist as i64) as Fx;
+        a.pos.x = a.pos.x.saturating_sub(push_x);
+        a.pos.y = a.pos.y.saturating_sub(push_y);
+        b.pos.x = b.pos.x.saturating_add(push_x);
+        b.pos.y = b.pos.y.saturating_add(push_y);
+
+        true
+    }
+}
+
+impl ClassicPhysics {
+    /// Advance the shared LCG and return its upper bits
+    fn next_random(rng_state: &mut u64) -> u64 {
+        *rng_state = rng_state.wrapping_mul(1103515245).wrapping_add(12345);
+        *rng_state >> 16
+    }
+
+    /// Squared length of a vector in raw 32.32 units (no rounding shift),
+    /// saturating at `i64::MAX` for vectors longer than ~2^31.5
+    fn length_sq(x: i64, y: i64) -> i64 {
+        x.saturating_mul(x).saturating_add(y.saturating_mul(y))
+    }
+
+    /// Reflect the ball off a top/bottom wall, applying restitution to the
+    /// normal (Y) component and friction to the tangential (X) component
+    fn bounce_off_wall(ball: &mut Ball, config: &Config) {
+        ball.vel.y = fx::mul_fx(ball.vel.y, config.wall_restitution).saturating_neg(); // Reverse Y velocity
+        ball.vel.x = ball
+            .vel
+            .x
+            .saturating_sub(fx::mul_fx(ball.vel.x, config.wall_friction));
+    }
+
+    /// Reflect the ball off a paddle face whose flat normal is
+    /// `(normal_x, 0)`, rotated by `tilt` turns toward +Y
+    fn reflect_off_face(ball: &mut Ball, normal_x: Fx, tilt: Fx) {
+        let flat = ball.vel.x.saturating_neg();
+        if tilt == 0 {
+            ball.vel.x = flat;
+            return;
+        }
+
+        // v' = v - 2 (v . n) n with n = (normal_x * cos, sin)
+        let sin = fx::sin_turns(tilt);
+        let cos = fx::sin_turns(tilt + FX_ONE / 4);
+        let n = Vec2::new(fx::mul_fx(normal_x, cos), sin);
+        let two_dot = ball.vel.dot(n).saturating_mul(2);
+        let reflected = ball.vel - n.mul_fx(two_dot);
+
+        // A grazing hit on a steep tilt could still point into the paddle;
+        // fall back to the flat bounce so the ball always leaves the face
+        if (reflected.x > 0) == (normal_x > 0) && reflected.x != 0 {
+            ball.vel = reflected;
+        } else {
+            ball.vel.x = flat;
+        }
+    }
+
+    /// Reflect the ball off a left/right end wall; the same restitution and
+    /// friction apply with the axes swapped
+    fn bounce_off_end_wall(ball: &mut Ball, config: &Config) {
+        ball.vel.x = fx::mul_fx(ball.vel.x, config.wall_restitution).saturating_neg(); // Reverse X velocity
+        ball.vel.y = ball
+            .vel
+            .y
+            .saturating_sub(fx::mul_fx(ball.vel.y, config.wall_friction));
+    }
+
+    /// Exact square root of a raw 32.32 squared magnitude, as a 16.16 `Fx`.
+    /// The result is the floor of the true root, saturating at `Fx::MAX`.
+    fn isqrt_fx(squared: i64) -> Fx {
+        if squared <= 0 {
+            return 0;
+        }
+        (squared as u64).isqrt().min(Fx::MAX as u64) as Fx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paddle_movement() {
+        let config = Config::default();
+        let mut paddle = Paddle::new(FX_ONE / 2);
+
+        // Test upward movement
+        let input_up = Input::new(127, 0); // Maximum up
+        ClassicPhysics.update_paddle(&mut paddle, &input_up, &config);
+
+        assert!(paddle.vy > 0); // Should have positive velocity
+
+        // Test downward movement
+        let input_down = Input::new(-127, 0); // Maximum down
+        ClassicPhysics.update_paddle(&mut paddle, &input_down, &config);
+
+        assert!(paddle.vy < 0); // Should have negative velocity
+
+        // Test no input
+        let input_none = Input::zero();
+        ClassicPhysics.update_paddle(&mut paddle, &input_none, &config);
+
+        assert_eq!(paddle.vy, 0); // Should have zero velocity
+    }
+
+    #[test]
+    fn test_paddle_inertia() {
+        let config = Config {
+            paddle_accel: FX_ONE / 2,
+            paddle_decel: FX_ONE,
+            ..Config::default()
+        };
+        let mut paddle = Paddle::new(FX_ONE / 2);
+
+        // Ramp up over several ticks instead of jumping to full speed
+        let input_up = Input::new(127, 0);
+        ClassicPhysics.update_paddle(&mut paddle, &input_up, &config);
+        assert_eq!(paddle.vy, FX_ONE / 2);
+        ClassicPhysics.update_paddle(&mut paddle, &input_up, &config);
+        assert_eq!(paddle.vy, FX_ONE);
+
+        // Coast down at the deceleration rate once input is released
+        let input_none = Input::zero();
+        ClassicPhysics.update_paddle(&mut paddle, &input_none, &config);
+        assert_eq!(paddle.vy, 0);
+    }
+
+    #[test]
+    fn test_paddle_analog_target_speed() {
+        let config = Config {
+            paddle_accel: FX_ONE / 4,
+            paddle_decel: FX_ONE / 4,
+            ..Config::default()
+        };
+        let mut paddle = Paddle::new(FX_ONE / 4);
+
+        // Half deflection settles at roughly half the paddle speed
+        let half_input = Input::new(64, 0);
+        for _ in 0..20 {
+            ClassicPhysics.update_paddle(&mut paddle, &half_input, &config);
+        }
+        let half_speed = config.paddle_speed / 2;
+        assert!(fx::abs_fx(paddle.vy - half_speed) < FX_ONE / 50);
+    }
+
+    #[test]
+    fn test_paddle_bounds() {
+        let config = Config::default();
+        let mut paddle = Paddle::new(0); // Start at bottom
+
+        // Try to move below bottom
+        let input_down = Input::new(-127, 0);
+        ClassicPhysics.update_paddle(&mut paddle, &input_down, &config);
+
+        assert!(paddle.y >= config.paddle_half_h); // Should be constrained
+
+        // Test top bound
+        paddle.y = FX_ONE; // Start at top
+        let input_up = Input::new(127, 0);
+        ClassicPhysics.update_paddle(&mut paddle, &input_up, &config);
+
+        assert!(paddle.y <= FX_ONE - config.paddle_half_h); // Should be constrained
+    }
+
+    #[test]
+    fn test_ball_wall_collision() {
+        let config = Config::default();
+        let mut ball = Ball::new(
+            Vec2::new(FX_ONE / 2, 0),  // At bottom wall
+            Vec2::new(0, -FX_ONE / 4), // Moving down
+        );
+
+        let info = ClassicPhysics.update_ball(&mut ball, &config);
+
+        assert_eq!(ball.pos.y, 0); // Should be at wall
+        assert!(ball.vel.y > 0); // Velocity should reverse
+
+        let info = info.expect("wall bounce should report a collision");
+        assert_eq!(info.point, Vec2::new(FX_ONE / 2, 0));
+        assert_eq!(info.normal, Vec2::new(0, FX_ONE));
+        assert_eq!(info.impact_offset, 0);
+    }
+
+    #[test]
+    fn test_wall_restitution_and_friction() {
+        let config = Config {
+            wall_restitution: FX_ONE / 2,
+            wall_friction: FX_ONE / 4,
+            ..Config::default()
+        };
+        let mut ball = Ball::new(Vec2::new(FX_ONE / 2, 0), Vec2::new(FX_ONE / 2, -FX_ONE / 4));
+
+        ClassicPhysics.update_ball(&mut ball, &config);
+
+        assert_eq!(ball.pos.y, 0);
+        assert_eq!(ball.vel.y, FX_ONE / 8); // Half the Y speed, reversed
+        assert_eq!(ball.vel.x, FX_ONE * 3 / 8); // Lost a quarter of X speed
+    }
+
+    #[test]
+    fn test_default_walls_are_perfectly_elastic() {
+        let config = Config::default();
+        let mut ball = Ball::new(
+            Vec2::new(FX_ONE / 2, FX_ONE),
+            Vec2::new(FX_ONE / 3, FX_ONE / 7),
+        );
+
+        ClassicPhysics.update_ball(&mut ball, &config);
+
+        assert_eq!(ball.vel.y, -(FX_ONE / 7));
+        assert_eq!(ball.vel.x, FX_ONE / 3);
+    }
+
+    #[test]
+    fn test_ball_substeps_cover_same_distance() {
+        let single = Config::default();
+        let quad = Config {
+            physics_substeps: 4,
+            ..Config::default()
+        };
+        let start = Ball::new(
+            Vec2::new(FX_ONE / 2, FX_ONE / 2),
+            Vec2::new(FX_ONE / 2, FX_ONE / 4),
+        );
+
+        let mut ball_single = start;
+        ClassicPhysics.update_ball(&mut ball_single, &single);
+
+        let mut ball_quad = start;
+        for _ in 0..quad.substeps() {
+            ClassicPhysics.update_ball(&mut ball_quad, &quad);
+        }
+
+        // Four quarter steps should land within rounding of one full step
+        assert!(fx::abs_fx(ball_single.pos.x - ball_quad.pos.x) <= 4);
+        assert!(fx::abs_fx(ball_single.pos.y - ball_quad.pos.y) <= 4);
+    }
+
+    #[test]
+    fn test_zero_substeps_treated_as_one() {
+        let config = Config {
+            physics_substeps: 0,
+            ..Config::default()
+        };
+        assert_eq!(config.substeps(), 1);
+        assert_eq!(config.substep_hz(), config.tick_hz as i32);
+    }
+
+    #[test]
+    fn test_scoring_detection() {
+        let config = Config::default();
+
+        // Ball past left edge
+        let ball_left = Ball::new(Vec2::new(-FX_ONE / 4, FX_ONE / 2), Vec2::zero());
+        assert_eq!(
+            ClassicPhysics.check_scoring(&ball_left, &config),
+            Some(Side::Right)
+        );
+
+        // Ball past right edge
+        let ball_right = Ball::new(Vec2::new(FX_ONE + FX_ONE / 4, FX_ONE / 2), Vec2::zero());
+        assert_eq!(
+            ClassicPhysics.check_scoring(&ball_right, &config),
+            Some(Side::Left)
+        );
+
+        // Ball in bounds
+        let ball_center = Ball::new(Vec2::new(FX_ONE / 2, FX_ONE / 2), Vec2::zero());
+        assert_eq!(ClassicPhysics.check_scoring(&ball_center, &config), None);
+    }
+
+    #[test]
+    fn test_goal_gap_bounces_outside_gap() {
+        let config = Config {
+            goal_gap: FX_ONE / 4, // Gap spans y in [0.375, 0.625]
+            ..Config::default()
+        };
+        assert!(config.in_goal_gap(FX_ONE / 2));
+        assert!(!config.in_goal_gap(FX_ONE / 8));
+
+        // Low on the left edge: hits the end wall and comes back
+        let mut ball = Ball::new(Vec2::new(0, FX_ONE / 8), Vec2::new(-FX_ONE / 2, 0));
+        let info = ClassicPhysics
+            .update_ball(&mut ball, &config)
+            .expect("ball should bounce off the end wall");
+        assert_eq!(ball.pos.x, 0);
+        assert_eq!(ball.vel.x, FX_ONE / 2);
+        assert_eq!(info.normal, Vec2::new(FX_ONE, 0));
+        assert_eq!(ClassicPhysics.check_scoring(&ball, &config), None);
+
+        // Through the gap on the right edge: no bounce, the point counts
+        let mut ball = Ball::new(Vec2::new(FX_ONE, FX_ONE / 2), Vec2::new(FX_ONE / 2, 0));
+        assert_eq!(ClassicPhysics.update_ball(&mut ball, &config), None);
+        assert_eq!(
+            ClassicPhysics.check_scoring(&ball, &config),
+            Some(Side::Left)
+        );
+    }
+
+    #[test]
+    fn test_obstacle_collision() {
+        let config = Config::default();
+        let obstacle = Obstacle::new(
+            Vec2::new(FX_ONE / 2, FX_ONE / 2),
+            Vec2::new(FX_ONE / 32, FX_ONE / 8),
+            ObstacleMotion::Static,
+        );
+
+        // Ball approaching the left face is reflected back
+        let touch_x = FX_ONE / 2 - FX_ONE / 32 - config.ball_radius + 4;
+        let mut ball = Ball::new(Vec2::new(touch_x, FX_ONE / 2), Vec2::new(FX_ONE / 2, 0));
+        let info = ClassicPhysics
+            .check_obstacle_collision(&mut ball, &obstacle, &config)
+            .expect("ball should hit the obstacle");
+        assert_eq!(ball.vel.x, -FX_ONE / 2);
+        assert_eq!(ball.pos.x, FX_ONE / 2 - FX_ONE / 32 - config.ball_radius);
+        assert_eq!(info.normal, Vec2::new(-FX_ONE, 0));
+        assert_eq!(info.point, Vec2::new(FX_ONE / 2 - FX_ONE / 32, FX_ONE / 2));
+        assert_eq!(info.impact_offset, 0);
+
+        // Far away: no contact
+        let mut ball = Ball::new(Vec2::new(FX_ONE / 8, FX_ONE / 8), Vec2::new(FX_ONE / 2, 0));
+        assert_eq!(
+            ClassicPhysics.check_obstacle_collision(&mut ball, &obstacle, &config),
+            None
+        );
+    }
+
+    #[test]
+    fn test_moving_obstacle_pushes_ball() {
+        let config = Config::default();
+        let mut obstacle = Obstacle::new(
+            Vec2::new(FX_ONE / 2, FX_ONE / 2),
+            Vec2::new(FX_ONE / 32, FX_ONE / 8),
+            ObstacleMotion::Static,
+        );
+        obstacle.vel = Vec2::new(FX_ONE, 0); // Surface moving right, into the ball
+
+        // Ball resting just inside the right face gets carried away
+        let touch_x = FX_ONE / 2 + FX_ONE / 32 + config.ball_radius - 4;
+        let mut ball = Ball::new(Vec2::new(touch_x, FX_ONE / 2), Vec2::zero());
+        let info = ClassicPhysics
+            .check_obstacle_collision(&mut ball, &obstacle, &config)
+            .expect("moving obstacle should hit the ball");
+        assert_eq!(info.normal, Vec2::new(FX_ONE, 0));
+        assert_eq!(ball.vel.x, FX_ONE * 2); // Reflected off the moving frame
+    }
+
+    #[test]
+    fn test_widescreen_field() {
+        let config = Config {
+            field_width: FX_ONE * 16 / 9,
+            ..Config::default()
+        };
+
+        // Past x = 1.0 is still in play on a wider field
+        let ball = Ball::new(Vec2::new(FX_ONE + FX_ONE / 4, FX_ONE / 2), Vec2::zero());
+        assert_eq!(ClassicPhysics.check_scoring(&ball, &config), None);
+        let ball = Ball::new(Vec2::new(config.field_width + 1, FX_ONE / 2), Vec2::zero());
+        assert_eq!(
+            ClassicPhysics.check_scoring(&ball, &config),
+            Some(Side::Left)
+        );
+
+        // Serves start from the center of the wide field
+        let mut ball = Ball::new(Vec2::zero(), Vec2::zero());
+        let mut rng = config.seed;
+        ClassicPhysics.serve_ball(&mut ball, Side::Left, &config, &mut rng);
+        assert_eq!(ball.pos, Vec2::new(config.field_width / 2, FX_ONE / 2));
+
+        // The right paddle sits near the far edge
+        let paddle = Paddle::new(FX_ONE / 2);
+        let mut ball = Ball::new(
+            Vec2::new(config.field_width - config.paddle_x, FX_ONE / 2),
+            Vec2::new(FX_ONE / 4, 0),
+        );
+        let hit = ClassicPhysics.check_paddle_collision(&mut ball, &paddle, Side::Right, &config);
+        assert!(hit.is_some());
+        assert!(ball.vel.x < 0);
+    }
+
+    #[test]
+    fn test_serve_ball() {
+        let config = Config::default();
+        let mut ball = Ball::new(Vec2::zero(), Vec2::zero());
+        let mut rng = 12345u64;
+
+        ClassicPhysics.serve_ball(&mut ball, Side::Left, &config, &mut rng);
+
+        // Ball should be centered
+        assert_eq!(ball.pos.x, FX_ONE / 2);
+        assert_eq!(ball.pos.y, FX_ONE / 2);
+
+        // Should have rightward velocity when left serves
+        assert!(ball.vel.x > 0);
+
+        // Test right serve
+        ClassicPhysics.serve_ball(&mut ball, Side::Right, &config, &mut rng);
+        assert!(ball.vel.x < 0); // Should have leftward velocity
+    }
+
+    #[test]
+    fn test_ball_speed_limiting() {
+        let max_speed = FX_ONE * 2; // 2.0 units/s
+
+        let mut ball = Ball::new(
+            Vec2::zero(),
+            Vec2::new(FX_ONE * 4, FX_ONE * 4), // Very fast
+        );
+
+        ClassicPhysics.limit_ball_speed(&mut ball, max_speed);
+
+        // Speed should be reduced
+        let final_speed_sq =
+            fx::mul_fx(ball.vel.x, ball.vel.x) + fx::mul_fx(ball.vel.y, ball.vel.y);
+        let max_speed_sq = fx::mul_fx(max_speed, max_speed);
+
+        assert!(final_speed_sq <= max_speed_sq + 1000); // Allow small tolerance
+    }
+
+    #[test]
+    fn test_paddle_collision() {
+        let config = Config::default();
+        let paddle = Paddle::new(FX_ONE / 2); // Center paddle
+
+        let mut ball = Ball::new(
+            Vec2::new(config.paddle_x, FX_ONE / 2), // At paddle position
+            Vec2::new(-FX_ONE / 4, 0),              // Moving toward left paddle
+        );
+
+        let hit = ClassicPhysics.check_paddle_collision(&mut ball, &paddle, Side::Left, &config);
+
+        assert!(hit.is_some()); // Should detect collision
+        assert!(ball.vel.x > 0); // Ball should reverse direction
+    }
+
+    #[test]
+    fn test_long_rally_speed_up_saturates() {
+        let config = Config::default();
+        let paddle = Paddle::new(FX_ONE / 2);
+
+        // Keep returning a near-limit ball from the right paddle; the
+        // repeated speed-up must clamp at the i32 range, never flip sign
+        let mut ball = Ball::new(Vec2::zero(), Vec2::new(Fx::MAX / 2, 0));
+        for _ in 0..64 {
+            ball.pos = Vec2::new(FX_ONE - config.paddle_x, FX_ONE / 2);
+            ball.vel.x = ball.vel.x.saturating_abs();
+            let hit =
+                ClassicPhysics.check_paddle_collision(&mut ball, &paddle, Side::Right, &config);
+            assert!(hit.is_some());
+            assert!(
+                ball.vel.x < 0,
+                "reflected velocity wrapped to {}",
+                ball.vel.x
+            );
+        }
+        assert_eq!(ball.vel.x, Fx::MIN);
+
+        // The speed limiter still handles the saturated extreme
+        ClassicPhysics.limit_ball_speed(&mut ball, config.ball_speed);
+        assert!(ball.vel.length() <= config.ball_speed);
+    }
+
+    #[test]
+    fn test_paddle_tilt_from_input() {
+        let config = Config::default();
+        let mut paddle = Paddle::new(FX_ONE / 2);
+
+        ClassicPhysics.update_paddle(&mut paddle, &Input::zero().with_tilt(127), &config);
+        assert_eq!(paddle.tilt, config.paddle_max_tilt);
+
+        ClassicPhysics.update_paddle(&mut paddle, &Input::zero().with_tilt(-127), &config);
+        assert_eq!(paddle.tilt, -config.paddle_max_tilt);
+
+        ClassicPhysics.update_paddle(&mut paddle, &Input::zero(), &config);
+        assert_eq!(paddle.tilt, 0);
+    }
+
+    #[test]
+    fn test_tilted_paddle_deflects_ball() {
+        let config = Config::default();
+        let mut paddle = Paddle::new(FX_ONE / 2);
+        paddle.tilt = FX_ONE / 36; // 10 degrees, face angled upward
+
+        // A flat incoming ball leaves with upward spin off the tilted face
+        let mut ball = Ball::new(
+            Vec2::new(config.paddle_x, FX_ONE / 2),
+            Vec2::new(-FX_ONE / 4, 0),
+        );
+        let hit = ClassicPhysics.check_paddle_collision(&mut ball, &paddle, Side::Left, &config);
+        assert!(hit.is_some());
+        assert!(ball.vel.x > 0);
+        assert!(ball.vel.y > 0);
+
+        // Mirrored on the right paddle: still deflected toward +Y
+        let mut ball = Ball::new(
+            Vec2::new(FX_ONE - config.paddle_x, FX_ONE / 2),
+            Vec2::new(FX_ONE / 4, 0),
+        );
+        let hit = ClassicPhysics.check_paddle_collision(&mut ball, &paddle, Side::Right, &config);
+        assert!(hit.is_some());
+        assert!(ball.vel.x < 0);
+        assert!(ball.vel.y > 0);
+    }
+
+    #[test]
+    fn test_bounce_jitter() {
+        let ball = Ball::new(Vec2::new(FX_ONE / 2, FX_ONE / 2), Vec2::new(FX_ONE / 2, 0));
+
+        // Disabled jitter leaves the ball and the RNG untouched
+        let config = Config::default();
+        let mut jittered = ball;
+        let mut rng = 42u64;
+        ClassicPhysics.apply_bounce_jitter(&mut jittered, &config, &mut rng);
+        assert_eq!(jittered, ball);
+        assert_eq!(rng, 42);
+
+        // Enabled jitter perturbs the angle deterministically
+        let config = Config {
+            bounce_jitter: FX_ONE / 72, // Up to 5 degrees
+            ..Config::default()
+        };
+        let mut a = ball;
+        let mut b = ball;
+        let (mut rng_a, mut rng_b) = (42u64, 42u64);
+        for _ in 0..8 {
+            ClassicPhysics.apply_bounce_jitter(&mut a, &config, &mut rng_a);
+            ClassicPhysics.apply_bounce_jitter(&mut b, &config, &mut rng_b);
+        }
+        assert_eq!(a, b);
+        assert_ne!(rng_a, 42);
+        assert_ne!(a.vel.y, 0);
+        assert!(a.vel.x > 0); // Still heading the same way
+    }
+
+    #[test]
+    fn test_paddle_collision_info() {
+        let config = Config::default();
+        let paddle = Paddle::new(FX_ONE / 2);
+        let hit_y = FX_ONE / 2 + config.paddle_half_h / 2; // Upper half of paddle
+
+        let mut ball = Ball::new(
+            Vec2::new(FX_ONE - config.paddle_x, hit_y),
+            Vec2::new(FX_ONE / 4, 0),
+        );
+
+        let info = ClassicPhysics
+            .check_paddle_collision(&mut ball, &paddle, Side::Right, &config)
+            .expect("ball should hit the right paddle");
+
+        assert_eq!(info.normal, Vec2::new(-FX_ONE, 0));
+        assert_eq!(
+            info.point.x,
+            FX_ONE - config.paddle_x - config.paddle_width / 2
+        );
+        assert_eq!(info.point.y, hit_y);
+        assert_eq!(info.impact_offset, FX_ONE / 2);
+        let expected_speed = fx::mul_fx(FX_ONE / 4, config.ball_speed_up);
+        assert!(fx::abs_fx(info.new_speed - expected_speed) < 16);
+    }
+
+    #[test]
+    fn test_paddle_miss_returns_none() {
+        let config = Config::default();
+        let paddle = Paddle::new(FX_ONE / 2);
+        let mut ball = Ball::new(Vec2::new(FX_ONE / 2, FX_ONE / 2), Vec2::new(-FX_ONE / 4, 0));
+
+        assert_eq!(
+            ClassicPhysics.check_paddle_collision(&mut ball, &paddle, Side::Left, &config),
+            None
+        );
+    }
+
+    #[test]
+    fn test_head_on_ball_collision_swaps_velocities() {
+        let config = Config::default();
+        let r = config.ball_radius;
+        let mut a = Ball::new(
+            Vec2::new(FX_ONE / 2 - r / 2, FX_ONE / 2),
+            Vec2::new(FX_ONE / 4, 0),
+        );
+        let mut b = Ball::new(
+            Vec2::new(FX_ONE / 2 + r / 2, FX_ONE / 2),
+            Vec2::new(-FX_ONE / 8, 0),
+        );
+
+        assert!(ClassicPhysics.collide_balls(&mut a, &mut b, &config));
+
+        assert_eq!(a.vel, Vec2::new(-FX_ONE / 8, 0));
+        assert_eq!(b.vel, Vec2::new(FX_ONE / 4, 0));
+        // Balls no longer overlap
+        assert!(b.pos.x - a.pos.x >= 2 * r);
+    }
+
+    #[test]
+    fn test_separating_balls_keep_velocity() {
+        let config = Config::default();
+        let mut a = Ball::new(Vec2::new(FX_ONE / 2, FX_ONE / 2), Vec2::new(-FX_ONE / 4, 0));
+        let mut b = Ball::new(
+            Vec2::new(FX_ONE / 2 + config.ball_radius, FX_ONE / 2),
+            Vec2::new(FX_ONE / 4, 0),
+        );
+
+        assert!(ClassicPhysics.collide_balls(&mut a, &mut b, &config));
+        assert_eq!(a.vel.x, -FX_ONE / 4);
+        assert_eq!(b.vel.x, FX_ONE / 4);
+    }
+
+    #[test]
+    fn test_distant_balls_do_not_collide() {
+        let config = Config::default();
+        let mut a = Ball::new(Vec2::new(FX_ONE / 4, FX_ONE / 2), Vec2::zero());
+        let mut b = Ball::new(Vec2::new(FX_ONE * 3 / 4, FX_ONE / 2), Vec2::zero());
+
+        assert!(!ClassicPhysics.collide_balls(&mut a, &mut b, &config));
+    }
+
+    #[test]
+    fn test_multi_ball_collisions_deterministic() {
+        let config = Config::default();
+        let r = config.ball_radius;
+        let balls = [
+            Ball::new(
+                Vec2::new(FX_ONE / 2, FX_ONE / 2),
+                Vec2::new(FX_ONE / 3, FX_ONE / 7),
+            ),
+            Ball::new(
+                Vec2::new(FX_ONE / 2 + r, FX_ONE / 2 + r / 3),
+                Vec2::new(-FX_ONE / 5, FX_ONE / 9),
+            ),
+            Ball::new(
+                Vec2::new(FX_ONE / 2 - r, FX_ONE / 2 - r / 2),
+                Vec2::new(FX_ONE / 6, -FX_ONE / 4),
+            ),
+        ];
+
+        let mut run1 = balls;
+        let mut run2 = balls;
+        for _ in 0..30 {
+            for ball in run1.iter_mut().chain(run2.iter_mut()) {
+                ClassicPhysics.update_ball(ball, &config);
+            }
+            let hits1 = ClassicPhysics.collide_all_balls(&mut run1, &config);
+            let hits2 = ClassicPhysics.collide_all_balls(&mut run2, &config);
+            assert_eq!(hits1, hits2);
+        }
+
+        assert_eq!(run1, run2);
+    }
+
+    #[test]
+    fn test_sqrt_fx() {
+        // Square root of a 16.16 value via its 32.32 square
+        let sqrt_fx = |value: Fx| ClassicPhysics::isqrt_fx((value as i64) << 16);
+
+        // Test some known values
+        assert_eq!(sqrt_fx(FX_ONE), FX_ONE); // sqrt(1) = 1
+        assert_eq!(sqrt_fx(FX_ONE * 4), FX_ONE * 2); // sqrt(4) = 2
+
+        // Test with small values - sqrt(0.25) = 0.5
+        let quarter = FX_ONE / 4;
+        let sqrt_quarter = sqrt_fx(quarter);
+        let expected = FX_ONE / 2; // 0.5
+        assert_eq!(sqrt_quarter, expected);
+
+        // Non-positive inputs have no real root
+        assert_eq!(sqrt_fx(0), 0);
+        assert_eq!(sqrt_fx(-FX_ONE), 0);
+    }
+
+    #[test]
+    fn test_isqrt_fx_is_exact_floor() {
+        // For every sampled input the result r satisfies r^2 <= n < (r+1)^2
+        for n in [
+            1i64,
+            2,
+            3,
+            15,
+            16,
+            17,
+            65_535,
+            1 << 32,
+            (1 << 40) + 12_345,
+            i64::MAX / 3,
+        ] {
+            let r = ClassicPhysics::isqrt_fx(n) as i64;
+            assert!(r * r <= n, "isqrt({n}) = {r} is too large");
+            assert!((r + 1) * (r + 1) > n, "isqrt({n}) = {r} is too small");
+        }
+
+        // Magnitudes beyond the Fx range saturate instead of wrapping
+        assert_eq!(ClassicPhysics::isqrt_fx(i64::MAX), Fx::MAX);
+    }
+
+    #[test]
+    fn test_speed_fx_pythagorean() {
+        // A 3-4-5 triangle is exact in fixed point
+        let vel = Vec2::new(FX_ONE * 3, FX_ONE * 4);
+        assert_eq!(vel.length(), FX_ONE * 5);
+    }
+
+    #[test]
+    fn test_deterministic_rng() {
+        let _config = Config::default();
+        let mut ball1 = Ball::new(Vec2::zero(), Vec2::zero());
+        let mut ball2 = Ball::new(Vec2::zero(), Vec2::zero());
+        let mut rng1 = 12345u64;
+        let mut rng2 = 12345u64;
+
+        ClassicPhysics.serve_ball(&mut ball1, Side::Left, &_config, &mut rng1);
+        ClassicPhysics.serve_ball(&mut ball2, Side::Left, &_config, &mut rng2);
+
+        // Same seed should produce identical results
+        assert_eq!(ball1.vel, ball2.vel);
+        assert_eq!(rng1, rng2);
+    }
+}
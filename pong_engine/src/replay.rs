@@ -0,0 +1,376 @@
+//! Binary replay container: rules, starting state, per-tick inputs and keyframes.
+//!
+//! Layout:
+//! `[magic:4 "PRPL"][version:1][config:97][seed:8][start_len:2][start snapshot]`,
+//! then records `[0x01][len:1][InputPair]` or `[0x02][len:2][Snapshot]`, and an
+//! optional footer `[0xFF][final_tick:4][final_hash:8]` closing the stream.
+
+use crate::serialization::{SerializationError, CONFIG_ENCODED_SIZE};
+use crate::types::*;
+
+/// File signature at the start of every replay
+pub const REPLAY_MAGIC: [u8; 4] = *b"PRPL";
+
+/// Replay format version written by this build
+pub const REPLAY_VERSION: u8 = 1;
+
+const RECORD_INPUT: u8 = 0x01;
+const RECORD_KEYFRAME: u8 = 0x02;
+const RECORD_FOOTER: u8 = 0xFF;
+
+/// Everything needed to start re-simulating a match
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayHeader {
+    pub version: u8,
+    pub config: Config,
+    pub seed: u64,
+    /// State at the first recorded tick
+    pub start: Snapshot,
+}
+
+/// One entry in the replay stream
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayRecord {
+    /// Inputs for one simulated tick
+    Input(InputPair),
+    /// Full state for seeking and drift checks
+    Keyframe(Snapshot),
+}
+
+/// Closing summary of a finished replay
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ReplayFooter {
+    /// Tick of the state after the last input
+    pub final_tick: Tick,
+    /// `Game::state_hash` of that state
+    pub final_hash: u64,
+}
+
+/// A whole replay held in memory
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    pub records: Vec<ReplayRecord>,
+    /// None while recording or when the file was cut short
+    pub footer: Option<ReplayFooter>,
+}
+
+impl Replay {
+    /// Start a replay from the given rules and starting state
+    pub fn new(config: Config, start: Snapshot) -> Self {
+        Replay {
+            header: ReplayHeader {
+                version: REPLAY_VERSION,
+                config,
+                seed: config.seed,
+                start,
+            },
+            records: Vec::new(),
+            footer: None,
+        }
+    }
+
+    /// Append the inputs for one tick
+    pub fn record_input(&mut self, inputs: InputPair) {
+        self.records.push(ReplayRecord::Input(inputs));
+    }
+
+    /// Append a keyframe snapshot
+    pub fn record_keyframe(&mut self, snapshot: Snapshot) {
+        self.records.push(ReplayRecord::Keyframe(snapshot));
+    }
+
+    /// Close the replay with the final tick and state hash
+    pub fn finish(&mut self, final_tick: Tick, final_hash: u64) {
+        self.footer = Some(ReplayFooter {
+            final_tick,
+            final_hash,
+        });
+    }
+
+    /// Recorded inputs in order, skipping keyframes
+    pub fn inputs(&self) -> impl Iterator<Item = &InputPair> {
+        self.records.iter().filter_map(|record| match record {
+            ReplayRecord::Input(pair) => Some(pair),
+            ReplayRecord::Keyframe(_) => None,
+        })
+    }
+
+    /// Serialize the whole replay
+    pub fn encode(&self) -> Vec<u8> {
+        let start = self.header.start.encode();
+        let mut bytes = Vec::with_capacity(
+            REPLAY_MAGIC.len()
+                + 1
+                + CONFIG_ENCODED_SIZE
+                + 10
+                + start.len()
+                + self.records.len() * 11,
+        );
+
+        bytes.extend_from_slice(&REPLAY_MAGIC);
+        bytes.push(self.header.version);
+        bytes.extend_from_slice(&self.header.config.encode());
+        bytes.extend_from_slice(&self.header.seed.to_le_bytes());
+        bytes.extend_from_slice(&(start.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&start);
+
+        for record in &self.records {
+            match record {
+                ReplayRecord::Input(pair) => {
+                    let encoded = pair.encode();
+                    bytes.push(RECORD_INPUT);
+                    bytes.push(encoded.len() as u8);
+                    bytes.extend_from_slice(&encoded);
+                }
+                ReplayRecord::Keyframe(snapshot) => {
+                    let encoded = snapshot.encode();
+                    bytes.push(RECORD_KEYFRAME);
+                    bytes.extend_from_slice(&(encoded.len() as u16).to_le_bytes());
+                    bytes.extend_from_slice(&encoded);
+                }
+            }
+        }
+
+        if let Some(footer) = self.footer {
+            bytes.push(RECORD_FOOTER);
+            bytes.extend_from_slice(&footer.final_tick.to_le_bytes());
+            bytes.extend_from_slice(&footer.final_hash.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    /// Deserialize a whole replay, failing on the first malformed record
+    pub fn decode(bytes: &[u8]) -> Result<Self, SerializationError> {
+        let mut reader = ReplayReader::new(bytes)?;
+        let records = reader.by_ref().collect::<Result<Vec<_>, _>>()?;
+        Ok(Replay {
+            header: reader.header().clone(),
+            records,
+            footer: reader.footer(),
+        })
+    }
+}
+
+/// Streams records out of an encoded replay without collecting them
+pub struct ReplayReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    header: ReplayHeader,
+    footer: Option<ReplayFooter>,
+    done: bool,
+}
+
+impl<'a> ReplayReader<'a> {
+    /// Parse the header; records are read lazily by iterating
+    pub fn new(bytes: &'a [u8]) -> Result<Self, SerializationError> {
+        let fixed = REPLAY_MAGIC.len() + 1 + CONFIG_ENCODED_SIZE + 10;
+        if bytes.len() < fixed {
+            return Err(SerializationError::UnexpectedEnd);
+        }
+        if bytes[..4] != REPLAY_MAGIC {
+            return Err(SerializationError::InvalidData);
+        }
+
+        let version = bytes[4];
+        if version != REPLAY_VERSION {
+            return Err(SerializationError::UnsupportedVersion);
+        }
+
+        let mut offset = 5;
+        let config = Config::decode(&bytes[offset..])?;
+        offset += CONFIG_ENCODED_SIZE;
+
+        let mut seed = [0u8; 8];
+        seed.copy_from_slice(&bytes[offset..offset + 8]);
+        offset += 8;
+
+        let start_len = u16::from_le_bytes([bytes[offset], bytes[offset + 1]]) as usize;
+        offset += 2;
+        let start = bytes
+            .get(offset..offset + start_len)
+            .ok_or(SerializationError::UnexpectedEnd)?;
+        let start = Snapshot::decode(start)?;
+        offset += start_len;
+
+        Ok(ReplayReader {
+            bytes,
+            offset,
+            header: ReplayHeader {
+                version,
+                config,
+                seed: u64::from_le_bytes(seed),
+                start,
+            },
+            footer: None,
+            done: false,
+        })
+    }
+
+    pub fn header(&self) -> &ReplayHeader {
+        &self.header
+    }
+
+    /// Footer, available once iteration has reached it
+    pub fn footer(&self) -> Option<ReplayFooter> {
+        self.footer
+    }
+
+    fn read_record(&mut self) -> Result<Option<ReplayRecord>, SerializationError> {
+        let Some(&tag) = self.bytes.get(self.offset) else {
+            return Ok(None); // Stream ended without a footer
+        };
+        let body = &self.bytes[self.offset + 1..];
+
+        match tag {
+            RECORD_INPUT => {
+                let len = *body.first().ok_or(SerializationError::UnexpectedEnd)? as usize;
+                let pair = body
+                    .get(1..1 + len)
+                    .ok_or(SerializationError::UnexpectedEnd)?;
+                let pair = InputPair::decode(pair)?;
+                self.offset += 2 + len;
+                Ok(Some(ReplayRecord::Input(pair)))
+            }
+            RECORD_KEYFRAME => {
+                let len = body.get(..2).ok_or(SerializationError::UnexpectedEnd)?;
+                let len = u16::from_le_bytes([len[0], len[1]]) as usize;
+                let snapshot = body
+                    .get(2..2 + len)
+                    .ok_or(SerializationError::UnexpectedEnd)?;
+                let snapshot = Snapshot::decode(snapshot)?;
+                self.offset += 3 + len;
+                Ok(Some(ReplayRecord::Keyframe(snapshot)))
+            }
+            RECORD_FOOTER => {
+                let footer = body.get(..12).ok_or(SerializationError::UnexpectedEnd)?;
+                let mut hash = [0u8; 8];
+                hash.copy_from_slice(&footer[4..12]);
+                self.footer = Some(ReplayFooter {
+                    final_tick: u32::from_le_bytes([footer[0], footer[1], footer[2], footer[3]]),
+                    final_hash: u64::from_le_bytes(hash),
+                });
+                self.offset += 13;
+                Ok(None)
+            }
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+impl Iterator for ReplayReader<'_> {
+    type Item = Result<ReplayRecord, SerializationError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.read_record() {
+            Ok(Some(record)) => Some(Ok(record)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(error) => {
+                self.done = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Game;
+
+    /// Record a short match with a keyframe every 10 ticks
+    fn record_match(ticks: u32) -> (Replay, Game) {
+        let config = Config {
+            seed: 1234,
+            ..Config::default()
+        };
+        let mut game = Game::new(config);
+        let mut replay = Replay::new(config, game.snapshot());
+
+        for tick in 0..ticks {
+            let axis = if tick % 20 < 10 { 60 } else { -60 };
+            let inputs = InputPair::new(tick, Input::new(axis, 1), Input::new(-axis, 1));
+            game.step(&inputs);
+            replay.record_input(inputs);
+            if tick % 10 == 9 {
+                replay.record_keyframe(game.snapshot());
+            }
+        }
+        replay.finish(game.tick, game.state_hash());
+        (replay, game)
+    }
+
+    #[test]
+    fn test_replay_round_trip() {
+        let (replay, _) = record_match(45);
+        let decoded = Replay::decode(&replay.encode()).unwrap();
+        assert_eq!(decoded, replay);
+        assert_eq!(decoded.inputs().count(), 45);
+        assert_eq!(decoded.header.seed, 1234);
+    }
+
+    #[test]
+    fn test_replay_reproduces_final_state() {
+        let (replay, original) = record_match(120);
+        let bytes = replay.encode();
+
+        // Re-simulate from the header while streaming records
+        let mut reader = ReplayReader::new(&bytes).unwrap();
+        let mut game = Game::new(reader.header().config);
+        game.restore(&reader.header().start).unwrap();
+        let mut keyframes = 0;
+        for record in reader.by_ref() {
+            match record.unwrap() {
+                ReplayRecord::Input(inputs) => {
+                    game.step(&inputs);
+                }
+                ReplayRecord::Keyframe(snapshot) => {
+                    assert_eq!(snapshot, game.snapshot());
+                    keyframes += 1;
+                }
+            }
+        }
+
+        let footer = reader.footer().unwrap();
+        assert_eq!(keyframes, 12);
+        assert_eq!(footer.final_tick, original.tick);
+        assert_eq!(footer.final_hash, game.state_hash());
+    }
+
+    #[test]
+    fn test_replay_decode_errors() {
+        let (replay, _) = record_match(5);
+        let bytes = replay.encode();
+
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(
+            Replay::decode(&bad_magic),
+            Err(SerializationError::InvalidData)
+        );
+
+        let mut future = bytes.clone();
+        future[4] = REPLAY_VERSION + 1;
+        assert_eq!(
+            Replay::decode(&future),
+            Err(SerializationError::UnsupportedVersion)
+        );
+
+        // Cutting into the footer is an error; cutting it off entirely is not
+        assert_eq!(
+            Replay::decode(&bytes[..bytes.len() - 3]),
+            Err(SerializationError::UnexpectedEnd)
+        );
+        let unfinished = Replay::decode(&bytes[..bytes.len() - 13]).unwrap();
+        assert_eq!(unfinished.footer, None);
+        assert_eq!(unfinished.records, replay.records);
+    }
+}
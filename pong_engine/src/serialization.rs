@@ -0,0 +1,1161 @@
+//! Compact binary serialization for network protocol.
+
+use crate::types::*;
+
+/// Serialization errors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SerializationError {
+    /// Buffer too small for serialization
+    BufferTooSmall,
+    /// Invalid data during deserialization
+    InvalidData,
+    /// Unexpected end of buffer
+    UnexpectedEnd,
+    /// Peer speaks a protocol version we cannot interoperate with
+    UnsupportedVersion,
+    /// Frame checksum did not match its contents
+    ChecksumMismatch,
+    /// Message authentication tag missing or wrong
+    AuthenticationFailed,
+}
+
+impl Input {
+    /// Serialize to 2 bytes: [axis_y: i8, buttons: u8], plus [tilt: i8] and then
+    /// [axis_x: i8] only when used, so plain input keeps the original size
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8; self.encoded_len()];
+        self.encode_into(&mut bytes)
+            .expect("buffer sized by encoded_len");
+        bytes
+    }
+
+    /// Length of `encode()` without allocating
+    pub fn encoded_len(&self) -> usize {
+        match (self.tilt != 0, self.axis_x != 0) {
+            (_, true) => 4,
+            (true, false) => 3,
+            (false, false) => 2,
+        }
+    }
+
+    /// Serialize into the front of `buf`, returning the number of bytes written
+    pub fn encode_into(&self, buf: &mut [u8]) -> Result<usize, SerializationError> {
+        let mut w = Writer::new(buf, self.encoded_len())?;
+        w.u8(self.axis_y as u8); // Cast i8 to u8 preserving bit pattern
+        w.u8(self.buttons.bits());
+        if self.tilt != 0 || self.axis_x != 0 {
+            w.u8(self.tilt as u8);
+        }
+        if self.axis_x != 0 {
+            w.u8(self.axis_x as u8);
+        }
+        Ok(w.finish())
+    }
+
+    /// Deserialize from 2 bytes (3 with tilt, 4 with axis_x)
+    pub fn decode(bytes: &[u8]) -> Result<Self, SerializationError> {
+        if bytes.len() < 2 {
+            return Err(SerializationError::UnexpectedEnd);
+        }
+
+        Ok(Input {
+            axis_y: bytes[0] as i8, // Cast u8 back to i8
+            axis_x: bytes.get(3).map_or(0, |&b| b as i8),
+            buttons: Buttons::from_bits_retain(bytes[1]),
+            tilt: bytes.get(2).map_or(0, |&b| b as i8),
+        })
+    }
+}
+
+/// InputPair flag byte (formerly reserved, always 0 from older peers):
+/// the tilt tail is followed by [a_axis_x: i8, b_axis_x: i8]
+const PAIR_FLAG_AXIS_X: u8 = 1 << 0;
+
+/// InputPair flag: a [sent_ms: u32] extension follows every other tail. The tilt
+/// tail is always written with it, so decoders that predate the flag ignore it.
+const PAIR_FLAG_TIMESTAMP: u8 = 1 << 1;
+
+impl InputPair {
+    /// Serialize to 9 bytes: [tick: u32, a_axis: i8, a_buttons: u8, b_axis: i8, b_buttons: u8,
+    /// flags: u8], plus [a_tilt: i8, b_tilt: i8] only when either paddle is tilting or
+    /// an X axis is used, plus [a_axis_x: i8, b_axis_x: i8] when flags has `PAIR_FLAG_AXIS_X`
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8; self.encoded_len()];
+        self.encode_into(&mut bytes)
+            .expect("buffer sized by encoded_len");
+        bytes
+    }
+
+    /// Length of `encode()` without allocating
+    pub fn encoded_len(&self) -> usize {
+        if self.has_axis_x() {
+            13
+        } else if self.a.tilt != 0 || self.b.tilt != 0 {
+            11
+        } else {
+            9
+        }
+    }
+
+    fn has_axis_x(&self) -> bool {
+        self.a.axis_x != 0 || self.b.axis_x != 0
+    }
+
+    /// Serialize into the front of `buf`, returning the number of bytes written
+    pub fn encode_into(&self, buf: &mut [u8]) -> Result<usize, SerializationError> {
+        self.write(buf, None)
+    }
+
+    /// Length of `encode_timestamped_into()`
+    pub fn timestamped_len(&self) -> usize {
+        self.encoded_len().max(11) + 4
+    }
+
+    /// Serialize with the sender's millisecond clock appended, for latency measurement
+    pub fn encode_timestamped_into(
+        &self,
+        sent_ms: u32,
+        buf: &mut [u8],
+    ) -> Result<usize, SerializationError> {
+        self.write(buf, Some(sent_ms))
+    }
+
+    /// Send timestamp of an encoded pair, if it carries one
+    pub fn decode_timestamp(bytes: &[u8]) -> Result<Option<u32>, SerializationError> {
+        let flags = *bytes.get(8).ok_or(SerializationError::UnexpectedEnd)?;
+        if flags & PAIR_FLAG_TIMESTAMP == 0 {
+            return Ok(None);
+        }
+
+        let offset = if flags & PAIR_FLAG_AXIS_X != 0 {
+            13
+        } else {
+            11
+        };
+        let ts = bytes
+            .get(offset..offset + 4)
+            .ok_or(SerializationError::UnexpectedEnd)?;
+        Ok(Some(u32::from_le_bytes([ts[0], ts[1], ts[2], ts[3]])))
+    }
+
+    fn write(&self, buf: &mut [u8], sent_ms: Option<u32>) -> Result<usize, SerializationError> {
+        let len = match sent_ms {
+            Some(_) => self.timestamped_len(),
+            None => self.encoded_len(),
+        };
+        let mut w = Writer::new(buf, len)?;
+
+        // Tick as little-endian u32
+        w.put(&self.tick.to_le_bytes());
+
+        // Input A
+        w.u8(self.a.axis_y as u8);
+        w.u8(self.a.buttons.bits());
+
+        // Input B
+        w.u8(self.b.axis_y as u8);
+        w.u8(self.b.buttons.bits());
+
+        // Flags (0 keeps the legacy layout)
+        let has_axis_x = self.has_axis_x();
+        let mut flags = if has_axis_x { PAIR_FLAG_AXIS_X } else { 0 };
+        if sent_ms.is_some() {
+            flags |= PAIR_FLAG_TIMESTAMP;
+        }
+        w.u8(flags);
+
+        // Tilt tail, always present ahead of the X axes and the timestamp
+        if len > 9 {
+            w.u8(self.a.tilt as u8);
+            w.u8(self.b.tilt as u8);
+        }
+
+        if has_axis_x {
+            w.u8(self.a.axis_x as u8);
+            w.u8(self.b.axis_x as u8);
+        }
+
+        if let Some(sent_ms) = sent_ms {
+            w.put(&sent_ms.to_le_bytes());
+        }
+
+        Ok(w.finish())
+    }
+
+    /// Deserialize from 9 bytes (11 with tilt, 13 with X axes, +4 with a timestamp)
+    pub fn decode(bytes: &[u8]) -> Result<Self, SerializationError> {
+        if bytes.len() < 9 {
+            return Err(SerializationError::UnexpectedEnd);
+        }
+
+        let tick = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+
+        let (a_tilt, b_tilt) = match bytes.get(9..11) {
+            Some(tilts) => (tilts[0] as i8, tilts[1] as i8),
+            None => (0, 0),
+        };
+
+        let (a_axis_x, b_axis_x) = if bytes[8] & PAIR_FLAG_AXIS_X != 0 {
+            let axes = bytes.get(11..13).ok_or(SerializationError::UnexpectedEnd)?;
+            (axes[0] as i8, axes[1] as i8)
+        } else {
+            (0, 0)
+        };
+
+        let a = Input {
+            axis_y: bytes[4] as i8,
+            axis_x: a_axis_x,
+            buttons: Buttons::from_bits_retain(bytes[5]),
+            tilt: a_tilt,
+        };
+
+        let b = Input {
+            axis_y: bytes[6] as i8,
+            axis_x: b_axis_x,
+            buttons: Buttons::from_bits_retain(bytes[7]),
+            tilt: b_tilt,
+        };
+
+        Ok(InputPair { tick, a, b })
+    }
+}
+
+/// 64-bit FNV-1a hash
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(FNV_OFFSET, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+impl Snapshot {
+    /// 64-bit FNV-1a hash of the encoded snapshot, for cross-peer desync checks
+    pub fn state_hash(&self) -> u64 {
+        fnv1a(&self.encode())
+    }
+
+    /// Serialize snapshot to compact binary format
+    /// Layout: [tick:4][status:3][paddles:16][ball:16][score:2][rng:8] = 49 bytes,
+    /// followed by [count:1][phase:2 * count][rally_hits:2][stall_ticks:2][config_hash:8]
+    /// [ball_count:1][ball:16 * ball_count] only when the game has obstacles, a
+    /// rally in progress, a config hash or extra balls (trailing zero fields are
+    /// omitted)
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8; self.encoded_len()];
+        self.encode_into(&mut bytes)
+            .expect("buffer sized by encoded_len");
+        bytes
+    }
+
+    /// Length of `encode()` without allocating
+    pub fn encoded_len(&self) -> usize {
+        let (rally, stall, hash) = self.tail_fields();
+        if self.obstacle_phases.is_empty() && !rally {
+            return 49;
+        }
+
+        let count = self.obstacle_phases.len().min(u8::MAX as usize);
+        let balls = match self.extra_balls.is_empty() {
+            true => 0,
+            false => 1 + 16 * self.extra_balls.len().min(u8::MAX as usize),
+        };
+        49 + 1 + 2 * count + 2 * rally as usize + 2 * stall as usize + 8 * hash as usize + balls
+    }
+
+    /// Which of rally_hits, stall_ticks and config_hash the tail carries; each
+    /// is written whenever it or any field after it is non-zero
+    fn tail_fields(&self) -> (bool, bool, bool) {
+        let hash = self.config_hash != 0 || !self.extra_balls.is_empty();
+        let stall = hash || self.stall_ticks != 0;
+        let rally = stall || self.rally_hits != 0;
+        (rally, stall, hash)
+    }
+
+    /// Serialize into the front of `buf`, returning the number of bytes written
+    pub fn encode_into(&self, buf: &mut [u8]) -> Result<usize, SerializationError> {
+        let mut bytes = Writer::new(buf, self.encoded_len())?;
+
+        // Tick (4 bytes)
+        bytes.put(&self.tick.to_le_bytes());
+
+        // Status (3 bytes: discriminant + data)
+        match self.status {
+            Status::Lobby => {
+                bytes.u8(0);
+                bytes.u8(0);
+                bytes.u8(0);
+            }
+            Status::Countdown(ticks) => {
+                bytes.u8(1);
+                bytes.put(&ticks.to_le_bytes());
+            }
+            Status::Playing => {
+                bytes.u8(2);
+                bytes.u8(0);
+                bytes.u8(0);
+            }
+            Status::Scored(side, ticks) => {
+                bytes.u8(3);
+                bytes.u8(side.index() as u8);
+                bytes.u8(ticks.min(u8::MAX as u16) as u8); // Serve delay fits in one byte
+            }
+            Status::GameOver(side) => {
+                bytes.u8(4);
+                bytes.u8(side.index() as u8);
+                bytes.u8(0);
+            }
+            Status::Warmup(ticks) => {
+                bytes.u8(5);
+                bytes.put(&ticks.to_le_bytes());
+            }
+            Status::Paused => {
+                bytes.u8(6);
+                bytes.u8(0);
+                bytes.u8(0);
+            }
+        }
+
+        // Paddles (16 bytes: 2 * (y:4 + vy:4))
+        for paddle in &self.paddles {
+            bytes.put(&paddle.y.to_le_bytes());
+            bytes.put(&paddle.vy.to_le_bytes());
+        }
+
+        // Ball (16 bytes: pos(8) + vel(8))
+        bytes.put(&self.ball.pos.x.to_le_bytes());
+        bytes.put(&self.ball.pos.y.to_le_bytes());
+        bytes.put(&self.ball.vel.x.to_le_bytes());
+        bytes.put(&self.ball.vel.y.to_le_bytes());
+
+        // Score (2 bytes)
+        bytes.put(&self.score);
+
+        // RNG state (8 bytes)
+        bytes.put(&self.rng.to_le_bytes());
+
+        // Obstacle phases, rally state and config hash (optional tail)
+        let (rally, stall, hash) = self.tail_fields();
+        if !self.obstacle_phases.is_empty() || rally {
+            let count = self.obstacle_phases.len().min(u8::MAX as usize);
+            bytes.u8(count as u8);
+            for phase in &self.obstacle_phases[..count] {
+                bytes.put(&phase.to_le_bytes());
+            }
+            if rally {
+                bytes.put(&self.rally_hits.to_le_bytes());
+            }
+            if stall {
+                bytes.put(&self.stall_ticks.to_le_bytes());
+            }
+            if hash {
+                bytes.put(&self.config_hash.to_le_bytes());
+            }
+            if !self.extra_balls.is_empty() {
+                let count = self.extra_balls.len().min(u8::MAX as usize);
+                bytes.u8(count as u8);
+                for ball in &self.extra_balls[..count] {
+                    bytes.put(&ball.pos.x.to_le_bytes());
+                    bytes.put(&ball.pos.y.to_le_bytes());
+                    bytes.put(&ball.vel.x.to_le_bytes());
+                    bytes.put(&ball.vel.y.to_le_bytes());
+                }
+            }
+        }
+
+        Ok(bytes.finish())
+    }
+
+    /// Deserialize snapshot from binary format
+    pub fn decode(bytes: &[u8]) -> Result<Self, SerializationError> {
+        if bytes.len() < 49 {
+            return Err(SerializationError::UnexpectedEnd);
+        }
+
+        let mut offset = 0;
+
+        // Tick
+        let tick = u32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ]);
+        offset += 4;
+
+        // Status
+        let status = match bytes[offset] {
+            0 => Status::Lobby,
+            1 => {
+                let ticks = u16::from_le_bytes([bytes[offset + 1], bytes[offset + 2]]);
+                Status::Countdown(ticks)
+            }
+            2 => Status::Playing,
+            3 => {
+                let side = Side::from_index(bytes[offset + 1] as usize)
+                    .ok_or(SerializationError::InvalidData)?;
+                let ticks = u16::from_le_bytes([bytes[offset + 2], 0]); // Only lower byte used
+                Status::Scored(side, ticks)
+            }
+            4 => {
+                let side = Side::from_index(bytes[offset + 1] as usize)
+                    .ok_or(SerializationError::InvalidData)?;
+                Status::GameOver(side)
+            }
+            5 => {
+                let ticks = u16::from_le_bytes([bytes[offset + 1], bytes[offset + 2]]);
+                Status::Warmup(ticks)
+            }
+            6 => Status::Paused,
+            _ => return Err(SerializationError::InvalidData),
+        };
+        offset += 3;
+
+        // Paddles
+        let mut paddles = [Paddle::new(0); 2];
+        for i in 0..2 {
+            let y = i32::from_le_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ]);
+            offset += 4;
+            let vy = i32::from_le_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ]);
+            offset += 4;
+            paddles[i] = Paddle { y, vy, tilt: 0 };
+        }
+
+        // Ball
+        let ball_pos_x = i32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ]);
+        offset += 4;
+        let ball_pos_y = i32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ]);
+        offset += 4;
+        let ball_vel_x = i32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ]);
+        offset += 4;
+        let ball_vel_y = i32::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+        ]);
+        offset += 4;
+
+        let ball = Ball {
+            pos: Vec2::new(ball_pos_x, ball_pos_y),
+            vel: Vec2::new(ball_vel_x, ball_vel_y),
+        };
+
+        // Score
+        let score = [bytes[offset], bytes[offset + 1]];
+        offset += 2;
+
+        // RNG state
+        let rng = u64::from_le_bytes([
+            bytes[offset],
+            bytes[offset + 1],
+            bytes[offset + 2],
+            bytes[offset + 3],
+            bytes[offset + 4],
+            bytes[offset + 5],
+            bytes[offset + 6],
+            bytes[offset + 7],
+        ]);
+        offset += 8;
+
+        // Obstacle phases
+        let mut obstacle_phases = Vec::new();
+        if let Some(&count) = bytes.get(offset) {
+            offset += 1;
+            let count = count as usize;
+            if bytes.len() < offset + count * 2 {
+                return Err(SerializationError::UnexpectedEnd);
+            }
+            for _ in 0..count {
+                obstacle_phases.push(u16::from_le_bytes([bytes[offset], bytes[offset + 1]]));
+                offset += 2;
+            }
+        }
+
+        // Rally counters
+        let read_counter = |at: usize| match bytes.get(at..at + 2) {
+            Some(value) => u16::from_le_bytes([value[0], value[1]]),
+            None => 0,
+        };
+        let rally_hits = read_counter(offset);
+        let stall_ticks = read_counter(offset + 2);
+        let config_hash = match bytes.get(offset + 4..offset + 12) {
+            Some(hash) => u64::from_le_bytes(hash.try_into().expect("8-byte slice")),
+            None => 0,
+        };
+
+        // Extra balls
+        let mut extra_balls = Vec::new();
+        offset += 12;
+        if let Some(&count) = bytes.get(offset) {
+            offset += 1;
+            let count = count as usize;
+            if bytes.len() < offset + count * 16 {
+                return Err(SerializationError::UnexpectedEnd);
+            }
+            let fx_at =
+                |at: usize| i32::from_le_bytes(bytes[at..at + 4].try_into().expect("4-byte slice"));
+            for _ in 0..count {
+                extra_balls.push(Ball {
+                    pos: Vec2::new(fx_at(offset), fx_at(offset + 4)),
+                    vel: Vec2::new(fx_at(offset + 8), fx_at(offset + 12)),
+                });
+                offset += 16;
+            }
+        }
+
+        Ok(Snapshot {
+            tick,
+            status,
+            paddles,
+            ball,
+            score,
+            rng,
+            obstacle_phases,
+            rally_hits,
+            stall_ticks,
+            config_hash,
+            extra_balls,
+        })
+    }
+}
+
+/// Size of an encoded `Config`
+pub const CONFIG_ENCODED_SIZE: usize = 97;
+
+/// Little-endian cursor over a buffer whose length has already been checked
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, offset: 0 }
+    }
+
+    fn take<const N: usize>(&mut self) -> [u8; N] {
+        let mut field = [0u8; N];
+        field.copy_from_slice(&self.bytes[self.offset..self.offset + N]);
+        self.offset += N;
+        field
+    }
+
+    fn u8(&mut self) -> u8 {
+        self.take::<1>()[0]
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_le_bytes(self.take())
+    }
+
+    fn fx(&mut self) -> Fx {
+        i32::from_le_bytes(self.take())
+    }
+
+    fn u64(&mut self) -> u64 {
+        u64::from_le_bytes(self.take())
+    }
+}
+
+/// Little-endian cursor that writes into a buffer checked up front
+struct Writer<'a> {
+    bytes: &'a mut [u8],
+    offset: usize,
+}
+
+impl<'a> Writer<'a> {
+    /// Fails with `BufferTooSmall` unless `bytes` can hold `len` bytes
+    fn new(bytes: &'a mut [u8], len: usize) -> Result<Self, SerializationError> {
+        if bytes.len() < len {
+            return Err(SerializationError::BufferTooSmall);
+        }
+        Ok(Writer { bytes, offset: 0 })
+    }
+
+    fn put(&mut self, field: &[u8]) {
+        self.bytes[self.offset..self.offset + field.len()].copy_from_slice(field);
+        self.offset += field.len();
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.put(&[value]);
+    }
+
+    /// Number of bytes written
+    fn finish(self) -> usize {
+        self.offset
+    }
+}
+
+impl Config {
+    /// Serialize to 97 bytes: every field little-endian in declaration order
+    /// (20 Fx fields at 4 bytes, five u8s, seed:8, tick_hz:2, drama_rally_hits:2)
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8; CONFIG_ENCODED_SIZE];
+        self.encode_into(&mut bytes)
+            .expect("buffer sized by CONFIG_ENCODED_SIZE");
+        bytes
+    }
+
+    /// Serialize into the front of `buf`, returning the number of bytes written
+    pub fn encode_into(&self, buf: &mut [u8]) -> Result<usize, SerializationError> {
+        let mut bytes = Writer::new(buf, CONFIG_ENCODED_SIZE)?;
+
+        bytes.put(&self.paddle_half_h.to_le_bytes());
+        bytes.put(&self.paddle_speed.to_le_bytes());
+        bytes.put(&self.paddle_accel.to_le_bytes());
+        bytes.put(&self.paddle_decel.to_le_bytes());
+        bytes.put(&self.ball_speed.to_le_bytes());
+        bytes.put(&self.ball_speed_up.to_le_bytes());
+        bytes.u8(self.rally_ramp_hits);
+        bytes.put(&self.rally_ramp_speed_up.to_le_bytes());
+        bytes.put(&self.wall_thickness.to_le_bytes());
+        bytes.put(&self.wall_restitution.to_le_bytes());
+        bytes.put(&self.wall_friction.to_le_bytes());
+        bytes.put(&self.paddle_x.to_le_bytes());
+        bytes.put(&self.paddle_max_tilt.to_le_bytes());
+        bytes.put(&self.bounce_jitter.to_le_bytes());
+        bytes.u8(self.max_score);
+        bytes.put(&self.seed.to_le_bytes());
+        bytes.put(&self.tick_hz.to_le_bytes());
+        bytes.put(&self.ball_radius.to_le_bytes());
+        bytes.put(&self.paddle_width.to_le_bytes());
+        bytes.u8(self.physics_substeps);
+        bytes.u8(self.warmup_secs);
+        bytes.put(&self.stuck_min_vx.to_le_bytes());
+        bytes.u8(self.stuck_timeout_secs);
+        bytes.put(&self.drama_rally_hits.to_le_bytes());
+        bytes.put(&self.drama_speed.to_le_bytes());
+        bytes.put(&self.field_width.to_le_bytes());
+        bytes.put(&self.goal_gap.to_le_bytes());
+        bytes.put(&self.goal_gap_y.to_le_bytes());
+
+        Ok(bytes.finish())
+    }
+
+    /// 64-bit FNV-1a hash of the encoded config; equal for configs that simulate identically
+    pub fn fingerprint(&self) -> u64 {
+        let mut bytes = [0u8; CONFIG_ENCODED_SIZE];
+        self.encode_into(&mut bytes)
+            .expect("buffer sized by CONFIG_ENCODED_SIZE");
+        fnv1a(&bytes)
+    }
+
+    /// `fingerprint` extended with an obstacle layout (rest positions, sizes
+    /// and motion), which lives on `Game` rather than in the config. Equal to
+    /// `fingerprint` when there are no obstacles.
+    pub fn layout_fingerprint(&self, obstacles: &[Obstacle]) -> u64 {
+        let mut bytes = self.encode();
+        for obstacle in obstacles {
+            let (tag, extent, period) = match obstacle.motion {
+                ObstacleMotion::Static => (0u8, Vec2::zero(), 0),
+                ObstacleMotion::PingPong { offset, period } => (1, offset, period),
+                ObstacleMotion::Sine { amplitude, period } => (2, amplitude, period),
+            };
+            for value in [
+                obstacle.origin.x,
+                obstacle.origin.y,
+                obstacle.half_size.x,
+                obstacle.half_size.y,
+                extent.x,
+                extent.y,
+            ] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+            bytes.push(tag);
+            bytes.extend_from_slice(&period.to_le_bytes());
+        }
+        fnv1a(&bytes)
+    }
+
+    /// Deserialize from 97 bytes; trailing bytes are ignored
+    pub fn decode(bytes: &[u8]) -> Result<Self, SerializationError> {
+        if bytes.len() < CONFIG_ENCODED_SIZE {
+            return Err(SerializationError::UnexpectedEnd);
+        }
+
+        // Struct literal fields evaluate in the order written, matching encode
+        let mut r = Reader::new(bytes);
+        Ok(Config {
+            paddle_half_h: r.fx(),
+            paddle_speed: r.fx(),
+            paddle_accel: r.fx(),
+            paddle_decel: r.fx(),
+            ball_speed: r.fx(),
+            ball_speed_up: r.fx(),
+            rally_ramp_hits: r.u8(),
+            rally_ramp_speed_up: r.fx(),
+            wall_thickness: r.fx(),
+            wall_restitution: r.fx(),
+            wall_friction: r.fx(),
+            paddle_x: r.fx(),
+            paddle_max_tilt: r.fx(),
+            bounce_jitter: r.fx(),
+            max_score: r.u8(),
+            seed: r.u64(),
+            tick_hz: r.u16(),
+            ball_radius: r.fx(),
+            paddle_width: r.fx(),
+            physics_substeps: r.u8(),
+            warmup_secs: r.u8(),
+            stuck_min_vx: r.fx(),
+            stuck_timeout_secs: r.u8(),
+            drama_rally_hits: r.u16(),
+            drama_speed: r.fx(),
+            field_width: r.fx(),
+            goal_gap: r.fx(),
+            goal_gap_y: r.fx(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_serialization() {
+        let input = Input::new(-100, 5);
+        let encoded = input.encode();
+        let decoded = Input::decode(&encoded).unwrap();
+
+        assert_eq!(input, decoded);
+        assert_eq!(encoded.len(), 2);
+    }
+
+    #[test]
+    fn test_input_serialization_edge_cases() {
+        // Test extreme values
+        let input_min = Input::new(-127, 0);
+        let input_max = Input::new(127, 255);
+
+        assert_eq!(input_min, Input::decode(&input_min.encode()).unwrap());
+        assert_eq!(input_max, Input::decode(&input_max.encode()).unwrap());
+    }
+
+    #[test]
+    fn test_input_pair_serialization() {
+        let pair = InputPair::new(12345, Input::new(-50, 1), Input::new(75, 2));
+
+        let encoded = pair.encode();
+        let decoded = InputPair::decode(&encoded).unwrap();
+
+        assert_eq!(pair, decoded);
+        assert_eq!(encoded.len(), 9);
+    }
+
+    #[test]
+    fn test_input_tilt_serialization() {
+        let input = Input::new(10, 1).with_tilt(-64);
+        let encoded = input.encode();
+        assert_eq!(encoded.len(), 3);
+        assert_eq!(Input::decode(&encoded).unwrap(), input);
+
+        let pair = InputPair::new(7, Input::zero(), Input::new(-5, 0).with_tilt(127));
+        let encoded = pair.encode();
+        assert_eq!(encoded.len(), 11);
+        assert_eq!(InputPair::decode(&encoded).unwrap(), pair);
+
+        // Legacy 9-byte pairs decode as untilted
+        let legacy = InputPair::decode(&encoded[..9]).unwrap();
+        assert_eq!(legacy.b.tilt, 0);
+        assert_eq!(legacy.b.axis_y, -5);
+    }
+
+    #[test]
+    fn test_input_axis_x_serialization() {
+        let input = Input::new(10, Buttons::READY | Buttons::DASH).with_axis_x(-90);
+        let encoded = input.encode();
+        assert_eq!(encoded.len(), 4);
+        assert_eq!(Input::decode(&encoded).unwrap(), input);
+
+        let pair = InputPair::new(
+            3,
+            Input::new(0, Buttons::PAUSE).with_axis_x(127),
+            Input::new(-5, 0),
+        );
+        let encoded = pair.encode();
+        assert_eq!(encoded.len(), 13);
+        assert_eq!(encoded[8], PAIR_FLAG_AXIS_X);
+        assert_eq!(InputPair::decode(&encoded).unwrap(), pair);
+
+        // A flagged pair missing its axis tail is truncated
+        assert_eq!(
+            InputPair::decode(&encoded[..11]),
+            Err(SerializationError::UnexpectedEnd)
+        );
+
+        // Input without X axes keeps the legacy byte-8 value of 0
+        let plain = InputPair::new(3, Input::new(1, 1), Input::zero());
+        assert_eq!(plain.encode()[8], 0);
+    }
+
+    #[test]
+    fn test_input_pair_timestamp() {
+        let mut buf = [0u8; 32];
+        let plain = InputPair::new(12, Input::new(40, 1), Input::new(-40, 0));
+        let axis_x = InputPair::new(
+            13,
+            Input {
+                axis_x: -9,
+                ..Input::new(1, 0)
+            },
+            Input::zero(),
+        );
+
+        for pair in [plain, axis_x] {
+            let len = pair.encode_timestamped_into(0xDEAD_BEEF, &mut buf).unwrap();
+            assert_eq!(len, pair.timestamped_len());
+
+            // Older decoders see the same pair; the timestamp rides in the extension
+            assert_eq!(InputPair::decode(&buf[..len]).unwrap(), pair);
+            assert_eq!(
+                InputPair::decode_timestamp(&buf[..len]),
+                Ok(Some(0xDEAD_BEEF))
+            );
+            assert_eq!(
+                InputPair::decode_timestamp(&buf[..len - 1]),
+                Err(SerializationError::UnexpectedEnd)
+            );
+            assert_eq!(InputPair::decode_timestamp(&pair.encode()), Ok(None));
+        }
+    }
+
+    #[test]
+    fn test_input_decode_insufficient_data() {
+        assert_eq!(Input::decode(&[]), Err(SerializationError::UnexpectedEnd));
+        assert_eq!(Input::decode(&[1]), Err(SerializationError::UnexpectedEnd));
+
+        assert_eq!(
+            InputPair::decode(&[1, 2, 3, 4]),
+            Err(SerializationError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_snapshot_serialization() {
+        let snapshot = Snapshot {
+            tick: 1000,
+            status: Status::Playing,
+            paddles: [
+                Paddle {
+                    y: FX_ONE / 2,
+                    vy: FX_ONE / 4,
+                    tilt: 0,
+                },
+                Paddle {
+                    y: FX_ONE / 3,
+                    vy: -FX_ONE / 8,
+                    tilt: 0,
+                },
+            ],
+            ball: Ball {
+                pos: Vec2::new(FX_ONE / 2, FX_ONE / 4),
+                vel: Vec2::new(FX_ONE / 8, -FX_ONE / 16),
+            },
+            score: [3, 2],
+            rng: 0xDEADBEEF_CAFEBABE,
+            obstacle_phases: Vec::new(),
+            rally_hits: 0,
+            stall_ticks: 0,
+            config_hash: 0,
+            extra_balls: Vec::new(),
+        };
+
+        let encoded = snapshot.encode();
+        let decoded = Snapshot::decode(&encoded).unwrap();
+
+        assert_eq!(snapshot.tick, decoded.tick);
+        assert_eq!(snapshot.status, decoded.status);
+        assert_eq!(snapshot.paddles, decoded.paddles);
+        assert_eq!(snapshot.ball.pos, decoded.ball.pos);
+        assert_eq!(snapshot.ball.vel, decoded.ball.vel);
+        assert_eq!(snapshot.score, decoded.score);
+        assert_eq!(snapshot.rng, decoded.rng);
+    }
+
+    #[test]
+    fn test_snapshot_all_status_variants() {
+        let statuses = [
+            Status::Lobby,
+            Status::Warmup(600),
+            Status::Countdown(180),
+            Status::Playing,
+            Status::Scored(Side::Left, 120),
+            Status::GameOver(Side::Right),
+            Status::Paused,
+        ];
+
+        for status in statuses {
+            let snapshot = Snapshot {
+                tick: 100,
+                status,
+                paddles: [Paddle::new(0), Paddle::new(0)],
+                ball: Ball::new(Vec2::zero(), Vec2::zero()),
+                score: [0, 0],
+                rng: 0,
+                obstacle_phases: Vec::new(),
+                rally_hits: 0,
+                stall_ticks: 0,
+                config_hash: 0,
+                extra_balls: Vec::new(),
+            };
+
+            let encoded = snapshot.encode();
+            assert_eq!(encoded.len(), 49);
+            let decoded = Snapshot::decode(&encoded).unwrap();
+            assert_eq!(snapshot.status, decoded.status);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_obstacle_phases() {
+        let snapshot = Snapshot {
+            tick: 42,
+            status: Status::Scored(Side::Right, 90),
+            paddles: [Paddle::new(FX_ONE / 2), Paddle::new(FX_ONE / 3)],
+            ball: Ball::new(Vec2::new(FX_ONE / 2, FX_ONE / 2), Vec2::zero()),
+            score: [1, 4],
+            rng: 7,
+            obstacle_phases: vec![0, 59, 1000],
+            rally_hits: 0,
+            stall_ticks: 0,
+            config_hash: 0,
+            extra_balls: Vec::new(),
+        };
+
+        let encoded = snapshot.encode();
+        assert_eq!(encoded.len(), 49 + 1 + 3 * 2);
+        assert_eq!(Snapshot::decode(&encoded).unwrap(), snapshot);
+
+        // Rally state rides along in the tail, with or without obstacles
+        let rally = Snapshot {
+            rally_hits: 9,
+            obstacle_phases: Vec::new(),
+            ..snapshot.clone()
+        };
+        let encoded_rally = rally.encode();
+        assert_eq!(encoded_rally.len(), 49 + 1 + 2);
+        assert_eq!(Snapshot::decode(&encoded_rally).unwrap(), rally);
+
+        let stalled = Snapshot {
+            stall_ticks: 300,
+            config_hash: 0,
+            ..rally.clone()
+        };
+        assert_eq!(Snapshot::decode(&stalled.encode()).unwrap(), stalled);
+
+        // A truncated phase list is rejected
+        assert_eq!(
+            Snapshot::decode(&encoded[..encoded.len() - 1]),
+            Err(SerializationError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_encode_into() {
+        let mut buf = [0xAAu8; 128];
+
+        let inputs = [
+            Input::new(-100, 5),
+            Input::new(10, 1).with_tilt(-3),
+            Input {
+                axis_x: 7,
+                ..Input::new(1, 0)
+            },
+        ];
+        for input in inputs {
+            let len = input.encode_into(&mut buf).unwrap();
+            assert_eq!(len, input.encoded_len());
+            assert_eq!(&buf[..len], &input.encode()[..]);
+        }
+
+        let pair = InputPair::new(99, inputs[1], inputs[2]);
+        let len = pair.encode_into(&mut buf).unwrap();
+        assert_eq!(len, 13);
+        assert_eq!(&buf[..len], &pair.encode()[..]);
+
+        let snapshot = crate::Game::new(Config::default()).snapshot();
+        let stalled = Snapshot {
+            obstacle_phases: vec![3, 4],
+            stall_ticks: 12,
+            config_hash: 0,
+            ..snapshot.clone()
+        };
+        for snapshot in [snapshot, stalled] {
+            let len = snapshot.encode_into(&mut buf).unwrap();
+            assert_eq!(len, snapshot.encoded_len());
+            assert_eq!(&buf[..len], &snapshot.encode()[..]);
+
+            // Exact fit succeeds, one byte short fails without writing a partial encoding
+            assert!(snapshot.encode_into(&mut buf[..len]).is_ok());
+            assert_eq!(
+                snapshot.encode_into(&mut buf[..len - 1]),
+                Err(SerializationError::BufferTooSmall)
+            );
+        }
+
+        let config = Config::default();
+        assert_eq!(config.encode_into(&mut buf), Ok(CONFIG_ENCODED_SIZE));
+        assert_eq!(&buf[..CONFIG_ENCODED_SIZE], &config.encode()[..]);
+        assert_eq!(
+            Input::new(1, 1).encode_into(&mut buf[..1]),
+            Err(SerializationError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn test_snapshot_state_hash() {
+        let mut game = crate::Game::new(Config::default());
+        let snapshot = game.snapshot();
+        assert_eq!(snapshot.state_hash(), game.state_hash());
+
+        game.step(&InputPair::new(0, Input::new(0, 1), Input::new(0, 1)));
+        assert_ne!(game.snapshot().state_hash(), snapshot.state_hash());
+    }
+
+    #[test]
+    fn test_snapshot_config_hash() {
+        let snapshot = crate::Game::new(Config::default()).snapshot();
+        assert_ne!(snapshot.config_hash, 0);
+
+        // The hash forces the counters into the tail even when they are zero
+        let encoded = snapshot.encode();
+        assert_eq!(encoded.len(), 49 + 1 + 2 + 2 + 8);
+        assert_eq!(Snapshot::decode(&encoded).unwrap(), snapshot);
+
+        // Older encodings without the hash decode to 0
+        let stalled = Snapshot {
+            stall_ticks: 5,
+            ..snapshot.clone()
+        };
+        let encoded = stalled.encode();
+        let without_hash = Snapshot::decode(&encoded[..encoded.len() - 8]).unwrap();
+        assert_eq!(without_hash.config_hash, 0);
+        assert_eq!(without_hash.stall_ticks, 5);
+    }
+
+    #[test]
+    fn test_config_fingerprint() {
+        let config = Config::default();
+        assert_eq!(config.fingerprint(), Config::default().fingerprint());
+        assert_ne!(
+            config.fingerprint(),
+            Config {
+                seed: config.seed + 1,
+                ..config
+            }
+            .fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_layout_fingerprint() {
+        let config = Config::default();
+        assert_eq!(config.layout_fingerprint(&[]), config.fingerprint());
+
+        let obstacle = Obstacle::new(
+            Vec2::new(FX_ONE / 2, FX_ONE / 2),
+            Vec2::new(FX_ONE / 64, FX_ONE / 16),
+            ObstacleMotion::Static,
+        );
+        let moving = Obstacle {
+            motion: ObstacleMotion::PingPong {
+                offset: Vec2::new(0, FX_ONE / 4),
+                period: 120,
+            },
+            ..obstacle
+        };
+        let one = config.layout_fingerprint(&[obstacle]);
+        assert_ne!(one, config.fingerprint());
+        assert_ne!(one, config.layout_fingerprint(&[obstacle, obstacle]));
+        assert_ne!(one, config.layout_fingerprint(&[moving]));
+        assert_eq!(one, config.layout_fingerprint(&[obstacle]));
+    }
+
+    #[test]
+    fn test_config_serialization() {
+        let config = Config::default();
+        let encoded = config.encode();
+        assert_eq!(encoded.len(), CONFIG_ENCODED_SIZE);
+        assert_eq!(Config::decode(&encoded).unwrap(), config);
+
+        // Every field survives, including ones far from their defaults
+        let custom = Config {
+            paddle_half_h: -1,
+            rally_ramp_hits: 7,
+            max_score: 21,
+            seed: u64::MAX - 1,
+            tick_hz: 240,
+            physics_substeps: 4,
+            warmup_secs: 3,
+            stuck_timeout_secs: 9,
+            drama_rally_hits: 0xBEEF,
+            goal_gap_y: i32::MIN,
+            ..config
+        };
+        assert_eq!(Config::decode(&custom.encode()).unwrap(), custom);
+
+        assert_eq!(
+            Config::decode(&encoded[..CONFIG_ENCODED_SIZE - 1]),
+            Err(SerializationError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_snapshot_decode_insufficient_data() {
+        let short_data = vec![0u8; 10]; // Too short
+        assert_eq!(
+            Snapshot::decode(&short_data),
+            Err(SerializationError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_snapshot_decode_invalid_status() {
+        let mut data = vec![0u8; 49];
+        data[4] = 99; // Invalid status discriminant
+        assert_eq!(
+            Snapshot::decode(&data),
+            Err(SerializationError::InvalidData)
+        );
+    }
+
+    #[test]
+    fn test_snapshot_decode_invalid_side() {
+        let mut data = vec![0u8; 49];
+        data[4] = 3; // Status::Scored
+        data[5] = 99; // Invalid side
+        assert_eq!(
+            Snapshot::decode(&data),
+            Err(SerializationError::InvalidData)
+        );
+    }
+}
@@ -0,0 +1,1924 @@
+//! Core types and constants for the deterministic Pong game engine.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::ops::{Add, AddAssign, BitAnd, BitOr, BitOrAssign, Index, IndexMut, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// Fixed-point type using 16.16 format (16 integer bits, 16 fractional bits)
+pub type Fx = i32;
+
+/// One unit in fixed-point format
+pub const FX_ONE: Fx = 1 << 16;
+
+/// Tick counter type
+pub type Tick = u32;
+
+/// Signed distance from `b` to `a`, correct across u32 wraparound as long as
+/// the ticks are within 2^31 of each other
+pub fn tick_diff(a: Tick, b: Tick) -> i32 {
+    a.wrapping_sub(b) as i32
+}
+
+/// True if tick `a` comes strictly after tick `b`, treating the counter as
+/// wrapping (so `0` is after `u32::MAX`)
+pub fn tick_after(a: Tick, b: Tick) -> bool {
+    tick_diff(a, b) > 0
+}
+
+/// Player/paddle side
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    /// Both sides in index order
+    pub const BOTH: [Side; 2] = [Side::Left, Side::Right];
+
+    /// Get the opposite side
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+
+    /// Index into per-side `[T; 2]` arrays (0 = left, 1 = right)
+    pub fn index(self) -> usize {
+        match self {
+            Side::Left => 0,
+            Side::Right => 1,
+        }
+    }
+
+    /// Side for a per-side array index, or None if out of range
+    pub fn from_index(index: usize) -> Option<Side> {
+        match index {
+            0 => Some(Side::Left),
+            1 => Some(Side::Right),
+            _ => None,
+        }
+    }
+}
+
+/// Per-side arrays (paddles, score) can be indexed by `Side` directly
+impl<T> Index<Side> for [T; 2] {
+    type Output = T;
+
+    fn index(&self, side: Side) -> &T {
+        &self[side.index()]
+    }
+}
+
+impl<T> IndexMut<Side> for [T; 2] {
+    fn index_mut(&mut self, side: Side) -> &mut T {
+        &mut self[side.index()]
+    }
+}
+
+/// Game status
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Status {
+    /// Waiting for players to be ready
+    Lobby,
+    /// Practice rally before the match, no points scored (ticks remaining)
+    Warmup(u16),
+    /// Countdown before serve (ticks remaining)
+    Countdown(u16),
+    /// Active gameplay
+    Playing,
+    /// Someone scored (scorer, ticks until next serve)
+    Scored(Side, u16),
+    /// Game over (winner)
+    GameOver(Side),
+    /// Held by the players; play continues from where it stopped
+    Paused,
+}
+
+impl Status {
+    /// True once the match is over and no further play happens
+    pub fn is_terminal(self) -> bool {
+        matches!(self, Status::GameOver(_))
+    }
+
+    /// Ticks left in the pre-serve countdown, or None when not counting down
+    pub fn countdown_remaining(self) -> Option<u16> {
+        match self {
+            Status::Countdown(ticks) => Some(ticks),
+            _ => None,
+        }
+    }
+
+    /// Side that just scored, while the post-point pause is running
+    pub fn scorer(self) -> Option<Side> {
+        match self {
+            Status::Scored(side, _) => Some(side),
+            _ => None,
+        }
+    }
+
+    /// Match winner, once the game is over
+    pub fn winner(self) -> Option<Side> {
+        match self {
+            Status::GameOver(side) => Some(side),
+            _ => None,
+        }
+    }
+}
+
+/// Game configuration
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))] // Missing fields fall back to defaults
+pub struct Config {
+    /// Half-height of paddle
+    pub paddle_half_h: Fx,
+    /// Paddle movement speed (units per tick)
+    pub paddle_speed: Fx,
+    /// Max paddle velocity gain per tick (>= paddle_speed means instant)
+    pub paddle_accel: Fx,
+    /// Max paddle velocity loss per tick when slowing or reversing
+    pub paddle_decel: Fx,
+    /// Initial ball speed
+    pub ball_speed: Fx,
+    /// Speed multiplier on paddle hit
+    pub ball_speed_up: Fx,
+    /// Paddle hits per rally speed ramp step (0 = no ramp)
+    pub rally_ramp_hits: u8,
+    /// Extra speed multiplier applied at each rally ramp step
+    pub rally_ramp_speed_up: Fx,
+    /// Wall thickness (usually 0)
+    pub wall_thickness: Fx,
+    /// Fraction of Y speed kept on a wall bounce (FX_ONE = perfectly elastic)
+    pub wall_restitution: Fx,
+    /// Fraction of X speed lost on a wall bounce (0 = frictionless)
+    pub wall_friction: Fx,
+    /// Paddle X position from edge
+    pub paddle_x: Fx,
+    /// Paddle tilt at full tilt input, in turns (FX_ONE = 360 degrees)
+    pub paddle_max_tilt: Fx,
+    /// Max random rotation of the ball on a paddle hit, in turns (0 = off)
+    pub bounce_jitter: Fx,
+    /// Score to win
+    pub max_score: u8,
+    /// Random seed
+    pub seed: u64,
+    /// Tick frequency (Hz)
+    pub tick_hz: u16,
+    /// Fixed ball collision radius
+    pub ball_radius: Fx,
+    /// Paddle width for collision detection
+    pub paddle_width: Fx,
+    /// Physics substeps per tick (ball integrated in 1/substeps increments)
+    pub physics_substeps: u8,
+    /// Warmup rally length in seconds before the countdown (0 = no warmup)
+    pub warmup_secs: u8,
+    /// Slowest horizontal ball speed before it is nudged (0 = never nudge)
+    pub stuck_min_vx: Fx,
+    /// Seconds without paddle contact before the ball is re-served (0 = never)
+    pub stuck_timeout_secs: u8,
+    /// Paddle hits after which a rally counts as high-tension (0 = never)
+    pub drama_rally_hits: u16,
+    /// Ball speed above which play counts as high-tension (0 = never)
+    pub drama_speed: Fx,
+    /// Field width in units; height is always 1.0 (FX_ONE = square field)
+    pub field_width: Fx,
+    /// Height of the scoring gap in each end wall (>= FX_ONE = open edge)
+    pub goal_gap: Fx,
+    /// Vertical center of the scoring gap
+    pub goal_gap_y: Fx,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            paddle_half_h: FX_ONE / 8,             // 1/8 unit = 8192 (exact)
+            paddle_speed: FX_ONE * 3,              // 3.0 units/s = 196608 (exact)
+            paddle_accel: FX_ONE * 3,              // Full speed in one tick
+            paddle_decel: FX_ONE * 6,              // Full reversal in one tick
+            ball_speed: FX_ONE / 2,                // 0.5 units/s = 32768 (exact)
+            ball_speed_up: FX_ONE + (FX_ONE / 20), // +5% per hit
+            rally_ramp_hits: 0,
+            rally_ramp_speed_up: FX_ONE + (FX_ONE / 10), // +10% per ramp step
+            wall_thickness: 0,
+            wall_restitution: FX_ONE,
+            wall_friction: 0,
+            paddle_x: fx::from_f32(0.05), // 5% from edge (precise conversion)
+            paddle_max_tilt: FX_ONE / 36, // 10 degrees
+            bounce_jitter: 0,
+            max_score: 11,
+            seed: 0xC0FFEE,
+            tick_hz: 60,
+            ball_radius: fx::from_f32(1.0 / 32.0), // Precise small ball radius
+            paddle_width: fx::from_f32(0.025),     // 2.5% width (precise conversion)
+            physics_substeps: 1,
+            warmup_secs: 0,
+            stuck_min_vx: 0,       // Classic rules: a crawling ball is left alone
+            stuck_timeout_secs: 0, // Never re-served
+            drama_rally_hits: 10,
+            drama_speed: FX_ONE, // Twice the serve speed
+            field_width: FX_ONE,
+            goal_gap: FX_ONE,
+            goal_gap_y: FX_ONE / 2,
+        }
+    }
+}
+
+impl Config {
+    /// Number of physics substeps per tick (never less than 1)
+    pub fn substeps(&self) -> u8 {
+        self.physics_substeps.max(1)
+    }
+
+    /// Warmup length in ticks (0 when warmup is disabled)
+    pub fn warmup_ticks(&self) -> u16 {
+        (self.warmup_secs as u16).saturating_mul(self.tick_hz)
+    }
+
+    /// Ticks without paddle contact before a re-serve (0 when disabled)
+    pub fn stuck_timeout_ticks(&self) -> u16 {
+        (self.stuck_timeout_secs as u16).saturating_mul(self.tick_hz)
+    }
+
+    /// Ball integration rate in substeps per second
+    pub fn substep_hz(&self) -> i32 {
+        self.tick_hz as i32 * self.substeps() as i32
+    }
+
+    /// Field width in units (never narrower than a tiny positive width)
+    pub fn width(&self) -> Fx {
+        self.field_width.max(1)
+    }
+
+    /// Center of the playing field
+    pub fn field_center(&self) -> Vec2 {
+        Vec2::new(self.width() / 2, FX_ONE / 2)
+    }
+
+    /// Whether a ball at height `y` lines up with the goal gap
+    pub fn in_goal_gap(&self, y: Fx) -> bool {
+        self.goal_gap >= FX_ONE
+            || fx::abs_fx(y.saturating_sub(self.goal_gap_y)) <= self.goal_gap / 2
+    }
+
+    /// X coordinate of a paddle's center line
+    pub fn paddle_center_x(&self, side: Side) -> Fx {
+        match side {
+            Side::Left => self.paddle_x,
+            Side::Right => self.width() - self.paddle_x,
+        }
+    }
+
+    /// Names of the fields whose values differ from `other` (empty when identical)
+    pub fn differing_fields(&self, other: &Config) -> Vec<&'static str> {
+        macro_rules! diff {
+            ($($field:ident),* $(,)?) => {{
+                let mut fields = Vec::new();
+                $(
+                    if self.$field != other.$field {
+                        fields.push(stringify!($field));
+                    }
+                )*
+                fields
+            }};
+        }
+
+        diff!(
+            paddle_half_h,
+            paddle_speed,
+            paddle_accel,
+            paddle_decel,
+            ball_speed,
+            ball_speed_up,
+            rally_ramp_hits,
+            rally_ramp_speed_up,
+            wall_thickness,
+            wall_restitution,
+            wall_friction,
+            paddle_x,
+            paddle_max_tilt,
+            bounce_jitter,
+            max_score,
+            seed,
+            tick_hz,
+            ball_radius,
+            paddle_width,
+            physics_substeps,
+            warmup_secs,
+            stuck_min_vx,
+            stuck_timeout_secs,
+            drama_rally_hits,
+            drama_speed,
+            field_width,
+            goal_gap,
+            goal_gap_y,
+        )
+    }
+}
+
+/// 2D vector in fixed-point
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vec2 {
+    pub x: Fx,
+    pub y: Fx,
+}
+
+impl Vec2 {
+    pub fn new(x: Fx, y: Fx) -> Self {
+        Vec2 { x, y }
+    }
+
+    pub fn zero() -> Self {
+        Vec2 { x: 0, y: 0 }
+    }
+
+    /// Scale both components by a fixed-point factor, saturating
+    pub fn mul_fx(self, scale: Fx) -> Self {
+        Vec2::new(fx::mul_fx(self.x, scale), fx::mul_fx(self.y, scale))
+    }
+
+    /// Dot product, saturating on overflow
+    pub fn dot(self, other: Vec2) -> Fx {
+        let x = ((self.x as i64) * (other.x as i64)) >> 16;
+        let y = ((self.y as i64) * (other.y as i64)) >> 16;
+        fx::saturate(x + y)
+    }
+
+    /// Squared length in raw 32.32 units (no rounding shift), so comparisons
+    /// between lengths are exact
+    pub fn length_sq(self) -> i64 {
+        let (x, y) = (self.x as i64, self.y as i64);
+        x.saturating_mul(x).saturating_add(y.saturating_mul(y))
+    }
+
+    /// Length, rounded down and saturating at `Fx::MAX`
+    pub fn length(self) -> Fx {
+        (self.length_sq() as u64).isqrt().min(Fx::MAX as u64) as Fx
+    }
+
+    /// Unit vector in the same direction; the zero vector stays zero
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+        if length == 0 {
+            return Vec2::zero();
+        }
+        Vec2::new(fx::div_fx(self.x, length), fx::div_fx(self.y, length))
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Vec2;
+
+    fn add(self, rhs: Vec2) -> Vec2 {
+        Vec2::new(self.x.saturating_add(rhs.x), self.y.saturating_add(rhs.y))
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Vec2;
+
+    fn sub(self, rhs: Vec2) -> Vec2 {
+        Vec2::new(self.x.saturating_sub(rhs.x), self.y.saturating_sub(rhs.y))
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Vec2;
+
+    fn neg(self) -> Vec2 {
+        Vec2::new(self.x.saturating_neg(), self.y.saturating_neg())
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, rhs: Vec2) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, rhs: Vec2) {
+        *self = *self - rhs;
+    }
+}
+
+/// Paddle state
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Paddle {
+    /// Y position (center)
+    pub y: Fx,
+    /// Y velocity
+    pub vy: Fx,
+    /// Face tilt in turns, positive angles the face toward +Y.
+    /// Recomputed from input every tick, so it is not part of snapshots.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tilt: Fx,
+}
+
+impl Paddle {
+    pub fn new(y: Fx) -> Self {
+        Paddle { y, vy: 0, tilt: 0 }
+    }
+}
+
+/// Ball state
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ball {
+    /// Position
+    pub pos: Vec2,
+    /// Velocity
+    pub vel: Vec2,
+}
+
+impl Ball {
+    pub fn new(pos: Vec2, vel: Vec2) -> Self {
+        Ball { pos, vel }
+    }
+}
+
+/// Deterministic motion path for an obstacle, measured in ticks
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ObstacleMotion {
+    /// Never moves
+    Static,
+    /// Slides linearly to `origin + offset` and back once per period
+    PingPong { offset: Vec2, period: u16 },
+    /// Oscillates sinusoidally around the origin once per period
+    Sine { amplitude: Vec2, period: u16 },
+}
+
+impl ObstacleMotion {
+    /// Length of one motion cycle in ticks (0 for static obstacles)
+    pub fn period(&self) -> u16 {
+        match *self {
+            ObstacleMotion::Static => 0,
+            ObstacleMotion::PingPong { period, .. } | ObstacleMotion::Sine { period, .. } => period,
+        }
+    }
+
+    /// Displacement from the origin at the given phase
+    pub fn offset_at(&self, phase: u16) -> Vec2 {
+        let period = self.period();
+        if period == 0 {
+            return Vec2::zero();
+        }
+
+        // Fraction of the cycle completed, in turns
+        let t = fx::from_ratio((phase % period) as i32, period as i32);
+        match *self {
+            ObstacleMotion::Static => Vec2::zero(),
+            ObstacleMotion::PingPong { offset, .. } => {
+                // Triangle wave: 0 -> 1 -> 0 over the period
+                let tri = FX_ONE - fx::abs_fx(2 * t - FX_ONE);
+                Vec2::new(fx::mul_fx(offset.x, tri), fx::mul_fx(offset.y, tri))
+            }
+            ObstacleMotion::Sine { amplitude, .. } => {
+                let s = fx::sin_turns(t);
+                Vec2::new(fx::mul_fx(amplitude.x, s), fx::mul_fx(amplitude.y, s))
+            }
+        }
+    }
+}
+
+/// Axis-aligned rectangular obstacle that the ball bounces off
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Obstacle {
+    /// Rest position of the center
+    pub origin: Vec2,
+    /// Half extents of the rectangle
+    pub half_size: Vec2,
+    /// Motion path followed around the origin
+    pub motion: ObstacleMotion,
+    /// Current center position
+    pub pos: Vec2,
+    /// Current velocity (units per second)
+    pub vel: Vec2,
+    /// Ticks into the current motion cycle
+    pub phase: u16,
+}
+
+impl Obstacle {
+    pub fn new(origin: Vec2, half_size: Vec2, motion: ObstacleMotion) -> Self {
+        Obstacle {
+            origin,
+            half_size,
+            motion,
+            pos: origin,
+            vel: Vec2::zero(),
+            phase: 0,
+        }
+    }
+
+    /// Jump to a phase, recomputing position and velocity from the path
+    pub fn set_phase(&mut self, phase: u16, tick_hz: u16) {
+        let period = self.motion.period().max(1);
+        let phase = phase % period;
+        let prev = self.motion.offset_at((phase + period - 1) % period);
+        let offset = self.motion.offset_at(phase);
+
+        self.phase = phase;
+        self.pos = Vec2::new(self.origin.x + offset.x, self.origin.y + offset.y);
+        self.vel = Vec2::new(
+            (offset.x - prev.x).saturating_mul(tick_hz as i32),
+            (offset.y - prev.y).saturating_mul(tick_hz as i32),
+        );
+    }
+
+    /// Advance the obstacle one tick along its motion path
+    pub fn advance(&mut self, tick_hz: u16) {
+        let period = self.motion.period().max(1);
+        self.set_phase((self.phase + 1) % period, tick_hz);
+    }
+}
+
+/// Button bitflags carried in each input. Unknown bits are kept as-is so
+/// inputs from newer peers round-trip unchanged.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Buttons(u8);
+
+impl Buttons {
+    /// Player is ready to start (bit 0, the original ready button)
+    pub const READY: Buttons = Buttons(1 << 0);
+    /// Dash / boost
+    pub const DASH: Buttons = Buttons(1 << 1);
+    /// Request a pause
+    pub const PAUSE: Buttons = Buttons(1 << 2);
+    /// Cosmetic taunt
+    pub const TAUNT: Buttons = Buttons(1 << 3);
+
+    /// No buttons pressed
+    pub const fn empty() -> Self {
+        Buttons(0)
+    }
+
+    /// Wrap raw wire bits, keeping any unnamed bits
+    pub const fn from_bits_retain(bits: u8) -> Self {
+        Buttons(bits)
+    }
+
+    /// Raw bits for the wire
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// True if every bit in `other` is set
+    pub const fn contains(self, other: Buttons) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: Buttons) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Buttons) {
+        self.0 &= !other.0;
+    }
+
+    /// Insert or remove `other` depending on `pressed`
+    pub fn set(&mut self, other: Buttons, pressed: bool) {
+        if pressed {
+            self.insert(other);
+        } else {
+            self.remove(other);
+        }
+    }
+}
+
+impl From<u8> for Buttons {
+    fn from(bits: u8) -> Self {
+        Buttons::from_bits_retain(bits)
+    }
+}
+
+impl From<Buttons> for u8 {
+    fn from(buttons: Buttons) -> Self {
+        buttons.bits()
+    }
+}
+
+impl BitOr for Buttons {
+    type Output = Buttons;
+
+    fn bitor(self, rhs: Buttons) -> Buttons {
+        Buttons(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Buttons {
+    fn bitor_assign(&mut self, rhs: Buttons) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for Buttons {
+    type Output = Buttons;
+
+    fn bitand(self, rhs: Buttons) -> Buttons {
+        Buttons(self.0 & rhs.0)
+    }
+}
+
+/// Player input for one tick
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Input {
+    /// Vertical axis input [-127, 127]
+    pub axis_y: i8,
+    /// Horizontal axis input [-127, 127]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub axis_x: i8,
+    /// Pressed buttons
+    pub buttons: Buttons,
+    /// Paddle tilt axis input [-127, 127]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tilt: i8,
+}
+
+impl Input {
+    pub fn new(axis_y: i8, buttons: impl Into<Buttons>) -> Self {
+        Input {
+            axis_y,
+            axis_x: 0,
+            buttons: buttons.into(),
+            tilt: 0,
+        }
+    }
+
+    pub fn zero() -> Self {
+        Input {
+            axis_y: 0,
+            axis_x: 0,
+            buttons: Buttons::empty(),
+            tilt: 0,
+        }
+    }
+
+    /// Set the horizontal axis
+    pub fn with_axis_x(mut self, axis_x: i8) -> Self {
+        self.axis_x = axis_x;
+        self
+    }
+
+    /// Set the tilt axis
+    pub fn with_tilt(mut self, tilt: i8) -> Self {
+        self.tilt = tilt;
+        self
+    }
+
+    /// Check if the ready button is pressed
+    pub fn is_ready(&self) -> bool {
+        self.buttons.contains(Buttons::READY)
+    }
+}
+
+/// Input pair for both players on a specific tick
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InputPair {
+    pub tick: Tick,
+    pub a: Input, // Left player
+    pub b: Input, // Right player
+}
+
+impl InputPair {
+    pub fn new(tick: Tick, a: Input, b: Input) -> Self {
+        InputPair { tick, a, b }
+    }
+
+    pub fn get_input(&self, side: Side) -> Input {
+        match side {
+            Side::Left => self.a,
+            Side::Right => self.b,
+        }
+    }
+}
+
+/// Game state snapshot for synchronization
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot {
+    pub tick: Tick,
+    pub status: Status,
+    pub paddles: [Paddle; 2],
+    pub ball: Ball,
+    pub score: [u8; 2],
+    pub rng: u64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub obstacle_phases: Vec<u16>, // Motion phase of each obstacle, in order
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rally_hits: u16, // Paddle hits in the current rally
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub stall_ticks: u16, // Ticks since the ball last touched a paddle
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub config_hash: u64, // `Config::layout_fingerprint` of the rules it was taken under (0 = unknown)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extra_balls: Vec<Ball>, // Balls in play besides `ball`
+}
+
+/// State hash recorded for one simulated tick
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub tick: Tick,
+    pub hash: u64,
+}
+
+/// Ring buffer of per-tick state hashes for cross-platform determinism checks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhysicsTrace {
+    capacity: usize,
+    entries: VecDeque<TraceEntry>,
+}
+
+impl PhysicsTrace {
+    /// Create a trace that keeps the most recent `capacity` ticks
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        PhysicsTrace {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Append a tick's hash, evicting the oldest entry when full
+    pub fn record(&mut self, tick: Tick, hash: u64) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(TraceEntry { tick, hash });
+    }
+
+    /// Recorded entries, oldest first
+    pub fn entries(&self) -> impl Iterator<Item = &TraceEntry> {
+        self.entries.iter()
+    }
+
+    /// Hash recorded for a specific tick, if still in the buffer
+    pub fn hash_at(&self, tick: Tick) -> Option<u64> {
+        self.entries.iter().find(|e| e.tick == tick).map(|e| e.hash)
+    }
+
+    /// First tick present in both traces whose hashes differ
+    pub fn first_divergence(&self, other: &PhysicsTrace) -> Option<Tick> {
+        self.entries
+            .iter()
+            .find(|e| other.hash_at(e.tick).is_some_and(|h| h != e.hash))
+            .map(|e| e.tick)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Screen rectangle for pre-computed rendering coordinates
+#[derive(Debug, Copy, Clone)]
+pub struct ScreenRect {
+    pub left: usize,
+    pub right: usize,
+    pub top: usize,
+    pub bottom: usize,
+}
+
+impl ScreenRect {
+    pub fn new(left: usize, right: usize, top: usize, bottom: usize) -> Self {
+        ScreenRect {
+            left,
+            right,
+            top,
+            bottom,
+        }
+    }
+}
+
+/// Pure physics view - client agnostic game state
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct View {
+    pub tick: Tick,
+    pub status: Status,
+    pub score: [u8; 2], // [left, right] scores
+
+    // Pure physics data (no screen coordinates)
+    pub left_paddle_y: Fx,
+    pub right_paddle_y: Fx,
+    pub left_paddle_tilt: Fx, // Face tilt in turns
+    pub right_paddle_tilt: Fx,
+    pub left_paddle_vy: Fx, // Paddle velocities in units/sec
+    pub right_paddle_vy: Fx,
+    pub paddle_half_h: Fx,
+    pub ball_pos: Vec2,
+    pub ball_vel: Vec2,      // Units/sec, for interpolation and motion blur
+    pub paddle_x_offset: Fx, // Distance from edge
+    pub paddle_width: Fx,
+    pub ball_radius: Fx,
+    pub field_width: Fx, // Field width in units (height is FX_ONE)
+    pub tension: Tension,
+    pub countdown_ticks_remaining: u16, // 0 unless counting down to a serve
+    pub serving_side: Option<Side>,     // Who serves next, while a serve is pending
+}
+
+/// Declares `ViewDelta` with one optional slot per listed `View` field, so the
+/// struct, `between` and `apply` can't drift apart when View grows
+macro_rules! view_delta {
+    ($($field:ident: $ty:ty),* $(,)?) => {
+        /// Fields of a `View` that changed since a previous frame, for pushing
+        /// minimal updates to clients. `tick` is always present; every other
+        /// field is None when unchanged and is omitted from serialized output.
+        #[derive(Debug, Default, Clone, PartialEq)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        pub struct ViewDelta {
+            pub tick: Tick,
+            $(
+                #[cfg_attr(
+                    feature = "serde",
+                    serde(
+                        default,
+                        skip_serializing_if = "Option::is_none",
+                        deserialize_with = "delta_field"
+                    )
+                )]
+                pub $field: Option<$ty>,
+            )*
+        }
+
+        impl ViewDelta {
+            /// Changes needed to turn `prev` into `next`
+            pub fn between(prev: &View, next: &View) -> Self {
+                ViewDelta {
+                    tick: next.tick,
+                    $($field: (prev.$field != next.$field).then_some(next.$field),)*
+                }
+            }
+
+            /// Delta carrying every field, for a client with no prior view
+            pub fn full(view: &View) -> Self {
+                ViewDelta {
+                    tick: view.tick,
+                    $($field: Some(view.$field),)*
+                }
+            }
+
+            /// Update `view` in place with the changed fields
+            pub fn apply(&self, view: &mut View) {
+                view.tick = self.tick;
+                $(
+                    if let Some(value) = self.$field {
+                        view.$field = value;
+                    }
+                )*
+            }
+
+            /// True if nothing but the tick changed
+            pub fn is_empty(&self) -> bool {
+                true $(&& self.$field.is_none())*
+            }
+        }
+    };
+}
+
+view_delta! {
+    status: Status,
+    score: [u8; 2],
+    left_paddle_y: Fx,
+    right_paddle_y: Fx,
+    left_paddle_tilt: Fx,
+    right_paddle_tilt: Fx,
+    left_paddle_vy: Fx,
+    right_paddle_vy: Fx,
+    paddle_half_h: Fx,
+    ball_pos: Vec2,
+    ball_vel: Vec2,
+    paddle_x_offset: Fx,
+    paddle_width: Fx,
+    ball_radius: Fx,
+    field_width: Fx,
+    tension: Tension,
+    countdown_ticks_remaining: u16,
+    serving_side: Option<Side>,
+}
+
+/// A present delta field is always `Some`, even when its value is itself
+/// None (e.g. `serving_side` becoming null)
+#[cfg(feature = "serde")]
+fn delta_field<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// Why the current tick counts as high-tension. Computed by the engine so
+/// both peers agree on when clients switch music or visuals.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tension {
+    /// A player is one point from winning
+    pub match_point: bool,
+    /// The rally has reached `drama_rally_hits` paddle hits
+    pub long_rally: bool,
+    /// The ball is moving faster than `drama_speed`
+    pub fast_ball: bool,
+}
+
+impl Tension {
+    /// True when any tension flag is set
+    pub fn is_high(&self) -> bool {
+        self.match_point || self.long_rally || self.fast_ball
+    }
+}
+
+/// Pixel-perfect rendering helper for consistent paddle heights
+pub struct RenderHelper {
+    field_width: usize,
+    field_height: usize,
+    paddle_height_pixels: usize, // Fixed height in pixels - calculated once
+    paddle_width_pixels: usize,  // Fixed width in pixels - calculated once
+    field_width_units: Fx,       // Physics width mapped onto field_width
+    paddle_x: [Fx; 2],           // Physics X of each paddle's center [left, right]
+}
+
+impl RenderHelper {
+    /// Create a new render helper with fixed paddle dimensions
+    pub fn new(field_width: usize, field_height: usize, config: &Config) -> Self {
+        // Calculate fixed paddle height in pixels (independent of position)
+        let paddle_height_ratio = fx::to_f32(config.paddle_half_h) * 2.0; // Full height ratio
+        let paddle_height_pixels = ((paddle_height_ratio * field_height as f32).max(2.0).round()
+            as usize)
+            .max(2) // Ensure minimum 2 pixels
+            .min(field_height / 3); // Ensure reasonable maximum
+
+        // Calculate fixed paddle width in pixels, relative to the field width
+        let field_width_units = config.width();
+        let paddle_width_ratio = fx::to_f32(config.paddle_width) / fx::to_f32(field_width_units);
+        let paddle_width_pixels = ((paddle_width_ratio * field_width as f32).max(1.0).round()
+            as usize)
+            .max(1) // Ensure minimum 1 pixel
+            .min(field_width / 10); // Ensure reasonable maximum
+
+        RenderHelper {
+            field_width,
+            field_height,
+            paddle_height_pixels,
+            paddle_width_pixels,
+            field_width_units,
+            paddle_x: [
+                config.paddle_center_x(Side::Left),
+                config.paddle_center_x(Side::Right),
+            ],
+        }
+    }
+
+    /// Convert physics Y coordinate to screen Y coordinate
+    pub fn physics_to_screen_y(&self, physics_y: Fx) -> usize {
+        let clamped = fx::clamp_fx(physics_y, 0, FX_ONE);
+        let normalized = fx::to_f32(clamped);
+        // Y-axis inversion for screen coordinates
+        let screen_coord = (1.0 - normalized) * (self.field_height - 1) as f32;
+        (screen_coord + 0.5) as usize // Round to nearest pixel
+    }
+
+    /// Convert physics X coordinate to screen X coordinate
+    pub fn physics_to_screen_x(&self, physics_x: Fx) -> usize {
+        let clamped = fx::clamp_fx(physics_x, 0, self.field_width_units);
+        let normalized = fx::to_f32(clamped) / fx::to_f32(self.field_width_units);
+        let screen_coord = normalized * (self.field_width - 1) as f32;
+        (screen_coord + 0.5) as usize // Round to nearest pixel
+    }
+
+    /// Get paddle rectangle with PERFECT consistent height - ALWAYS same height
+    pub fn get_paddle_rect(&self, paddle_y: Fx, side: Side) -> ScreenRect {
+        // Calculate paddle center in screen coordinates
+        let center_y = self.physics_to_screen_y(paddle_y);
+
+        // ABSOLUTELY guaranteed consistent height - never changes for any reason
+        let half_height = self.paddle_height_pixels / 2;
+
+        // Always use exact same top/bottom calculation
+        // If this goes off-screen, so be it - consistency is more important
+        let top = center_y.saturating_sub(half_height);
+        let bottom = top + self.paddle_height_pixels - 1; // Always exactly paddle_height_pixels tall
+
+        // Ensure we stay within bounds without changing height
+        let (final_top, final_bottom) = if bottom >= self.field_height {
+            // Slide the entire paddle up to fit, maintaining exact height
+            let final_bottom = self.field_height - 1;
+            let final_top = final_bottom - self.paddle_height_pixels + 1;
+            (final_top, final_bottom)
+        } else if top == 0 {
+            // Already at top, height is correct
+            (top, bottom)
+        } else {
+            // Normal case - paddle fits perfectly
+            (top, bottom)
+        };
+
+        // Verify height is always consistent (debug assertion)
+        debug_assert_eq!(
+            final_bottom - final_top + 1,
+            self.paddle_height_pixels,
+            "Paddle height inconsistency! Expected {}, got {}",
+            self.paddle_height_pixels,
+            final_bottom - final_top + 1
+        );
+
+        // Calculate X position from the same paddle line physics collides with
+        let center_x = self.physics_to_screen_x(self.paddle_x[side]);
+        let half_width = self.paddle_width_pixels / 2;
+        let left = center_x.saturating_sub(half_width);
+        let right = (center_x + half_width).min(self.field_width.saturating_sub(1));
+
+        ScreenRect::new(left, right, final_top, final_bottom)
+    }
+
+    /// Get ball position in screen coordinates
+    pub fn get_ball_position(&self, ball_pos: Vec2) -> (usize, usize) {
+        (
+            self.physics_to_screen_x(ball_pos.x),
+            self.physics_to_screen_y(ball_pos.y),
+        )
+    }
+
+    /// Get ball position in fractional cells (16.16 fixed point, same axes as
+    /// `get_ball_position`) for sub-cell renderers such as Braille or canvas.
+    /// `fx::round_fx` of each component gives the whole-cell position.
+    pub fn get_ball_position_precise(&self, ball_pos: Vec2) -> Vec2 {
+        let x = fx::clamp_fx(ball_pos.x, 0, self.field_width_units) as i64;
+        let y = fx::clamp_fx(ball_pos.y, 0, FX_ONE) as i64;
+        let max_col = self.field_width.saturating_sub(1) as i64;
+        let max_row = self.field_height.saturating_sub(1) as i64;
+
+        // Y-axis inversion for screen coordinates
+        Vec2::new(
+            fx::saturate(x * max_col * FX_ONE as i64 / self.field_width_units.max(1) as i64),
+            fx::saturate((FX_ONE as i64 - y) * max_row),
+        )
+    }
+
+    /// Get the fixed paddle height in pixels (always consistent)
+    pub fn paddle_height_pixels(&self) -> usize {
+        self.paddle_height_pixels
+    }
+
+    /// Get field dimensions
+    pub fn field_dimensions(&self) -> (usize, usize) {
+        (self.field_width, self.field_height)
+    }
+}
+
+/// Pixel rectangle for canvas/GPU rendering (device pixels, Y down)
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PixelRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl PixelRect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        PixelRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Round to whole pixels as (x, y, width, height), clamping negatives to 0
+    pub fn snapped(&self) -> (u32, u32, u32, u32) {
+        let x = self.x.round().max(0.0);
+        let y = self.y.round().max(0.0);
+        let right = (self.x + self.width).round().max(x);
+        let bottom = (self.y + self.height).round().max(y);
+        (x as u32, y as u32, (right - x) as u32, (bottom - y) as u32)
+    }
+}
+
+/// Rendering helper for pixel viewports (canvas, GPU). Physics space is
+/// scaled uniformly to fit the viewport, letterboxed on the long axis, and
+/// multiplied by the DPI scale so results are in device pixels.
+/// Terminal clients should use the cell-based `RenderHelper` instead.
+#[derive(Debug, Copy, Clone)]
+pub struct PixelRenderHelper {
+    viewport_width: f32, // Device pixels
+    viewport_height: f32,
+    scale: f32, // Device pixels per physics unit
+    field_rect: PixelRect,
+    field_width_units: Fx,
+    paddle_x: [Fx; 2],
+    paddle_half_h: Fx,
+    paddle_width: Fx,
+    ball_radius: Fx,
+}
+
+impl PixelRenderHelper {
+    /// Create a helper for a viewport given in logical pixels; `dpi_scale` is
+    /// the device pixel ratio (1.0 for standard displays)
+    pub fn new(viewport_width: f32, viewport_height: f32, dpi_scale: f32, config: &Config) -> Self {
+        let dpi_scale = if dpi_scale > 0.0 { dpi_scale } else { 1.0 };
+        let viewport_width = viewport_width.max(0.0) * dpi_scale;
+        let viewport_height = viewport_height.max(0.0) * dpi_scale;
+
+        // Uniform scale so the whole field fits, centered in the viewport
+        let field_width_units = config.width();
+        let aspect = fx::to_f32(field_width_units);
+        let scale = (viewport_width / aspect).min(viewport_height);
+        let field_rect = PixelRect::new(
+            (viewport_width - aspect * scale) / 2.0,
+            (viewport_height - scale) / 2.0,
+            aspect * scale,
+            scale,
+        );
+
+        PixelRenderHelper {
+            viewport_width,
+            viewport_height,
+            scale,
+            field_rect,
+            field_width_units,
+            paddle_x: [
+                config.paddle_center_x(Side::Left),
+                config.paddle_center_x(Side::Right),
+            ],
+            paddle_half_h: config.paddle_half_h,
+            paddle_width: config.paddle_width,
+            ball_radius: config.ball_radius,
+        }
+    }
+
+    /// Create a helper for an integer viewport in logical pixels
+    pub fn from_u32(
+        viewport_width: u32,
+        viewport_height: u32,
+        dpi_scale: f32,
+        config: &Config,
+    ) -> Self {
+        Self::new(
+            viewport_width as f32,
+            viewport_height as f32,
+            dpi_scale,
+            config,
+        )
+    }
+
+    /// Convert a physics position to device pixels (Y down)
+    pub fn physics_to_pixel(&self, pos: Vec2) -> (f32, f32) {
+        (
+            self.field_rect.x + fx::to_f32(pos.x) * self.scale,
+            self.field_rect.y + (1.0 - fx::to_f32(pos.y)) * self.scale,
+        )
+    }
+
+    /// Convert a physics length to device pixels
+    pub fn physics_to_pixels(&self, length: Fx) -> f32 {
+        fx::to_f32(length) * self.scale
+    }
+
+    /// Playing field inside the letterbox bars
+    pub fn field_rect(&self) -> PixelRect {
+        self.field_rect
+    }
+
+    /// Paddle rectangle centered on its physics position
+    pub fn get_paddle_rect(&self, paddle_y: Fx, side: Side) -> PixelRect {
+        let (center_x, center_y) = self.physics_to_pixel(Vec2::new(self.paddle_x[side], paddle_y));
+        let width = self.physics_to_pixels(self.paddle_width);
+        let height = self.physics_to_pixels(self.paddle_half_h * 2);
+        PixelRect::new(
+            center_x - width / 2.0,
+            center_y - height / 2.0,
+            width,
+            height,
+        )
+    }
+
+    /// Ball center and radius in device pixels
+    pub fn get_ball_circle(&self, ball_pos: Vec2) -> (f32, f32, f32) {
+        let (x, y) = self.physics_to_pixel(ball_pos);
+        (x, y, self.physics_to_pixels(self.ball_radius))
+    }
+
+    /// Device pixels per physics unit
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Viewport size in device pixels
+    pub fn viewport_dimensions(&self) -> (f32, f32) {
+        (self.viewport_width, self.viewport_height)
+    }
+
+    /// Physics width mapped across the field rectangle
+    pub fn field_width_units(&self) -> Fx {
+        self.field_width_units
+    }
+}
+
+/// Details of a ball collision, reported by physics for events and effects
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollisionInfo {
+    /// Contact point in field coordinates
+    pub point: Vec2,
+    /// Unit surface normal pointing back into the field
+    pub normal: Vec2,
+    /// Hit position relative to the paddle center, -FX_ONE..FX_ONE (0 for walls)
+    pub impact_offset: Fx,
+    /// Ball speed after the collision
+    pub new_speed: Fx,
+}
+
+/// Game events that can occur during a tick
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Event {
+    Scored {
+        scorer: Side,
+        score: [u8; 2], // New score after this point
+    },
+    /// Ball was crawling horizontally and got nudged toward a paddle
+    BallNudged,
+    /// Ball went too long without touching a paddle and was re-served
+    BallReserved { server: Side },
+    /// Ball bounced off a paddle
+    PaddleHit { side: Side, contact: CollisionInfo },
+    /// Ball bounced off a wall (top, bottom, or an end wall outside the goal)
+    WallHit { contact: CollisionInfo },
+    /// Ball bounced off an obstacle (index into `Game::obstacles`)
+    ObstacleHit { index: u8, contact: CollisionInfo },
+}
+
+/// Fixed-point utility functions
+pub mod fx {
+    use super::{Fx, FX_ONE};
+
+    /// Convert from floating point
+    pub fn from_f32(f: f32) -> Fx {
+        (f * (FX_ONE as f32)) as Fx
+    }
+
+    /// Convert to floating point
+    pub fn to_f32(value: Fx) -> f32 {
+        (value as f32) / (FX_ONE as f32)
+    }
+
+    /// Narrow a wide intermediate to `Fx`, saturating at `Fx::MIN`/`Fx::MAX`
+    pub fn saturate(value: i64) -> Fx {
+        value.clamp(Fx::MIN as i64, Fx::MAX as i64) as Fx
+    }
+
+    /// Fixed-point value of the fraction `n / d`, saturating on overflow.
+    /// A zero denominator saturates toward the sign of `n` like `div_fx`.
+    pub fn from_ratio(n: i32, d: i32) -> Fx {
+        if d == 0 {
+            return div_fx(n, 0);
+        }
+        saturate(((n as i64) << 16) / d as i64)
+    }
+
+    /// Multiply two fixed-point numbers, saturating on overflow
+    pub fn mul_fx(a: Fx, b: Fx) -> Fx {
+        saturate((a as i64) * (b as i64) >> 16)
+    }
+
+    /// Multiply two fixed-point numbers, or None on overflow
+    pub fn checked_mul_fx(a: Fx, b: Fx) -> Option<Fx> {
+        Fx::try_from(((a as i64) * (b as i64)) >> 16).ok()
+    }
+
+    /// Divide two fixed-point numbers, saturating on overflow.
+    /// Division by zero saturates toward the sign of `a` (0 / 0 is 0).
+    pub fn div_fx(a: Fx, b: Fx) -> Fx {
+        if b == 0 {
+            return match a.signum() {
+                1 => Fx::MAX,
+                -1 => Fx::MIN,
+                _ => 0,
+            };
+        }
+        saturate(((a as i64) << 16) / (b as i64))
+    }
+
+    /// Divide two fixed-point numbers, or None on overflow or division by zero
+    pub fn checked_div_fx(a: Fx, b: Fx) -> Option<Fx> {
+        if b == 0 {
+            return None;
+        }
+        Fx::try_from(((a as i64) << 16) / (b as i64)).ok()
+    }
+
+    /// Linear interpolation from `a` (t = 0) to `b` (t = FX_ONE), saturating
+    pub fn lerp(a: Fx, b: Fx, t: Fx) -> Fx {
+        let span = b as i64 - a as i64;
+        saturate(a as i64 + ((span * t as i64) >> 16))
+    }
+
+    /// Smaller of two values
+    pub fn min_fx(a: Fx, b: Fx) -> Fx {
+        a.min(b)
+    }
+
+    /// Larger of two values
+    pub fn max_fx(a: Fx, b: Fx) -> Fx {
+        a.max(b)
+    }
+
+    /// Round down to a whole unit
+    pub fn floor_fx(a: Fx) -> Fx {
+        a & !(FX_ONE - 1)
+    }
+
+    /// Round up to a whole unit, saturating at the largest whole unit
+    pub fn ceil_fx(a: Fx) -> Fx {
+        floor_fx(a.saturating_add(FX_ONE - 1))
+    }
+
+    /// Round to the nearest whole unit, halves away from zero
+    pub fn round_fx(a: Fx) -> Fx {
+        if a >= 0 {
+            floor_fx(a.saturating_add(FX_ONE / 2))
+        } else {
+            -floor_fx(a.saturating_neg().saturating_add(FX_ONE / 2))
+        }
+    }
+
+    /// Round toward zero to a whole unit
+    pub fn trunc_fx(a: Fx) -> Fx {
+        if a >= 0 {
+            floor_fx(a)
+        } else {
+            -floor_fx(a.saturating_neg())
+        }
+    }
+
+    /// Absolute value, saturating `Fx::MIN` to `Fx::MAX`
+    pub fn abs_fx(a: Fx) -> Fx {
+        a.saturating_abs()
+    }
+
+    /// Sine of an angle given in turns (FX_ONE = 360 degrees), using
+    /// Bhaskara's rational approximation so results are exact integers on
+    /// every platform (max error ~0.2%)
+    pub fn sin_turns(turns: Fx) -> Fx {
+        let t = turns & (FX_ONE - 1); // Wrap into one turn
+        let (half, negative) = if t >= FX_ONE / 2 {
+            (t - FX_ONE / 2, true)
+        } else {
+            (t, false)
+        };
+
+        // u in [0, 1) across the half turn; sin ~= 16u(1-u) / (5 - 4u(1-u))
+        let u = half * 2;
+        let p = mul_fx(u, FX_ONE - u);
+        let s = div_fx(16 * p, 5 * FX_ONE - 4 * p);
+        if negative {
+            -s
+        } else {
+            s
+        }
+    }
+
+    /// Clamp between min and max
+    pub fn clamp_fx(value: Fx, min: Fx, max: Fx) -> Fx {
+        if value < min {
+            min
+        } else if value > max {
+            max
+        } else {
+            value
+        }
+    }
+}
+
+/// `Fx` newtype that displays and parses as a decimal ("0.125" rather than 8192).
+/// Display prints the shortest decimal that parses back to the same value;
+/// parsing truncates toward zero, matching `fx::from_f32`.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fixed(pub Fx);
+
+impl From<Fx> for Fixed {
+    fn from(value: Fx) -> Self {
+        Fixed(value)
+    }
+}
+
+impl From<Fixed> for Fx {
+    fn from(value: Fixed) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let magnitude = (self.0 as i64).unsigned_abs();
+        let sign = if self.0 < 0 { "-" } else { "" };
+
+        // 1/65536 > 1e-5, so five fractional digits always land in range
+        let mut scale = 1u64;
+        for digits in 0..=5 {
+            let candidate = (magnitude * scale).div_ceil(FX_ONE as u64);
+            if candidate * (FX_ONE as u64) < (magnitude + 1) * scale || digits == 5 {
+                let whole = candidate / scale;
+                if digits == 0 {
+                    return write!(f, "{sign}{whole}");
+                }
+                let frac = candidate % scale;
+                return write!(f, "{sign}{whole}.{frac:0digits$}");
+            }
+            scale *= 10;
+        }
+        unreachable!()
+    }
+}
+
+/// Errors from parsing a decimal into `Fixed`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseFxError {
+    /// Empty string or missing digits
+    Empty,
+    /// Character that is not a sign, digit or single decimal point
+    InvalidDigit,
+    /// Value does not fit in 16.16 fixed point
+    OutOfRange,
+}
+
+impl fmt::Display for ParseFxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseFxError::Empty => write!(f, "Empty fixed-point value"),
+            ParseFxError::InvalidDigit => write!(f, "Invalid digit in fixed-point value"),
+            ParseFxError::OutOfRange => write!(f, "Fixed-point value out of range"),
+        }
+    }
+}
+
+impl std::error::Error for ParseFxError {}
+
+impl FromStr for Fixed {
+    type Err = ParseFxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let (whole_str, frac_str) = digits.split_once('.').unwrap_or((digits, ""));
+        if whole_str.is_empty() && frac_str.is_empty() {
+            return Err(ParseFxError::Empty);
+        }
+        let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if !all_digits(whole_str) || !all_digits(frac_str) {
+            return Err(ParseFxError::InvalidDigit);
+        }
+
+        let limit = if negative { 1u64 << 31 } else { Fx::MAX as u64 };
+        let mut whole = 0u64;
+        for b in whole_str.bytes() {
+            whole = whole * 10 + (b - b'0') as u64;
+            if whole > limit >> 16 {
+                return Err(ParseFxError::OutOfRange);
+            }
+        }
+
+        // Digits beyond 1e-18 cannot change a 1/65536 truncation
+        let mut frac = 0u64;
+        let mut scale = 1u64;
+        for b in frac_str.bytes().take(18) {
+            frac = frac * 10 + (b - b'0') as u64;
+            scale *= 10;
+        }
+        let frac_fx = ((frac as u128 * FX_ONE as u128) / scale as u128) as u64;
+
+        let magnitude = (whole << 16) + frac_fx;
+        if magnitude > limit {
+            return Err(ParseFxError::OutOfRange);
+        }
+        let value = magnitude as i64;
+        Ok(Fixed(if negative { -value } else { value } as Fx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_point_conversion() {
+        assert_eq!(fx::from_f32(1.0), FX_ONE);
+        assert_eq!(fx::from_f32(0.5), FX_ONE / 2);
+        assert_eq!(fx::to_f32(FX_ONE), 1.0);
+        assert_eq!(fx::to_f32(FX_ONE / 2), 0.5);
+    }
+
+    #[test]
+    fn test_fixed_point_arithmetic() {
+        let a = FX_ONE / 2; // 0.5
+        let b = FX_ONE * 2; // 2.0
+
+        assert_eq!(fx::mul_fx(a, b), FX_ONE); // 0.5 * 2.0 = 1.0
+        assert_eq!(fx::div_fx(FX_ONE, a), b); // 1.0 / 0.5 = 2.0
+    }
+
+    #[test]
+    fn test_fixed_point_saturation() {
+        // Products and quotients past the i32 range clamp instead of wrapping
+        assert_eq!(fx::mul_fx(Fx::MAX, 2 * FX_ONE), Fx::MAX);
+        assert_eq!(fx::mul_fx(Fx::MAX, -2 * FX_ONE), Fx::MIN);
+        assert_eq!(fx::div_fx(Fx::MAX, FX_ONE / 2), Fx::MAX);
+        assert_eq!(fx::div_fx(Fx::MIN, -FX_ONE), Fx::MAX);
+
+        // Division by zero saturates toward the dividend's sign
+        assert_eq!(fx::div_fx(FX_ONE, 0), Fx::MAX);
+        assert_eq!(fx::div_fx(-FX_ONE, 0), Fx::MIN);
+        assert_eq!(fx::div_fx(0, 0), 0);
+
+        assert_eq!(fx::abs_fx(Fx::MIN), Fx::MAX);
+    }
+
+    #[test]
+    fn test_vec2_ops() {
+        let a = Vec2::new(FX_ONE, FX_ONE * 2);
+        let b = Vec2::new(FX_ONE * 3, -FX_ONE);
+        assert_eq!(a + b, Vec2::new(FX_ONE * 4, FX_ONE));
+        assert_eq!(a - b, Vec2::new(-FX_ONE * 2, FX_ONE * 3));
+        assert_eq!(-a, Vec2::new(-FX_ONE, -FX_ONE * 2));
+        assert_eq!(a.mul_fx(FX_ONE / 2), Vec2::new(FX_ONE / 2, FX_ONE));
+        assert_eq!(a.dot(b), FX_ONE);
+
+        let mut c = a;
+        c += b;
+        c -= a;
+        assert_eq!(c, b);
+
+        // Saturating rather than wrapping
+        let big = Vec2::new(Fx::MAX, Fx::MIN);
+        assert_eq!(big + big, big);
+        assert_eq!(-big, Vec2::new(-Fx::MAX, Fx::MAX));
+    }
+
+    #[test]
+    fn test_vec2_length_and_normalize() {
+        let v = Vec2::new(FX_ONE * 3, FX_ONE * 4);
+        assert_eq!(v.length(), FX_ONE * 5);
+        assert_eq!(v.normalize(), Vec2::new(FX_ONE * 3 / 5, FX_ONE * 4 / 5));
+        assert_eq!(Vec2::zero().normalize(), Vec2::zero());
+        assert_eq!(Vec2::new(Fx::MAX, Fx::MAX).length(), Fx::MAX);
+
+        let unit = Vec2::new(-FX_ONE * 7, FX_ONE).normalize();
+        assert!((unit.length() - FX_ONE).abs() <= 2);
+    }
+
+    #[test]
+    fn test_fixed_display() {
+        assert_eq!(Fixed(8192).to_string(), "0.125");
+        assert_eq!(Fixed(FX_ONE * 3).to_string(), "3");
+        assert_eq!(Fixed(-FX_ONE / 2).to_string(), "-0.5");
+        assert_eq!(Fixed(0).to_string(), "0");
+        assert_eq!(Fixed(fx::from_f32(0.05)).to_string(), "0.05");
+        assert_eq!(Fixed(1).to_string(), "0.00002");
+    }
+
+    #[test]
+    fn test_fixed_parse() {
+        assert_eq!("0.125".parse::<Fixed>(), Ok(Fixed(8192)));
+        assert_eq!("-1.5".parse::<Fixed>(), Ok(Fixed(-FX_ONE * 3 / 2)));
+        assert_eq!("+2".parse::<Fixed>(), Ok(Fixed(FX_ONE * 2)));
+        assert_eq!(".25".parse::<Fixed>(), Ok(Fixed(FX_ONE / 4)));
+        assert_eq!("0.05".parse::<Fixed>(), Ok(Fixed(fx::from_f32(0.05))));
+        assert_eq!("".parse::<Fixed>(), Err(ParseFxError::Empty));
+        assert_eq!("-.".parse::<Fixed>(), Err(ParseFxError::Empty));
+        assert_eq!("1.2.3".parse::<Fixed>(), Err(ParseFxError::InvalidDigit));
+        assert_eq!("abc".parse::<Fixed>(), Err(ParseFxError::InvalidDigit));
+        assert_eq!("32768".parse::<Fixed>(), Err(ParseFxError::OutOfRange));
+        assert_eq!("-32768".parse::<Fixed>(), Ok(Fixed(Fx::MIN)));
+    }
+
+    #[test]
+    fn test_fixed_round_trip() {
+        let samples = [
+            0,
+            1,
+            -1,
+            3276,
+            8192,
+            12345,
+            -99999,
+            FX_ONE,
+            Fx::MAX,
+            Fx::MIN,
+        ];
+        for value in samples
+            .into_iter()
+            .chain((0..2000).map(|i| i * 7919 - 5_000_000))
+        {
+            let text = Fixed(value).to_string();
+            assert_eq!(text.parse::<Fixed>(), Ok(Fixed(value)), "{text}");
+        }
+    }
+
+    #[test]
+    fn test_fx_ratio_and_lerp() {
+        assert_eq!(fx::from_ratio(1, 4), FX_ONE / 4);
+        assert_eq!(fx::from_ratio(-3, 2), -FX_ONE * 3 / 2);
+        assert_eq!(fx::from_ratio(1, 0), Fx::MAX);
+        assert_eq!(fx::from_ratio(i32::MAX, 1), Fx::MAX);
+
+        assert_eq!(fx::lerp(0, FX_ONE, FX_ONE / 4), FX_ONE / 4);
+        assert_eq!(fx::lerp(FX_ONE, -FX_ONE, FX_ONE / 2), 0);
+        assert_eq!(fx::lerp(Fx::MIN, Fx::MAX, FX_ONE), Fx::MAX);
+
+        assert_eq!(fx::min_fx(3, -2), -2);
+        assert_eq!(fx::max_fx(3, -2), 3);
+    }
+
+    #[test]
+    fn test_fx_checked_ops() {
+        assert_eq!(fx::checked_mul_fx(FX_ONE * 2, FX_ONE * 3), Some(FX_ONE * 6));
+        assert_eq!(fx::checked_mul_fx(Fx::MAX, FX_ONE * 2), None);
+        assert_eq!(fx::checked_div_fx(FX_ONE, FX_ONE * 4), Some(FX_ONE / 4));
+        assert_eq!(fx::checked_div_fx(FX_ONE, 0), None);
+        assert_eq!(fx::checked_div_fx(Fx::MAX, FX_ONE / 2), None);
+    }
+
+    #[test]
+    fn test_fx_rounding() {
+        let x = FX_ONE * 5 / 2; // 2.5
+        assert_eq!(fx::floor_fx(x), FX_ONE * 2);
+        assert_eq!(fx::ceil_fx(x), FX_ONE * 3);
+        assert_eq!(fx::round_fx(x), FX_ONE * 3);
+        assert_eq!(fx::trunc_fx(x), FX_ONE * 2);
+
+        assert_eq!(fx::floor_fx(-x), -FX_ONE * 3);
+        assert_eq!(fx::ceil_fx(-x), -FX_ONE * 2);
+        assert_eq!(fx::round_fx(-x), -FX_ONE * 3);
+        assert_eq!(fx::trunc_fx(-x), -FX_ONE * 2);
+
+        assert_eq!(fx::round_fx(FX_ONE * 2 + 1), FX_ONE * 2);
+        assert_eq!(fx::ceil_fx(FX_ONE), FX_ONE);
+    }
+
+    #[test]
+    fn test_sin_turns() {
+        assert_eq!(fx::sin_turns(0), 0);
+        assert_eq!(fx::sin_turns(FX_ONE / 4), FX_ONE); // sin(90) = 1
+        assert_eq!(fx::sin_turns(FX_ONE / 2), 0);
+        assert_eq!(fx::sin_turns(FX_ONE * 3 / 4), -FX_ONE); // sin(270) = -1
+        assert_eq!(fx::sin_turns(FX_ONE + FX_ONE / 4), FX_ONE); // Wraps
+
+        // sin(30) = 0.5 within the approximation error
+        let s = fx::sin_turns(FX_ONE / 12);
+        assert!(fx::abs_fx(s - FX_ONE / 2) < FX_ONE / 200);
+    }
+
+    #[test]
+    fn test_obstacle_motion_paths() {
+        let ping_pong = ObstacleMotion::PingPong {
+            offset: Vec2::new(0, FX_ONE / 4),
+            period: 120,
+        };
+        assert_eq!(ping_pong.offset_at(0), Vec2::zero());
+        assert_eq!(ping_pong.offset_at(60), Vec2::new(0, FX_ONE / 4));
+        assert_eq!(ping_pong.offset_at(120), Vec2::zero()); // Wraps
+
+        let sine = ObstacleMotion::Sine {
+            amplitude: Vec2::new(FX_ONE / 8, 0),
+            period: 60,
+        };
+        assert_eq!(sine.offset_at(15), Vec2::new(FX_ONE / 8, 0));
+        assert_eq!(sine.offset_at(45), Vec2::new(-FX_ONE / 8, 0));
+
+        // Advancing follows the path and reports velocity in units/s
+        let mut obstacle = Obstacle::new(
+            Vec2::new(FX_ONE / 2, FX_ONE / 2),
+            Vec2::new(FX_ONE / 32, FX_ONE / 16),
+            ping_pong,
+        );
+        for _ in 0..60 {
+            obstacle.advance(60);
+        }
+        assert_eq!(obstacle.phase, 60);
+        assert_eq!(obstacle.pos, Vec2::new(FX_ONE / 2, FX_ONE * 3 / 4));
+        assert!(obstacle.vel.y > 0);
+
+        // Static obstacles never move
+        let mut fixed = Obstacle::new(Vec2::zero(), Vec2::zero(), ObstacleMotion::Static);
+        fixed.advance(60);
+        assert_eq!(fixed.pos, Vec2::zero());
+        assert_eq!(fixed.phase, 0);
+    }
+
+    #[test]
+    fn test_physics_trace_ring_buffer() {
+        let mut trace = PhysicsTrace::new(3);
+        for tick in 0..5 {
+            trace.record(tick, tick as u64 * 10);
+        }
+
+        // Only the newest three ticks are kept
+        assert_eq!(trace.len(), 3);
+        let ticks: Vec<Tick> = trace.entries().map(|e| e.tick).collect();
+        assert_eq!(ticks, vec![2, 3, 4]);
+        assert_eq!(trace.hash_at(1), None);
+        assert_eq!(trace.hash_at(3), Some(30));
+
+        let mut other = trace.clone();
+        assert_eq!(trace.first_divergence(&other), None);
+        other.entries.iter_mut().for_each(|e| {
+            if e.tick >= 3 {
+                e.hash ^= 1;
+            }
+        });
+        assert_eq!(trace.first_divergence(&other), Some(3));
+    }
+
+    #[test]
+    fn test_tension_is_high() {
+        assert!(!Tension::default().is_high());
+        let tense = Tension {
+            fast_ball: true,
+            ..Tension::default()
+        };
+        assert!(tense.is_high());
+    }
+
+    #[test]
+    fn test_tick_wraparound() {
+        assert_eq!(tick_diff(5, 3), 2);
+        assert_eq!(tick_diff(3, 5), -2);
+        assert_eq!(tick_diff(0, Tick::MAX), 1);
+        assert_eq!(tick_diff(Tick::MAX - 1, 1), -3);
+
+        assert!(tick_after(1, 0));
+        assert!(!tick_after(0, 0));
+        assert!(tick_after(2, Tick::MAX));
+        assert!(!tick_after(Tick::MAX, 2));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let pair = InputPair::new(
+            9,
+            Input::new(-20, Buttons::READY).with_tilt(5),
+            Input::new(64, 0),
+        );
+        let json = serde_json::to_string(&pair).unwrap();
+        assert_eq!(serde_json::from_str::<InputPair>(&json).unwrap(), pair);
+
+        let snapshot = crate::Game::new(Config::default()).snapshot();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(serde_json::from_str::<Snapshot>(&json).unwrap(), snapshot);
+
+        // Fields added after the first release are optional
+        let input: Input = serde_json::from_str(r#"{"axis_y":3,"buttons":1}"#).unwrap();
+        assert_eq!(input, Input::new(3, Buttons::READY));
+    }
+
+    #[test]
+    fn test_status_helpers() {
+        assert!(Status::GameOver(Side::Right).is_terminal());
+        assert!(!Status::Playing.is_terminal());
+
+        assert_eq!(Status::Countdown(42).countdown_remaining(), Some(42));
+        assert_eq!(Status::Warmup(42).countdown_remaining(), None);
+
+        assert_eq!(Status::Scored(Side::Left, 10).scorer(), Some(Side::Left));
+        assert_eq!(Status::Playing.scorer(), None);
+
+        assert_eq!(Status::GameOver(Side::Right).winner(), Some(Side::Right));
+        assert_eq!(Status::Scored(Side::Right, 1).winner(), None);
+    }
+
+    #[test]
+    fn test_view_delta_round_trip() {
+        let mut game = crate::Game::new(Config::default());
+        let before = game.view();
+        game.status = Status::Playing;
+        game.step(&InputPair::new(0, Input::new(127, 0), Input::zero()));
+        let after = game.view();
+
+        let delta = ViewDelta::between(&before, &after);
+        assert_eq!(delta.tick, 1);
+        assert!(delta.left_paddle_y.is_some());
+        assert_eq!(delta.right_paddle_y, None); // Unchanged
+        assert_eq!(delta.serving_side, Some(None)); // Serve no longer pending
+
+        let mut patched = before;
+        delta.apply(&mut patched);
+        assert_eq!(patched, after);
+
+        assert!(ViewDelta::between(&after, &after).is_empty());
+
+        let mut blank = before;
+        blank.score = [9, 9];
+        ViewDelta::full(&after).apply(&mut blank);
+        assert_eq!(blank, after);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_view_delta_serde_omits_unchanged() {
+        let mut view = crate::Game::new(Config::default()).view();
+        let before = view;
+        view.tick += 1;
+        view.serving_side = None;
+
+        let delta = ViewDelta::between(&before, &view);
+        let json = serde_json::to_string(&delta).unwrap();
+        assert_eq!(json, r#"{"tick":1,"serving_side":null}"#);
+        assert_eq!(serde_json::from_str::<ViewDelta>(&json).unwrap(), delta);
+    }
+
+    #[test]
+    fn test_side_opposite() {
+        assert_eq!(Side::Left.opposite(), Side::Right);
+        assert_eq!(Side::Right.opposite(), Side::Left);
+    }
+
+    #[test]
+    fn test_index_by_side() {
+        let mut score = [3u8, 5u8];
+        assert_eq!(score[Side::Left], 3);
+        assert_eq!(score[Side::Right], 5);
+
+        score[Side::Right] += 1;
+        assert_eq!(score, [3, 6]);
+
+        for (i, side) in Side::BOTH.into_iter().enumerate() {
+            assert_eq!(side.index(), i);
+            assert_eq!(Side::from_index(i), Some(side));
+        }
+        assert_eq!(Side::from_index(2), None);
+    }
+
+    #[test]
+    fn test_input_ready() {
+        let input_ready = Input::new(0, 1);
+        let input_not_ready = Input::new(0, 0);
+
+        assert!(input_ready.is_ready());
+        assert!(!input_not_ready.is_ready());
+    }
+
+    #[test]
+    fn test_buttons_flags() {
+        let mut buttons = Buttons::READY | Buttons::TAUNT;
+        assert!(buttons.contains(Buttons::READY));
+        assert!(!buttons.contains(Buttons::DASH));
+        assert_eq!(buttons.bits(), 0b1001);
+
+        buttons.set(Buttons::DASH, true);
+        buttons.remove(Buttons::READY);
+        assert_eq!(buttons, Buttons::DASH | Buttons::TAUNT);
+        assert!(!Input::new(0, buttons).is_ready());
+
+        // Raw bits from the wire are retained, including unnamed ones
+        assert_eq!(Buttons::from(0xF1).bits(), 0xF1);
+        assert!(Input::new(0, 1).is_ready());
+    }
+
+    #[test]
+    fn test_input_pair_get_input() {
+        let left_input = Input::new(127, 1);
+        let right_input = Input::new(-127, 0);
+        let pair = InputPair::new(0, left_input, right_input);
+
+        assert_eq!(pair.get_input(Side::Left), left_input);
+        assert_eq!(pair.get_input(Side::Right), right_input);
+    }
+
+    #[test]
+    fn test_config_differing_fields() {
+        let config = Config::default();
+        assert!(config.differing_fields(&config).is_empty());
+
+        let other = Config {
+            max_score: 5,
+            goal_gap_y: 0,
+            ..config
+        };
+        assert_eq!(
+            config.differing_fields(&other),
+            vec!["max_score", "goal_gap_y"]
+        );
+    }
+}
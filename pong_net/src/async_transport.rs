@@ -0,0 +1,339 @@
+//! Async counterpart of `Transport`, for tokio-based servers
+//!
+//! `AsyncTransport` pulls messages with `recv` instead of pushing them to a
+//! callback, so one task (or a `select!`) can serve a connection without a
+//! dedicated thread. The adapters convert in both directions:
+//! `SyncAsAsync` lets async code use any existing `Transport`, and
+//! `AsyncAsSync` runs an `AsyncTransport` on a tokio runtime so `Lockstep`
+//! and friends can drive it.
+
+use crate::transport::{
+    lock, wall_ms, CloseReason, Lifecycle, Transport, TransportError, TransportMetrics,
+};
+use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Poll, Waker};
+use tokio::runtime::Handle;
+use tokio::sync::mpsc;
+
+/// Transport abstraction with async send and receive
+pub trait AsyncTransport: Send + Sync {
+    /// Send bytes to the remote peer
+    fn send(&self, bytes: &[u8]) -> impl Future<Output = Result<(), TransportError>> + Send;
+
+    /// Wait for the next message; `None` once the connection has ended
+    fn recv(&self) -> impl Future<Output = Option<Vec<u8>>> + Send;
+
+    /// Check if transport is currently connected and ready to send
+    fn is_open(&self) -> bool;
+
+    /// Close the transport connection
+    fn close(&self) -> impl Future<Output = Result<(), TransportError>> + Send;
+
+    /// Get connection status as a human-readable string
+    fn status(&self) -> String;
+}
+
+/// Messages delivered by a sync transport's callback, waiting for `recv`
+#[derive(Default)]
+struct Inbox {
+    queue: VecDeque<Vec<u8>>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+/// Presents a sync `Transport` as an `AsyncTransport`
+///
+/// Sends go straight to the wrapped transport. A peer disconnect the wrapped
+/// transport notices on its own ends `recv` the next time it is polled
+/// (i.e. on the next message or wakeup); `close` ends it immediately.
+pub struct SyncAsAsync<T: Transport> {
+    transport: Mutex<T>,
+    inbox: Arc<Mutex<Inbox>>,
+}
+
+impl<T: Transport> SyncAsAsync<T> {
+    pub fn new(mut transport: T) -> Self {
+        let inbox = Arc::new(Mutex::new(Inbox::default()));
+        let sink = Arc::clone(&inbox);
+        transport.set_on_message(Box::new(move |bytes| {
+            let mut inbox = lock(&sink);
+            inbox.queue.push_back(bytes);
+            if let Some(waker) = inbox.waker.take() {
+                waker.wake();
+            }
+        }));
+        Self {
+            transport: Mutex::new(transport),
+            inbox,
+        }
+    }
+
+    /// Unwrap the sync transport (its message callback stays installed)
+    pub fn into_inner(self) -> T {
+        self.transport
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<T: Transport> AsyncTransport for SyncAsAsync<T> {
+    fn send(&self, bytes: &[u8]) -> impl Future<Output = Result<(), TransportError>> + Send {
+        let result = lock(&self.transport).send(bytes);
+        async move { result }
+    }
+
+    fn recv(&self) -> impl Future<Output = Option<Vec<u8>>> + Send {
+        poll_fn(move |cx| {
+            // Checked before taking the inbox: a loopback peer's send holds its
+            // transport lock while it fills our inbox
+            let open = self.is_open();
+            let mut inbox = lock(&self.inbox);
+            if let Some(bytes) = inbox.queue.pop_front() {
+                return Poll::Ready(Some(bytes));
+            }
+            if inbox.closed || !open {
+                return Poll::Ready(None);
+            }
+            inbox.waker = Some(cx.waker().clone());
+            Poll::Pending
+        })
+    }
+
+    fn is_open(&self) -> bool {
+        lock(&self.transport).is_open()
+    }
+
+    fn close(&self) -> impl Future<Output = Result<(), TransportError>> + Send {
+        let result = lock(&self.transport).close();
+        let mut inbox = lock(&self.inbox);
+        inbox.closed = true;
+        if let Some(waker) = inbox.waker.take() {
+            waker.wake();
+        }
+        async move { result }
+    }
+
+    fn status(&self) -> String {
+        lock(&self.transport).status()
+    }
+}
+
+/// State shared with the receive task
+#[derive(Default)]
+struct Delivery {
+    on_message: Option<Box<dyn Fn(Vec<u8>) + Send + Sync>>,
+    /// Messages received before a callback was set
+    pending: VecDeque<Vec<u8>>,
+    /// Why sending stopped, if it failed
+    error: Option<String>,
+    metrics: TransportMetrics,
+    lifecycle: Lifecycle,
+}
+
+/// Presents an `AsyncTransport` as a sync `Transport`
+///
+/// A receive task and a send task run on the given tokio runtime; `send`
+/// only queues, so it never blocks and may be called from inside the
+/// runtime. Queued sends go out in order.
+pub struct AsyncAsSync<A: AsyncTransport + 'static> {
+    transport: Arc<A>,
+    handle: Handle,
+    outgoing: mpsc::UnboundedSender<Vec<u8>>,
+    delivery: Arc<Mutex<Delivery>>,
+    closed: Arc<AtomicBool>,
+}
+
+impl<A: AsyncTransport + 'static> AsyncAsSync<A> {
+    /// Start pumping `transport` on the runtime behind `handle`
+    pub fn new(transport: A, handle: Handle) -> Self {
+        let lifecycle = match transport.is_open() {
+            true => Lifecycle::open_now(),
+            false => Lifecycle::default(),
+        };
+        let transport = Arc::new(transport);
+        let delivery = Arc::new(Mutex::new(Delivery {
+            lifecycle,
+            ..Delivery::default()
+        }));
+        let closed = Arc::new(AtomicBool::new(false));
+
+        let reader = Arc::clone(&transport);
+        let inbox = Arc::clone(&delivery);
+        handle.spawn(async move {
+            while let Some(bytes) = reader.recv().await {
+                let mut delivery = lock(&inbox);
+                delivery.metrics.record_received(bytes.len(), wall_ms());
+                match &delivery.on_message {
+                    Some(callback) => callback(bytes),
+                    None => delivery.pending.push_back(bytes),
+                }
+            }
+            lock(&inbox).lifecycle.closed(CloseReason::Remote);
+        });
+
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        let writer = Arc::clone(&transport);
+        let errors = Arc::clone(&delivery);
+        let writer_closed = Arc::clone(&closed);
+        handle.spawn(async move {
+            while let Some(bytes) = outgoing_rx.recv().await {
+                let result = writer.send(&bytes).await;
+                let mut delivery = lock(&errors);
+                if let Err(e) = result {
+                    delivery.metrics.record_send_error();
+                    delivery.error = Some(e.to_string());
+                    writer_closed.store(true, Ordering::Release);
+                    delivery.lifecycle.closed(CloseReason::Error(e.to_string()));
+                    break;
+                }
+                delivery.metrics.record_sent(bytes.len(), wall_ms());
+            }
+        });
+
+        Self {
+            transport,
+            handle,
+            outgoing,
+            delivery,
+            closed,
+        }
+    }
+
+    /// The wrapped async transport
+    pub fn transport(&self) -> &Arc<A> {
+        &self.transport
+    }
+}
+
+impl<A: AsyncTransport + 'static> Transport for AsyncAsSync<A> {
+    fn send(&self, bytes: &[u8]) -> Result<(), TransportError> {
+        if !self.is_open() {
+            return Err(TransportError::NotConnected);
+        }
+        self.outgoing
+            .send(bytes.to_vec())
+            .map_err(|_| TransportError::SendFailed("Send task stopped".to_string()))
+    }
+
+    fn set_on_message(&mut self, callback: Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>) {
+        let mut delivery = lock(&self.delivery);
+        for bytes in delivery.pending.drain(..) {
+            callback(bytes);
+        }
+        delivery.on_message = Some(callback);
+    }
+
+    fn is_open(&self) -> bool {
+        !self.closed.load(Ordering::Acquire) && self.transport.is_open()
+    }
+
+    fn close(&mut self) -> Result<(), TransportError> {
+        if self.closed.swap(true, Ordering::AcqRel) {
+            return Err(TransportError::AlreadyClosed);
+        }
+        lock(&self.delivery).lifecycle.closed(CloseReason::Local);
+        // Closing may await I/O; let the runtime finish it
+        let transport = Arc::clone(&self.transport);
+        self.handle.spawn(async move {
+            let _ = transport.close().await;
+        });
+        Ok(())
+    }
+
+    fn status(&self) -> String {
+        match &lock(&self.delivery).error {
+            Some(error) => format!("{} ({error})", self.transport.status()),
+            None => self.transport.status(),
+        }
+    }
+
+    /// Counts messages as the tasks send and receive them, not as `send` queues them
+    fn metrics(&self) -> TransportMetrics {
+        lock(&self.delivery).metrics
+    }
+
+    fn set_on_open(&mut self, callback: Box<dyn Fn() + Send + Sync + 'static>) {
+        lock(&self.delivery).lifecycle.set_on_open(callback);
+    }
+
+    /// Fires `Remote` once the receive task sees the connection end
+    fn set_on_close(&mut self, callback: Box<dyn Fn(CloseReason) + Send + Sync + 'static>) {
+        lock(&self.delivery).lifecycle.set_on_close(callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::LoopbackTransport;
+    use std::time::{Duration, Instant};
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_sync_as_async() {
+        let (left, right) = LoopbackTransport::pair();
+        let left = SyncAsAsync::new(left);
+        let right = SyncAsAsync::new(right);
+
+        runtime().block_on(async {
+            left.send(b"ping").await.unwrap();
+            assert_eq!(right.recv().await, Some(b"ping".to_vec()));
+
+            // A receive already waiting is woken by the next message
+            let (received, sent) = tokio::join!(right.recv(), async {
+                tokio::task::yield_now().await;
+                left.send(b"later").await
+            });
+            assert_eq!(received, Some(b"later".to_vec()));
+            sent.unwrap();
+
+            right.close().await.unwrap();
+            assert_eq!(right.recv().await, None);
+            assert!(!left.is_open());
+        });
+    }
+
+    #[test]
+    fn test_async_as_sync() {
+        let runtime = runtime();
+        let (left, right) = LoopbackTransport::pair();
+        let mut left = AsyncAsSync::new(SyncAsAsync::new(left), runtime.handle().clone());
+        let right = SyncAsAsync::new(right);
+
+        left.send(b"one").unwrap();
+        left.send(b"two").unwrap();
+        runtime.block_on(async {
+            assert_eq!(right.recv().await, Some(b"one".to_vec()));
+            assert_eq!(right.recv().await, Some(b"two".to_vec()));
+            right.send(b"back").await.unwrap();
+        });
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        left.set_on_message(Box::new(move |bytes| sink.lock().unwrap().push(bytes)));
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while received.lock().unwrap().is_empty() {
+            assert!(Instant::now() < deadline, "timed out");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(*received.lock().unwrap(), vec![b"back".to_vec()]);
+        let metrics = left.metrics();
+        assert_eq!((metrics.messages_sent, metrics.bytes_sent), (2, 6));
+        assert_eq!(metrics.messages_received, 1);
+
+        left.close().unwrap();
+        assert!(!left.is_open());
+        assert_eq!(left.send(b"late"), Err(TransportError::NotConnected));
+        assert_eq!(left.close(), Err(TransportError::AlreadyClosed));
+    }
+}
@@ -0,0 +1,202 @@
+//! Time sources for the netcode.
+//!
+//! `std::time::Instant` and `SystemTime` panic on wasm32-unknown-unknown, so
+//! sessions read time through a `Clock`: `SystemClock` natively and
+//! `PerformanceClock` in the browser (feature `wasm`). `ManualClock` lets tests
+//! and simulations move time by hand.
+
+use std::ops::{Add, AddAssign};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A moment on a `Clock`, as the time elapsed since that clock's origin.
+/// Only comparable with instants from the same clock.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(Duration);
+
+impl Instant {
+    /// The instant `elapsed` after the clock's origin
+    pub const fn from_origin(elapsed: Duration) -> Self {
+        Instant(elapsed)
+    }
+
+    /// Time from the clock's origin to this instant
+    pub fn since_origin(self) -> Duration {
+        self.0
+    }
+
+    /// Time from `earlier` to this instant, or zero if `earlier` is later
+    pub fn saturating_duration_since(self, earlier: Instant) -> Duration {
+        self.0.saturating_sub(earlier.0)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, duration: Duration) -> Instant {
+        Instant(self.0 + duration)
+    }
+}
+
+impl AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, duration: Duration) {
+        self.0 += duration;
+    }
+}
+
+/// Where a session gets the time
+pub trait Clock: Send + Sync {
+    /// Monotonic time, for timeouts and tick pacing
+    fn now(&self) -> Instant;
+
+    /// Wall-clock milliseconds truncated to u32, as carried by Ping/Pong and
+    /// timestamped inputs
+    fn wall_ms(&self) -> u32;
+}
+
+/// `std::time` clock for native builds
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    origin: std::time::Instant,
+}
+
+impl SystemClock {
+    /// Create a clock whose origin is now
+    pub fn new() -> Self {
+        Self {
+            origin: std::time::Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant(self.origin.elapsed())
+    }
+
+    fn wall_ms(&self) -> u32 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u32
+    }
+}
+
+#[cfg(feature = "wasm")]
+mod js {
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    extern "C" {
+        #[wasm_bindgen(js_namespace = performance, js_name = now)]
+        pub fn performance_now() -> f64;
+
+        #[wasm_bindgen(js_namespace = Date, js_name = now)]
+        pub fn date_now() -> f64;
+    }
+}
+
+/// Browser clock on `performance.now()` and `Date.now()`, available in
+/// windows and workers alike
+#[cfg(feature = "wasm")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PerformanceClock;
+
+#[cfg(feature = "wasm")]
+impl Clock for PerformanceClock {
+    fn now(&self) -> Instant {
+        Instant(Duration::from_secs_f64(
+            js::performance_now().max(0.0) / 1000.0,
+        ))
+    }
+
+    fn wall_ms(&self) -> u32 {
+        js::date_now() as u64 as u32
+    }
+}
+
+/// Clock that only moves when told to; clones share the same time
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    elapsed_us: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Create a clock stopped at its origin
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move time forward by `duration`
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed_us
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        Instant(Duration::from_micros(
+            self.elapsed_us.load(Ordering::Relaxed),
+        ))
+    }
+
+    fn wall_ms(&self) -> u32 {
+        (self.elapsed_us.load(Ordering::Relaxed) / 1000) as u32
+    }
+}
+
+/// The clock sessions use unless given another: `PerformanceClock` in wasm
+/// builds with the `wasm` feature, `SystemClock` everywhere else
+pub fn default_clock() -> Box<dyn Clock> {
+    #[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+    {
+        Box::new(PerformanceClock)
+    }
+    #[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+    {
+        Box::new(SystemClock::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock_shared_between_clones() {
+        let clock = ManualClock::new();
+        let handle = clock.clone();
+        assert_eq!(clock.now(), Instant::default());
+
+        handle.advance(Duration::from_millis(1500));
+        assert_eq!(clock.now().since_origin(), Duration::from_millis(1500));
+        assert_eq!(clock.wall_ms(), 1500);
+    }
+
+    #[test]
+    fn test_instant_arithmetic() {
+        let start = Instant::from_origin(Duration::from_millis(10));
+        let mut later = start + Duration::from_millis(5);
+        later += Duration::from_millis(5);
+
+        assert!(later > start);
+        assert_eq!(
+            later.saturating_duration_since(start),
+            Duration::from_millis(10)
+        );
+        assert_eq!(start.saturating_duration_since(later), Duration::ZERO);
+
+        let clock = SystemClock::new();
+        let first = clock.now();
+        assert!(clock.now() >= first);
+    }
+}
@@ -0,0 +1,190 @@
+//! Run-length encoding for snapshot payloads (feature `compression`).
+//!
+//! Token stream: a control byte `c < 0x80` is followed by `c + 1` literal bytes;
+//! `c >= 0x80` is followed by one byte repeated `(c & 0x7F) + 3` times. Snapshots
+//! are mostly small fixed-point values, so their zero padding collapses well.
+
+use pong_engine::serialization::SerializationError;
+
+/// Shortest repeat worth a run token (a 2-byte token never grows the output)
+const MIN_RUN: usize = 3;
+
+/// Longest repeat a single run token can describe
+const MAX_RUN: usize = 0x7F + MIN_RUN;
+
+/// Longest literal a single token can carry
+const MAX_LITERAL: usize = 0x80;
+
+/// Control byte bit marking a run token
+const RUN_FLAG: u8 = 0x80;
+
+/// Largest payload `decompress` will produce, so a hostile frame can't balloon
+pub const MAX_DECOMPRESSED_SIZE: usize = u16::MAX as usize;
+
+enum Token<'a> {
+    Literal(&'a [u8]),
+    Run(u8, usize),
+}
+
+impl Token<'_> {
+    fn encoded_len(&self) -> usize {
+        match self {
+            Token::Literal(bytes) => 1 + bytes.len(),
+            Token::Run(..) => 2,
+        }
+    }
+}
+
+/// Split `data` into tokens, in order
+fn for_each_token<'a>(data: &'a [u8], mut f: impl FnMut(Token<'a>)) {
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i < data.len() {
+        let byte = data[i];
+        let run = data[i..]
+            .iter()
+            .take(MAX_RUN)
+            .take_while(|&&b| b == byte)
+            .count();
+
+        if run >= MIN_RUN {
+            for chunk in data[literal_start..i].chunks(MAX_LITERAL) {
+                f(Token::Literal(chunk));
+            }
+            f(Token::Run(byte, run));
+            i += run;
+            literal_start = i;
+        } else {
+            i += 1;
+        }
+    }
+
+    for chunk in data[literal_start..].chunks(MAX_LITERAL) {
+        f(Token::Literal(chunk));
+    }
+}
+
+/// Size of `compress(data)` without allocating
+pub fn compressed_len(data: &[u8]) -> usize {
+    let mut len = 0;
+    for_each_token(data, |token| len += token.encoded_len());
+    len
+}
+
+/// Compress into the front of `buf`, returning the number of bytes written
+pub fn compress_into(data: &[u8], buf: &mut [u8]) -> Result<usize, SerializationError> {
+    if buf.len() < compressed_len(data) {
+        return Err(SerializationError::BufferTooSmall);
+    }
+
+    let mut offset = 0;
+    for_each_token(data, |token| match token {
+        Token::Literal(bytes) => {
+            buf[offset] = (bytes.len() - 1) as u8;
+            buf[offset + 1..offset + 1 + bytes.len()].copy_from_slice(bytes);
+            offset += 1 + bytes.len();
+        }
+        Token::Run(byte, count) => {
+            buf[offset] = RUN_FLAG | (count - MIN_RUN) as u8;
+            buf[offset + 1] = byte;
+            offset += 2;
+        }
+    });
+    Ok(offset)
+}
+
+/// Compress `data`
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0u8; compressed_len(data)];
+    compress_into(data, &mut bytes).expect("buffer sized by compressed_len");
+    bytes
+}
+
+/// Expand a token stream written by `compress`
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, SerializationError> {
+    let mut data = Vec::with_capacity(bytes.len() * 2);
+    let mut offset = 0;
+
+    while let Some(&control) = bytes.get(offset) {
+        if control & RUN_FLAG == 0 {
+            let len = control as usize + 1;
+            let literal = bytes
+                .get(offset + 1..offset + 1 + len)
+                .ok_or(SerializationError::UnexpectedEnd)?;
+            data.extend_from_slice(literal);
+            offset += 1 + len;
+        } else {
+            let &byte = bytes
+                .get(offset + 1)
+                .ok_or(SerializationError::UnexpectedEnd)?;
+            let count = (control & !RUN_FLAG) as usize + MIN_RUN;
+            data.resize(data.len() + count, byte);
+            offset += 2;
+        }
+
+        if data.len() > MAX_DECOMPRESSED_SIZE {
+            return Err(SerializationError::InvalidData);
+        }
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pong_engine::types::*;
+    use pong_engine::Game;
+
+    #[test]
+    fn test_round_trip() {
+        let mut mixed: Vec<u8> = (0..=255).collect();
+        mixed.extend([0; 400]);
+        mixed.extend([1, 2, 2, 3, 3, 3, 4, 4, 4, 4]);
+
+        for data in [Vec::new(), vec![7], vec![9; 3], vec![5; 131], mixed] {
+            let compressed = compress(&data);
+            assert_eq!(compressed.len(), compressed_len(&data));
+            assert_eq!(decompress(&compressed).unwrap(), data);
+        }
+
+        // Incompressible input costs one control byte per 128
+        let noise: Vec<u8> = (0..=255).collect();
+        assert_eq!(compressed_len(&noise), 256 + 2);
+    }
+
+    #[test]
+    fn test_snapshot_shrinks() {
+        let config = Config::default();
+        let mut snapshot = Game::new(config).snapshot();
+        snapshot.obstacle_phases = vec![0; 40];
+        let encoded = snapshot.encode();
+
+        let compressed = compress(&encoded);
+        assert!(compressed.len() < encoded.len() / 2);
+        assert_eq!(
+            Snapshot::decode(&decompress(&compressed).unwrap()).unwrap(),
+            snapshot
+        );
+    }
+
+    #[test]
+    fn test_decompress_errors() {
+        let compressed = compress(&[1, 2, 3, 4, 5]);
+        assert_eq!(
+            decompress(&compressed[..3]),
+            Err(SerializationError::UnexpectedEnd)
+        );
+        assert_eq!(decompress(&[0x85]), Err(SerializationError::UnexpectedEnd));
+
+        // 600 maximal runs would expand past the limit
+        let bomb = [0xFF, 0].repeat(600);
+        assert_eq!(decompress(&bomb), Err(SerializationError::InvalidData));
+
+        let mut buf = [0u8; 4];
+        assert_eq!(
+            compress_into(&[1, 2, 3, 4, 5], &mut buf),
+            Err(SerializationError::BufferTooSmall)
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Length-prefixed framing and streaming decode for carrying wire messages over byte streams
+
+use crate::wire_protocol::WireMsg;
+use pong_engine::serialization::SerializationError;
+
+/// Size of the little-endian u16 length prefix on every frame
+pub const FRAME_HEADER_SIZE: usize = 2;
+
+/// Largest payload a single frame can carry
+pub const MAX_FRAME_PAYLOAD: usize = u16::MAX as usize;
+
+/// `WireDecoder` frame limit; comfortably above the largest message this build sends
+pub const DEFAULT_MAX_FRAME: usize = 4096;
+
+/// Splits a byte stream (TCP, Unix socket, stdin pipe) back into whole messages.
+///
+/// Each frame is `[len: u16 LE][payload]`. Feed whatever the stream produced with
+/// `push`, then pull complete frames out with `next_frame` / `next_message`.
+#[derive(Debug, Default, Clone)]
+pub struct Framer {
+    buffer: Vec<u8>,
+}
+
+impl Framer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prefix `payload` with its length
+    pub fn frame(payload: &[u8]) -> Result<Vec<u8>, SerializationError> {
+        if payload.len() > MAX_FRAME_PAYLOAD {
+            return Err(SerializationError::BufferTooSmall);
+        }
+
+        let mut bytes = Vec::with_capacity(FRAME_HEADER_SIZE + payload.len());
+        bytes.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(payload);
+        Ok(bytes)
+    }
+
+    /// Encode a wire message and frame it
+    pub fn frame_msg(msg: &WireMsg) -> Result<Vec<u8>, SerializationError> {
+        Self::frame(&msg.encode())
+    }
+
+    /// Append bytes read from the stream (any split, including mid-header)
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Payload length announced by the frame at the front of the buffer, once its
+    /// header has arrived (the payload itself may still be incomplete)
+    pub fn next_frame_len(&self) -> Option<usize> {
+        match self.buffer[..] {
+            [lo, hi, ..] => Some(u16::from_le_bytes([lo, hi]) as usize),
+            _ => None,
+        }
+    }
+
+    /// Next complete frame's payload, or None until more bytes arrive
+    pub fn next_frame(&mut self) -> Option<Vec<u8>> {
+        let len = self.next_frame_len()?;
+        if self.buffer.len() < FRAME_HEADER_SIZE + len {
+            return None;
+        }
+
+        let payload = self.buffer[FRAME_HEADER_SIZE..FRAME_HEADER_SIZE + len].to_vec();
+        self.buffer.drain(..FRAME_HEADER_SIZE + len);
+        Some(payload)
+    }
+
+    /// Next complete frame decoded as a wire message
+    pub fn next_message(&mut self) -> Option<Result<WireMsg, SerializationError>> {
+        self.next_frame().map(|payload| WireMsg::decode(&payload))
+    }
+
+    /// Bytes received but not yet returned as a frame
+    pub fn pending_bytes(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Drop any partial frame (e.g. after the stream reconnects)
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+/// Turns arbitrary chunks from a stream transport into whole wire messages.
+///
+/// Wraps a `Framer` and refuses frames announcing more than `max_frame` bytes: a
+/// stream whose length prefix is garbage can't be resynchronized, so instead of
+/// waiting forever for 64 KiB that will never form a message, the buffer is dropped.
+#[derive(Debug, Clone)]
+pub struct WireDecoder {
+    framer: Framer,
+    max_frame: usize,
+    decoded: u64,
+    errors: u64,
+}
+
+impl Default for WireDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WireDecoder {
+    pub fn new() -> Self {
+        Self::with_max_frame(DEFAULT_MAX_FRAME)
+    }
+
+    /// Decoder rejecting frames longer than `max_frame` bytes
+    pub fn with_max_frame(max_frame: usize) -> Self {
+        WireDecoder {
+            framer: Framer::new(),
+            max_frame: max_frame.min(MAX_FRAME_PAYLOAD),
+            decoded: 0,
+            errors: 0,
+        }
+    }
+
+    /// Buffer a chunk exactly as the transport delivered it
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.framer.push(chunk);
+    }
+
+    /// Next complete frame's payload, still encoded. An oversized frame yields
+    /// `InvalidData` once and discards everything buffered.
+    pub fn next_frame(&mut self) -> Option<Result<Vec<u8>, SerializationError>> {
+        if self.framer.next_frame_len()? > self.max_frame {
+            self.framer.clear();
+            self.errors += 1;
+            return Some(Err(SerializationError::InvalidData));
+        }
+        self.framer.next_frame().map(Ok)
+    }
+
+    /// Next complete message, or None until more bytes arrive
+    pub fn next_message(&mut self) -> Option<Result<WireMsg, SerializationError>> {
+        let payload = match self.next_frame()? {
+            Ok(payload) => payload,
+            Err(error) => return Some(Err(error)), // Already counted
+        };
+
+        let result = WireMsg::decode(&payload);
+        match result {
+            Ok(_) => self.decoded += 1,
+            Err(_) => self.errors += 1,
+        }
+        Some(result)
+    }
+
+    /// Buffer `chunk` and return every message it completed, in order
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<Result<WireMsg, SerializationError>> {
+        self.push(chunk);
+        std::iter::from_fn(|| self.next_message()).collect()
+    }
+
+    /// Bytes of a partial frame waiting for the rest of the stream
+    pub fn pending_bytes(&self) -> usize {
+        self.framer.pending_bytes()
+    }
+
+    /// Messages decoded successfully so far
+    pub fn messages_decoded(&self) -> u64 {
+        self.decoded
+    }
+
+    /// Frames rejected so far (oversized, corrupt or unknown)
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// Forget any partial frame, e.g. after the stream reconnects
+    pub fn reset(&mut self) {
+        self.framer.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pong_engine::types::Input;
+
+    #[test]
+    fn test_frame_round_trip() {
+        let msg = WireMsg::input_pair(7, Input::new(10, 1), Input::new(-10, 0));
+        let framed = Framer::frame_msg(&msg).unwrap();
+        assert_eq!(framed.len(), FRAME_HEADER_SIZE + msg.encoded_size());
+
+        let mut framer = Framer::new();
+        framer.push(&framed);
+        assert_eq!(framer.next_message(), Some(Ok(msg)));
+        assert_eq!(framer.next_frame(), None);
+        assert_eq!(framer.pending_bytes(), 0);
+    }
+
+    #[test]
+    fn test_partial_and_coalesced_reads() {
+        let first = WireMsg::ping(1);
+        let second = WireMsg::chat("hello");
+        let mut stream = Framer::frame_msg(&first).unwrap();
+        stream.extend(Framer::frame_msg(&second).unwrap());
+
+        // Feed one byte at a time; each message appears exactly once it is whole
+        let mut framer = Framer::new();
+        let mut received = Vec::new();
+        for byte in &stream {
+            framer.push(std::slice::from_ref(byte));
+            while let Some(msg) = framer.next_message() {
+                received.push(msg.unwrap());
+            }
+        }
+        assert_eq!(received, vec![first.clone(), second.clone()]);
+
+        // Both in a single read
+        framer.push(&stream);
+        assert_eq!(framer.next_message(), Some(Ok(first)));
+        assert_eq!(framer.next_message(), Some(Ok(second)));
+        assert_eq!(framer.next_message(), None);
+    }
+
+    #[test]
+    fn test_wire_decoder_chunks() {
+        let messages = vec![
+            WireMsg::input_pair(1, Input::new(5, 0), Input::new(-5, 1)),
+            WireMsg::chat("split me"),
+            WireMsg::ping(3),
+        ];
+        let stream: Vec<u8> = messages
+            .iter()
+            .flat_map(|msg| Framer::frame_msg(msg).unwrap())
+            .collect();
+
+        // Any chunking of the stream yields the same messages
+        for chunk_size in [1, 3, 7, stream.len()] {
+            let mut decoder = WireDecoder::new();
+            let mut received = Vec::new();
+            for chunk in stream.chunks(chunk_size) {
+                received.extend(decoder.feed(chunk).into_iter().map(Result::unwrap));
+            }
+            assert_eq!(received, messages);
+            assert_eq!(decoder.pending_bytes(), 0);
+            assert_eq!(decoder.messages_decoded(), 3);
+        }
+
+        // Half a frame stays buffered until the rest arrives
+        let mut decoder = WireDecoder::new();
+        assert!(decoder.feed(&stream[..5]).is_empty());
+        assert_eq!(decoder.pending_bytes(), 5);
+        decoder.reset();
+        assert_eq!(decoder.pending_bytes(), 0);
+    }
+
+    #[test]
+    fn test_wire_decoder_rejects_oversized_frames() {
+        let mut decoder = WireDecoder::with_max_frame(64);
+        let mut stream = Framer::frame(&[0; 100]).unwrap();
+        stream.extend(Framer::frame_msg(&WireMsg::ping(1)).unwrap());
+
+        // The bogus length poisons everything after it, which is dropped
+        assert_eq!(
+            decoder.feed(&stream[..10]),
+            vec![Err(SerializationError::InvalidData)]
+        );
+        assert_eq!(decoder.pending_bytes(), 0);
+        assert_eq!(decoder.errors(), 1);
+
+        let ping = Framer::frame_msg(&WireMsg::ping(2)).unwrap();
+        assert_eq!(decoder.feed(&ping), vec![Ok(WireMsg::ping(2))]);
+
+        // A corrupt frame within the limit costs only itself
+        let mut corrupt = Framer::frame(&[0xFF, 0, 0, 0, 0]).unwrap();
+        corrupt.extend(ping);
+        assert_eq!(
+            decoder.feed(&corrupt),
+            vec![
+                Err(SerializationError::ChecksumMismatch),
+                Ok(WireMsg::ping(2))
+            ]
+        );
+        assert_eq!(decoder.errors(), 2);
+        assert_eq!(decoder.messages_decoded(), 2);
+    }
+
+    #[test]
+    fn test_frame_limits() {
+        assert_eq!(
+            Framer::frame(&vec![0; MAX_FRAME_PAYLOAD + 1]),
+            Err(SerializationError::BufferTooSmall)
+        );
+        assert_eq!(
+            Framer::frame(&vec![0; MAX_FRAME_PAYLOAD]).unwrap().len(),
+            FRAME_HEADER_SIZE + MAX_FRAME_PAYLOAD
+        );
+
+        // A corrupt payload surfaces as a decode error without desynchronizing the stream
+        let mut framer = Framer::new();
+        framer.push(&Framer::frame(&[0xFF, 0, 0, 0, 0]).unwrap());
+        framer.push(&Framer::frame_msg(&WireMsg::ping(9)).unwrap());
+        assert_eq!(
+            framer.next_message(),
+            Some(Err(SerializationError::ChecksumMismatch))
+        );
+        assert_eq!(framer.next_message(), Some(Ok(WireMsg::ping(9))));
+
+        framer.push(&[5, 0, 1]);
+        framer.clear();
+        assert_eq!(framer.pending_bytes(), 0);
+    }
+}
@@ -0,0 +1,325 @@
+//! Keepalive frames and dead-peer detection.
+//!
+//! A connection can die without either end closing it (a WebRTC peer whose
+//! network vanished, an expired NAT binding) while the transport still
+//! reports open. `KeepaliveTransport` tags each message with a frame byte,
+//! sends a bare keepalive frame whenever nothing else has gone out for an
+//! interval, and declares the peer dead once nothing at all has arrived for
+//! the timeout. Both ends must be wrapped.
+
+use crate::clock::{default_clock, Clock, Instant};
+use crate::transport::{lock, CloseReason, Lifecycle, Transport, TransportError, TransportMetrics};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Frame byte ahead of an application message
+const DATA_FRAME: u8 = 0;
+
+/// A frame carrying nothing but proof of life
+const KEEPALIVE_FRAME: u8 = 1;
+
+type MessageCallback = Arc<dyn Fn(Vec<u8>) + Send + Sync>;
+
+/// How often to prove we're alive, and how long to wait for the peer to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepaliveConfig {
+    /// Send a keepalive after this long without sending anything
+    pub interval: Duration,
+    /// Give up on the peer after this long without receiving anything
+    pub timeout: Duration,
+}
+
+impl Default for KeepaliveConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Where unwrapped messages go
+#[derive(Default)]
+struct Delivery {
+    on_message: Option<MessageCallback>,
+    /// Messages that arrived before a callback was set
+    pending: VecDeque<Vec<u8>>,
+}
+
+/// Wraps a transport with keepalives and a receive timeout.
+///
+/// Nothing runs in the background: call `poll` regularly (e.g. every frame)
+/// to send keepalives and check the timeout. When the peer times out
+/// `is_open` turns false and the close callback fires with `TimedOut`; the
+/// inner transport's own closes are passed on with their reasons.
+pub struct KeepaliveTransport<T: Transport> {
+    inner: T,
+    config: KeepaliveConfig,
+    clock: Box<dyn Clock>,
+    delivery: Arc<Mutex<Delivery>>,
+    /// Shared with the inner transport's close callback
+    lifecycle: Arc<Mutex<Lifecycle>>,
+    /// Set by the inner callback on any frame, consumed by `poll`
+    heard: Arc<AtomicBool>,
+    /// Set by `send`, consumed by `poll`
+    sent: AtomicBool,
+    last_heard: Instant,
+    last_sent: Instant,
+    timed_out: bool,
+    closed: bool,
+}
+
+impl<T: Transport> KeepaliveTransport<T> {
+    /// Wrap `inner`, starting the timeout now
+    pub fn new(mut inner: T, config: KeepaliveConfig) -> Self {
+        let delivery = Arc::new(Mutex::new(Delivery::default()));
+        let heard = Arc::new(AtomicBool::new(false));
+
+        let sink = Arc::clone(&delivery);
+        let alive = Arc::clone(&heard);
+        inner.set_on_message(Box::new(move |mut bytes| {
+            alive.store(true, Ordering::Release);
+            if bytes.first() != Some(&DATA_FRAME) {
+                return;
+            }
+            bytes.remove(0);
+            let mut delivery = lock(&sink);
+            let Some(callback) = delivery.on_message.clone() else {
+                delivery.pending.push_back(bytes);
+                return;
+            };
+            // Released first so the callback may send without deadlocking
+            drop(delivery);
+            callback(bytes);
+        }));
+
+        let lifecycle = Arc::new(Mutex::new(match inner.is_open() {
+            true => Lifecycle::open_now(),
+            false => Lifecycle::default(),
+        }));
+        let ended = Arc::clone(&lifecycle);
+        inner.set_on_close(Box::new(move |reason| {
+            lock(&ended).closed(reason);
+        }));
+
+        let clock = default_clock();
+        let now = clock.now();
+        Self {
+            inner,
+            config,
+            clock,
+            delivery,
+            lifecycle,
+            heard,
+            sent: AtomicBool::new(false),
+            last_heard: now,
+            last_sent: now,
+            timed_out: false,
+            closed: false,
+        }
+    }
+
+    /// Take time from `clock` instead of the system clock, restarting the
+    /// interval and timeout
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Box::new(clock);
+        self.last_heard = self.clock.now();
+        self.last_sent = self.last_heard;
+    }
+
+    /// Change the interval and timeout
+    pub fn set_config(&mut self, config: KeepaliveConfig) {
+        self.config = config;
+    }
+
+    /// Whether the peer went silent for longer than the timeout
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    /// The wrapped transport
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Send a keepalive if we've been quiet, and check the peer hasn't been
+    pub fn poll(&mut self) {
+        if self.closed || self.timed_out {
+            return;
+        }
+
+        let now = self.clock.now();
+        if self.heard.swap(false, Ordering::AcqRel) {
+            self.last_heard = now;
+        }
+        if self.sent.swap(false, Ordering::AcqRel) {
+            self.last_sent = now;
+        }
+
+        if !self.inner.is_open() {
+            // For inner transports that can't report their own close
+            lock(&self.lifecycle).closed(CloseReason::Remote);
+        } else if now.saturating_duration_since(self.last_heard) >= self.config.timeout {
+            self.timed_out = true;
+            lock(&self.lifecycle).closed(CloseReason::TimedOut);
+            let _ = self.inner.close();
+        } else if now.saturating_duration_since(self.last_sent) >= self.config.interval
+            && self.inner.send(&[KEEPALIVE_FRAME]).is_ok()
+        {
+            self.last_sent = now;
+        }
+    }
+}
+
+impl<T: Transport> Transport for KeepaliveTransport<T> {
+    fn send(&self, bytes: &[u8]) -> Result<(), TransportError> {
+        if !self.is_open() {
+            return Err(TransportError::NotConnected);
+        }
+        let mut frame = Vec::with_capacity(1 + bytes.len());
+        frame.push(DATA_FRAME);
+        frame.extend_from_slice(bytes);
+        self.inner.send(&frame)?;
+        self.sent.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    fn set_on_message(&mut self, callback: Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>) {
+        let mut delivery = lock(&self.delivery);
+        for bytes in delivery.pending.drain(..) {
+            callback(bytes);
+        }
+        delivery.on_message = Some(Arc::from(callback));
+    }
+
+    fn is_open(&self) -> bool {
+        !self.closed && !self.timed_out && self.inner.is_open()
+    }
+
+    fn close(&mut self) -> Result<(), TransportError> {
+        if self.closed {
+            return Err(TransportError::AlreadyClosed);
+        }
+        self.closed = true;
+        lock(&self.lifecycle).closed(CloseReason::Local);
+        if self.timed_out {
+            // Closed the inner transport already
+            return Ok(());
+        }
+        self.inner.close()
+    }
+
+    fn status(&self) -> String {
+        if self.timed_out && !self.closed {
+            format!(
+                "Peer timed out (nothing received for {}ms)",
+                self.config.timeout.as_millis()
+            )
+        } else {
+            self.inner.status()
+        }
+    }
+
+    /// The inner transport's traffic, keepalives and frame bytes included
+    fn metrics(&self) -> TransportMetrics {
+        self.inner.metrics()
+    }
+
+    fn set_on_open(&mut self, callback: Box<dyn Fn() + Send + Sync + 'static>) {
+        lock(&self.lifecycle).set_on_open(callback);
+    }
+
+    fn set_on_close(&mut self, callback: Box<dyn Fn(CloseReason) + Send + Sync + 'static>) {
+        lock(&self.lifecycle).set_on_close(callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::transport::LoopbackTransport;
+
+    fn keepalive_pair(
+        clock: &ManualClock,
+    ) -> (
+        KeepaliveTransport<LoopbackTransport>,
+        KeepaliveTransport<LoopbackTransport>,
+    ) {
+        let (left, right) = LoopbackTransport::pair();
+        let mut left = KeepaliveTransport::new(left, KeepaliveConfig::default());
+        let mut right = KeepaliveTransport::new(right, KeepaliveConfig::default());
+        left.set_clock(clock.clone());
+        right.set_clock(clock.clone());
+        (left, right)
+    }
+
+    #[test]
+    fn test_keepalive_holds_idle_connection_open() {
+        let clock = ManualClock::new();
+        let (mut left, mut right) = keepalive_pair(&clock);
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        right.set_on_message(Box::new(move |bytes| sink.lock().unwrap().push(bytes)));
+
+        left.send(b"hello").unwrap();
+        for _ in 0..20 {
+            clock.advance(Duration::from_millis(500));
+            left.poll();
+            right.poll();
+        }
+        assert!(left.is_open() && right.is_open());
+        // Keepalives aren't delivered, and the frame byte is stripped
+        assert_eq!(*received.lock().unwrap(), vec![b"hello".to_vec()]);
+        assert!(left.metrics().messages_sent > 5);
+    }
+
+    #[test]
+    fn test_silent_peer_times_out() {
+        let clock = ManualClock::new();
+        let (mut left, right) = keepalive_pair(&clock);
+        let closes = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&closes);
+        left.set_on_close(Box::new(move |reason| sink.lock().unwrap().push(reason)));
+
+        // The right end never polls, so it never sends keepalives
+        clock.advance(Duration::from_secs(4));
+        left.poll();
+        assert!(left.is_open());
+        right.send(b"late").unwrap();
+        clock.advance(Duration::from_secs(4));
+        left.poll();
+        assert!(left.is_open(), "data counts as proof of life");
+
+        clock.advance(Duration::from_secs(5));
+        left.poll();
+        left.poll();
+        assert!(left.timed_out());
+        assert!(!left.is_open());
+        assert!(!right.is_open());
+        assert_eq!(*closes.lock().unwrap(), vec![CloseReason::TimedOut]);
+        assert_eq!(left.send(b"gone"), Err(TransportError::NotConnected));
+        assert!(left.status().contains("timed out"));
+        assert_eq!(left.close(), Ok(()));
+        assert_eq!(left.close(), Err(TransportError::AlreadyClosed));
+    }
+
+    #[test]
+    fn test_peer_close_fires_callback() {
+        let clock = ManualClock::new();
+        let (mut left, mut right) = keepalive_pair(&clock);
+        let closes = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&closes);
+        left.set_on_close(Box::new(move |reason| sink.lock().unwrap().push(reason)));
+
+        // Passed on from the inner transport as it happens, not at the next poll
+        right.close().unwrap();
+        assert_eq!(*closes.lock().unwrap(), vec![CloseReason::Remote]);
+        left.poll();
+        assert!(!left.is_open());
+        assert!(!left.timed_out());
+        assert_eq!(*closes.lock().unwrap(), vec![CloseReason::Remote]);
+    }
+}
@@ -0,0 +1,32 @@
+//! Pong networking - wire protocol, transports and lockstep synchronization
+
+#[cfg(feature = "async")]
+pub mod async_transport;
+#[cfg(feature = "auth")]
+pub mod auth;
+pub mod clock;
+#[cfg(feature = "compression")]
+pub mod compression;
+pub mod framing;
+pub mod keepalive;
+pub mod lockstep;
+pub mod multiplex;
+pub mod reconnecting;
+pub mod referee;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod relay;
+pub mod rollback;
+pub mod session;
+pub mod simulated;
+#[cfg(test)]
+mod soak;
+pub mod spectator;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tcp;
+pub mod transport;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod udp;
+#[cfg(unix)]
+pub mod unix;
+pub mod wire_protocol;
+pub mod wire_tap;
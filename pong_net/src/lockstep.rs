@@ -0,0 +1,5004 @@
+//! Lockstep networking protocol for synchronized multiplayer gameplay
+
+#[cfg(feature = "auth")]
+use crate::auth::MessageAuth;
+use crate::clock::{default_clock, Clock, Instant};
+use crate::framing::WireDecoder;
+use crate::transport::{lock, CloseReason, Transport, TransportError, TransportMetrics};
+use crate::wire_protocol::{
+    capabilities, input_for_peer, negotiate_version, EncodeOptions, WireMsg, WireMsgRef,
+    MAX_BATCH_INPUTS, PROTOCOL_VERSION,
+};
+use pong_engine::ai;
+use pong_engine::physics::{ClassicPhysics, PhysicsEngine};
+use pong_engine::replay::Replay;
+use pong_engine::serialization::SerializationError;
+use pong_engine::types::*;
+use pong_engine::{Game, RestoreError};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Errors that can occur in lockstep protocol
+#[derive(Debug, Clone, PartialEq)]
+pub enum LockstepError {
+    /// Transport error
+    Transport(String),
+    /// Serialization error
+    Serialization(SerializationError),
+    /// Invalid message received
+    InvalidMessage(String),
+    /// Game is not running
+    NotRunning,
+    /// Tick synchronization error
+    SyncError(String),
+    /// Peer's protocol version is too old to interoperate with
+    VersionMismatch { local: u8, remote: u8 },
+    /// Peers run different rules; lists the differing Config fields
+    ConfigMismatch { fields: Vec<&'static str> },
+    /// Received snapshot was taken under different rules and was not applied
+    SnapshotRejected(RestoreError),
+    /// The peer's Hello didn't advertise this `capabilities` bit, so the
+    /// feature is disabled for this session
+    PeerLacksCapability(u32),
+    /// An input for this tick lies beyond the `INPUT_WINDOW` we buffer ahead
+    InputOutsideWindow(Tick),
+}
+
+impl From<TransportError> for LockstepError {
+    fn from(error: TransportError) -> Self {
+        LockstepError::Transport(error.to_string())
+    }
+}
+
+impl From<RestoreError> for LockstepError {
+    fn from(error: RestoreError) -> Self {
+        LockstepError::SnapshotRejected(error)
+    }
+}
+
+impl From<SerializationError> for LockstepError {
+    fn from(error: SerializationError) -> Self {
+        LockstepError::Serialization(error)
+    }
+}
+
+/// Events that can occur during lockstep processing
+#[derive(Debug, Clone, PartialEq)]
+pub enum LockstepEvent {
+    /// Game advanced to a new tick with these events
+    GameAdvanced { tick: Tick, events: Vec<Event> },
+    /// Nothing heard from the peer within the timeout (see `set_peer_timeout`);
+    /// the simulation is suspended until it is heard from again
+    PeerDisconnected,
+    /// The peer's traffic resumed after a `PeerDisconnected`
+    PeerReconnected,
+    /// The transport reported it closed under us (not by our own `close`);
+    /// the simulation is suspended as for `PeerDisconnected` until `reattach`
+    ConnectionLost { reason: CloseReason },
+    /// Ping response received
+    PongReceived { round_trip_ms: u32 },
+    /// Snapshot received from peer
+    SnapshotReceived { tick: Tick },
+    /// Peer asked for our state (we already answered with a snapshot)
+    SnapshotRequested { since_tick: Tick },
+    /// Peer handshake accepted; `version` is the negotiated protocol version
+    PeerHello { version: u8, capabilities: u32 },
+    /// (Host) a guest asked to join; answer with `welcome`
+    PeerJoined,
+    /// (Guest) the host assigned our side and shared its rules
+    Welcomed { side: Side, config: Config },
+    /// The host scheduled the match to begin at `start_tick`
+    MatchStarting { start_tick: Tick },
+    /// Both peers confirmed they simulate with identical configs
+    ConfigVerified,
+    /// Chat text from the peer
+    ChatReceived { text: String },
+    /// Reaction from the peer (see `wire_protocol::emotes`)
+    EmoteReceived { emote: u8 },
+    /// The peers' state hashes first disagreed after simulating `tick`
+    DesyncDetected { tick: Tick, local: u64, remote: u64 },
+    /// The timekeeper's snapshot for `tick` replaced our diverged state
+    Resynced { tick: Tick },
+    /// The peer's input for `missing_tick` is overdue (see `set_stall_threshold`)
+    WaitingForRemote { missing_tick: Tick, waited_ms: u32 },
+    /// The input awaited since `WaitingForRemote` arrived after `waited_ms`
+    Resumed { waited_ms: u32 },
+    /// The peer paused the match; both sides hold before stepping `at_tick`
+    PeerPaused { at_tick: Tick },
+    /// The peer lifted the pause held at `at_tick`
+    PeerResumed { at_tick: Tick },
+    /// After `reattach`, both peers share the state at `tick` and play on
+    SessionResumed { tick: Tick },
+    /// `dropped` peer inputs from `tick` on lay beyond `INPUT_WINDOW` and were discarded
+    InputOverflow { tick: Tick, dropped: u32 },
+    /// The peer stayed disconnected, so the AI plays its side from `tick` on
+    /// (see `set_ai_takeover`)
+    AiTookOver { tick: Tick },
+    /// The peer sent `conflicting` inputs from `tick` on that differ from ones
+    /// already received for the same ticks; the first ones were kept
+    InputConflict { tick: Tick, conflicting: u32 },
+    /// A snapshot for `tick` was older than the ticks we can replay and was ignored
+    StaleSnapshot { tick: Tick },
+    /// The session is over and the transport closed, after our `shutdown` or
+    /// the peer's (`by_peer`)
+    SessionEnded { by_peer: bool },
+    /// A game of the series ended; `series` is the games each side has won
+    GameOver { winner: Side, series: [u8; 2] },
+    /// `winner` took a majority of the series' games; no next game follows
+    SeriesOver { winner: Side, series: [u8; 2] },
+    /// Game number `game` of the series begins at `start_tick`, served by `server`
+    NextGame {
+        game: u8,
+        start_tick: Tick,
+        server: Side,
+        series: [u8; 2],
+    },
+}
+
+/// Receives a session's events as they happen, for event-driven UIs that
+/// would rather not collect the `Vec<LockstepEvent>` each call returns (see
+/// `Lockstep::set_observer`). Every method does nothing by default.
+pub trait LockstepObserver {
+    /// The simulation stepped `tick`, producing `events`
+    fn on_game_advanced(&mut self, _tick: Tick, _events: &[Event]) {}
+
+    /// The peers' state hashes disagreed after `tick`
+    fn on_desync(&mut self, _tick: Tick, _local: u64, _remote: u64) {}
+
+    /// Any other event: the peer's handshake, connection, chat, pauses, series
+    fn on_peer_event(&mut self, _event: &LockstepEvent) {}
+
+    /// Session health, refreshed once a second while ticking
+    fn on_stats(&mut self, _metrics: &LockstepMetrics) {}
+}
+
+/// Local inputs kept to answer the peer's `InputRequest`
+const RETRANSMIT_HISTORY: usize = 120;
+
+/// Least time between two `InputRequest`s for the same tick
+const INPUT_REQUEST_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long `shutdown` waits for the peer to answer its Bye
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// How often `drive` pings the peer by default
+pub const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Most overdue ticks one `drive` call runs before skipping the backlog
+const MAX_CATCH_UP_TICKS: u32 = 4;
+
+/// Ticks between the snapshots relayed to spectators by default
+pub const DEFAULT_SPECTATOR_SNAPSHOT_INTERVAL: u32 = 300;
+
+/// Default ticks between keyframes in a recorded replay
+pub const DEFAULT_REPLAY_KEYFRAME_INTERVAL: u32 = 600;
+
+/// How long a tick may wait on remote input before `WaitingForRemote`
+pub const DEFAULT_STALL_THRESHOLD: Duration = Duration::from_millis(250);
+
+/// Recent samples `network_stats` summarizes
+const NETWORK_STATS_WINDOW: usize = 32;
+
+/// Connection quality over the last `NETWORK_STATS_WINDOW` samples, for a
+/// signal-bars indicator or choosing an input delay. All zero until measured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NetworkStats {
+    /// Round trips in the window
+    pub rtt_samples: u32,
+    /// Most recent round trip in ms
+    pub last_rtt_ms: u32,
+    /// Mean round trip
+    pub avg_rtt_ms: u32,
+    /// Fastest round trip
+    pub min_rtt_ms: u32,
+    /// Slowest round trip
+    pub max_rtt_ms: u32,
+    /// Mean change between consecutive round trips
+    pub jitter_ms: u32,
+    /// Longest silence between two messages from the peer
+    pub max_gap_ms: u32,
+    /// Our sequenced messages the peer reported missing (see `set_sequencing`)
+    pub lost_messages: u32,
+}
+
+/// Session health since `start`, for debug overlays and server dashboards
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LockstepMetrics {
+    /// Ticks simulated over the last full second
+    pub ticks_per_second: f32,
+    /// Ticks simulated in total
+    pub ticks_advanced: u32,
+    /// Ticks held past the stall threshold (each `WaitingForRemote`)
+    pub stalls: u32,
+    /// Mean number of peer inputs buffered, sampled on every `tick`
+    pub avg_buffer_depth: f32,
+    /// Messages received from the peer
+    pub messages_in: u32,
+    /// Messages sent to the peer
+    pub messages_out: u32,
+    /// Bytes received from the peer
+    pub bytes_in: u64,
+    /// Bytes sent to the peer
+    pub bytes_out: u64,
+}
+
+/// Push onto a window of the most recent `NETWORK_STATS_WINDOW` samples
+fn push_sample(window: &mut VecDeque<u32>, sample: u32) {
+    if window.len() == NETWORK_STATS_WINDOW {
+        window.pop_front();
+    }
+    window.push_back(sample);
+}
+
+/// How much the non-timekeeper stretches or shrinks its tick interval to keep pace
+const PACING_ADJUST_PERCENT: u32 = 2;
+
+/// Smoothed remote lead (peer inputs buffered ahead of us) we keep between;
+/// below it we outrun the peer, above it the peer outruns us
+const REMOTE_LEAD_RANGE: (f32, f32) = (0.5, 2.0);
+
+/// Ticks ahead of the current one whose inputs we buffer; later ones are rejected
+pub const INPUT_WINDOW: usize = 512;
+
+/// Inputs for ticks in `[base, base + INPUT_WINDOW)`, in a ring indexed by tick.
+/// Each slot remembers its tick, so entries left behind as the base moves can't
+/// answer for a later tick; they are simply overwritten.
+struct InputWindow {
+    slots: Vec<Option<(Tick, Input)>>,
+}
+
+impl InputWindow {
+    fn new() -> Self {
+        Self {
+            slots: vec![None; INPUT_WINDOW],
+        }
+    }
+
+    // INPUT_WINDOW divides 2^32, so slots stay consecutive across tick wraparound
+    fn slot(tick: Tick) -> usize {
+        tick as usize % INPUT_WINDOW
+    }
+
+    /// Store `input` for `tick` if it lies in the window starting at `base`
+    fn insert(&mut self, base: Tick, tick: Tick, input: Input) -> bool {
+        let ahead = tick_diff(tick, base);
+        if ahead < 0 || ahead as usize >= INPUT_WINDOW {
+            return false;
+        }
+        self.slots[Self::slot(tick)] = Some((tick, input));
+        true
+    }
+
+    fn get(&self, tick: Tick) -> Option<Input> {
+        match self.slots[Self::slot(tick)] {
+            Some((t, input)) if t == tick => Some(input),
+            _ => None,
+        }
+    }
+
+    fn contains(&self, tick: Tick) -> bool {
+        self.get(tick).is_some()
+    }
+
+    fn remove(&mut self, tick: Tick) -> Option<Input> {
+        let input = self.get(tick)?;
+        self.slots[Self::slot(tick)] = None;
+        Some(input)
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(Tick) -> bool) {
+        for slot in &mut self.slots {
+            if slot.is_some_and(|(tick, _)| !keep(tick)) {
+                *slot = None;
+            }
+        }
+    }
+
+    fn ticks(&self) -> impl Iterator<Item = Tick> + '_ {
+        self.slots.iter().flatten().map(|&(tick, _)| tick)
+    }
+
+    fn len(&self) -> usize {
+        self.slots.iter().flatten().count()
+    }
+
+    fn clear(&mut self) {
+        self.slots.fill(None);
+    }
+}
+
+/// Stepped input pairs kept so a snapshot from slightly behind us can be replayed
+const RESYNC_HISTORY: usize = 16;
+
+/// One-way input delay measured from timestamped inputs. Each sample includes
+/// any offset between the two peers' clocks, so values can be skewed or negative
+/// until the clocks agree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InputDelayStats {
+    /// Timestamped inputs measured so far
+    pub samples: u32,
+    /// Most recent inbound (peer to us) delay in ms
+    pub last_ms: i32,
+    /// Smallest inbound delay seen
+    pub min_ms: i32,
+    /// Largest inbound delay seen
+    pub max_ms: i32,
+    /// Smoothed inbound delay (moving average, 1/8 weight per sample)
+    pub avg_ms: i32,
+    /// Outbound (us to peer) delay, estimated as the last ping round trip
+    /// minus `avg_ms`; None until a ping has completed
+    pub outbound_ms: Option<i32>,
+}
+
+impl InputDelayStats {
+    fn record(&mut self, delay_ms: i32) {
+        if self.samples == 0 {
+            self.min_ms = delay_ms;
+            self.max_ms = delay_ms;
+            self.avg_ms = delay_ms;
+        } else {
+            self.min_ms = self.min_ms.min(delay_ms);
+            self.max_ms = self.max_ms.max(delay_ms);
+            self.avg_ms += (delay_ms - self.avg_ms) / 8;
+        }
+        self.last_ms = delay_ms;
+        self.samples = self.samples.saturating_add(1);
+    }
+}
+
+/// Counters for `Sequenced` traffic in both directions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SequenceStats {
+    /// Envelopes we sent
+    pub sent: u32,
+    /// Envelopes received from the peer, not counting duplicates
+    pub received: u32,
+    /// Envelopes received more than once and dropped
+    pub duplicates: u32,
+    /// Our envelopes the peer's acks reported missing
+    pub lost: u32,
+}
+
+/// Standing in a best-of-N series (see `Lockstep::set_best_of`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Series {
+    /// Games in the series
+    pub best_of: u8,
+    /// Games won by each side, left then right
+    pub wins: [u8; 2],
+    /// Number of the game being played, from 1
+    pub game: u8,
+}
+
+impl Series {
+    fn new(best_of: u8) -> Self {
+        Self {
+            best_of,
+            wins: [0, 0],
+            game: 1,
+        }
+    }
+
+    /// Wins that take the series
+    pub fn wins_needed(&self) -> u8 {
+        self.best_of / 2 + 1
+    }
+
+    /// Side that has taken the series, once decided
+    pub fn winner(&self) -> Option<Side> {
+        Side::BOTH
+            .into_iter()
+            .find(|&side| self.wins[side] >= self.wins_needed())
+    }
+}
+
+/// Which of the peer's sequence numbers have arrived, in `Ack` form
+#[derive(Debug, Default)]
+struct ReceiveWindow {
+    latest: Option<u32>,
+    mask: u32,
+}
+
+impl ReceiveWindow {
+    /// Note `seq` as received; false if it already was
+    fn record(&mut self, seq: u32) -> bool {
+        let Some(latest) = self.latest else {
+            self.latest = Some(seq);
+            return true;
+        };
+
+        let ahead = tick_diff(seq, latest);
+        if ahead > 0 {
+            // The old latest becomes bit `ahead - 1`; anything shifted past bit 31 is forgotten
+            let shift = ahead as u32;
+            self.mask = self.mask.checked_shl(shift).unwrap_or(0)
+                | 1u32.checked_shl(shift - 1).unwrap_or(0);
+            self.latest = Some(seq);
+            return true;
+        }
+        if ahead == 0 {
+            return false;
+        }
+
+        // Too old to tell; let it through rather than drop a first delivery
+        let Some(bit) = 1u32.checked_shl((-ahead - 1) as u32) else {
+            return true;
+        };
+        let fresh = self.mask & bit == 0;
+        self.mask |= bit;
+        fresh
+    }
+}
+
+/// Core adapter trait for the game engine
+pub trait CoreAdapter {
+    /// Step the game simulation forward one tick
+    fn step(&mut self, inputs: &InputPair) -> Option<Event>;
+
+    /// Get the current game view for rendering
+    fn view(&self) -> View;
+
+    /// Create a snapshot of the current game state
+    fn snapshot(&self) -> Snapshot;
+
+    /// Restore game state from a snapshot, refusing one taken under other rules
+    fn restore(&mut self, snapshot: &Snapshot) -> Result<(), RestoreError>;
+
+    /// Get the current tick number
+    fn current_tick(&self) -> Tick;
+
+    /// Hash of the simulation state, compared between peers to catch desyncs
+    fn state_hash(&self) -> u64 {
+        self.snapshot().state_hash()
+    }
+
+    /// Take every event from the last step in order, collisions included
+    fn drain_events(&mut self) -> Vec<Event>;
+
+    /// The rules being simulated
+    fn config(&self) -> Config;
+
+    /// Reset scores for the next game of a series, keeping the tick, with
+    /// `server` serving from `seed`
+    fn start_next_game(&mut self, seed: u64, server: Side);
+
+    /// Show the match as held (`Status::Paused` in the view) or release it
+    fn set_paused(&mut self, paused: bool);
+}
+
+/// Lockstep protocol implementation
+pub struct Lockstep<C: CoreAdapter, T: Transport> {
+    /// Game engine adapter
+    core: C,
+    /// Network transport
+    transport: T,
+    /// Time source for timeouts, pacing and ping timestamps
+    clock: Box<dyn Clock>,
+    /// Current tick we're processing
+    current_tick: Tick,
+    /// Which side we are (Left or Right)
+    local_side: Side,
+    /// Whether we're the timekeeper (controls when to start new matches)
+    is_timekeeper: bool,
+    /// Target tick rate (ticks per second)
+    tick_hz: u16,
+    /// Pace our ticks to the peer's progress (only applies to the non-timekeeper)
+    clock_sync: bool,
+    /// Smoothed count of consecutive remote inputs buffered from the current tick
+    remote_lead: f32,
+    /// Newest tick the peer has sent input for
+    latest_remote_tick: Option<Tick>,
+    /// Smoothed `frame_advantage`
+    frame_advantage_avg: f32,
+    /// Buffer of local inputs waiting to be processed
+    local_input_buffer: InputWindow,
+    /// Buffer of remote inputs waiting to be processed
+    remote_input_buffer: InputWindow,
+    /// Last timestamp when we sent a ping
+    last_ping_time: Option<Instant>,
+    /// Running state
+    is_running: bool,
+    /// Negotiated protocol version, once the peer's Hello arrives
+    peer_version: Option<u8>,
+    /// Capabilities advertised by the peer
+    peer_capabilities: u32,
+    /// How many recent local inputs each input message carries (1 = plain InputPair)
+    input_redundancy: usize,
+    /// Recently sent local inputs on consecutive ticks, oldest first
+    sent_inputs: VecDeque<(Tick, Input)>,
+    /// Local inputs kept for resending on request, in tick order
+    retained_inputs: VecDeque<(Tick, Input)>,
+    /// Tick we last asked the peer to resend from, and when
+    last_input_request: Option<(Tick, Instant)>,
+    /// Config we announced via `sync_config`; while set, ticks wait for the peer's
+    local_config: Option<Config>,
+    /// Config the peer announced
+    peer_config: Option<Config>,
+    /// Exchange a state hash every this many ticks (0 = never)
+    hash_interval: u32,
+    /// Our state hashes not yet matched against the peer's
+    local_hashes: HashMap<Tick, u64>,
+    /// Peer state hashes not yet matched against ours
+    remote_hashes: HashMap<Tick, u64>,
+    /// First tick the hashes disagreed on, once a desync has been reported
+    desync_tick: Option<Tick>,
+    /// On a desync, fetch and apply the timekeeper's snapshot
+    auto_resync: bool,
+    /// We asked for a snapshot to recover from a desync
+    resync_pending: bool,
+    /// A new transport was attached; waiting for the peer to catch up with us
+    rejoin_pending: bool,
+    /// Most recently stepped input pairs, oldest first
+    recent_pairs: VecDeque<InputPair>,
+    /// Reused for every outgoing message; grows to the largest one sent
+    send_buffer: Vec<u8>,
+    /// Partial frames from `on_stream_bytes`
+    stream_decoder: WireDecoder,
+    /// Events from frames applied before `on_stream_bytes` hit a bad one,
+    /// returned by its next call
+    stream_events: Vec<LockstepEvent>,
+    /// Stamp outgoing InputPairs with our clock
+    timestamp_inputs: bool,
+    /// Ticks local input may run ahead of the current tick
+    local_input_delay: u32,
+    /// Latest local input submitted while the delay window was full
+    held_input: Option<Input>,
+    /// Inbound delay measured from the peer's timestamped inputs
+    input_delay: InputDelayStats,
+    /// Round trip of the last answered ping
+    last_rtt_ms: Option<u32>,
+    /// Recent round trips in ms, oldest first
+    rtt_window: VecDeque<u32>,
+    /// Recent intervals between received messages in ms
+    gap_window: VecDeque<u32>,
+    /// Suspend after this long without a message from the peer
+    peer_timeout: Option<Duration>,
+    /// When the peer was last heard from (or `start`, before that)
+    last_received: Option<Instant>,
+    /// Set by the transport's close callback, taken by the next tick
+    connection_lost: Arc<Mutex<Option<CloseReason>>>,
+    /// Peer timed out; ticks hold until it is heard from again
+    suspended: bool,
+    /// Let the AI play the peer's side once it disconnects
+    ai_takeover: bool,
+    /// The AI has replaced the peer; the match is now local
+    ai_controlled: bool,
+    /// When `shutdown` sent its Bye, while waiting for the peer's
+    closing_since: Option<Instant>,
+    /// Report `WaitingForRemote` after waiting this long on the peer's input
+    stall_threshold: Option<Duration>,
+    /// Tick held for remote input and when the wait began
+    stalled_since: Option<(Tick, Instant)>,
+    /// `WaitingForRemote` was emitted for the current stall
+    stall_reported: bool,
+    /// When `drive` runs the next tick
+    next_tick_at: Option<Instant>,
+    /// `drive` pings the peer this often
+    ping_interval: Option<Duration>,
+    /// Agreed tick both peers hold before stepping, while a pause is in effect
+    pause_tick: Option<Tick>,
+    /// Best-of-N standing, when playing a series
+    series: Option<Series>,
+    /// The current game's end has been counted in `series`
+    game_decided: bool,
+    /// Next game of the series, as start tick, seed and server
+    next_game: Option<(Tick, u64, Side)>,
+    /// Read-only viewers sent each confirmed InputPair (see `spectator::Spectator`)
+    spectators: Vec<Box<dyn Transport>>,
+    /// Relay a snapshot to spectators every this many ticks (0 = only on join)
+    spectator_snapshot_interval: u32,
+    /// Match being recorded since `record_replay`
+    replay: Option<Replay>,
+    /// Record a keyframe every this many ticks (0 = only received snapshots)
+    replay_keyframe_interval: u32,
+    /// Wrap outgoing messages in `Sequenced` envelopes once the peer supports them
+    sequencing: bool,
+    /// Sequence number of the next envelope we send
+    next_seq: u32,
+    /// Newest of our sequence numbers an ack has accounted for
+    acked_through: Option<u32>,
+    /// Peer sequence numbers received, for acks and duplicate detection
+    receive_window: ReceiveWindow,
+    /// Envelopes arrived since our last Ack
+    ack_pending: bool,
+    /// The peer reported a loss; the next input history goes out in full
+    resend_pending: bool,
+    /// Counters for sequenced traffic
+    sequence_stats: SequenceStats,
+    /// Counters behind `metrics`
+    metrics: LockstepMetrics,
+    /// Told about every event as well as the caller (see `set_observer`)
+    observer: Option<Box<dyn LockstepObserver>>,
+    /// Sum and count of buffer depth samples, for `avg_buffer_depth`
+    depth_samples: (u64, u32),
+    /// Start of the current second and `ticks_advanced` then, for `ticks_per_second`
+    rate_window: Option<(Instant, u32)>,
+    /// Seals outgoing and verifies incoming messages when a room secret is set
+    #[cfg(feature = "auth")]
+    auth: Option<MessageAuth>,
+}
+
+impl<C: CoreAdapter, T: Transport> Lockstep<C, T> {
+    /// Create a new lockstep protocol instance
+    pub fn new(core: C, transport: T, tick_hz: u16, local_side: Side, is_timekeeper: bool) -> Self {
+        let mut instance = Self {
+            current_tick: core.current_tick(),
+            core,
+            transport,
+            clock: default_clock(),
+            local_side,
+            is_timekeeper,
+            tick_hz,
+            clock_sync: true,
+            remote_lead: 1.0,
+            latest_remote_tick: None,
+            frame_advantage_avg: 0.0,
+            local_input_buffer: InputWindow::new(),
+            remote_input_buffer: InputWindow::new(),
+            last_ping_time: None,
+            is_running: false,
+            peer_version: None,
+            peer_capabilities: 0,
+            input_redundancy: 1,
+            sent_inputs: VecDeque::new(),
+            retained_inputs: VecDeque::new(),
+            last_input_request: None,
+            local_config: None,
+            peer_config: None,
+            hash_interval: 0,
+            local_hashes: HashMap::new(),
+            remote_hashes: HashMap::new(),
+            desync_tick: None,
+            auto_resync: true,
+            resync_pending: false,
+            rejoin_pending: false,
+            recent_pairs: VecDeque::new(),
+            send_buffer: Vec::new(),
+            stream_decoder: WireDecoder::new(),
+            stream_events: Vec::new(),
+            timestamp_inputs: false,
+            local_input_delay: 0,
+            held_input: None,
+            input_delay: InputDelayStats::default(),
+            last_rtt_ms: None,
+            rtt_window: VecDeque::new(),
+            gap_window: VecDeque::new(),
+            peer_timeout: None,
+            last_received: None,
+            connection_lost: Arc::new(Mutex::new(None)),
+            suspended: false,
+            ai_takeover: false,
+            ai_controlled: false,
+            closing_since: None,
+            stall_threshold: Some(DEFAULT_STALL_THRESHOLD),
+            stalled_since: None,
+            stall_reported: false,
+            next_tick_at: None,
+            ping_interval: Some(DEFAULT_PING_INTERVAL),
+            pause_tick: None,
+            series: None,
+            game_decided: false,
+            next_game: None,
+            spectators: Vec::new(),
+            spectator_snapshot_interval: DEFAULT_SPECTATOR_SNAPSHOT_INTERVAL,
+            replay: None,
+            replay_keyframe_interval: DEFAULT_REPLAY_KEYFRAME_INTERVAL,
+            sequencing: false,
+            next_seq: 0,
+            acked_through: None,
+            receive_window: ReceiveWindow::default(),
+            ack_pending: false,
+            resend_pending: false,
+            sequence_stats: SequenceStats::default(),
+            metrics: LockstepMetrics::default(),
+            observer: None,
+            depth_samples: (0, 0),
+            rate_window: None,
+            #[cfg(feature = "auth")]
+            auth: None,
+        };
+
+        // Set up the message handler for incoming network messages
+        // Note: This is a bit tricky because we need to handle the callback
+        // For now, we'll leave this for the client to handle externally
+        instance.watch_transport();
+
+        instance
+    }
+
+    /// Note when the transport closes under us, for `check_connection`
+    fn watch_transport(&mut self) {
+        *self.lost_connection() = None;
+        let lost = Arc::clone(&self.connection_lost);
+        self.transport.set_on_close(Box::new(move |reason| {
+            if reason != CloseReason::Local {
+                *lock(&lost) = Some(reason);
+            }
+        }));
+    }
+
+    fn lost_connection(&self) -> std::sync::MutexGuard<'_, Option<CloseReason>> {
+        lock(&self.connection_lost)
+    }
+
+    /// Start the lockstep protocol
+    pub fn start(&mut self) -> Result<(), LockstepError> {
+        if !self.transport.is_open() {
+            return Err(LockstepError::Transport(
+                "Transport not connected".to_string(),
+            ));
+        }
+
+        self.is_running = true;
+        self.current_tick = self.core.current_tick();
+        self.remote_lead = 1.0;
+        self.latest_remote_tick = None;
+        self.frame_advantage_avg = 0.0;
+
+        // Clear any stale buffered inputs
+        self.local_input_buffer.clear();
+        self.remote_input_buffer.clear();
+        self.sent_inputs.clear();
+        self.retained_inputs.clear();
+        self.held_input = None;
+        self.last_input_request = None;
+        self.clear_hashes();
+        self.recent_pairs.clear();
+        self.stream_decoder.reset();
+        self.stream_events.clear();
+        self.input_delay = InputDelayStats::default();
+        self.last_rtt_ms = None;
+        self.rtt_window.clear();
+        self.gap_window.clear();
+        self.last_received = Some(self.clock.now());
+        self.suspended = false;
+        self.ai_controlled = false;
+        self.closing_since = None;
+        self.stalled_since = None;
+        self.stall_reported = false;
+        self.pause_tick = None;
+        self.sync_paused();
+        if let Some(series) = &mut self.series {
+            *series = Series::new(series.best_of);
+        }
+        self.game_decided = false;
+        self.next_game = None;
+        self.rejoin_pending = false;
+        self.next_tick_at = None;
+        self.last_ping_time = None;
+        self.next_seq = 0;
+        self.acked_through = None;
+        self.receive_window = ReceiveWindow::default();
+        self.ack_pending = false;
+        self.resend_pending = false;
+        self.sequence_stats = SequenceStats::default();
+        self.metrics = LockstepMetrics::default();
+        self.depth_samples = (0, 0);
+        self.rate_window = None;
+
+        // Announce our protocol version; the peer answers with its own
+        self.send_msg(&WireMsg::hello())?;
+
+        Ok(())
+    }
+
+    /// Continue this session over `transport` after the old connection dropped.
+    /// Both peers call it once reconnected. The timekeeper sends its snapshot
+    /// and any inputs the peer may have missed; the other peer restores that
+    /// snapshot, resends its own inputs from there and play carries on.
+    /// `SessionResumed` reports when the peers are realigned.
+    pub fn reattach(&mut self, transport: T) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+        if !transport.is_open() {
+            return Err(LockstepError::Transport(
+                "Transport not connected".to_string(),
+            ));
+        }
+
+        self.transport = transport;
+        self.watch_transport();
+        self.rejoin()
+    }
+
+    /// Like `reattach`, for a transport that reconnected in place (e.g. a
+    /// `ReconnectingTransport` reporting `Opened`)
+    pub fn rejoin(&mut self) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+
+        // Per-connection state starts over; the simulation does not
+        self.stream_decoder.reset();
+        self.last_received = Some(self.clock.now());
+        self.next_seq = 0;
+        self.acked_through = None;
+        self.receive_window = ReceiveWindow::default();
+        self.ack_pending = false;
+        self.last_input_request = None;
+        self.clear_hashes();
+        self.rejoin_pending = true;
+
+        self.send_msg(&WireMsg::hello())?;
+        if self.is_timekeeper {
+            self.send_snapshot()?;
+            self.resend_inputs(self.current_tick)?;
+        }
+        Ok(())
+    }
+
+    /// Read time from `clock` instead of the platform default (see `clock`);
+    /// set it before `start`, as instants from different clocks don't compare
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Box::new(clock);
+    }
+
+    /// Report every event to `observer` as it happens, in addition to returning
+    /// it, replacing any observer set before
+    pub fn set_observer(&mut self, observer: impl LockstepObserver + 'static) {
+        self.observer = Some(Box::new(observer));
+    }
+
+    /// Stop reporting to the observer, handing it back
+    pub fn take_observer(&mut self) -> Option<Box<dyn LockstepObserver>> {
+        self.observer.take()
+    }
+
+    /// Hand `events` to the observer, if one is set
+    fn notify(&mut self, events: &[LockstepEvent]) {
+        let Some(observer) = &mut self.observer else {
+            return;
+        };
+        for event in events {
+            match event {
+                LockstepEvent::GameAdvanced { tick, events } => {
+                    observer.on_game_advanced(*tick, events)
+                }
+                LockstepEvent::DesyncDetected {
+                    tick,
+                    local,
+                    remote,
+                } => observer.on_desync(*tick, *local, *remote),
+                event => observer.on_peer_event(event),
+            }
+        }
+    }
+
+    /// Swap in a fresh simulation for rules learned during the handshake
+    pub(crate) fn replace_core(&mut self, core: C, tick_hz: u16) {
+        self.current_tick = core.current_tick();
+        self.core = core;
+        self.tick_hz = tick_hz;
+        self.sync_paused();
+    }
+
+    /// Stop the lockstep protocol
+    pub fn stop(&mut self) {
+        self.is_running = false;
+        self.local_input_buffer.clear();
+        self.remote_input_buffer.clear();
+        self.sent_inputs.clear();
+        self.retained_inputs.clear();
+    }
+
+    /// Leave the session cleanly: resend the inputs the peer may still need,
+    /// say Bye and stop simulating. The transport closes once the peer answers
+    /// or `SHUTDOWN_TIMEOUT` passes, reported by `SessionEnded` from `tick`.
+    pub fn shutdown(&mut self) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+        if self.closing_since.is_some() {
+            return Ok(());
+        }
+
+        self.resend_inputs(self.current_tick)?;
+        if self.ack_pending {
+            if let Some(latest) = self.receive_window.latest {
+                let mask = self.receive_window.mask;
+                self.send_msg(&WireMsg::Ack { latest, mask })?;
+            }
+            self.ack_pending = false;
+        }
+        // A peer that doesn't know Bye gets none, and won't be waited for
+        if self.peer_supports(capabilities::BYE) {
+            self.send_msg(&WireMsg::Bye)?;
+        }
+        self.closing_since = Some(self.clock.now());
+        Ok(())
+    }
+
+    /// Whether `shutdown` is waiting for the peer's Bye
+    pub fn is_shutting_down(&self) -> bool {
+        self.closing_since.is_some()
+    }
+
+    /// Close the transport and stop, reporting the end of the session
+    fn end_session(&mut self, by_peer: bool, events: &mut Vec<LockstepEvent>) {
+        // A transport the peer already closed has nothing left to do
+        let _ = self.transport.close();
+        self.stop();
+        self.closing_since = None;
+        events.push(LockstepEvent::SessionEnded { by_peer });
+    }
+
+    /// Report `PeerDisconnected` and suspend the simulation when nothing arrives
+    /// from the peer for `timeout` (None disables the watchdog)
+    pub fn set_peer_timeout(&mut self, timeout: Option<Duration>) {
+        self.peer_timeout = timeout;
+    }
+
+    /// Whether the peer timed out and the simulation is on hold
+    pub fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    /// Instead of suspending when the peer times out (see `set_peer_timeout`),
+    /// let the AI play its side so the match carries on locally. The takeover
+    /// lasts for the rest of the session: later traffic from the peer is ignored.
+    pub fn set_ai_takeover(&mut self, enabled: bool) {
+        self.ai_takeover = enabled;
+    }
+
+    /// Whether the AI has taken over the peer's side
+    pub fn is_ai_controlled(&self) -> bool {
+        self.ai_controlled
+    }
+
+    /// Hand the peer's side to the AI, for good
+    fn take_over_remote(&mut self, events: &mut Vec<LockstepEvent>) {
+        self.suspended = false;
+        self.ai_controlled = true;
+        // Nobody is left to agree a pause or resume with
+        self.pause_tick = None;
+        self.sync_paused();
+        self.stalled_since = None;
+        self.stall_reported = false;
+        events.push(LockstepEvent::AiTookOver {
+            tick: self.current_tick,
+        });
+    }
+
+    /// Suspend as soon as the transport reports the connection lost
+    fn check_connection(&mut self, events: &mut Vec<LockstepEvent>) {
+        let Some(reason) = self.lost_connection().take() else {
+            return;
+        };
+        // An AI already playing the peer's side carries on regardless
+        if !self.ai_controlled {
+            self.suspended = true;
+        }
+        events.push(LockstepEvent::ConnectionLost { reason });
+    }
+
+    /// Suspend once the peer has been silent for longer than the timeout
+    fn check_peer_timeout(&mut self, now: Instant, events: &mut Vec<LockstepEvent>) {
+        let (Some(timeout), Some(last)) = (self.peer_timeout, self.last_received) else {
+            return;
+        };
+        if !self.suspended && now.saturating_duration_since(last) > timeout {
+            self.suspended = true;
+            events.push(LockstepEvent::PeerDisconnected);
+        }
+    }
+
+    /// Report `WaitingForRemote` once a tick has waited `threshold` on the
+    /// peer's input, and `Resumed` when it arrives (None disables)
+    pub fn set_stall_threshold(&mut self, threshold: Option<Duration>) {
+        self.stall_threshold = threshold;
+    }
+
+    /// Track how long the current tick has been held for remote input
+    fn check_stall(&mut self, now: Instant, events: &mut Vec<LockstepEvent>) {
+        if let Some((tick, since)) = self.stalled_since {
+            if tick == self.current_tick && !self.remote_input_buffer.contains(tick) {
+                let waited = now.saturating_duration_since(since);
+                if let Some(threshold) = self.stall_threshold {
+                    if !self.stall_reported && waited > threshold {
+                        self.stall_reported = true;
+                        self.metrics.stalls = self.metrics.stalls.saturating_add(1);
+                        events.push(LockstepEvent::WaitingForRemote {
+                            missing_tick: tick,
+                            waited_ms: duration_ms(waited),
+                        });
+                    }
+                }
+                return;
+            }
+
+            self.stalled_since = None;
+            if self.stall_reported {
+                self.stall_reported = false;
+                events.push(LockstepEvent::Resumed {
+                    waited_ms: duration_ms(now.saturating_duration_since(since)),
+                });
+            }
+        }
+
+        if self.is_waiting_for_remote() {
+            self.stalled_since = Some((self.current_tick, now));
+        }
+    }
+
+    /// Attach a read-only viewer. It is sent our Hello and current snapshot
+    /// now, then every confirmed InputPair and a periodic snapshot; anything it
+    /// sends back is ignored. Spectators whose transport fails are dropped.
+    pub fn add_spectator(&mut self, transport: Box<dyn Transport>) -> Result<(), LockstepError> {
+        if !transport.is_open() {
+            return Err(LockstepError::Transport(
+                "Spectator transport not connected".to_string(),
+            ));
+        }
+
+        transport.send(&WireMsg::hello().encode())?;
+        transport.send(&WireMsg::snapshot(&self.core.snapshot()).encode())?;
+        self.spectators.push(transport);
+        Ok(())
+    }
+
+    /// Number of attached spectators
+    pub fn spectator_count(&self) -> usize {
+        self.spectators.len()
+    }
+
+    /// Relay a snapshot to spectators every `ticks` ticks so they can correct
+    /// drift and late joiners catch up (0 sends one only when they attach)
+    pub fn set_spectator_snapshot_interval(&mut self, ticks: u32) {
+        self.spectator_snapshot_interval = ticks;
+    }
+
+    /// Record every confirmed InputPair from the current state on into a
+    /// replay collected with `take_replay`. Snapshots we restore are recorded
+    /// as keyframes, so a resync shows up in the replay.
+    pub fn record_replay(&mut self) {
+        self.replay = Some(Replay::new(self.core.config(), self.core.snapshot()));
+    }
+
+    /// Record a keyframe every `ticks` ticks while recording (0 = only
+    /// received snapshots)
+    pub fn set_replay_keyframe_interval(&mut self, ticks: u32) {
+        self.replay_keyframe_interval = ticks;
+    }
+
+    /// Whether `record_replay` is collecting a replay
+    pub fn is_recording(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// Stop recording and return the replay, closed with the current tick and
+    /// state hash
+    pub fn take_replay(&mut self) -> Option<Replay> {
+        let mut replay = self.replay.take()?;
+        let snapshot = self.core.snapshot();
+        replay.finish(snapshot.tick, self.core.state_hash());
+        Some(replay)
+    }
+
+    /// Send `msg` to every spectator, dropping those that can't take it
+    fn relay_to_spectators(&mut self, msg: &WireMsg) {
+        if self.spectators.is_empty() {
+            return;
+        }
+
+        let bytes = msg.encode();
+        self.spectators
+            .retain(|spectator| spectator.is_open() && spectator.send(&bytes).is_ok());
+    }
+
+    /// Exchange state hashes with the peer every `ticks` ticks (0 disables)
+    /// and report a `DesyncDetected` event when they disagree
+    pub fn set_hash_interval(&mut self, ticks: u32) {
+        self.hash_interval = ticks;
+    }
+
+    /// First tick on which the peers' state hashes disagreed, if any
+    pub fn desync_tick(&self) -> Option<Tick> {
+        self.desync_tick
+    }
+
+    /// When hashes disagree, have the non-timekeeper request the timekeeper's
+    /// snapshot and apply it, reporting `Resynced` (on by default)
+    pub fn set_auto_resync(&mut self, enabled: bool) {
+        self.auto_resync = enabled;
+    }
+
+    fn clear_hashes(&mut self) {
+        self.local_hashes.clear();
+        self.remote_hashes.clear();
+        self.desync_tick = None;
+        self.resync_pending = false;
+    }
+
+    /// Compare both hashes for `tick` once present; reports only the first desync
+    fn check_hash(
+        &mut self,
+        tick: Tick,
+        events: &mut Vec<LockstepEvent>,
+    ) -> Result<(), LockstepError> {
+        let (Some(&local), Some(&remote)) =
+            (self.local_hashes.get(&tick), self.remote_hashes.get(&tick))
+        else {
+            return Ok(());
+        };
+        self.local_hashes.remove(&tick);
+        self.remote_hashes.remove(&tick);
+
+        if local != remote && self.desync_tick.is_none() {
+            self.desync_tick = Some(tick);
+            events.push(LockstepEvent::DesyncDetected {
+                tick,
+                local,
+                remote,
+            });
+
+            // The timekeeper's state is authoritative; the other peer adopts it
+            if self.auto_resync && !self.is_timekeeper {
+                self.resync_pending = true;
+                self.send_msg(&WireMsg::SnapshotRequest {
+                    since_tick: self.current_tick,
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget unmatched hashes too old to ever be compared
+    fn prune_hashes(&mut self) {
+        const KEEP_INTERVALS: u32 = 32;
+        let horizon = self.hash_interval.saturating_mul(KEEP_INTERVALS);
+        let oldest = self.current_tick.wrapping_sub(horizon);
+        self.local_hashes.retain(|&t, _| !tick_after(oldest, t));
+        self.remote_hashes.retain(|&t, _| !tick_after(oldest, t));
+    }
+
+    /// Buffer the remote half of a received InputPair
+    fn accept_remote_pair(&mut self, input_pair: InputPair, events: &mut Vec<LockstepEvent>) {
+        // Extract the remote input for our current tick
+        let remote_input = match self.local_side {
+            Side::Left => input_pair.b,  // We're left, so remote is right (b)
+            Side::Right => input_pair.a, // We're right, so remote is left (a)
+        };
+
+        self.accept_remote_inputs(input_pair.tick, [remote_input], events);
+    }
+
+    /// Buffer the peer's inputs for consecutive ticks from `start_tick`.
+    /// Conflicts resolve the same way on every peer:
+    /// - the first input received for a tick is kept; a different one arriving
+    ///   later is dropped and reported as `InputConflict`
+    /// - inputs for ticks we already simulated are stale and ignored
+    /// - inputs beyond `INPUT_WINDOW` are dropped and reported as `InputOverflow`
+    fn accept_remote_inputs(
+        &mut self,
+        start_tick: Tick,
+        inputs: impl IntoIterator<Item = Input>,
+        events: &mut Vec<LockstepEvent>,
+    ) {
+        let mut overflow: Option<(Tick, u32)> = None;
+        let mut conflict: Option<(Tick, u32)> = None;
+        for (offset, input) in inputs.into_iter().enumerate() {
+            let tick = start_tick.wrapping_add(offset as u32);
+            if self
+                .latest_remote_tick
+                .is_none_or(|latest| tick_after(tick, latest))
+            {
+                self.latest_remote_tick = Some(tick);
+            }
+            if tick_diff(tick, self.current_tick) < 0 {
+                continue;
+            }
+            if let Some(held) = self.remote_input_buffer.get(tick) {
+                // Repeats from redundancy and resends are expected; changes are not
+                if held != input {
+                    let (_, conflicting) = conflict.get_or_insert((tick, 0));
+                    *conflicting += 1;
+                }
+                continue;
+            }
+            if !self
+                .remote_input_buffer
+                .insert(self.current_tick, tick, input)
+            {
+                let (_, dropped) = overflow.get_or_insert((tick, 0));
+                *dropped += 1;
+            }
+        }
+
+        if let Some((tick, dropped)) = overflow {
+            events.push(LockstepEvent::InputOverflow { tick, dropped });
+        }
+        if let Some((tick, conflicting)) = conflict {
+            events.push(LockstepEvent::InputConflict { tick, conflicting });
+        }
+    }
+
+    /// Encode into the reusable send buffer and hand it to the transport,
+    /// inside a `Sequenced` envelope when sequencing is active
+    fn send_msg(&mut self, msg: &WireMsg) -> Result<(), LockstepError> {
+        // The peer is gone for good; nothing is listening
+        if self.ai_controlled {
+            return Ok(());
+        }
+
+        // Hello must stay readable by any peer, and acking acks would never end
+        let sequenced =
+            self.is_sequencing() && !matches!(msg, WireMsg::Hello { .. } | WireMsg::Ack { .. });
+        let options = EncodeOptions {
+            seq: sequenced.then_some(self.next_seq),
+            peer_capabilities: self.peer_capabilities,
+        };
+        let size = msg.encoded_size_with(&options);
+        #[cfg(feature = "auth")]
+        let size = match self.auth {
+            Some(_) => MessageAuth::sealed_len(size),
+            None => size,
+        };
+        if self.send_buffer.len() < size {
+            self.send_buffer.resize(size, 0);
+        }
+
+        let len = msg.encode_with(&options, &mut self.send_buffer)?;
+        #[cfg(feature = "auth")]
+        let len = match &self.auth {
+            Some(auth) => auth.seal(&mut self.send_buffer, len)?,
+            None => len,
+        };
+        self.transport.send(&self.send_buffer[..len])?;
+        self.metrics.messages_out = self.metrics.messages_out.saturating_add(1);
+        self.metrics.bytes_out += len as u64;
+
+        if sequenced {
+            self.next_seq = self.next_seq.wrapping_add(1);
+            self.sequence_stats.sent = self.sequence_stats.sent.saturating_add(1);
+        }
+        Ok(())
+    }
+
+    /// Number our outgoing messages so the peer can ack them, letting input
+    /// redundancy (see `set_input_redundancy`) go out only after a reported loss.
+    /// Takes effect once the peer's Hello advertises `capabilities::SEQUENCING`.
+    pub fn set_sequencing(&mut self, enabled: bool) {
+        self.sequencing = enabled;
+    }
+
+    /// Authenticate all traffic with a key derived from `secret` (shared with the
+    /// peer out of band), or stop with None. Set it before `start` so the Hello
+    /// handshake is sealed too; from then on unsealed or forged messages are
+    /// rejected with `SerializationError::AuthenticationFailed`.
+    #[cfg(feature = "auth")]
+    pub fn set_room_secret(&mut self, secret: Option<&[u8]>) {
+        self.auth = secret.map(MessageAuth::new);
+    }
+
+    /// Whether outgoing messages are currently sequenced
+    pub fn is_sequencing(&self) -> bool {
+        self.sequencing && self.peer_supports(capabilities::SEQUENCING)
+    }
+
+    /// Sent, received, duplicate and lost envelope counts
+    pub fn sequence_stats(&self) -> SequenceStats {
+        self.sequence_stats
+    }
+
+    /// Count our envelopes the peer's ack shows missing; any loss schedules a resend
+    fn on_ack(&mut self, latest: u32, mask: u32) {
+        // Ignore acks for envelopes we never sent
+        if tick_diff(latest, self.next_seq) >= 0 {
+            return;
+        }
+        // Envelopes up to `acked_through` were judged by an earlier ack
+        let from = match self.acked_through {
+            Some(through) if tick_diff(latest, through) <= 0 => return,
+            Some(through) => through.wrapping_add(1),
+            None => 0,
+        };
+
+        let span = latest.wrapping_sub(from);
+        let missing = (0..span.min(32)).filter(|i| mask & (1 << i) == 0).count() as u32
+            + span.saturating_sub(32);
+        self.acked_through = Some(latest);
+
+        if missing > 0 {
+            self.sequence_stats.lost = self.sequence_stats.lost.saturating_add(missing);
+            self.resend_pending = true;
+        }
+    }
+
+    /// Resend the last `count` local inputs with every new one (1 disables redundancy).
+    /// Useful over unreliable transports, where one lost datagram would otherwise stall.
+    pub fn set_input_redundancy(&mut self, count: usize) {
+        self.input_redundancy = count.clamp(1, MAX_BATCH_INPUTS);
+        while self.sent_inputs.len() > self.input_redundancy {
+            self.sent_inputs.pop_front();
+        }
+    }
+
+    /// Send inputs as InputPairs stamped with our millisecond clock so the peer
+    /// can measure input delay (only applies without input redundancy)
+    pub fn set_input_timestamps(&mut self, enabled: bool) {
+        self.timestamp_inputs = enabled;
+    }
+
+    /// Per-direction input delay, from the peer's timestamped inputs and our pings
+    pub fn input_delay_stats(&self) -> InputDelayStats {
+        InputDelayStats {
+            outbound_ms: self
+                .last_rtt_ms
+                .filter(|_| self.input_delay.samples > 0)
+                .map(|rtt| rtt as i32 - self.input_delay.avg_ms),
+            ..self.input_delay
+        }
+    }
+
+    fn record_rtt(&mut self, round_trip_ms: u32) {
+        self.last_rtt_ms = Some(round_trip_ms);
+        push_sample(&mut self.rtt_window, round_trip_ms);
+    }
+
+    /// Throughput, stall and traffic figures since `start`
+    pub fn metrics(&self) -> LockstepMetrics {
+        let (total, samples) = self.depth_samples;
+        LockstepMetrics {
+            avg_buffer_depth: if samples == 0 {
+                0.0
+            } else {
+                total as f32 / samples as f32
+            },
+            ..self.metrics
+        }
+    }
+
+    /// Sample buffer depth and roll the tick rate over each second
+    fn sample_metrics(&mut self, now: Instant) {
+        let (total, samples) = &mut self.depth_samples;
+        *total += self.remote_input_buffer.len() as u64;
+        *samples = samples.saturating_add(1);
+
+        let ticks = self.metrics.ticks_advanced;
+        let Some((since, ticks_then)) = self.rate_window else {
+            self.rate_window = Some((now, ticks));
+            return;
+        };
+        let elapsed = now.saturating_duration_since(since);
+        if elapsed >= Duration::from_secs(1) {
+            self.metrics.ticks_per_second =
+                ticks.wrapping_sub(ticks_then) as f32 / elapsed.as_secs_f32();
+            self.rate_window = Some((now, ticks));
+
+            let metrics = self.metrics();
+            if let Some(observer) = &mut self.observer {
+                observer.on_stats(&metrics);
+            }
+        }
+    }
+
+    /// Round trip, jitter and gap figures over recent pings and messages
+    pub fn network_stats(&self) -> NetworkStats {
+        let rtts = &self.rtt_window;
+        let count = rtts.len() as u64;
+        let mean = |total: u64, n: u64| if n == 0 { 0 } else { (total / n) as u32 };
+
+        let deltas: Vec<u32> = rtts
+            .iter()
+            .zip(rtts.iter().skip(1))
+            .map(|(a, b)| a.abs_diff(*b))
+            .collect();
+
+        NetworkStats {
+            rtt_samples: count as u32,
+            last_rtt_ms: rtts.back().copied().unwrap_or(0),
+            avg_rtt_ms: mean(rtts.iter().map(|&r| r as u64).sum(), count),
+            min_rtt_ms: rtts.iter().copied().min().unwrap_or(0),
+            max_rtt_ms: rtts.iter().copied().max().unwrap_or(0),
+            jitter_ms: mean(deltas.iter().map(|&d| d as u64).sum(), deltas.len() as u64),
+            max_gap_ms: self.gap_window.iter().copied().max().unwrap_or(0),
+            lost_messages: self.sequence_stats.lost,
+        }
+    }
+
+    /// Submit local input (see `submit_local_input`)
+    pub fn on_local_input(&mut self, axis_y: i8, buttons: u8) -> Result<(), LockstepError> {
+        self.submit_local_input(Input::new(axis_y, buttons))
+    }
+
+    /// Submit a full local input (including tilt) for the first tick from the
+    /// current one that has none yet, up to `set_local_input_delay` ticks ahead.
+    /// Once every tick in that window has gone out, the input is held instead,
+    /// later calls replacing it, and sent for the next tick to open. Sampling
+    /// input at display rate thus sends one input per tick, never a second,
+    /// different one for a tick the peer already has.
+    pub fn submit_local_input(&mut self, input: Input) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+
+        match self.open_local_tick() {
+            Some(tick) => self.send_local_input(tick, input),
+            None => {
+                self.held_input = Some(input);
+                Ok(())
+            }
+        }
+    }
+
+    /// Let local input run up to `ticks` ahead of the current tick, giving it
+    /// that long to reach the peer before it's needed (0 = current tick only)
+    pub fn set_local_input_delay(&mut self, ticks: u32) {
+        self.local_input_delay = ticks.min(INPUT_WINDOW as u32 - 1);
+    }
+
+    /// First tick in the input delay window we haven't sent input for
+    fn open_local_tick(&self) -> Option<Tick> {
+        (0..=self.local_input_delay)
+            .map(|offset| self.current_tick.wrapping_add(offset))
+            .find(|&tick| !self.local_input_buffer.contains(tick))
+    }
+
+    /// Send the input held while the delay window was full, once a tick opens
+    fn flush_held_input(&mut self) -> Result<(), LockstepError> {
+        if let (Some(input), Some(tick)) = (self.held_input, self.open_local_tick()) {
+            self.held_input = None;
+            self.send_local_input(tick, input)?;
+        }
+        Ok(())
+    }
+
+    /// Buffer our input for `tick` (as the peer will read it) and send it
+    fn send_local_input(&mut self, tick: Tick, input: Input) -> Result<(), LockstepError> {
+        let input = input_for_peer(input, self.peer_capabilities);
+        self.local_input_buffer
+            .insert(self.current_tick, tick, input);
+        self.retain_input(tick, input);
+
+        if self.input_redundancy > 1 {
+            return self.send_input_history(tick, input);
+        }
+
+        // Peers that understand it get just our half, tagged with our side
+        if !self.timestamp_inputs && self.peer_supports(capabilities::SIDE_INPUT) {
+            let wire_msg = WireMsg::SideInput {
+                tick,
+                side: self.local_side,
+                input,
+            };
+            return self.send_msg(&wire_msg);
+        }
+
+        // Older peers expect an InputPair; the remote half is a placeholder
+        let remote_input = Input::zero();
+        let input_pair = match self.local_side {
+            Side::Left => InputPair::new(tick, input, remote_input),
+            Side::Right => InputPair::new(tick, remote_input, input),
+        };
+
+        if self.timestamp_inputs {
+            self.send_msg(&WireMsg::TimedInputPair {
+                pair: input_pair,
+                sent_ms: self.clock.wall_ms(),
+            })?;
+        } else {
+            self.send_msg(&WireMsg::InputPair(input_pair))?;
+        }
+
+        Ok(())
+    }
+
+    /// Record `input` for the current tick and send it along with recent predecessors
+    fn send_input_history(&mut self, tick: Tick, input: Input) -> Result<(), LockstepError> {
+        // Resubmitting a tick replaces it; a gap (e.g. after a snapshot) restarts the run
+        if self.sent_inputs.back().map(|&(t, _)| t) == Some(tick) {
+            self.sent_inputs.pop_back();
+        }
+        if let Some(&(last, _)) = self.sent_inputs.back() {
+            if last.wrapping_add(1) != tick {
+                self.sent_inputs.clear();
+            }
+        }
+        self.sent_inputs.push_back((tick, input));
+        while self.sent_inputs.len() > self.input_redundancy {
+            self.sent_inputs.pop_front();
+        }
+
+        // With sequencing, the history only goes out in full after a reported loss
+        let count = if self.is_sequencing() && !self.resend_pending {
+            1
+        } else {
+            self.sent_inputs.len()
+        };
+        self.resend_pending = false;
+
+        let skip = self.sent_inputs.len() - count;
+        let inputs = self
+            .sent_inputs
+            .iter()
+            .skip(skip)
+            .map(|&(_, input)| input)
+            .collect();
+        let wire_msg = WireMsg::input_history(tick, inputs);
+        self.send_msg(&wire_msg)?;
+
+        Ok(())
+    }
+
+    /// Submit local inputs for consecutive ticks starting at the first tick
+    /// without input yet, sent to the peer as a single batch (useful when
+    /// catching up after a stall). Ticks whose input already went out keep it.
+    pub fn submit_local_inputs(&mut self, inputs: &[Input]) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+        let sent = (0..INPUT_WINDOW)
+            .find(|&offset| {
+                let tick = self.current_tick.wrapping_add(offset as u32);
+                !self.local_input_buffer.contains(tick)
+            })
+            .unwrap_or(INPUT_WINDOW);
+        if sent + inputs.len() > INPUT_WINDOW {
+            return Err(LockstepError::InputOutsideWindow(
+                self.current_tick.wrapping_add(INPUT_WINDOW as u32),
+            ));
+        }
+
+        for (index, chunk) in inputs.chunks(MAX_BATCH_INPUTS).enumerate() {
+            let start_tick = self
+                .current_tick
+                .wrapping_add((sent + index * MAX_BATCH_INPUTS) as u32);
+            let chunk: Vec<Input> = chunk
+                .iter()
+                .map(|&input| input_for_peer(input, self.peer_capabilities))
+                .collect();
+            for (offset, &input) in chunk.iter().enumerate() {
+                let tick = start_tick.wrapping_add(offset as u32);
+                self.local_input_buffer
+                    .insert(self.current_tick, tick, input);
+                self.retain_input(tick, input);
+            }
+
+            let wire_msg = WireMsg::input_batch(start_tick, chunk);
+            self.send_msg(&wire_msg)?;
+        }
+
+        Ok(())
+    }
+
+    /// Keep `input` for resending; resubmitting a tick drops what followed it
+    fn retain_input(&mut self, tick: Tick, input: Input) {
+        while self
+            .retained_inputs
+            .back()
+            .is_some_and(|&(t, _)| !tick_after(tick, t))
+        {
+            self.retained_inputs.pop_back();
+        }
+        self.retained_inputs.push_back((tick, input));
+        if self.retained_inputs.len() > RETRANSMIT_HISTORY {
+            self.retained_inputs.pop_front();
+        }
+    }
+
+    /// Answer an `InputRequest` with our retained inputs from `from_tick` on,
+    /// as batches of consecutive ticks
+    fn resend_inputs(&mut self, from_tick: Tick) -> Result<(), LockstepError> {
+        let mut batches: Vec<(Tick, Vec<Input>)> = Vec::new();
+        for &(tick, input) in &self.retained_inputs {
+            if tick_diff(tick, from_tick) < 0 {
+                continue;
+            }
+            match batches.last_mut() {
+                Some((start, inputs))
+                    if start.wrapping_add(inputs.len() as u32) == tick
+                        && inputs.len() < MAX_BATCH_INPUTS =>
+                {
+                    inputs.push(input)
+                }
+                _ => batches.push((tick, vec![input])),
+            }
+        }
+
+        for (start_tick, inputs) in batches {
+            self.send_msg(&WireMsg::input_batch(start_tick, inputs))?;
+        }
+        Ok(())
+    }
+
+    /// Ask the peer to resend its inputs from the current tick when they look
+    /// lost: later ticks arrived without it, or the wait has dragged on
+    fn request_missing_inputs(&mut self, now: Instant) -> Result<(), LockstepError> {
+        let tick = self.current_tick;
+        if !self.peer_supports(capabilities::INPUT_RESEND)
+            || self.remote_input_buffer.contains(tick)
+        {
+            return Ok(());
+        }
+
+        let gap = self
+            .remote_input_buffer
+            .ticks()
+            .any(|t| tick_after(t, tick));
+        let overdue = matches!(self.stalled_since, Some((t, since))
+            if t == tick && now.saturating_duration_since(since) >= INPUT_REQUEST_INTERVAL);
+        if !gap && !overdue {
+            return Ok(());
+        }
+
+        if let Some((t, at)) = self.last_input_request {
+            if t == tick && now.saturating_duration_since(at) < INPUT_REQUEST_INTERVAL {
+                return Ok(());
+            }
+        }
+
+        self.send_msg(&WireMsg::InputRequest { from_tick: tick })?;
+        self.last_input_request = Some((tick, now));
+        Ok(())
+    }
+
+    /// (Guest) ask the host for a side and config
+    pub fn join(&mut self) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+
+        self.send_msg(&WireMsg::Join)?;
+        Ok(())
+    }
+
+    /// (Host) admit the guest on the side opposite ours, playing by `config`
+    pub fn welcome(&mut self, config: &Config) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+
+        let wire_msg = WireMsg::Welcome {
+            assigned_side: self.local_side.opposite(),
+            config: *config,
+        };
+        self.send_msg(&wire_msg)?;
+        Ok(())
+    }
+
+    /// (Host) tell the guest which tick the match starts on
+    pub fn start_match(&mut self, start_tick: Tick) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+
+        let wire_msg = WireMsg::StartMatch { start_tick };
+        self.send_msg(&wire_msg)?;
+        Ok(())
+    }
+
+    /// Announce the config we simulate with. Until the peer's matching config
+    /// arrives, `tick` holds the simulation instead of risking a silent desync.
+    pub fn sync_config(&mut self, config: &Config) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+
+        self.local_config = Some(*config);
+        self.send_msg(&WireMsg::ConfigSync(*config))?;
+
+        match self.peer_config {
+            Some(peer) => Self::verify_configs(config, &peer),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether `sync_config` was called and the peer reported the same config
+    pub fn is_config_verified(&self) -> bool {
+        matches!((self.local_config, self.peer_config), (Some(local), Some(peer)) if local == peer)
+    }
+
+    fn verify_configs(local: &Config, peer: &Config) -> Result<(), LockstepError> {
+        let fields = local.differing_fields(peer);
+        if fields.is_empty() {
+            Ok(())
+        } else {
+            Err(LockstepError::ConfigMismatch { fields })
+        }
+    }
+
+    /// Pause the match on both peers. The peer may already have stepped every
+    /// tick we sent input for, so both hold just after our newest input;
+    /// returns that tick. Ticks stay there until either side calls `resume`.
+    pub fn pause(&mut self) -> Result<Tick, LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+        self.require_after_hello(capabilities::PAUSE)?;
+        if let Some(at_tick) = self.pause_tick {
+            return Ok(at_tick);
+        }
+
+        let at_tick = self.tick_after_local_input();
+        self.send_msg(&WireMsg::Pause { at_tick })?;
+        self.pause_tick = Some(at_tick);
+        self.sync_paused();
+        Ok(at_tick)
+    }
+
+    /// Lift the pause on both peers (does nothing when not paused)
+    pub fn resume(&mut self) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+        let Some(at_tick) = self.pause_tick else {
+            return Ok(());
+        };
+
+        self.send_msg(&WireMsg::Resume { at_tick })?;
+        self.pause_tick = None;
+        self.sync_paused();
+        Ok(())
+    }
+
+    /// Whether a pause has reached its tick and holds the simulation
+    pub fn is_paused(&self) -> bool {
+        self.pause_tick == Some(self.current_tick)
+    }
+
+    /// Show `is_paused` in the game's view
+    fn sync_paused(&mut self) {
+        let paused = self.is_paused();
+        self.core.set_paused(paused);
+    }
+
+    /// Tick the agreed pause holds at, if one is in effect (it may lie a tick ahead)
+    pub fn pause_tick(&self) -> Option<Tick> {
+        self.pause_tick
+    }
+
+    /// First tick we haven't sent input for, which the peer can't have stepped yet
+    fn tick_after_local_input(&self) -> Tick {
+        let current = self.current_tick;
+        self.local_input_buffer
+            .ticks()
+            .map(|t| tick_diff(t, current))
+            .filter(|&ahead| ahead >= 0)
+            .max()
+            .map_or(current, |ahead| current.wrapping_add(ahead as u32 + 1))
+    }
+
+    /// Play a best-of-`games` series instead of a single game (0 turns it
+    /// off). Both peers set the same length. When a game ends the timekeeper
+    /// announces the next one's seed and server (the loser) with `NextGame`,
+    /// and both reset on the same tick, until one side wins a majority.
+    /// The peer must support `capabilities::SERIES`.
+    pub fn set_best_of(&mut self, games: u8) {
+        self.series = (games > 0).then(|| Series::new(games));
+        self.game_decided = false;
+        self.next_game = None;
+    }
+
+    /// Standing in the series, if playing one
+    pub fn series(&self) -> Option<Series> {
+        self.series
+    }
+
+    /// Count a finished game toward the series. Unless that decides it, the
+    /// timekeeper schedules the next game past every input it has sent.
+    fn check_game_over(&mut self, events: &mut Vec<LockstepEvent>) -> Result<(), LockstepError> {
+        let Status::GameOver(winner) = self.core.view().status else {
+            return Ok(());
+        };
+        let Some(series) = &mut self.series else {
+            return Ok(());
+        };
+        self.game_decided = true;
+        series.wins[winner] = series.wins[winner].saturating_add(1);
+        let series = *series;
+
+        events.push(LockstepEvent::GameOver {
+            winner,
+            series: series.wins,
+        });
+        if series.winner().is_some() {
+            events.push(LockstepEvent::SeriesOver {
+                winner,
+                series: series.wins,
+            });
+            return Ok(());
+        }
+
+        if self.is_timekeeper {
+            self.require_after_hello(capabilities::SERIES)?;
+            let start_tick = self.tick_after_local_input();
+            let seed = next_game_seed(self.core.config().seed, series.game);
+            let server = winner.opposite();
+            self.send_msg(&WireMsg::NextGame {
+                start_tick,
+                seed,
+                server,
+            })?;
+            self.next_game = Some((start_tick, seed, server));
+        }
+        Ok(())
+    }
+
+    /// Start the scheduled next game of the series on the current tick
+    fn begin_next_game(&mut self, seed: u64, server: Side, events: &mut Vec<LockstepEvent>) {
+        self.next_game = None;
+        self.game_decided = false;
+        self.core.start_next_game(seed, server);
+        if let Some(replay) = &mut self.replay {
+            replay.record_keyframe(self.core.snapshot());
+        }
+
+        if let Some(series) = &mut self.series {
+            series.game = series.game.saturating_add(1);
+            events.push(LockstepEvent::NextGame {
+                game: series.game,
+                start_tick: self.current_tick,
+                server,
+                series: series.wins,
+            });
+        }
+    }
+
+    /// Send a chat message to the peer (truncated to `MAX_CHAT_BYTES`)
+    pub fn send_chat(&mut self, text: &str) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+        self.require_after_hello(capabilities::CHAT)?;
+
+        self.send_msg(&WireMsg::chat(text))?;
+        Ok(())
+    }
+
+    /// Send a predefined reaction (see `wire_protocol::emotes`) to the peer
+    pub fn send_emote(&mut self, emote: u8) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+        self.require_after_hello(capabilities::CHAT)?;
+
+        self.send_msg(&WireMsg::Emote(emote))?;
+        Ok(())
+    }
+
+    /// Handle raw bytes from a stream transport (TCP, pipes) carrying
+    /// length-prefixed frames (see `framing`): a chunk may hold part of a
+    /// message or several. Stops at the first bad message; the rest stay buffered,
+    /// and events from the messages before it are returned by the next call.
+    pub fn on_stream_bytes(&mut self, chunk: &[u8]) -> Result<Vec<LockstepEvent>, LockstepError> {
+        self.stream_decoder.push(chunk);
+
+        let mut events = std::mem::take(&mut self.stream_events);
+        while let Some(frame) = self.stream_decoder.next_frame() {
+            match frame
+                .map_err(LockstepError::from)
+                .and_then(|frame| self.on_net_message(frame))
+            {
+                Ok(frame_events) => events.extend(frame_events),
+                Err(err) => {
+                    self.stream_events = events;
+                    return Err(err);
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    /// Process incoming network message
+    pub fn on_net_message(&mut self, bytes: Vec<u8>) -> Result<Vec<LockstepEvent>, LockstepError> {
+        let events = self.process_message(bytes)?;
+        self.notify(&events);
+        Ok(events)
+    }
+
+    fn process_message(&mut self, bytes: Vec<u8>) -> Result<Vec<LockstepEvent>, LockstepError> {
+        if !self.is_running || self.ai_controlled {
+            return Ok(vec![]);
+        }
+        self.metrics.messages_in = self.metrics.messages_in.saturating_add(1);
+        self.metrics.bytes_in += bytes.len() as u64;
+
+        // Borrow payloads straight from the received buffer
+        #[cfg(feature = "auth")]
+        let decoded = match &self.auth {
+            Some(auth) => WireMsg::decode_authenticated_ref(&bytes, auth),
+            None => WireMsg::decode_ref(&bytes),
+        };
+        #[cfg(not(feature = "auth"))]
+        let decoded = WireMsg::decode_ref(&bytes);
+        let mut wire_msg = decoded?;
+        let mut events = Vec::new();
+
+        if let WireMsgRef::Sequenced { seq, msg } = wire_msg {
+            if !self.receive_window.record(seq) {
+                self.sequence_stats.duplicates = self.sequence_stats.duplicates.saturating_add(1);
+                return Ok(events);
+            }
+            self.sequence_stats.received = self.sequence_stats.received.saturating_add(1);
+            self.ack_pending = true;
+            wire_msg = *msg;
+        }
+
+        // Any valid message shows the peer is still there
+        let now = self.clock.now();
+        if let Some(last) = self.last_received {
+            let gap = now.saturating_duration_since(last);
+            push_sample(&mut self.gap_window, duration_ms(gap));
+        }
+        self.last_received = Some(now);
+        if self.suspended {
+            self.suspended = false;
+            events.push(LockstepEvent::PeerReconnected);
+        }
+
+        match wire_msg {
+            WireMsgRef::InputPair(input_pair) => self.accept_remote_pair(input_pair, &mut events),
+            WireMsgRef::TimedInputPair { pair, sent_ms } => {
+                // Wrapping keeps this right across u32 rollover; signed since clocks may differ
+                let delay_ms = self.clock.wall_ms().wrapping_sub(sent_ms) as i32;
+                self.input_delay.record(delay_ms);
+                self.accept_remote_pair(pair, &mut events);
+            }
+            WireMsgRef::Snapshot(snapshot_data) => {
+                let snapshot = Snapshot::decode(&snapshot_data)?;
+
+                // Restoring one from before the pairs we still hold would lose
+                // the inputs in between, so it's kept out
+                let replayable_from = self
+                    .recent_pairs
+                    .front()
+                    .map_or(self.current_tick, |pair| pair.tick);
+                if tick_after(replayable_from, snapshot.tick) {
+                    events.push(LockstepEvent::StaleSnapshot {
+                        tick: snapshot.tick,
+                    });
+                    return Ok(events);
+                }
+
+                self.core.restore(&snapshot)?;
+                self.current_tick = snapshot.tick;
+                self.sync_paused();
+
+                // Drop buffered inputs the snapshot has already covered
+                let tick = snapshot.tick;
+                self.local_input_buffer.retain(|t| !tick_after(tick, t));
+                self.remote_input_buffer.retain(|t| !tick_after(tick, t));
+
+                // Ticks we had already stepped past the snapshot need their inputs again
+                for pair in self.recent_pairs.drain(..) {
+                    if !tick_after(tick, pair.tick) {
+                        let (local, remote) = match self.local_side {
+                            Side::Left => (pair.a, pair.b),
+                            Side::Right => (pair.b, pair.a),
+                        };
+                        if !self.local_input_buffer.contains(pair.tick) {
+                            self.local_input_buffer.insert(tick, pair.tick, local);
+                        }
+                        if !self.remote_input_buffer.contains(pair.tick) {
+                            self.remote_input_buffer.insert(tick, pair.tick, remote);
+                        }
+                    }
+                }
+
+                // Spectators follow our state, so they need the correction too
+                self.relay_to_spectators(&WireMsg::snapshot(&snapshot));
+                if let Some(replay) = &mut self.replay {
+                    replay.record_keyframe(snapshot);
+                }
+
+                events.push(LockstepEvent::SnapshotReceived { tick });
+                if self.resync_pending {
+                    // Start hash comparison afresh from the shared state
+                    self.clear_hashes();
+                    events.push(LockstepEvent::Resynced { tick });
+                }
+                if self.rejoin_pending {
+                    // Inputs sent over the old connection may never have arrived
+                    self.rejoin_pending = false;
+                    self.resend_inputs(tick)?;
+                    events.push(LockstepEvent::SessionResumed { tick });
+                }
+            }
+            WireMsgRef::Ping(timestamp) => {
+                // Respond with a pong
+                let pong = WireMsg::pong(timestamp);
+                self.send_msg(&pong)?;
+            }
+            WireMsgRef::SnapshotRequest { since_tick } => {
+                self.send_snapshot()?;
+                // The peer is adopting our state, so any desync is about to be resolved
+                if self.desync_tick.is_some() {
+                    self.clear_hashes();
+                }
+                events.push(LockstepEvent::SnapshotRequested { since_tick });
+            }
+            WireMsgRef::Pong { original_timestamp } => {
+                // Our own timestamp came back; wrapping keeps this right across u32 rollover
+                let round_trip_ms = self.clock.wall_ms().wrapping_sub(original_timestamp);
+                self.record_rtt(round_trip_ms);
+                events.push(LockstepEvent::PongReceived { round_trip_ms });
+            }
+            WireMsgRef::InputBatch { start_tick, inputs } => {
+                // A batch carries only the sender's own inputs
+                self.accept_remote_inputs(start_tick, inputs, &mut events);
+            }
+            WireMsgRef::InputHistory {
+                latest_tick,
+                inputs,
+            } => {
+                let start_tick = latest_tick.wrapping_sub(inputs.len().saturating_sub(1) as u32);
+                self.accept_remote_inputs(start_tick, inputs, &mut events);
+            }
+            WireMsgRef::Join => {
+                if !self.is_timekeeper {
+                    return Err(LockstepError::InvalidMessage(
+                        "Join sent to a guest".to_string(),
+                    ));
+                }
+                events.push(LockstepEvent::PeerJoined);
+            }
+            WireMsgRef::Welcome {
+                assigned_side,
+                config,
+            } => {
+                if self.is_timekeeper {
+                    return Err(LockstepError::InvalidMessage(
+                        "Welcome sent to the host".to_string(),
+                    ));
+                }
+                self.local_side = assigned_side;
+                events.push(LockstepEvent::Welcomed {
+                    side: assigned_side,
+                    config,
+                });
+            }
+            WireMsgRef::StartMatch { start_tick } => {
+                events.push(LockstepEvent::MatchStarting { start_tick });
+            }
+            WireMsgRef::Chat(text) => {
+                events.push(LockstepEvent::ChatReceived {
+                    text: text.to_string(),
+                });
+            }
+            WireMsgRef::Emote(emote) => {
+                events.push(LockstepEvent::EmoteReceived { emote });
+            }
+            WireMsgRef::StateHash { tick, hash } => {
+                self.remote_hashes.insert(tick, hash);
+                self.check_hash(tick, &mut events)?;
+            }
+            WireMsgRef::ConfigSync(config) => {
+                self.peer_config = Some(config);
+                if let Some(local) = self.local_config {
+                    Self::verify_configs(&local, &config)?;
+                    events.push(LockstepEvent::ConfigVerified);
+                }
+            }
+            WireMsgRef::Hello {
+                version,
+                capabilities,
+            } => {
+                let negotiated =
+                    negotiate_version(version).map_err(|_| LockstepError::VersionMismatch {
+                        local: PROTOCOL_VERSION,
+                        remote: version,
+                    })?;
+                self.peer_version = Some(negotiated);
+                self.peer_capabilities = capabilities;
+
+                events.push(LockstepEvent::PeerHello {
+                    version: negotiated,
+                    capabilities,
+                });
+                // The timekeeper's state went out on reattach; the peer is back
+                if self.rejoin_pending && self.is_timekeeper {
+                    self.rejoin_pending = false;
+                    events.push(LockstepEvent::SessionResumed {
+                        tick: self.current_tick,
+                    });
+                }
+            }
+            WireMsgRef::Ack { latest, mask } => self.on_ack(latest, mask),
+            WireMsgRef::InputRequest { from_tick } => self.resend_inputs(from_tick)?,
+            WireMsgRef::Pause { at_tick } => {
+                if tick_after(self.current_tick, at_tick) {
+                    return Err(LockstepError::SyncError(format!(
+                        "Pause at tick {at_tick}, already simulated"
+                    )));
+                }
+                // When both peers pause at once, the earlier tick wins on both
+                let at_tick = match self.pause_tick {
+                    Some(ours) if tick_after(at_tick, ours) => ours,
+                    _ => at_tick,
+                };
+                self.pause_tick = Some(at_tick);
+                self.sync_paused();
+                events.push(LockstepEvent::PeerPaused { at_tick });
+            }
+            WireMsgRef::Resume { at_tick } => {
+                // A resume for a pause we no longer hold is stale
+                if self.pause_tick == Some(at_tick) {
+                    self.pause_tick = None;
+                    self.sync_paused();
+                    events.push(LockstepEvent::PeerResumed { at_tick });
+                }
+            }
+            WireMsgRef::SideInput { tick, side, input } => {
+                if side == self.local_side {
+                    return Err(LockstepError::InvalidMessage(
+                        "SideInput for our own side".to_string(),
+                    ));
+                }
+                self.accept_remote_inputs(tick, [input], &mut events);
+            }
+            WireMsgRef::NextGame {
+                start_tick,
+                seed,
+                server,
+            } => {
+                if self.series.is_none() {
+                    return Err(LockstepError::InvalidMessage(
+                        "NextGame outside a series".to_string(),
+                    ));
+                }
+                if tick_after(self.current_tick, start_tick) {
+                    return Err(LockstepError::SyncError(format!(
+                        "Next game at tick {start_tick}, already simulated"
+                    )));
+                }
+                self.next_game = Some((start_tick, seed, server));
+            }
+            WireMsgRef::Bye => {
+                // Our own Bye answered, or the peer leaving first and expecting one back
+                let by_peer = self.closing_since.is_none();
+                if by_peer {
+                    self.send_msg(&WireMsg::Bye)?;
+                }
+                self.end_session(by_peer, &mut events);
+            }
+            WireMsgRef::Sequenced { .. } => {
+                return Err(LockstepError::InvalidMessage(
+                    "Nested Sequenced envelope".to_string(),
+                ));
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Let the non-timekeeper nudge its tick interval to follow the peer's
+    /// progress (on by default; see `tick_interval`)
+    pub fn set_clock_sync(&mut self, enabled: bool) {
+        self.clock_sync = enabled;
+    }
+
+    /// How long the game loop should wait before the next `tick`. The timekeeper
+    /// always ticks at `tick_hz`; the other peer runs slightly faster while the
+    /// peer's inputs pile up ahead of it and slightly slower while it keeps
+    /// waiting for them, so neither clock drifts ahead for good.
+    pub fn tick_interval(&self) -> Duration {
+        let nominal = Duration::from_secs(1) / self.tick_hz.max(1) as u32;
+        if !self.clock_sync || self.is_timekeeper {
+            return nominal;
+        }
+
+        let (low, high) = REMOTE_LEAD_RANGE;
+        if self.remote_lead > high {
+            nominal * (100 - PACING_ADJUST_PERCENT) / 100
+        } else if self.remote_lead < low {
+            nominal * (100 + PACING_ADJUST_PERCENT) / 100
+        } else {
+            nominal
+        }
+    }
+
+    /// Smoothed number of peer inputs already buffered when we reach a tick
+    pub fn remote_lead(&self) -> f32 {
+        self.remote_lead
+    }
+
+    fn sample_remote_lead(&mut self) {
+        let mut lead = 0;
+        while lead < MAX_BATCH_INPUTS
+            && self
+                .remote_input_buffer
+                .contains(self.current_tick.wrapping_add(lead as u32))
+        {
+            lead += 1;
+        }
+        self.remote_lead += (lead as f32 - self.remote_lead) / 8.0;
+
+        let advantage = self.frame_advantage() as f32;
+        self.frame_advantage_avg += (advantage - self.frame_advantage_avg) / 8.0;
+    }
+
+    /// Ticks our newest submitted input is ahead of the peer's newest one
+    /// (negative when behind; 0 until both have sent input). A peer that stays
+    /// ahead keeps stalling on the other, so lengthen frames while this is
+    /// positive and shorten them while it is negative.
+    pub fn frame_advantage(&self) -> i32 {
+        let local = self.retained_inputs.back().map(|&(tick, _)| tick);
+        match (local, self.latest_remote_tick) {
+            (Some(local), Some(remote)) => tick_diff(local, remote),
+            _ => 0,
+        }
+    }
+
+    /// `frame_advantage` smoothed over recent ticks, steadier for pacing decisions
+    pub fn average_frame_advantage(&self) -> f32 {
+        self.frame_advantage_avg
+    }
+
+    /// Run the session from the game loop: call it every frame with the player's
+    /// current controls. Ticks run on `tick_interval` pacing (catching up a few
+    /// after a slow frame), each using `input` unless one was already submitted
+    /// for it, and the peer is pinged every ping interval. Timeouts and stalls
+    /// are checked as each tick runs. Sleep until `next_deadline` between calls.
+    pub fn drive(
+        &mut self,
+        now: Instant,
+        input: Input,
+    ) -> Result<Vec<LockstepEvent>, LockstepError> {
+        if !self.is_running {
+            return Ok(vec![]);
+        }
+
+        let mut events = Vec::new();
+
+        if let Some(interval) = self.ping_interval {
+            let due = self
+                .last_ping_time
+                .is_none_or(|last| now.saturating_duration_since(last) >= interval);
+            if due {
+                self.ping()?;
+                self.last_ping_time = Some(now);
+            }
+        }
+
+        let mut next = self.next_tick_at.unwrap_or(now);
+        let mut ran = 0;
+        while next <= now {
+            if ran == MAX_CATCH_UP_TICKS {
+                // Too far behind to catch up; carry on from now
+                next = now + self.tick_interval();
+                break;
+            }
+            ran += 1;
+
+            if !self.local_input_buffer.contains(self.current_tick) && !self.is_paused() {
+                self.submit_local_input(input)?;
+            }
+            let tick = self.current_tick;
+            events.extend(self.tick_at(now)?);
+            next += self.tick_interval();
+
+            // Held up (waiting, paused or suspended): try again next interval
+            if self.current_tick == tick {
+                next = next.max(now + self.tick_interval());
+                break;
+            }
+        }
+        self.next_tick_at = Some(next);
+
+        Ok(events)
+    }
+
+    /// When `drive` next has a tick to run, once it has been called
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.next_tick_at
+    }
+
+    /// Ping the peer this often from `drive` (None stops it)
+    pub fn set_ping_interval(&mut self, interval: Option<Duration>) {
+        self.ping_interval = interval;
+    }
+
+    /// Try to advance the simulation (call this regularly in your game loop)
+    pub fn tick(&mut self) -> Result<Vec<LockstepEvent>, LockstepError> {
+        self.tick_at(self.clock.now())
+    }
+
+    fn tick_at(&mut self, now: Instant) -> Result<Vec<LockstepEvent>, LockstepError> {
+        let events = self.advance(now)?;
+        self.notify(&events);
+        Ok(events)
+    }
+
+    fn advance(&mut self, now: Instant) -> Result<Vec<LockstepEvent>, LockstepError> {
+        if !self.is_running {
+            return Ok(vec![]);
+        }
+
+        let mut events = Vec::new();
+        self.sample_metrics(now);
+
+        // Nothing more is simulated once we've said Bye
+        if let Some(since) = self.closing_since {
+            if !self.peer_supports(capabilities::BYE)
+                || now.saturating_duration_since(since) >= SHUTDOWN_TIMEOUT
+            {
+                self.end_session(false, &mut events);
+            }
+            return Ok(events);
+        }
+
+        self.check_connection(&mut events);
+        self.check_peer_timeout(now, &mut events);
+        if self.suspended {
+            if !self.ai_takeover {
+                return Ok(events);
+            }
+            self.take_over_remote(&mut events);
+        }
+
+        // Acknowledge the peer's envelopes at most once per tick
+        if self.ack_pending {
+            if let Some(latest) = self.receive_window.latest {
+                let mask = self.receive_window.mask;
+                self.send_msg(&WireMsg::Ack { latest, mask })?;
+            }
+            self.ack_pending = false;
+        }
+
+        // Hold the simulation until an announced config has been matched
+        if self.local_config.is_some() && !self.is_config_verified() {
+            return Ok(events);
+        }
+
+        // Both peers hold at an agreed pause tick until one resumes
+        if self.is_paused() {
+            self.check_stall(now, &mut events);
+            return Ok(events);
+        }
+
+        // Both peers start the announced next game before stepping its tick
+        if let Some((start_tick, seed, server)) = self.next_game {
+            if start_tick == self.current_tick {
+                self.begin_next_game(seed, server, &mut events);
+            }
+        }
+
+        // Inputs the peer sent before leaving still count; the AI plays on from there
+        if self.ai_controlled && !self.remote_input_buffer.contains(self.current_tick) {
+            let input = ai::track_ball(&self.core.view(), self.local_side.opposite());
+            let base = self.current_tick;
+            self.remote_input_buffer.insert(base, base, input);
+        }
+
+        // Check if we have both local and remote inputs for the current tick
+        if let (Some(local_input), Some(remote_input)) = (
+            self.local_input_buffer.get(self.current_tick),
+            self.remote_input_buffer.get(self.current_tick),
+        ) {
+            // Create input pair based on our side
+            let input_pair = match self.local_side {
+                Side::Left => InputPair::new(self.current_tick, local_input, remote_input),
+                Side::Right => InputPair::new(self.current_tick, remote_input, local_input),
+            };
+
+            // Step the simulation
+            self.core.step(&input_pair);
+            let game_events = self.core.drain_events();
+            self.relay_to_spectators(&WireMsg::InputPair(input_pair));
+            if let Some(replay) = &mut self.replay {
+                replay.record_input(input_pair);
+            }
+            self.recent_pairs.push_back(input_pair);
+            if self.recent_pairs.len() > RESYNC_HISTORY {
+                self.recent_pairs.pop_front();
+            }
+
+            // Clean up processed inputs
+            self.local_input_buffer.remove(self.current_tick);
+            self.remote_input_buffer.remove(self.current_tick);
+
+            // Advance tick
+            let stepped_tick = self.current_tick;
+            self.metrics.ticks_advanced = self.metrics.ticks_advanced.saturating_add(1);
+            self.current_tick = self.current_tick.wrapping_add(1);
+            self.sync_paused();
+
+            let interval = self.spectator_snapshot_interval;
+            if interval > 0 && self.current_tick % interval == 0 {
+                self.relay_to_spectators(&WireMsg::snapshot(&self.core.snapshot()));
+            }
+            let interval = self.replay_keyframe_interval;
+            if interval > 0 && self.current_tick % interval == 0 {
+                if let Some(replay) = &mut self.replay {
+                    replay.record_keyframe(self.core.snapshot());
+                }
+            }
+
+            events.push(LockstepEvent::GameAdvanced {
+                tick: stepped_tick,
+                events: game_events,
+            });
+            if self.series.is_some() && !self.game_decided {
+                self.check_game_over(&mut events)?;
+            }
+
+            if self.hash_interval > 0 && stepped_tick % self.hash_interval == 0 {
+                let hash = self.core.state_hash();
+                let wire_msg = WireMsg::StateHash {
+                    tick: stepped_tick,
+                    hash,
+                };
+                self.send_msg(&wire_msg)?;
+
+                self.local_hashes.insert(stepped_tick, hash);
+                self.check_hash(stepped_tick, &mut events)?;
+                self.prune_hashes();
+            }
+        }
+
+        self.flush_held_input()?;
+        self.check_stall(now, &mut events);
+        self.request_missing_inputs(now)?;
+        self.sample_remote_lead();
+        Ok(events)
+    }
+
+    /// Ask the remote peer to send us its snapshot (e.g. after a desync)
+    pub fn request_snapshot(&mut self) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+
+        let wire_msg = WireMsg::SnapshotRequest {
+            since_tick: self.current_tick,
+        };
+        self.send_msg(&wire_msg)?;
+
+        Ok(())
+    }
+
+    /// Push our current snapshot to the remote peer
+    pub fn send_snapshot(&mut self) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+
+        let snapshot = self.core.snapshot();
+        let wire_msg = WireMsg::snapshot(&snapshot);
+        self.send_msg(&wire_msg)?;
+
+        Ok(())
+    }
+
+    /// Send a ping to measure round-trip time
+    pub fn ping(&mut self) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+
+        let ping = WireMsg::ping(self.clock.wall_ms());
+        self.send_msg(&ping)?;
+
+        self.last_ping_time = Some(self.clock.now());
+
+        Ok(())
+    }
+
+    /// The side we control
+    pub fn local_side(&self) -> Side {
+        self.local_side
+    }
+
+    /// Whether we keep time (and host) for this session
+    pub fn is_timekeeper(&self) -> bool {
+        self.is_timekeeper
+    }
+
+    /// The simulation being driven
+    pub fn core(&self) -> &C {
+        &self.core
+    }
+
+    /// The transport messages go out on
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Mutable access to the transport, e.g. to drain a mock in tests
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Get the current game view
+    pub fn view(&self) -> View {
+        self.core.view()
+    }
+
+    /// Get the current tick
+    pub fn current_tick(&self) -> Tick {
+        self.current_tick
+    }
+
+    /// Check if we're waiting for remote input
+    pub fn is_waiting_for_remote(&self) -> bool {
+        if !self.is_running {
+            return false;
+        }
+
+        !self.is_paused()
+            && self.local_input_buffer.contains(self.current_tick)
+            && !self.remote_input_buffer.contains(self.current_tick)
+    }
+
+    /// Get transport status
+    pub fn transport_status(&self) -> String {
+        self.transport.status()
+    }
+
+    /// Traffic the transport has carried
+    pub fn transport_metrics(&self) -> TransportMetrics {
+        self.transport.metrics()
+    }
+
+    /// Check if transport is connected
+    pub fn is_connected(&self) -> bool {
+        self.transport.is_open()
+    }
+
+    /// Negotiated protocol version, or None before the peer's Hello
+    pub fn peer_version(&self) -> Option<u8> {
+        self.peer_version
+    }
+
+    /// Capability bits the peer advertised in its Hello
+    pub fn peer_capabilities(&self) -> u32 {
+        self.peer_capabilities
+    }
+
+    /// Capabilities both this build and the peer support (none before its Hello)
+    pub fn negotiated_capabilities(&self) -> u32 {
+        capabilities::negotiate(self.peer_capabilities)
+    }
+
+    /// Whether every bit of `capability` can be used with this peer
+    pub fn peer_supports(&self, capability: u32) -> bool {
+        self.negotiated_capabilities() & capability == capability
+    }
+
+    /// Refuse a feature once the peer's Hello shows it lacks it. Before the
+    /// Hello arrives messages go out anyway; a peer that can't read them drops them.
+    fn require_after_hello(&self, capability: u32) -> Result<(), LockstepError> {
+        if self.peer_version.is_some() && !self.peer_supports(capability) {
+            return Err(LockstepError::PeerLacksCapability(capability));
+        }
+        Ok(())
+    }
+
+    /// Get buffered input counts for debugging
+    pub fn get_buffer_info(&self) -> (usize, usize) {
+        (
+            self.local_input_buffer.len(),
+            self.remote_input_buffer.len(),
+        )
+    }
+}
+
+/// Seed for the game after game number `game` of a series, mixed from the
+/// match seed (splitmix64) so every game serves differently
+fn next_game_seed(seed: u64, game: u8) -> u64 {
+    let mut z = seed.wrapping_add((game as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Whole milliseconds in `duration`, saturating at u32::MAX
+fn duration_ms(duration: Duration) -> u32 {
+    duration.as_millis().min(u32::MAX as u128) as u32
+}
+
+/// Simple adapter for the Game struct
+pub struct GameAdapter<P: PhysicsEngine = ClassicPhysics> {
+    game: Game<P>,
+}
+
+impl<P: PhysicsEngine> GameAdapter<P> {
+    pub fn new(game: Game<P>) -> Self {
+        Self { game }
+    }
+
+    pub fn game(&self) -> &Game<P> {
+        &self.game
+    }
+
+    pub fn game_mut(&mut self) -> &mut Game<P> {
+        &mut self.game
+    }
+}
+
+impl<P: PhysicsEngine> CoreAdapter for GameAdapter<P> {
+    fn step(&mut self, inputs: &InputPair) -> Option<Event> {
+        self.game.step(inputs)
+    }
+
+    fn view(&self) -> View {
+        self.game.view()
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        self.game.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: &Snapshot) -> Result<(), RestoreError> {
+        self.game.restore(snapshot)
+    }
+
+    fn current_tick(&self) -> Tick {
+        self.game.tick
+    }
+
+    fn state_hash(&self) -> u64 {
+        self.game.state_hash()
+    }
+
+    fn drain_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.game.events)
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.game.set_paused(paused);
+    }
+
+    fn config(&self) -> Config {
+        self.game.config
+    }
+
+    fn start_next_game(&mut self, seed: u64, server: Side) {
+        self.game.start_next_game(seed, server);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framing::Framer;
+    use crate::spectator::{Spectator, SpectatorEvent};
+    use crate::transport::RecordingMockTransport;
+    use crate::wire_protocol::emotes;
+    use pong_engine::replay::ReplayRecord;
+
+    #[test]
+    fn test_lockstep_creation() {
+        let game = Game::new(Config::default());
+        let adapter = GameAdapter::new(game);
+        let transport = RecordingMockTransport::new();
+
+        let lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+
+        assert_eq!(lockstep.current_tick(), 0);
+        assert_eq!(lockstep.local_side, Side::Left);
+        assert!(lockstep.is_timekeeper);
+        assert!(!lockstep.is_running);
+    }
+
+    #[test]
+    fn test_game_adapter_core_extras() {
+        let rules = Config {
+            max_score: 3,
+            ..Config::default()
+        };
+        let mut adapter = GameAdapter::new(Game::new(rules));
+        assert_eq!(adapter.config(), rules);
+        assert_eq!(adapter.state_hash(), adapter.snapshot().state_hash());
+
+        // Step until something happens; draining hands it over exactly once
+        while adapter.game().events().is_empty() {
+            let tick = adapter.current_tick();
+            let ready = Input::new(0, Buttons::READY);
+            adapter.step(&InputPair::new(tick, ready, ready));
+        }
+        let events = adapter.game().events().to_vec();
+        assert_eq!(adapter.drain_events(), events);
+        assert!(adapter.drain_events().is_empty());
+    }
+
+    #[test]
+    fn test_lockstep_start_stop() {
+        let game = Game::new(Config::default());
+        let adapter = GameAdapter::new(game);
+        let transport = RecordingMockTransport::new();
+
+        let mut lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+
+        assert!(lockstep.start().is_ok());
+        assert!(lockstep.is_running);
+
+        lockstep.stop();
+        assert!(!lockstep.is_running);
+    }
+
+    #[test]
+    fn test_lockstep_start_failed_transport() {
+        let game = Game::new(Config::default());
+        let adapter = GameAdapter::new(game);
+        let transport = RecordingMockTransport::new_closed();
+
+        let mut lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+
+        let result = lockstep.start();
+        assert!(result.is_err());
+        assert!(!lockstep.is_running);
+    }
+
+    #[test]
+    fn test_local_input_submission() {
+        let game = Game::new(Config::default());
+        let adapter = GameAdapter::new(game);
+        let mut transport = RecordingMockTransport::new();
+
+        let mut lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+        lockstep.start().unwrap();
+
+        // Submit local input
+        assert!(lockstep.on_local_input(50, 1).is_ok());
+
+        // Check that input was buffered
+        assert!(lockstep.local_input_buffer.contains(0));
+        assert!(lockstep.is_waiting_for_remote());
+
+        // Check that message was sent via transport (after the Hello from start)
+        let sent_messages = lockstep.transport.sent_messages();
+        assert_eq!(sent_messages.len(), 2);
+        assert_eq!(
+            WireMsg::decode(&sent_messages[0]).unwrap(),
+            WireMsg::hello()
+        );
+
+        // Verify the sent message is an InputPair
+        let wire_msg = WireMsg::decode(&sent_messages[1]).unwrap();
+        match wire_msg {
+            WireMsg::InputPair(input_pair) => {
+                assert_eq!(input_pair.tick, 0);
+                assert_eq!(input_pair.a.axis_y, 50); // We're left side
+                assert_eq!(input_pair.a.buttons, Buttons::READY);
+            }
+            _ => panic!("Expected InputPair message"),
+        }
+    }
+
+    #[test]
+    fn test_input_not_submitted_when_not_running() {
+        let game = Game::new(Config::default());
+        let adapter = GameAdapter::new(game);
+        let transport = RecordingMockTransport::new();
+
+        let mut lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+
+        let result = lockstep.on_local_input(50, 1);
+        assert_eq!(result, Err(LockstepError::NotRunning));
+    }
+
+    #[test]
+    fn test_remote_input_processing() {
+        let game = Game::new(Config::default());
+        let adapter = GameAdapter::new(game);
+        let transport = RecordingMockTransport::new();
+
+        let mut lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+        lockstep.start().unwrap();
+
+        // Create a remote input message (from Right side's perspective)
+        let remote_input = Input::new(-25, 2);
+        let input_pair = InputPair::new(0, Input::zero(), remote_input); // Left=zero, Right=remote
+        let wire_msg = WireMsg::InputPair(input_pair);
+        let bytes = wire_msg.encode();
+
+        let events = lockstep.on_net_message(bytes).unwrap();
+        assert!(events.is_empty()); // No events from just receiving input
+
+        // Check that remote input was buffered
+        assert!(lockstep.remote_input_buffer.contains(0));
+        let buffered_input = lockstep.remote_input_buffer.get(0).unwrap();
+        assert_eq!(buffered_input.axis_y, -25);
+        assert_eq!(buffered_input.buttons, Buttons::DASH);
+    }
+
+    #[test]
+    fn test_simulation_advancement() {
+        let game = Game::new(Config::default());
+        let adapter = GameAdapter::new(game);
+        let transport = RecordingMockTransport::new();
+
+        let mut lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+        lockstep.start().unwrap();
+
+        let initial_tick = lockstep.current_tick();
+
+        // Submit local input
+        lockstep.on_local_input(10, 0).unwrap();
+
+        // Submit remote input via network message
+        let remote_input = Input::new(-10, 0);
+        let input_pair = InputPair::new(initial_tick, Input::zero(), remote_input);
+        let wire_msg = WireMsg::InputPair(input_pair);
+        lockstep.on_net_message(wire_msg.encode()).unwrap();
+
+        // Now both inputs are available, tick should advance
+        let events = lockstep.tick().unwrap();
+
+        // Check that tick advanced
+        assert_eq!(lockstep.current_tick(), initial_tick + 1);
+
+        // Input buffers should be cleaned up
+        assert!(!lockstep.local_input_buffer.contains(initial_tick));
+        assert!(!lockstep.remote_input_buffer.contains(initial_tick));
+
+        // Every advanced tick is reported, even a quiet one
+        assert_eq!(
+            events,
+            vec![LockstepEvent::GameAdvanced {
+                tick: initial_tick,
+                events: vec![],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_collision_events_reported() {
+        let mut game = Game::new(Config::default());
+        game.status = Status::Playing;
+
+        // Ball about to hit the left paddle
+        game.ball.pos = Vec2::new(game.config.paddle_x + 200, game.paddles[0].y);
+        game.ball.vel = Vec2::new(-FX_ONE / 2, 0);
+
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(game),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        lockstep.start().unwrap();
+        lockstep.on_local_input(0, 0).unwrap();
+        let remote = WireMsg::InputPair(InputPair::new(0, Input::zero(), Input::zero()));
+        lockstep.on_net_message(remote.encode()).unwrap();
+
+        match lockstep.tick().unwrap().as_slice() {
+            [LockstepEvent::GameAdvanced { tick: 0, events }] => assert!(matches!(
+                events.as_slice(),
+                [Event::PaddleHit {
+                    side: Side::Left,
+                    ..
+                }]
+            )),
+            other => panic!("expected the paddle hit to be reported, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_no_advancement_without_both_inputs() {
+        let game = Game::new(Config::default());
+        let adapter = GameAdapter::new(game);
+        let transport = RecordingMockTransport::new();
+
+        let mut lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+        lockstep.start().unwrap();
+
+        let initial_tick = lockstep.current_tick();
+
+        // Submit only local input
+        lockstep.on_local_input(10, 0).unwrap();
+
+        // Try to tick - should not advance without remote input
+        let events = lockstep.tick().unwrap();
+
+        assert_eq!(lockstep.current_tick(), initial_tick); // No advancement
+        assert!(events.is_empty()); // No events
+        assert!(lockstep.is_waiting_for_remote());
+    }
+
+    #[test]
+    fn test_tick_wraps_around() {
+        let mut game = Game::new(Config::default());
+        game.tick = Tick::MAX;
+        let adapter = GameAdapter::new(game);
+        let transport = RecordingMockTransport::new();
+
+        let mut lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+        lockstep.start().unwrap();
+
+        lockstep.on_local_input(0, 0).unwrap();
+        let remote = WireMsg::input_pair(Tick::MAX, Input::zero(), Input::zero());
+        lockstep.on_net_message(remote.encode()).unwrap();
+        lockstep.tick().unwrap();
+        assert_eq!(lockstep.current_tick(), 0);
+
+        // A late input for the pre-wrap tick is stale, not far in the future
+        let late = WireMsg::input_pair(Tick::MAX, Input::zero(), Input::new(5, 0));
+        lockstep.on_net_message(late.encode()).unwrap();
+        assert!(!lockstep.remote_input_buffer.contains(Tick::MAX));
+    }
+
+    #[test]
+    fn test_input_batch_roundtrip() {
+        let inputs: Vec<Input> = (0..4).map(|i| Input::new(i * 20, 0)).collect();
+
+        // Sender buffers every tick locally and sends one frame
+        let mut sender = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        sender.start().unwrap();
+        sender.submit_local_inputs(&inputs).unwrap();
+        assert_eq!(sender.get_buffer_info(), (4, 0));
+        let sent = sender.transport.sent_messages();
+        assert_eq!(sent.len(), 2); // Hello + one batch
+        assert_eq!(
+            WireMsg::decode(&sent[1]).unwrap(),
+            WireMsg::input_batch(0, inputs.clone())
+        );
+
+        // Receiver fills its remote buffer and can run through all four ticks
+        let mut receiver = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Right,
+            false,
+        );
+        receiver.start().unwrap();
+        receiver.on_net_message(sent[1].clone()).unwrap();
+        assert_eq!(receiver.get_buffer_info(), (0, 4));
+
+        receiver.submit_local_inputs(&[Input::zero(); 4]).unwrap();
+        for _ in 0..4 {
+            receiver.tick().unwrap();
+        }
+        assert_eq!(receiver.current_tick(), 4);
+        assert_eq!(receiver.get_buffer_info(), (0, 0));
+    }
+
+    #[test]
+    fn test_input_batch_follows_sent_input() {
+        let new_peer = |side: Side, is_timekeeper: bool| {
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(Game::new(Config::default())),
+                RecordingMockTransport::new(),
+                60,
+                side,
+                is_timekeeper,
+            );
+            lockstep.start().unwrap();
+            lockstep
+        };
+        let mut left = new_peer(Side::Left, true);
+        let mut right = new_peer(Side::Right, false);
+        let up = Input::new(-100, 0);
+        let down = Input::new(100, 0);
+
+        // Tick 0 already went out, so the batch covers ticks 1 to 3
+        left.submit_local_input(up).unwrap();
+        left.submit_local_inputs(&[down; 3]).unwrap();
+        assert_eq!(left.get_buffer_info(), (4, 0));
+        let batch = left.transport.sent_messages().last().cloned().unwrap();
+        assert_eq!(
+            WireMsg::decode(&batch).unwrap(),
+            WireMsg::input_batch(1, vec![down; 3])
+        );
+
+        right.submit_local_inputs(&[Input::zero(); 4]).unwrap();
+        let events = deliver(&mut left, &mut right);
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, LockstepEvent::InputConflict { .. })));
+        deliver(&mut right, &mut left);
+        for _ in 0..4 {
+            left.tick().unwrap();
+            right.tick().unwrap();
+        }
+        assert_eq!(left.current_tick(), 4);
+        assert_eq!(right.current_tick(), 4);
+        assert_eq!(left.core.state_hash(), right.core.state_hash());
+
+        // Only the rest of the window is free
+        assert_eq!(
+            left.submit_local_inputs(&vec![Input::zero(); INPUT_WINDOW]),
+            Ok(())
+        );
+        assert_eq!(
+            left.submit_local_inputs(&[Input::zero()]),
+            Err(LockstepError::InputOutsideWindow(4 + INPUT_WINDOW as Tick))
+        );
+    }
+
+    #[test]
+    fn test_redundant_input_survives_loss() {
+        let mut sender = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        sender.set_input_redundancy(3);
+        sender.start().unwrap();
+
+        let mut receiver = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Right,
+            false,
+        );
+        receiver.start().unwrap();
+
+        // Sender plays ticks 0..3 on its own inputs plus the receiver's
+        for tick in 0..3u32 {
+            sender.on_local_input(tick as i8 + 1, 0).unwrap();
+            let remote = WireMsg::input_pair(tick, Input::zero(), Input::zero());
+            sender.on_net_message(remote.encode()).unwrap();
+            sender.tick().unwrap();
+        }
+
+        let sent = sender.transport.sent_messages();
+        assert_eq!(sent.len(), 4); // Hello + three histories
+        assert_eq!(
+            WireMsg::decode(&sent[3]).unwrap(),
+            WireMsg::input_history(
+                2,
+                vec![Input::new(1, 0), Input::new(2, 0), Input::new(3, 0)]
+            )
+        );
+
+        // Ticks 0 and 1 are lost; the last message alone lets the receiver catch up,
+        // and a late duplicate of tick 1 is deduplicated
+        receiver.on_net_message(sent[3].clone()).unwrap();
+        receiver.on_net_message(sent[2].clone()).unwrap();
+        assert_eq!(receiver.get_buffer_info(), (0, 3));
+        for _ in 0..3 {
+            receiver.on_local_input(0, 0).unwrap();
+            receiver.tick().unwrap();
+        }
+        assert_eq!(receiver.current_tick(), 3);
+    }
+
+    #[test]
+    fn test_sequencing_resends_only_after_loss() {
+        let mut sender = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        sender.set_input_redundancy(3);
+        sender.set_sequencing(true);
+        sender.start().unwrap();
+
+        let mut receiver = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Right,
+            false,
+        );
+        receiver.start().unwrap();
+
+        // Nothing is sequenced until the peer's Hello advertises support
+        assert!(!sender.is_sequencing());
+        let hello = receiver.transport.sent_messages()[0].clone();
+        sender.on_net_message(hello).unwrap();
+        assert!(sender.is_sequencing());
+
+        for tick in 0..3u32 {
+            sender.on_local_input(tick as i8 + 1, 0).unwrap();
+            let remote = WireMsg::input_pair(tick, Input::zero(), Input::zero());
+            sender.on_net_message(remote.encode()).unwrap();
+            sender.tick().unwrap();
+        }
+
+        // No loss reported yet, so each tick carries only its own input
+        let sent = sender.transport.sent_messages();
+        assert_eq!(sent.len(), 4); // Hello + three inputs
+        assert_eq!(
+            WireMsg::decode(&sent[2]).unwrap(),
+            WireMsg::sequenced(1, WireMsg::input_history(1, vec![Input::new(2, 0)]))
+        );
+
+        // Envelope 1 is lost and envelope 2 arrives twice
+        receiver.on_net_message(sent[1].clone()).unwrap();
+        receiver.on_net_message(sent[3].clone()).unwrap();
+        receiver.on_net_message(sent[3].clone()).unwrap();
+        receiver.tick().unwrap();
+
+        let ack = receiver.transport.sent_messages().last().unwrap().clone();
+        assert_eq!(
+            WireMsg::decode(&ack).unwrap(),
+            WireMsg::Ack {
+                latest: 2,
+                mask: 0b10
+            }
+        );
+        assert_eq!(
+            receiver.sequence_stats(),
+            SequenceStats {
+                received: 2,
+                duplicates: 1,
+                ..Default::default()
+            }
+        );
+
+        // The ack reveals the gap; the next input goes out with full redundancy
+        sender.on_net_message(ack.clone()).unwrap();
+        assert_eq!(sender.sequence_stats().lost, 1);
+        sender.on_local_input(4, 0).unwrap();
+        let resend = sender.transport.sent_messages().last().unwrap().clone();
+        assert_eq!(
+            WireMsg::decode(&resend).unwrap(),
+            WireMsg::sequenced(
+                3,
+                WireMsg::input_history(
+                    3,
+                    vec![Input::new(2, 0), Input::new(3, 0), Input::new(4, 0)]
+                )
+            )
+        );
+
+        // A repeated ack doesn't count the same loss twice
+        sender.on_net_message(ack).unwrap();
+        assert_eq!(sender.sequence_stats().lost, 1);
+
+        receiver.on_net_message(resend).unwrap();
+        assert_eq!(receiver.get_buffer_info(), (0, 4));
+    }
+
+    #[cfg(feature = "auth")]
+    #[test]
+    fn test_room_secret_rejects_outsiders() {
+        let new_peer = |side, secret: Option<&[u8]>| {
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(Game::new(Config::default())),
+                RecordingMockTransport::new(),
+                60,
+                side,
+                side == Side::Left,
+            );
+            lockstep.set_room_secret(secret);
+            lockstep.start().unwrap();
+            lockstep
+        };
+        let mut host = new_peer(Side::Left, Some(b"room-42"));
+        let guest = new_peer(Side::Right, Some(b"room-42"));
+        let outsider = new_peer(Side::Right, Some(b"room-43"));
+
+        // The sealed Hello is longer than a plain one and verifies with the shared key
+        let hello = guest.transport.sent_messages()[0].clone();
+        assert_eq!(
+            hello.len(),
+            MessageAuth::sealed_len(WireMsg::hello().encoded_size())
+        );
+        let events = host.on_net_message(hello).unwrap();
+        assert!(matches!(events[0], LockstepEvent::PeerHello { .. }));
+
+        // Wrong secret, or no secret at all, is rejected before anything is applied
+        let spoofed = outsider.transport.sent_messages()[0].clone();
+        let injected = WireMsg::input_pair(0, Input::zero(), Input::new(127, 0)).encode();
+        for bytes in [spoofed, injected] {
+            assert_eq!(
+                host.on_net_message(bytes),
+                Err(LockstepError::Serialization(
+                    SerializationError::AuthenticationFailed
+                ))
+            );
+        }
+        assert_eq!(host.get_buffer_info(), (0, 0));
+    }
+
+    #[test]
+    fn test_lobby_handshake() {
+        let mut host = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Right,
+            true,
+        );
+        host.start().unwrap();
+
+        // The guest guesses a side; the host's Welcome overrides it
+        let mut guest = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Right,
+            false,
+        );
+        guest.start().unwrap();
+
+        guest.join().unwrap();
+        let join = guest.transport.sent_messages().last().unwrap().clone();
+        assert_eq!(
+            host.on_net_message(join.clone()).unwrap(),
+            vec![LockstepEvent::PeerJoined]
+        );
+
+        let rules = Config {
+            max_score: 3,
+            ..Config::default()
+        };
+        host.welcome(&rules).unwrap();
+        host.start_match(10).unwrap();
+        let sent = host.transport.sent_messages();
+        let (welcome, start) = (sent[sent.len() - 2].clone(), sent[sent.len() - 1].clone());
+
+        assert_eq!(
+            guest.on_net_message(welcome.clone()).unwrap(),
+            vec![LockstepEvent::Welcomed {
+                side: Side::Left,
+                config: rules
+            }]
+        );
+        assert_eq!(guest.local_side, Side::Left);
+        assert_eq!(
+            guest.on_net_message(start).unwrap(),
+            vec![LockstepEvent::MatchStarting { start_tick: 10 }]
+        );
+
+        // Roles can't be crossed
+        assert!(matches!(
+            guest.on_net_message(join),
+            Err(LockstepError::InvalidMessage(_))
+        ));
+        assert!(matches!(
+            host.on_net_message(welcome),
+            Err(LockstepError::InvalidMessage(_))
+        ));
+    }
+
+    #[test]
+    fn test_config_sync() {
+        let new_peer = |side| {
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(Game::new(Config::default())),
+                RecordingMockTransport::new(),
+                60,
+                side,
+                side == Side::Left,
+            );
+            lockstep.start().unwrap();
+            lockstep
+        };
+        let config = Config::default();
+
+        // Matching configs verify on whichever side learns of both
+        let mut left = new_peer(Side::Left);
+        let mut right = new_peer(Side::Right);
+        left.sync_config(&config).unwrap();
+        let announce = left.transport.sent_messages().last().unwrap().clone();
+        assert!(right.on_net_message(announce).unwrap().is_empty());
+        right.sync_config(&config).unwrap();
+        assert!(right.is_config_verified());
+
+        let reply = right.transport.sent_messages().last().unwrap().clone();
+        assert_eq!(
+            left.on_net_message(reply).unwrap(),
+            vec![LockstepEvent::ConfigVerified]
+        );
+        assert!(left.is_config_verified());
+
+        // Ticks are held until verification
+        let mut waiting = new_peer(Side::Left);
+        waiting.sync_config(&config).unwrap();
+        waiting.on_local_input(0, 0).unwrap();
+        let remote = WireMsg::input_pair(0, Input::zero(), Input::zero());
+        waiting.on_net_message(remote.encode()).unwrap();
+        waiting.tick().unwrap();
+        assert_eq!(waiting.current_tick(), 0);
+
+        // A mismatch names the differing fields
+        let other = Config {
+            max_score: 3,
+            ..config
+        };
+        assert_eq!(
+            waiting.on_net_message(WireMsg::ConfigSync(other).encode()),
+            Err(LockstepError::ConfigMismatch {
+                fields: vec!["max_score"]
+            })
+        );
+        assert!(!waiting.is_config_verified());
+    }
+
+    #[test]
+    fn test_chat_exchange() {
+        let game = Game::new(Config::default());
+        let adapter = GameAdapter::new(game);
+        let transport = RecordingMockTransport::new();
+
+        let mut lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+        assert_eq!(lockstep.send_chat("hi"), Err(LockstepError::NotRunning));
+        lockstep.start().unwrap();
+
+        lockstep.send_chat("glhf").unwrap();
+        let sent = lockstep.transport.sent_messages();
+        assert_eq!(
+            WireMsg::decode(sent.last().unwrap()).unwrap(),
+            WireMsg::chat("glhf")
+        );
+
+        let events = lockstep
+            .on_net_message(WireMsg::chat("gg").encode())
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![LockstepEvent::ChatReceived {
+                text: "gg".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_stream_bytes_reassembled() {
+        let game = Game::new(Config::default());
+        let adapter = GameAdapter::new(game);
+        let transport = RecordingMockTransport::new();
+
+        let mut lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+        lockstep.start().unwrap();
+
+        let mut stream = Framer::frame_msg(&WireMsg::chat("one")).unwrap();
+        stream.extend(Framer::frame_msg(&WireMsg::Emote(emotes::GG)).unwrap());
+        stream.extend(Framer::frame_msg(&WireMsg::chat("two")).unwrap());
+
+        // Split mid-header and mid-payload
+        let (first, rest) = stream.split_at(1);
+        let (second, third) = rest.split_at(12); // Frame one is 12 bytes
+        assert!(lockstep.on_stream_bytes(first).unwrap().is_empty());
+        assert_eq!(
+            lockstep.on_stream_bytes(second).unwrap(),
+            vec![LockstepEvent::ChatReceived {
+                text: "one".to_string()
+            }]
+        );
+        assert_eq!(
+            lockstep.on_stream_bytes(third).unwrap(),
+            vec![
+                LockstepEvent::EmoteReceived { emote: emotes::GG },
+                LockstepEvent::ChatReceived {
+                    text: "two".to_string()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stream_keeps_events_before_bad_frame() {
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        lockstep.start().unwrap();
+
+        let mut corrupt = WireMsg::chat("two").encode();
+        *corrupt.last_mut().unwrap() ^= 1; // Break the checksum
+        let mut stream = Framer::frame_msg(&WireMsg::chat("one")).unwrap();
+        stream.extend(Framer::frame(&corrupt).unwrap());
+        assert!(lockstep.on_stream_bytes(&stream).is_err());
+
+        // The chat applied before the bad frame is still reported
+        let three = Framer::frame_msg(&WireMsg::chat("three")).unwrap();
+        assert_eq!(
+            lockstep.on_stream_bytes(&three).unwrap(),
+            vec![
+                LockstepEvent::ChatReceived {
+                    text: "one".to_string()
+                },
+                LockstepEvent::ChatReceived {
+                    text: "three".to_string()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_features_disabled_for_older_peer() {
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        lockstep.set_sequencing(true);
+        lockstep.start().unwrap();
+
+        // A peer from before chat and sequencing existed
+        let old_hello = WireMsg::Hello {
+            version: PROTOCOL_VERSION,
+            capabilities: capabilities::INPUT_TILT | capabilities::INPUT_AXIS_X,
+        };
+        lockstep.on_net_message(old_hello.encode()).unwrap();
+        assert_eq!(
+            lockstep.negotiated_capabilities(),
+            capabilities::INPUT_TILT | capabilities::INPUT_AXIS_X
+        );
+
+        assert_eq!(
+            lockstep.send_chat("hi"),
+            Err(LockstepError::PeerLacksCapability(capabilities::CHAT))
+        );
+        assert_eq!(
+            lockstep.send_emote(emotes::GG),
+            Err(LockstepError::PeerLacksCapability(capabilities::CHAT))
+        );
+
+        // Snapshots go out plain and inputs unsequenced, so the old peer can read them
+        assert!(!lockstep.is_sequencing());
+        lockstep.send_snapshot().unwrap();
+        lockstep.on_local_input(1, 0).unwrap();
+        let sent = lockstep.transport.sent_messages();
+        assert_eq!(sent[1][0], 0x02);
+        assert_eq!(sent[2][0], 0x01);
+        assert_eq!(sent.len(), 3); // Hello, snapshot, input
+    }
+
+    #[test]
+    fn test_input_fields_follow_peer_capabilities() {
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        lockstep.start().unwrap();
+        let hello = WireMsg::Hello {
+            version: PROTOCOL_VERSION,
+            capabilities: capabilities::SUPPORTED & !capabilities::INPUT_TILT,
+        };
+        lockstep.on_net_message(hello.encode()).unwrap();
+
+        // The peer can't read tilt, so we don't simulate it either
+        let input = Input::new(5, 0).with_tilt(30).with_axis_x(4);
+        lockstep.submit_local_input(input).unwrap();
+        let expected = Input::new(5, 0).with_axis_x(4);
+        assert_eq!(lockstep.local_input_buffer.get(0), Some(expected));
+        let sent = lockstep.transport.sent_messages();
+        assert_eq!(
+            WireMsg::decode(sent.last().unwrap()).unwrap(),
+            WireMsg::SideInput {
+                tick: 0,
+                side: Side::Left,
+                input: expected
+            }
+        );
+    }
+
+    #[test]
+    fn test_emote_bypasses_inputs() {
+        let game = Game::new(Config::default());
+        let adapter = GameAdapter::new(game);
+        let transport = RecordingMockTransport::new();
+
+        let mut lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+        lockstep.start().unwrap();
+
+        lockstep.send_emote(emotes::GG).unwrap();
+        let sent = lockstep.transport.sent_messages();
+        assert_eq!(
+            WireMsg::decode(sent.last().unwrap()).unwrap(),
+            WireMsg::Emote(emotes::GG)
+        );
+
+        let events = lockstep
+            .on_net_message(WireMsg::Emote(emotes::LAUGH).encode())
+            .unwrap();
+        assert_eq!(
+            events,
+            vec![LockstepEvent::EmoteReceived {
+                emote: emotes::LAUGH
+            }]
+        );
+        assert_eq!(lockstep.get_buffer_info(), (0, 0));
+        assert_eq!(lockstep.current_tick(), 0);
+    }
+
+    #[test]
+    fn test_state_hash_desync_detection() {
+        let game = Game::new(Config::default());
+        let adapter = GameAdapter::new(game);
+        let transport = RecordingMockTransport::new();
+
+        let mut lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+        lockstep.set_hash_interval(2);
+        lockstep.start().unwrap();
+
+        // Step ticks 0..4 with neutral remote input
+        let step = |lockstep: &mut Lockstep<GameAdapter, RecordingMockTransport>| {
+            let tick = lockstep.current_tick();
+            lockstep.on_local_input(0, 0).unwrap();
+            let remote = WireMsg::input_pair(tick, Input::zero(), Input::zero());
+            lockstep.on_net_message(remote.encode()).unwrap();
+            lockstep.tick().unwrap()
+        };
+        for _ in 0..4 {
+            step(&mut lockstep);
+        }
+
+        // Hashes went out for ticks 0 and 2
+        let hashes: Vec<(Tick, u64)> = lockstep
+            .transport
+            .sent_messages()
+            .iter()
+            .filter_map(|bytes| match WireMsg::decode(bytes).unwrap() {
+                WireMsg::StateHash { tick, hash } => Some((tick, hash)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            hashes.iter().map(|&(t, _)| t).collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+
+        // A matching peer hash is quiet; a differing one is reported once
+        let (tick0, hash0) = hashes[0];
+        let agree = WireMsg::StateHash {
+            tick: tick0,
+            hash: hash0,
+        };
+        assert!(lockstep.on_net_message(agree.encode()).unwrap().is_empty());
+
+        let (tick2, hash2) = hashes[1];
+        let disagree = WireMsg::StateHash {
+            tick: tick2,
+            hash: hash2 ^ 1,
+        };
+        assert_eq!(
+            lockstep.on_net_message(disagree.encode()).unwrap(),
+            vec![LockstepEvent::DesyncDetected {
+                tick: 2,
+                local: hash2,
+                remote: hash2 ^ 1
+            }]
+        );
+        assert_eq!(lockstep.desync_tick(), Some(2));
+
+        // A peer hash arriving before we simulate that tick is matched later
+        let early = WireMsg::StateHash { tick: 4, hash: 0 };
+        assert!(lockstep.on_net_message(early.encode()).unwrap().is_empty());
+        let events = step(&mut lockstep);
+        // Already reported the first desync
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, LockstepEvent::DesyncDetected { .. })));
+        assert_eq!(lockstep.desync_tick(), Some(2));
+    }
+
+    /// Deliver everything `from` sent to `to`, returning `to`'s events
+    fn deliver(
+        from: &mut Lockstep<GameAdapter, RecordingMockTransport>,
+        to: &mut Lockstep<GameAdapter, RecordingMockTransport>,
+    ) -> Vec<LockstepEvent> {
+        let mut events = Vec::new();
+        while let Some(bytes) = from.transport.pop_sent_message() {
+            events.extend(to.on_net_message(bytes).unwrap());
+        }
+        events
+    }
+
+    #[test]
+    fn test_side_input_exchange() {
+        let new_peer = |side: Side, is_timekeeper: bool| {
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(Game::new(Config::default())),
+                RecordingMockTransport::new(),
+                60,
+                side,
+                is_timekeeper,
+            );
+            lockstep.start().unwrap();
+            lockstep
+        };
+        let mut left = new_peer(Side::Left, true);
+        let mut right = new_peer(Side::Right, false);
+
+        // Before the peer's Hello, inputs go out as InputPairs
+        left.on_local_input(5, 0).unwrap();
+        let first = left.transport.sent_messages().last().unwrap().clone();
+        assert!(matches!(
+            WireMsg::decode(&first).unwrap(),
+            WireMsg::InputPair(_)
+        ));
+        left.transport.clear_sent_messages();
+
+        deliver(&mut right, &mut left);
+        left.set_local_input_delay(1);
+        left.on_local_input(6, 0).unwrap();
+        let sent = left.transport.sent_messages().last().unwrap().clone();
+        assert_eq!(
+            WireMsg::decode(&sent).unwrap(),
+            WireMsg::SideInput {
+                tick: 1,
+                side: Side::Left,
+                input: Input::new(6, 0)
+            }
+        );
+
+        right.on_net_message(first).unwrap();
+        right.on_net_message(sent).unwrap();
+        for _ in 0..2 {
+            right.on_local_input(0, 0).unwrap();
+            right.tick().unwrap();
+        }
+        assert_eq!(right.current_tick(), 2);
+
+        // Our own side's input can only come from us
+        let own = WireMsg::SideInput {
+            tick: 2,
+            side: Side::Right,
+            input: Input::zero(),
+        };
+        assert!(matches!(
+            right.on_net_message(own.encode()),
+            Err(LockstepError::InvalidMessage(_))
+        ));
+    }
+
+    #[test]
+    fn test_local_input_queue() {
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        lockstep.set_local_input_delay(2);
+        lockstep.start().unwrap();
+        lockstep.transport.clear_sent_messages();
+
+        // Samples fill the delay window, then only the latest is held
+        for axis in 1..=5 {
+            lockstep.on_local_input(axis, 0).unwrap();
+        }
+        let sent: Vec<WireMsg> = lockstep
+            .transport
+            .sent_messages()
+            .iter()
+            .map(|bytes| WireMsg::decode(bytes).unwrap())
+            .collect();
+        assert_eq!(
+            sent,
+            (0..3)
+                .map(|tick| WireMsg::input_pair(tick, Input::new(tick as i8 + 1, 0), Input::zero()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(lockstep.get_buffer_info().0, 3);
+
+        // Stepping opens tick 3 for the held sample
+        lockstep.transport.clear_sent_messages();
+        let remote = WireMsg::input_pair(0, Input::zero(), Input::zero());
+        lockstep.on_net_message(remote.encode()).unwrap();
+        lockstep.tick().unwrap();
+        let sent = lockstep.transport.sent_messages();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(
+            WireMsg::decode(&sent[0]).unwrap(),
+            WireMsg::input_pair(3, Input::new(5, 0), Input::zero())
+        );
+
+        // The window is full again, so the next sample waits for tick 4
+        lockstep.on_local_input(9, 0).unwrap();
+        assert_eq!(lockstep.transport.sent_messages().len(), 1);
+    }
+
+    #[test]
+    fn test_replay_records_confirmed_inputs() {
+        let new_peer = |side: Side, is_timekeeper: bool| {
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(Game::new(Config::default())),
+                RecordingMockTransport::new(),
+                60,
+                side,
+                is_timekeeper,
+            );
+            lockstep.start().unwrap();
+            lockstep
+        };
+        let mut left = new_peer(Side::Left, true);
+        let mut right = new_peer(Side::Right, false);
+        deliver(&mut left, &mut right);
+        deliver(&mut right, &mut left);
+
+        assert!(left.take_replay().is_none());
+        left.record_replay();
+        left.set_replay_keyframe_interval(4);
+        assert!(left.is_recording());
+
+        for tick in 0..10i8 {
+            left.on_local_input(tick * 10, 0).unwrap();
+            right.on_local_input(-tick * 10, 0).unwrap();
+            deliver(&mut left, &mut right);
+            deliver(&mut right, &mut left);
+            left.tick().unwrap();
+            right.tick().unwrap();
+        }
+
+        // A snapshot from the timekeeper lands in the replay as a keyframe
+        right.record_replay();
+        left.send_snapshot().unwrap();
+        deliver(&mut left, &mut right);
+        assert!(matches!(
+            right.take_replay().unwrap().records[..],
+            [ReplayRecord::Keyframe(_)]
+        ));
+
+        let replay = left.take_replay().unwrap();
+        assert!(!left.is_recording());
+        assert_eq!(replay.inputs().count(), 10);
+        let keyframes = replay.records.len() - 10;
+        assert_eq!(keyframes, 2);
+
+        // Re-simulating the replay arrives at both peers' final state
+        let replay = Replay::decode(&replay.encode()).unwrap();
+        let mut game = Game::new(replay.header.config);
+        game.restore(&replay.header.start).unwrap();
+        for pair in replay.inputs() {
+            game.step(pair);
+        }
+        let footer = replay.footer.unwrap();
+        assert_eq!(footer.final_tick, 10);
+        assert_eq!(footer.final_hash, game.state_hash());
+        assert_eq!(game.view(), right.view());
+    }
+
+    #[test]
+    fn test_spectator_follows_relayed_match() {
+        let new_peer = |side: Side, is_timekeeper: bool| {
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(Game::new(Config::default())),
+                RecordingMockTransport::new(),
+                60,
+                side,
+                is_timekeeper,
+            );
+            lockstep.start().unwrap();
+            lockstep
+        };
+        let mut left = new_peer(Side::Left, true);
+        let mut right = new_peer(Side::Right, false);
+        deliver(&mut left, &mut right);
+        deliver(&mut right, &mut left);
+
+        let viewer = RecordingMockTransport::new();
+        left.add_spectator(Box::new(viewer.clone())).unwrap();
+        left.set_spectator_snapshot_interval(4);
+        assert_eq!(left.spectator_count(), 1);
+        assert!(left
+            .add_spectator(Box::new(RecordingMockTransport::new_closed()))
+            .is_err());
+
+        for tick in 0..6i8 {
+            left.on_local_input(tick * 10, 0).unwrap();
+            right.on_local_input(-tick * 10, 0).unwrap();
+            deliver(&mut left, &mut right);
+            deliver(&mut right, &mut left);
+            left.tick().unwrap();
+            right.tick().unwrap();
+        }
+
+        // Hello and the joining snapshot, six pairs and one periodic snapshot
+        let relayed = viewer.sent_messages();
+        assert_eq!(relayed.len(), 9);
+
+        let mut spectator = Spectator::new(GameAdapter::new(Game::new(Config::default())));
+        for bytes in &relayed {
+            spectator.on_net_message(bytes).unwrap();
+        }
+        assert_eq!(spectator.current_tick(), 6);
+        assert_eq!(spectator.view(), left.view());
+
+        // A viewer that goes away is dropped
+        let mut viewer = viewer;
+        viewer.set_open(false);
+        left.on_local_input(0, 0).unwrap();
+        right.on_local_input(0, 0).unwrap();
+        deliver(&mut right, &mut left);
+        left.tick().unwrap();
+        assert_eq!(left.spectator_count(), 0);
+    }
+
+    #[test]
+    fn test_collision_events_reach_peers_and_spectators() {
+        // Both peers start mid-rally with the ball about to hit the left paddle
+        let new_peer = |side: Side, is_timekeeper: bool| {
+            let mut game = Game::new(Config::default());
+            game.status = Status::Playing;
+            game.ball.pos = Vec2::new(game.config.paddle_x + 200, game.paddles[0].y);
+            game.ball.vel = Vec2::new(-FX_ONE / 2, 0);
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(game),
+                RecordingMockTransport::new(),
+                60,
+                side,
+                is_timekeeper,
+            );
+            lockstep.start().unwrap();
+            lockstep
+        };
+        let mut left = new_peer(Side::Left, true);
+        let mut right = new_peer(Side::Right, false);
+        deliver(&mut left, &mut right);
+        deliver(&mut right, &mut left);
+        let viewer = RecordingMockTransport::new();
+        left.add_spectator(Box::new(viewer.clone())).unwrap();
+
+        left.on_local_input(0, 0).unwrap();
+        right.on_local_input(0, 0).unwrap();
+        deliver(&mut left, &mut right);
+        deliver(&mut right, &mut left);
+
+        let is_paddle_hit = |events: &[Event]| {
+            matches!(
+                events,
+                [Event::PaddleHit {
+                    side: Side::Left,
+                    ..
+                }]
+            )
+        };
+        for peer in [&mut left, &mut right] {
+            match peer.tick().unwrap().as_slice() {
+                [LockstepEvent::GameAdvanced { tick: 0, events }] => {
+                    assert!(is_paddle_hit(events), "got {events:?}")
+                }
+                other => panic!("expected the paddle hit to be reported, got {other:?}"),
+            }
+        }
+
+        let mut spectator = Spectator::new(GameAdapter::new(Game::new(Config::default())));
+        let events: Vec<_> = viewer
+            .sent_messages()
+            .iter()
+            .flat_map(|bytes| spectator.on_net_message(bytes).unwrap())
+            .collect();
+        match events.as_slice() {
+            [.., SpectatorEvent::GameAdvanced { tick: 0, events }] => {
+                assert!(is_paddle_hit(events), "got {events:?}")
+            }
+            other => panic!("expected the paddle hit to be replayed, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_pause_holds_both_peers_at_same_tick() {
+        let new_peer = |side: Side, is_timekeeper: bool| {
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(Game::new(Config::default())),
+                RecordingMockTransport::new(),
+                60,
+                side,
+                is_timekeeper,
+            );
+            lockstep.start().unwrap();
+            lockstep
+        };
+        let mut left = new_peer(Side::Left, true);
+        let mut right = new_peer(Side::Right, false);
+        deliver(&mut left, &mut right);
+        deliver(&mut right, &mut left);
+
+        // Left already sent input for tick 0, so the pause lands on tick 1
+        left.on_local_input(0, 0).unwrap();
+        assert_eq!(left.pause().unwrap(), 1);
+        assert!(!left.is_paused());
+        assert_ne!(left.view().status, Status::Paused);
+        right.on_local_input(0, 0).unwrap();
+        assert_eq!(
+            deliver(&mut left, &mut right),
+            vec![LockstepEvent::PeerPaused { at_tick: 1 }]
+        );
+        deliver(&mut right, &mut left);
+
+        for _ in 0..2 {
+            left.tick().unwrap();
+            right.tick().unwrap();
+            left.on_local_input(0, 0).unwrap();
+            right.on_local_input(0, 0).unwrap();
+            deliver(&mut left, &mut right);
+            deliver(&mut right, &mut left);
+        }
+        assert_eq!((left.current_tick(), right.current_tick()), (1, 1));
+        assert!(left.is_paused() && right.is_paused());
+        assert!(!right.is_waiting_for_remote());
+        assert_eq!(left.view().status, Status::Paused);
+        assert_eq!(right.view().status, Status::Paused);
+        assert_eq!(left.core().state_hash(), right.core().state_hash());
+
+        // Either side may resume
+        right.resume().unwrap();
+        assert_eq!(right.view().status, Status::Lobby);
+        assert_eq!(
+            deliver(&mut right, &mut left),
+            vec![LockstepEvent::PeerResumed { at_tick: 1 }]
+        );
+        assert_eq!(left.view().status, Status::Lobby);
+        left.tick().unwrap();
+        right.tick().unwrap();
+        assert_eq!((left.current_tick(), right.current_tick()), (2, 2));
+        assert_eq!(left.pause_tick(), None);
+    }
+
+    #[test]
+    fn test_simultaneous_pauses_agree_on_earlier_tick() {
+        let new_peer = |side: Side, is_timekeeper: bool| {
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(Game::new(Config::default())),
+                RecordingMockTransport::new(),
+                60,
+                side,
+                is_timekeeper,
+            );
+            lockstep.start().unwrap();
+            lockstep
+        };
+        let mut left = new_peer(Side::Left, true);
+        let mut right = new_peer(Side::Right, false);
+        deliver(&mut left, &mut right);
+        deliver(&mut right, &mut left);
+
+        right.on_local_input(0, 0).unwrap();
+        assert_eq!(left.pause().unwrap(), 0);
+        assert_eq!(right.pause().unwrap(), 1);
+        deliver(&mut left, &mut right);
+        deliver(&mut right, &mut left);
+        assert_eq!(left.pause_tick(), Some(0));
+        assert_eq!(right.pause_tick(), Some(0));
+
+        // A pause for a tick we already stepped can't be honored
+        let stale = WireMsg::Pause { at_tick: 0 };
+        right.resume().unwrap();
+        deliver(&mut right, &mut left);
+        left.on_local_input(0, 0).unwrap();
+        deliver(&mut left, &mut right);
+        right.tick().unwrap();
+        assert!(matches!(
+            right.on_net_message(stale.encode()),
+            Err(LockstepError::SyncError(_))
+        ));
+    }
+
+    #[test]
+    fn test_reattach_resumes_session() {
+        let new_peer = |side: Side, is_timekeeper: bool| {
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(Game::new(Config::default())),
+                RecordingMockTransport::new(),
+                60,
+                side,
+                is_timekeeper,
+            );
+            lockstep.start().unwrap();
+            lockstep
+        };
+        let mut left = new_peer(Side::Left, true);
+        let mut right = new_peer(Side::Right, false);
+        deliver(&mut left, &mut right);
+        deliver(&mut right, &mut left);
+
+        for tick in 0..3i8 {
+            left.on_local_input(tick * 20, 0).unwrap();
+            right.on_local_input(-tick * 20, 0).unwrap();
+            deliver(&mut left, &mut right);
+            deliver(&mut right, &mut left);
+            left.tick().unwrap();
+            right.tick().unwrap();
+        }
+
+        // The connection drops, swallowing both inputs for tick 3
+        left.on_local_input(30, 0).unwrap();
+        right.on_local_input(-30, 0).unwrap();
+        left.tick().unwrap();
+        right.tick().unwrap();
+        assert!(left.is_waiting_for_remote() && right.is_waiting_for_remote());
+
+        assert!(matches!(
+            left.reattach(RecordingMockTransport::new_closed()),
+            Err(LockstepError::Transport(_))
+        ));
+        left.reattach(RecordingMockTransport::new()).unwrap();
+        // As if the right peer's transport had reconnected in place
+        right.transport_mut().clear_sent_messages();
+        right.rejoin().unwrap();
+
+        let events = deliver(&mut left, &mut right);
+        assert!(events.contains(&LockstepEvent::SessionResumed { tick: 3 }));
+        let events = deliver(&mut right, &mut left);
+        assert!(events.contains(&LockstepEvent::SessionResumed { tick: 3 }));
+
+        left.tick().unwrap();
+        right.tick().unwrap();
+        assert_eq!((left.current_tick(), right.current_tick()), (4, 4));
+        assert_eq!(left.view(), right.view());
+    }
+
+    #[test]
+    fn test_drive_paces_ticks_and_pings() {
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            50,
+            Side::Left,
+            true,
+        );
+        lockstep.start().unwrap();
+        for tick in 0..10 {
+            let remote = WireMsg::input_pair(tick, Input::zero(), Input::new(3, 0));
+            lockstep.on_net_message(remote.encode()).unwrap();
+        }
+        lockstep.transport.clear_sent_messages();
+
+        let start = lockstep.clock.now();
+        let interval = Duration::from_millis(20);
+        lockstep.drive(start, Input::new(9, 0)).unwrap();
+        assert_eq!(lockstep.current_tick(), 1);
+        assert_eq!(lockstep.next_deadline(), Some(start + interval));
+
+        // Nothing is due mid-interval
+        lockstep.drive(start + interval / 2, Input::zero()).unwrap();
+        assert_eq!(lockstep.current_tick(), 1);
+
+        // Overdue ticks catch up, but only so far
+        lockstep.drive(start + interval * 3, Input::zero()).unwrap();
+        assert_eq!(lockstep.current_tick(), 4);
+        lockstep
+            .drive(start + interval * 20, Input::zero())
+            .unwrap();
+        assert_eq!(lockstep.current_tick(), 8);
+        assert_eq!(lockstep.next_deadline(), Some(start + interval * 21));
+
+        // Submitted inputs were ours, and only the first drive pinged
+        let sent: Vec<WireMsg> = lockstep
+            .transport
+            .sent_messages()
+            .iter()
+            .map(|bytes| WireMsg::decode(bytes).unwrap())
+            .collect();
+        assert_eq!(
+            sent.iter()
+                .filter(|msg| matches!(msg, WireMsg::Ping(_)))
+                .count(),
+            1
+        );
+        assert_eq!(
+            sent[1],
+            WireMsg::input_pair(0, Input::new(9, 0), Input::zero())
+        );
+
+        // Once the peer's inputs run out, ticks hold and retry next interval
+        let later = start + interval * 40;
+        lockstep.drive(later, Input::zero()).unwrap();
+        assert_eq!(lockstep.current_tick(), 10);
+        lockstep.drive(later + interval, Input::zero()).unwrap();
+        assert_eq!(lockstep.current_tick(), 10);
+        assert_eq!(lockstep.next_deadline(), Some(later + interval * 2));
+    }
+
+    #[test]
+    fn test_metrics_track_session_health() {
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        lockstep.start().unwrap();
+        let hello = lockstep.transport.sent_messages()[0].len() as u64;
+        assert_eq!(lockstep.metrics().messages_out, 1);
+        assert_eq!(lockstep.metrics().bytes_out, hello);
+
+        // Two remote inputs buffered, then stepped one per tick over a second
+        let batch = WireMsg::input_batch(0, vec![Input::zero(); 2]);
+        let batch_len = batch.encode().len() as u64;
+        lockstep.on_net_message(batch.encode()).unwrap();
+        let start = lockstep.clock.now();
+        for ms in [0, 500, 1000] {
+            lockstep.on_local_input(0, 0).unwrap();
+            lockstep.tick_at(start + Duration::from_millis(ms)).unwrap();
+        }
+
+        let metrics = lockstep.metrics();
+        assert_eq!(metrics.ticks_advanced, 2);
+        assert_eq!(metrics.ticks_per_second, 2.0);
+        assert_eq!(metrics.avg_buffer_depth, 1.0);
+        assert_eq!(metrics.messages_in, 1);
+        assert_eq!(metrics.bytes_in, batch_len);
+        assert_eq!(metrics.messages_out, 4);
+
+        // The third tick has no remote input and stalls
+        lockstep.tick_at(start + Duration::from_secs(2)).unwrap();
+        assert_eq!(lockstep.metrics().stalls, 1);
+    }
+
+    #[test]
+    fn test_observer_receives_events() {
+        #[derive(Default)]
+        struct Log {
+            advanced: Vec<Tick>,
+            desyncs: Vec<Tick>,
+            peer_events: Vec<LockstepEvent>,
+            stats: Vec<u32>,
+        }
+        struct Recorder(std::rc::Rc<std::cell::RefCell<Log>>);
+        impl LockstepObserver for Recorder {
+            fn on_game_advanced(&mut self, tick: Tick, _events: &[Event]) {
+                self.0.borrow_mut().advanced.push(tick);
+            }
+            fn on_desync(&mut self, tick: Tick, _local: u64, _remote: u64) {
+                self.0.borrow_mut().desyncs.push(tick);
+            }
+            fn on_peer_event(&mut self, event: &LockstepEvent) {
+                self.0.borrow_mut().peer_events.push(event.clone());
+            }
+            fn on_stats(&mut self, metrics: &LockstepMetrics) {
+                self.0.borrow_mut().stats.push(metrics.ticks_advanced);
+            }
+        }
+
+        // A ball about to leave past the left paddle scores on the first tick
+        let mut game = Game::new(Config::default());
+        game.status = Status::Playing;
+        game.ball.pos = Vec2::new(0, FX_ONE / 10);
+        game.ball.vel = Vec2::new(-FX_ONE / 10, 0);
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(game),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        let log = std::rc::Rc::default();
+        lockstep.set_observer(Recorder(std::rc::Rc::clone(&log)));
+        lockstep.set_hash_interval(1);
+        lockstep.start().unwrap();
+
+        let hello = WireMsg::hello().encode();
+        let returned = lockstep.on_net_message(hello).unwrap();
+        assert_eq!(log.borrow().peer_events, returned);
+
+        let start = lockstep.clock.now();
+        let batch = WireMsg::input_batch(0, vec![Input::zero(); 2]);
+        lockstep.on_net_message(batch.encode()).unwrap();
+        let wrong_hash = WireMsg::StateHash { tick: 0, hash: 1 };
+        lockstep.on_net_message(wrong_hash.encode()).unwrap();
+        for ms in [0, 1000] {
+            lockstep.on_local_input(0, 0).unwrap();
+            lockstep.tick_at(start + Duration::from_millis(ms)).unwrap();
+        }
+
+        let log = log.borrow();
+        assert_eq!(log.advanced, vec![0, 1]);
+        assert_eq!(log.desyncs, vec![0]);
+        // Sampled as the second tick starts, one tick in
+        assert_eq!(log.stats, vec![1]);
+        drop(log);
+
+        // The observer can be taken back, after which events are only returned
+        assert!(lockstep.take_observer().is_some());
+        assert!(lockstep.take_observer().is_none());
+    }
+
+    #[test]
+    fn test_conflicting_data_resolution() {
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        let start = lockstep.core.snapshot();
+        lockstep.start().unwrap();
+
+        // The first input for a tick stands; a repeat is fine, a change is reported
+        let first = WireMsg::input_pair(0, Input::zero(), Input::new(10, 0));
+        let changed = WireMsg::input_pair(0, Input::zero(), Input::new(20, 0));
+        assert!(lockstep.on_net_message(first.encode()).unwrap().is_empty());
+        assert!(lockstep.on_net_message(first.encode()).unwrap().is_empty());
+        assert_eq!(
+            lockstep.on_net_message(changed.encode()).unwrap(),
+            vec![LockstepEvent::InputConflict {
+                tick: 0,
+                conflicting: 1
+            }]
+        );
+
+        lockstep.on_local_input(0, 0).unwrap();
+        lockstep.tick().unwrap();
+        let mut expected = Game::new(Config::default());
+        expected.step(&InputPair::new(0, Input::zero(), Input::new(10, 0)));
+        assert_eq!(lockstep.view(), expected.view());
+
+        // Once the ticks after a snapshot can't be replayed, it is ignored
+        for tick in 1..=RESYNC_HISTORY as u32 + 1 {
+            let remote = WireMsg::input_pair(tick, Input::zero(), Input::zero());
+            lockstep.on_net_message(remote.encode()).unwrap();
+            lockstep.on_local_input(0, 0).unwrap();
+            lockstep.tick().unwrap();
+        }
+        let tick = lockstep.current_tick();
+        assert_eq!(
+            lockstep
+                .on_net_message(WireMsg::snapshot(&start).encode())
+                .unwrap(),
+            vec![LockstepEvent::StaleSnapshot { tick: 0 }]
+        );
+        assert_eq!(lockstep.current_tick(), tick);
+    }
+
+    #[test]
+    fn test_input_window_bounds_buffers() {
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        lockstep.start().unwrap();
+
+        // A batch straddling the end of the window keeps only what fits
+        let start = INPUT_WINDOW as Tick - 2;
+        let batch = WireMsg::input_batch(start, vec![Input::new(1, 0); 5]);
+        let events = lockstep.on_net_message(batch.encode()).unwrap();
+        assert_eq!(
+            events,
+            vec![LockstepEvent::InputOverflow {
+                tick: INPUT_WINDOW as Tick,
+                dropped: 3
+            }]
+        );
+        assert_eq!(lockstep.get_buffer_info(), (0, 2));
+
+        assert_eq!(
+            lockstep.submit_local_inputs(&vec![Input::zero(); INPUT_WINDOW + 1]),
+            Err(LockstepError::InputOutsideWindow(INPUT_WINDOW as Tick))
+        );
+        assert_eq!(lockstep.get_buffer_info(), (0, 2));
+    }
+
+    #[test]
+    fn test_input_window_slots_remember_their_tick() {
+        let mut window = InputWindow::new();
+        assert!(window.insert(0, 3, Input::new(1, 0)));
+        assert!(!window.insert(10, 3, Input::new(2, 0)));
+
+        // The slot for tick 3 also serves 3 + INPUT_WINDOW, but never answers for it
+        let later = 3 + INPUT_WINDOW as Tick;
+        assert_eq!(window.get(later), None);
+        assert!(window.insert(10, later, Input::new(3, 0)));
+        assert_eq!(window.get(3), None);
+        assert_eq!(window.get(later), Some(Input::new(3, 0)));
+
+        // Consecutive across wraparound
+        assert!(window.insert(Tick::MAX, Tick::MAX, Input::zero()));
+        assert!(window.insert(Tick::MAX, 0, Input::zero()));
+        assert_eq!(window.len(), 3);
+        window.retain(|t| t != 0);
+        assert_eq!(window.ticks().count(), 2);
+    }
+
+    #[test]
+    fn test_frame_advantage() {
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        lockstep.start().unwrap();
+        assert_eq!(lockstep.frame_advantage(), 0);
+
+        // We queued six ticks of input while the peer has sent three
+        lockstep.submit_local_inputs(&[Input::zero(); 6]).unwrap();
+        let batch = WireMsg::input_batch(0, vec![Input::zero(); 3]);
+        lockstep.on_net_message(batch.encode()).unwrap();
+        assert_eq!(lockstep.frame_advantage(), 3);
+
+        // Late duplicates don't move the peer backwards
+        let stale = WireMsg::input_pair(1, Input::zero(), Input::zero());
+        lockstep.on_net_message(stale.encode()).unwrap();
+        assert_eq!(lockstep.frame_advantage(), 3);
+
+        for _ in 0..20 {
+            lockstep.tick().unwrap();
+        }
+        assert_eq!(lockstep.current_tick(), 3);
+        assert!(lockstep.average_frame_advantage() > 2.5);
+
+        // The peer catching up and overtaking us turns it negative
+        let ahead = WireMsg::input_batch(3, vec![Input::zero(); 5]);
+        lockstep.on_net_message(ahead.encode()).unwrap();
+        assert_eq!(lockstep.frame_advantage(), -2);
+    }
+
+    #[test]
+    fn test_lost_input_resent_on_request() {
+        let new_peer = |side: Side, is_timekeeper: bool| {
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(Game::new(Config::default())),
+                RecordingMockTransport::new(),
+                60,
+                side,
+                is_timekeeper,
+            );
+            lockstep.start().unwrap();
+            lockstep
+        };
+        let mut left = new_peer(Side::Left, true);
+        let mut right = new_peer(Side::Right, false);
+        deliver(&mut left, &mut right);
+        deliver(&mut right, &mut left);
+
+        // Left's inputs for ticks 0 and 1 are lost; only a copy of tick 1 arrives
+        left.submit_local_inputs(&[Input::new(5, 0), Input::new(7, 0)])
+            .unwrap();
+        left.transport.clear_sent_messages();
+        let late = WireMsg::input_pair(1, Input::new(7, 0), Input::zero());
+        right.on_net_message(late.encode()).unwrap();
+        right.on_local_input(0, 0).unwrap();
+        right.transport.clear_sent_messages();
+
+        // The gap prompts a request, repeated only after an interval
+        let now = right.clock.now();
+        right.tick_at(now).unwrap();
+        right.tick_at(now).unwrap();
+        let sent = right.transport.sent_messages();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(
+            WireMsg::decode(&sent[0]).unwrap(),
+            WireMsg::InputRequest { from_tick: 0 }
+        );
+
+        // Left answers from its retained inputs and right catches up
+        deliver(&mut right, &mut left);
+        assert_eq!(
+            WireMsg::decode(&left.transport.sent_messages()[0]).unwrap(),
+            WireMsg::input_batch(0, vec![Input::new(5, 0), Input::new(7, 0)])
+        );
+        deliver(&mut left, &mut right);
+        right.tick_at(now).unwrap();
+        assert_eq!(right.current_tick(), 1);
+    }
+
+    #[test]
+    fn test_desync_resynced_from_timekeeper() {
+        let new_peer = |side: Side| {
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(Game::new(Config::default())),
+                RecordingMockTransport::new(),
+                60,
+                side,
+                side == Side::Left,
+            );
+            lockstep.set_hash_interval(4);
+            lockstep.start().unwrap();
+            lockstep
+        };
+        let mut host = new_peer(Side::Left);
+        let mut guest = new_peer(Side::Right);
+
+        let mut host_events = Vec::new();
+        let mut guest_events = Vec::new();
+        for tick in 0..16 {
+            if tick == 6 {
+                // Something (a bug, a bad restore) nudges the guest's state
+                guest.core.game_mut().score = [3, 0];
+            }
+            host.on_local_input(1, 0).unwrap();
+            guest.on_local_input(-1, 0).unwrap();
+            host_events.extend(deliver(&mut guest, &mut host));
+            guest_events.extend(deliver(&mut host, &mut guest));
+            host_events.extend(host.tick().unwrap());
+            guest_events.extend(guest.tick().unwrap());
+            host_events.extend(deliver(&mut guest, &mut host));
+            guest_events.extend(deliver(&mut host, &mut guest));
+        }
+
+        // The guest noticed at the tick-8 hash, asked, and adopted the host's state
+        assert!(guest_events
+            .iter()
+            .any(|e| matches!(e, LockstepEvent::DesyncDetected { tick: 8, .. })));
+        assert!(host_events
+            .iter()
+            .any(|e| matches!(e, LockstepEvent::SnapshotRequested { .. })));
+        assert!(guest_events
+            .iter()
+            .any(|e| matches!(e, LockstepEvent::Resynced { .. })));
+
+        // Play carried on in agreement: no further desync, same state
+        assert_eq!(guest.desync_tick(), None);
+        assert_eq!(host.desync_tick(), None);
+        assert_eq!(guest.current_tick(), host.current_tick());
+        assert_eq!(guest.core.snapshot(), host.core.snapshot());
+        assert_eq!(guest.core.game().score, [0, 0]);
+    }
+
+    #[test]
+    fn test_peer_timeout_suspends() {
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        lockstep.set_peer_timeout(Some(Duration::from_millis(500)));
+        lockstep.start().unwrap();
+
+        // Within the timeout nothing happens
+        let mut events = Vec::new();
+        let start = lockstep.clock.now();
+        lockstep.check_peer_timeout(start + Duration::from_millis(100), &mut events);
+        assert!(events.is_empty());
+
+        // Past it, the disconnect is reported once and ticks hold
+        let later = start + Duration::from_secs(2);
+        lockstep.check_peer_timeout(later, &mut events);
+        lockstep.check_peer_timeout(later, &mut events);
+        assert_eq!(events, vec![LockstepEvent::PeerDisconnected]);
+        assert!(lockstep.is_suspended());
+
+        lockstep.on_local_input(0, 0).unwrap();
+        let remote = WireMsg::input_pair(0, Input::zero(), Input::zero());
+        let events = lockstep.on_net_message(remote.encode()).unwrap();
+        assert_eq!(events, vec![LockstepEvent::PeerReconnected]);
+        assert!(!lockstep.is_suspended());
+
+        lockstep.tick().unwrap();
+        assert_eq!(lockstep.current_tick(), 1);
+    }
+
+    #[test]
+    fn test_connection_lost_suspends() {
+        let transport = RecordingMockTransport::new();
+        let mut handle = transport.clone();
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            transport,
+            60,
+            Side::Left,
+            true,
+        );
+        lockstep.start().unwrap();
+        lockstep.on_local_input(0, 0).unwrap();
+
+        // Reported on the next tick, without waiting for a peer timeout
+        handle.set_open(false);
+        let events = lockstep.tick().unwrap();
+        assert_eq!(
+            events,
+            vec![LockstepEvent::ConnectionLost {
+                reason: CloseReason::Remote
+            }]
+        );
+        assert!(lockstep.is_suspended());
+        assert!(lockstep.tick().unwrap().is_empty());
+
+        // A new connection is watched in turn; closing it ourselves isn't a loss
+        let mut replacement = RecordingMockTransport::new();
+        lockstep.reattach(replacement.clone()).unwrap();
+        replacement.close().unwrap();
+        assert!(lockstep.lost_connection().is_none());
+    }
+
+    #[test]
+    fn test_ai_takes_over_disconnected_peer() {
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        lockstep.set_peer_timeout(Some(Duration::from_millis(500)));
+        lockstep.set_ai_takeover(true);
+        lockstep.start().unwrap();
+
+        // The peer's last input before dropping out is still used
+        let remote = WireMsg::input_pair(0, Input::zero(), Input::new(60, 0));
+        lockstep.on_net_message(remote.encode()).unwrap();
+        lockstep.on_local_input(0, 0).unwrap();
+        lockstep.tick().unwrap();
+        assert_eq!(lockstep.current_tick(), 1);
+
+        let later = lockstep.clock.now() + Duration::from_secs(2);
+        lockstep.on_local_input(0, 0).unwrap();
+        let events = lockstep.tick_at(later).unwrap();
+        assert_eq!(
+            events[..2],
+            [
+                LockstepEvent::PeerDisconnected,
+                LockstepEvent::AiTookOver { tick: 1 }
+            ]
+        );
+        assert!(lockstep.is_ai_controlled());
+        assert!(!lockstep.is_suspended());
+        assert_eq!(lockstep.current_tick(), 2);
+
+        // The match runs on local input alone, and the peer can't come back
+        lockstep.transport.clear_sent_messages();
+        for _ in 0..10 {
+            lockstep.on_local_input(0, 0).unwrap();
+            lockstep.tick_at(later).unwrap();
+        }
+        assert_eq!(lockstep.current_tick(), 12);
+        assert!(lockstep.transport.sent_messages().is_empty());
+        let late = WireMsg::input_pair(12, Input::zero(), Input::zero());
+        assert!(lockstep.on_net_message(late.encode()).unwrap().is_empty());
+        assert!(lockstep.is_ai_controlled());
+    }
+
+    #[test]
+    fn test_best_of_series() {
+        let rules = Config {
+            max_score: 1,
+            ..Config::default()
+        };
+        let new_peer = |side: Side, is_timekeeper: bool| {
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(Game::new(rules)),
+                RecordingMockTransport::new(),
+                60,
+                side,
+                is_timekeeper,
+            );
+            lockstep.set_best_of(3);
+            lockstep.start().unwrap();
+            lockstep
+        };
+        let mut left = new_peer(Side::Left, true);
+        let mut right = new_peer(Side::Right, false);
+        deliver(&mut left, &mut right);
+        deliver(&mut right, &mut left);
+
+        // Right camps at one edge, so games end quickly
+        let mut left_events = Vec::new();
+        let mut right_events = Vec::new();
+        while left.series().and_then(|series| series.winner()).is_none() {
+            assert!(left.current_tick() < 20_000, "series never finished");
+            left.submit_local_input(Input::new(0, Buttons::READY))
+                .unwrap();
+            right
+                .submit_local_input(Input::new(-127, Buttons::READY))
+                .unwrap();
+            right_events.extend(deliver(&mut left, &mut right));
+            left_events.extend(deliver(&mut right, &mut left));
+            left_events.extend(left.tick().unwrap());
+            right_events.extend(right.tick().unwrap());
+        }
+        assert_eq!(left.view(), right.view());
+
+        // Both peers saw the same series, game by game
+        let series_events = |events: Vec<LockstepEvent>| -> Vec<LockstepEvent> {
+            events
+                .into_iter()
+                .filter(|event| {
+                    matches!(
+                        event,
+                        LockstepEvent::GameOver { .. }
+                            | LockstepEvent::SeriesOver { .. }
+                            | LockstepEvent::NextGame { .. }
+                    )
+                })
+                .collect()
+        };
+        let left_events = series_events(left_events);
+        assert_eq!(left_events, series_events(right_events));
+        let LockstepEvent::GameOver { winner, series } = left_events[0] else {
+            panic!("expected GameOver, got {:?}", left_events[0]);
+        };
+        assert_eq!(series.iter().sum::<u8>(), 1);
+        match left_events[1] {
+            LockstepEvent::NextGame {
+                game,
+                server,
+                series: standing,
+                ..
+            } => {
+                // The loser serves the next game
+                assert_eq!((game, server, standing), (2, winner.opposite(), series));
+            }
+            ref other => panic!("expected NextGame, got {other:?}"),
+        }
+        let Some(&LockstepEvent::SeriesOver { winner, series }) = left_events.last() else {
+            panic!("expected SeriesOver, got {left_events:?}");
+        };
+        assert_eq!(series[winner], 2);
+        let series = left.series().unwrap();
+        assert_eq!(series.game, series.wins.iter().sum::<u8>());
+
+        // A NextGame for a tick already simulated can't be honored
+        let late = WireMsg::NextGame {
+            start_tick: 0,
+            seed: 1,
+            server: Side::Left,
+        };
+        assert!(matches!(
+            right.on_net_message(late.encode()),
+            Err(LockstepError::SyncError(_))
+        ));
+    }
+
+    #[test]
+    fn test_shutdown_says_bye() {
+        let new_peer = |side: Side, is_timekeeper: bool| {
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(Game::new(Config::default())),
+                RecordingMockTransport::new(),
+                60,
+                side,
+                is_timekeeper,
+            );
+            lockstep.start().unwrap();
+            lockstep
+        };
+        let mut left = new_peer(Side::Left, true);
+        let mut right = new_peer(Side::Right, false);
+        deliver(&mut left, &mut right);
+        deliver(&mut right, &mut left);
+
+        // Input submitted just before leaving still reaches the peer
+        left.on_local_input(30, 0).unwrap();
+        left.transport.clear_sent_messages();
+        left.shutdown().unwrap();
+        assert!(left.is_shutting_down());
+        assert!(left.tick().unwrap().is_empty());
+        assert_eq!(left.current_tick(), 0);
+
+        let events = deliver(&mut left, &mut right);
+        assert_eq!(events, vec![LockstepEvent::SessionEnded { by_peer: true }]);
+        assert!(!right.transport.is_open());
+        assert_eq!(right.get_buffer_info(), (0, 0));
+
+        let events = deliver(&mut right, &mut left);
+        assert_eq!(events, vec![LockstepEvent::SessionEnded { by_peer: false }]);
+        assert!(!left.is_shutting_down());
+        assert!(!left.transport.is_open());
+        assert!(matches!(left.shutdown(), Err(LockstepError::NotRunning)));
+
+        // Without an answer, the wait gives up after the timeout
+        let mut left = new_peer(Side::Left, true);
+        let mut right = new_peer(Side::Right, false);
+        deliver(&mut right, &mut left);
+        left.shutdown().unwrap();
+        let start = left.clock.now();
+        assert!(left.tick_at(start).unwrap().is_empty());
+        assert_eq!(
+            left.tick_at(start + SHUTDOWN_TIMEOUT).unwrap(),
+            vec![LockstepEvent::SessionEnded { by_peer: false }]
+        );
+
+        // A peer that never said Hello isn't waited for at all
+        right.shutdown().unwrap();
+        assert!(right
+            .transport
+            .sent_messages()
+            .iter()
+            .all(|bytes| { !matches!(WireMsg::decode(bytes), Ok(WireMsg::Bye)) }));
+        assert_eq!(
+            right.tick().unwrap(),
+            vec![LockstepEvent::SessionEnded { by_peer: false }]
+        );
+    }
+
+    #[test]
+    fn test_stall_reports_waiting_and_resumed() {
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        lockstep.start().unwrap();
+        lockstep.on_local_input(0, 0).unwrap();
+
+        let start = lockstep.clock.now();
+        assert!(lockstep.tick_at(start).unwrap().is_empty());
+        let brief = start + Duration::from_millis(100);
+        assert!(lockstep.tick_at(brief).unwrap().is_empty());
+
+        // Past the threshold the wait is reported once
+        let later = start + Duration::from_millis(400);
+        assert_eq!(
+            lockstep.tick_at(later).unwrap(),
+            vec![LockstepEvent::WaitingForRemote {
+                missing_tick: 0,
+                waited_ms: 400
+            }]
+        );
+        assert!(lockstep
+            .tick_at(later + Duration::from_millis(100))
+            .unwrap()
+            .is_empty());
+
+        let remote = WireMsg::input_pair(0, Input::zero(), Input::zero());
+        lockstep.on_net_message(remote.encode()).unwrap();
+        let events = lockstep.tick_at(start + Duration::from_secs(1)).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                LockstepEvent::GameAdvanced {
+                    tick: 0,
+                    events: vec![]
+                },
+                LockstepEvent::Resumed { waited_ms: 1000 }
+            ]
+        );
+        assert_eq!(lockstep.current_tick(), 1);
+    }
+
+    #[test]
+    fn test_network_stats() {
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        lockstep.start().unwrap();
+        assert_eq!(lockstep.network_stats(), NetworkStats::default());
+
+        for rtt in [40, 60, 50, 70] {
+            lockstep.record_rtt(rtt);
+        }
+        let stats = lockstep.network_stats();
+        assert_eq!(stats.rtt_samples, 4);
+        assert_eq!(stats.last_rtt_ms, 70);
+        assert_eq!(stats.avg_rtt_ms, 55);
+        assert_eq!((stats.min_rtt_ms, stats.max_rtt_ms), (40, 70));
+        assert_eq!(stats.jitter_ms, 16); // (20 + 10 + 20) / 3
+
+        // Only the most recent samples count
+        for _ in 0..NETWORK_STATS_WINDOW {
+            lockstep.record_rtt(30);
+        }
+        let stats = lockstep.network_stats();
+        assert_eq!(stats.rtt_samples, NETWORK_STATS_WINDOW as u32);
+        assert_eq!((stats.min_rtt_ms, stats.max_rtt_ms), (30, 30));
+        assert_eq!(stats.jitter_ms, 0);
+
+        // Each received message adds an arrival gap
+        lockstep
+            .on_net_message(WireMsg::Emote(emotes::GG).encode())
+            .unwrap();
+        assert_eq!(lockstep.gap_window.len(), 1);
+        assert_eq!(lockstep.network_stats().max_gap_ms, lockstep.gap_window[0]);
+    }
+
+    #[test]
+    fn test_clock_sync_paces_non_timekeeper() {
+        let new_peer = |is_timekeeper| {
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(Game::new(Config::default())),
+                RecordingMockTransport::new(),
+                50,
+                Side::Right,
+                is_timekeeper,
+            );
+            lockstep.start().unwrap();
+            lockstep
+        };
+        let nominal = Duration::from_millis(20);
+
+        // The peer's inputs pile up ahead of us: speed up
+        let mut guest = new_peer(false);
+        let mut host = new_peer(true);
+        for lockstep in [&mut guest, &mut host] {
+            let batch = WireMsg::input_batch(0, vec![Input::zero(); 40]);
+            lockstep.on_net_message(batch.encode()).unwrap();
+            for _ in 0..20 {
+                lockstep.on_local_input(0, 0).unwrap();
+                lockstep.tick().unwrap();
+            }
+        }
+        assert!(guest.remote_lead() > 2.0);
+        assert_eq!(guest.tick_interval(), nominal * 98 / 100);
+        assert_eq!(host.tick_interval(), nominal); // The timekeeper sets the pace
+
+        // We keep waiting on the peer: slow down
+        let mut guest = new_peer(false);
+        for _ in 0..20 {
+            guest.on_local_input(0, 0).unwrap();
+            guest.tick().unwrap();
+        }
+        assert!(guest.remote_lead() < 0.5);
+        assert_eq!(guest.tick_interval(), nominal * 102 / 100);
+
+        guest.set_clock_sync(false);
+        assert_eq!(guest.tick_interval(), nominal);
+    }
+
+    #[test]
+    fn test_ping_pong_round_trip() {
+        let game = Game::new(Config::default());
+        let adapter = GameAdapter::new(game);
+        let transport = RecordingMockTransport::new();
+
+        let mut lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+        lockstep.start().unwrap();
+
+        // A Ping is answered with a Pong, not another Ping
+        let events = lockstep
+            .on_net_message(WireMsg::ping(1234).encode())
+            .unwrap();
+        assert!(events.is_empty());
+        let sent = lockstep.transport.sent_messages();
+        assert_eq!(
+            WireMsg::decode(sent.last().unwrap()).unwrap(),
+            WireMsg::pong(1234)
+        );
+
+        // Our own Ping coming back as a Pong yields the round trip
+        lockstep.ping().unwrap();
+        let ping = lockstep.transport.sent_messages().last().unwrap().clone();
+        let WireMsg::Ping(timestamp) = WireMsg::decode(&ping).unwrap() else {
+            panic!("Expected Ping message");
+        };
+        let events = lockstep
+            .on_net_message(WireMsg::pong(timestamp).encode())
+            .unwrap();
+        match events.as_slice() {
+            [LockstepEvent::PongReceived { round_trip_ms }] => assert!(*round_trip_ms < 1000),
+            other => panic!("Expected PongReceived, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_timestamped_inputs() {
+        let transport = RecordingMockTransport::new();
+        let adapter = GameAdapter::new(Game::new(Config::default()));
+        let mut sender = Lockstep::new(adapter, transport, 60, Side::Left, true);
+        sender.start().unwrap();
+        sender.set_input_timestamps(true);
+        sender.submit_local_input(Input::new(30, 1)).unwrap();
+
+        let sent = sender.transport.sent_messages();
+        let timed = sent.last().unwrap().clone();
+        match WireMsg::decode(&timed).unwrap() {
+            WireMsg::TimedInputPair { pair, .. } => assert_eq!(pair.a, Input::new(30, 1)),
+            other => panic!("Expected TimedInputPair, got {other:?}"),
+        }
+
+        let transport = RecordingMockTransport::new();
+        let adapter = GameAdapter::new(Game::new(Config::default()));
+        let mut receiver = Lockstep::new(adapter, transport, 60, Side::Right, false);
+        receiver.start().unwrap();
+        assert_eq!(receiver.input_delay_stats(), InputDelayStats::default());
+
+        receiver.on_net_message(timed).unwrap();
+        receiver.submit_local_input(Input::zero()).unwrap();
+        receiver.tick().unwrap();
+        assert_eq!(receiver.current_tick(), 1); // The stamped input was used
+
+        // Same process, same clock: a small non-negative delay, no outbound estimate yet
+        let stats = receiver.input_delay_stats();
+        assert_eq!(stats.samples, 1);
+        assert!((0..1000).contains(&stats.last_ms));
+        assert_eq!(stats.outbound_ms, None);
+
+        receiver
+            .on_net_message(WireMsg::pong(receiver.clock.wall_ms()).encode())
+            .unwrap();
+        assert!(receiver.input_delay_stats().outbound_ms.is_some());
+    }
+
+    #[test]
+    fn test_snapshot_request_answered() {
+        let mut behind = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            Side::Right,
+            false,
+        );
+        behind.start().unwrap();
+        behind.request_snapshot().unwrap();
+        let request = behind.transport.sent_messages().last().unwrap().clone();
+        assert_eq!(
+            WireMsg::decode(&request).unwrap(),
+            WireMsg::SnapshotRequest { since_tick: 0 }
+        );
+
+        // The peer ahead of us answers with its own state
+        let mut game = Game::new(Config::default());
+        for tick in 0..5 {
+            game.step(&InputPair::new(tick, Input::zero(), Input::zero()));
+        }
+        let mut ahead = Lockstep::new(
+            GameAdapter::new(game),
+            RecordingMockTransport::new(),
+            60,
+            Side::Left,
+            true,
+        );
+        ahead.start().unwrap();
+        assert_eq!(
+            ahead.on_net_message(request).unwrap(),
+            vec![LockstepEvent::SnapshotRequested { since_tick: 0 }]
+        );
+
+        let reply = ahead.transport.sent_messages().last().unwrap().clone();
+        assert_eq!(
+            behind.on_net_message(reply).unwrap(),
+            vec![LockstepEvent::SnapshotReceived { tick: 5 }]
+        );
+        assert_eq!(behind.current_tick(), 5);
+    }
+
+    #[test]
+    fn test_hello_negotiation() {
+        let game = Game::new(Config::default());
+        let adapter = GameAdapter::new(game);
+        let transport = RecordingMockTransport::new();
+
+        let mut lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+        lockstep.start().unwrap();
+        assert_eq!(lockstep.peer_version(), None);
+
+        // A newer peer is downgraded to our version
+        let hello = WireMsg::Hello {
+            version: PROTOCOL_VERSION + 1,
+            capabilities: 0b101,
+        };
+        let events = lockstep.on_net_message(hello.encode()).unwrap();
+        assert_eq!(
+            events,
+            vec![LockstepEvent::PeerHello {
+                version: PROTOCOL_VERSION,
+                capabilities: 0b101
+            }]
+        );
+        assert_eq!(lockstep.peer_version(), Some(PROTOCOL_VERSION));
+        assert_eq!(lockstep.peer_capabilities(), 0b101);
+        assert!(lockstep.peer_supports(capabilities::INPUT_TILT));
+        assert!(!lockstep.peer_supports(capabilities::INPUT_TILT | capabilities::INPUT_AXIS_X));
+
+        // A peer older than we support is rejected
+        let ancient = WireMsg::Hello {
+            version: 0,
+            capabilities: 0,
+        };
+        assert_eq!(
+            lockstep.on_net_message(ancient.encode()),
+            Err(LockstepError::VersionMismatch {
+                local: PROTOCOL_VERSION,
+                remote: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_buffer_info() {
+        let game = Game::new(Config::default());
+        let adapter = GameAdapter::new(game);
+        let transport = RecordingMockTransport::new();
+
+        let mut lockstep = Lockstep::new(adapter, transport, 60, Side::Left, true);
+        lockstep.start().unwrap();
+
+        let (local_count, remote_count) = lockstep.get_buffer_info();
+        assert_eq!(local_count, 0);
+        assert_eq!(remote_count, 0);
+
+        // Add some inputs
+        lockstep.on_local_input(10, 0).unwrap();
+
+        let (local_count, remote_count) = lockstep.get_buffer_info();
+        assert_eq!(local_count, 1);
+        assert_eq!(remote_count, 0);
+    }
+}
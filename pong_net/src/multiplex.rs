@@ -0,0 +1,467 @@
+//! Logical channels sharing one transport.
+//!
+//! Every frame starts with a channel byte: the channel id in the low seven
+//! bits, and the high bit set on all but the last fragment of a message.
+//! Immediate channels (inputs, chat) send whole messages straight away.
+//! Bulk channels (snapshots, replays) split messages into chunks and queue
+//! them, and `Multiplexer::poll` sends a limited number of bytes per call, so
+//! a large transfer never sits ahead of an input in the underlying stream.
+
+use crate::transport::{
+    lock, wall_ms, CloseReason, Lifecycle, Transport, TransportError, TransportMetrics,
+};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Conventional channel for game inputs and session control
+pub const INPUT_CHANNEL: u8 = 0;
+/// Conventional channel for chat
+pub const CHAT_CHANNEL: u8 = 1;
+/// Conventional channel for snapshots and replays
+pub const BULK_CHANNEL: u8 = 2;
+
+/// Highest usable channel id
+pub const MAX_CHANNEL: u8 = 0x7F;
+
+/// Channel byte bit marking a fragment with more to follow
+const MORE_FRAGMENTS: u8 = 0x80;
+
+/// Largest message a channel reassembles, so a hostile peer can't balloon it
+pub const MAX_MESSAGE_SIZE: usize = 1 << 20;
+
+type MessageCallback = Arc<dyn Fn(Vec<u8>) + Send + Sync>;
+
+/// How a channel's sends reach the transport
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Sent whole, as soon as `send` is called
+    Immediate,
+    /// Chunked and queued, sent from `Multiplexer::poll`
+    Bulk,
+}
+
+/// Chunking and pacing for bulk channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MuxConfig {
+    /// Largest bulk payload per frame
+    pub chunk_size: usize,
+    /// Bulk bytes `poll` sends per call (at least one chunk always goes)
+    pub bulk_bytes_per_poll: usize,
+}
+
+impl Default for MuxConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size: 1024,
+            bulk_bytes_per_poll: 16 * 1024,
+        }
+    }
+}
+
+/// One channel's receive side
+#[derive(Default)]
+struct ChannelState {
+    /// A `MuxChannel` currently owns this id
+    open: bool,
+    on_message: Option<MessageCallback>,
+    /// Messages that arrived before a callback was set
+    pending: VecDeque<Vec<u8>>,
+    /// Fragments of the message being reassembled
+    partial: Vec<u8>,
+    /// The partial message outgrew `MAX_MESSAGE_SIZE`; skip to its end
+    discarding: bool,
+    metrics: TransportMetrics,
+    lifecycle: Lifecycle,
+}
+
+/// State shared by the multiplexer, its channels and the inner callback
+struct Shared<T: Transport> {
+    inner: Mutex<T>,
+    channels: Arc<Mutex<HashMap<u8, ChannelState>>>,
+    /// Bulk frames waiting for `poll`, each message's fragments contiguous
+    bulk: Mutex<VecDeque<Vec<u8>>>,
+    config: Mutex<MuxConfig>,
+}
+
+/// Splits one transport into channels.
+///
+/// Both ends need a `Multiplexer`. Messages for a channel the receiving end
+/// hasn't opened yet are held until it does. Nothing runs in the background:
+/// call `poll` regularly (e.g. every frame) to move bulk traffic.
+pub struct Multiplexer<T: Transport> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Transport> Multiplexer<T> {
+    pub fn new(mut inner: T, config: MuxConfig) -> Self {
+        let channels = Arc::new(Mutex::new(HashMap::<u8, ChannelState>::new()));
+        let sink = Arc::clone(&channels);
+        inner.set_on_message(Box::new(move |bytes| {
+            let Some((&header, payload)) = bytes.split_first() else {
+                return;
+            };
+            let mut channels = lock(&sink);
+            let channel = channels.entry(header & !MORE_FRAGMENTS).or_default();
+            if !channel.discarding {
+                channel.partial.extend_from_slice(payload);
+                if channel.partial.len() > MAX_MESSAGE_SIZE {
+                    channel.partial = Vec::new();
+                    channel.discarding = true;
+                }
+            }
+            if header & MORE_FRAGMENTS != 0 {
+                return;
+            }
+            if std::mem::take(&mut channel.discarding) {
+                return;
+            }
+
+            let message = std::mem::take(&mut channel.partial);
+            channel.metrics.record_received(message.len(), wall_ms());
+            let Some(callback) = channel.on_message.clone() else {
+                channel.pending.push_back(message);
+                return;
+            };
+            // Released first so the callback may send without deadlocking
+            drop(channels);
+            callback(message);
+        }));
+        let ended = Arc::clone(&channels);
+        inner.set_on_close(Box::new(move |reason| {
+            for channel in lock(&ended).values_mut().filter(|channel| channel.open) {
+                channel.lifecycle.closed(reason.clone());
+            }
+        }));
+
+        Self {
+            shared: Arc::new(Shared {
+                inner: Mutex::new(inner),
+                channels,
+                bulk: Mutex::new(VecDeque::new()),
+                config: Mutex::new(config),
+            }),
+        }
+    }
+
+    /// Open channel `id` (up to `MAX_CHANNEL`), handing over any messages
+    /// already received on it once a callback is set
+    pub fn channel(&self, id: u8, priority: Priority) -> Result<MuxChannel<T>, TransportError> {
+        if id > MAX_CHANNEL {
+            return Err(TransportError::InvalidConfig(format!(
+                "Channel id {id} above {MAX_CHANNEL}"
+            )));
+        }
+        let lifecycle = match self.is_open() {
+            true => Lifecycle::open_now(),
+            false => Lifecycle::default(),
+        };
+        let mut channels = lock(&self.shared.channels);
+        let channel = channels.entry(id).or_default();
+        if channel.open {
+            return Err(TransportError::InvalidConfig(format!(
+                "Channel {id} already open"
+            )));
+        }
+        channel.open = true;
+        channel.lifecycle = lifecycle;
+        Ok(MuxChannel {
+            id,
+            priority,
+            shared: Arc::clone(&self.shared),
+            closed: false,
+        })
+    }
+
+    /// Change chunking and pacing; chunks already queued keep their size
+    pub fn set_config(&self, config: MuxConfig) {
+        *lock(&self.shared.config) = config;
+    }
+
+    /// Send queued bulk frames, up to the per-poll byte budget
+    pub fn poll(&self) {
+        let budget = lock(&self.shared.config).bulk_bytes_per_poll;
+        let inner = lock(&self.shared.inner);
+        let mut bulk = lock(&self.shared.bulk);
+        let mut sent = 0;
+        while let Some(frame) = bulk.front() {
+            if sent > 0 && sent + frame.len() > budget {
+                break;
+            }
+            if inner.send(frame).is_err() {
+                // Left queued; a closed transport is the caller's to notice
+                break;
+            }
+            sent += frame.len();
+            bulk.pop_front();
+        }
+    }
+
+    /// Bulk bytes (including channel bytes) waiting for `poll`
+    pub fn queued_bytes(&self) -> usize {
+        lock(&self.shared.bulk).iter().map(Vec::len).sum()
+    }
+
+    pub fn is_open(&self) -> bool {
+        lock(&self.shared.inner).is_open()
+    }
+
+    /// Close the underlying transport, and with it every channel
+    pub fn close(&self) -> Result<(), TransportError> {
+        lock(&self.shared.bulk).clear();
+        lock(&self.shared.inner).close()
+    }
+
+    /// The underlying transport's traffic, all channels together
+    pub fn metrics(&self) -> TransportMetrics {
+        lock(&self.shared.inner).metrics()
+    }
+}
+
+/// One logical channel of a `Multiplexer`, usable anywhere a transport is
+pub struct MuxChannel<T: Transport> {
+    id: u8,
+    priority: Priority,
+    shared: Arc<Shared<T>>,
+    closed: bool,
+}
+
+impl<T: Transport> MuxChannel<T> {
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Give the id back, dropping the callback and anything undelivered
+    fn release(&mut self) {
+        if std::mem::replace(&mut self.closed, true) {
+            return;
+        }
+        if let Some(channel) = lock(&self.shared.channels).get_mut(&self.id) {
+            channel.open = false;
+            channel.on_message = None;
+            channel.pending.clear();
+            channel.lifecycle.closed(CloseReason::Local);
+        }
+    }
+
+    fn record(&self, result: &Result<(), TransportError>, len: usize) {
+        let mut channels = lock(&self.shared.channels);
+        let metrics = &mut channels.entry(self.id).or_default().metrics;
+        match result {
+            Ok(()) => metrics.record_sent(len, wall_ms()),
+            Err(_) => metrics.record_send_error(),
+        }
+    }
+}
+
+impl<T: Transport> Transport for MuxChannel<T> {
+    fn send(&self, bytes: &[u8]) -> Result<(), TransportError> {
+        if !self.is_open() {
+            return Err(TransportError::NotConnected);
+        }
+
+        let result = match self.priority {
+            Priority::Immediate => {
+                let mut frame = Vec::with_capacity(1 + bytes.len());
+                frame.push(self.id);
+                frame.extend_from_slice(bytes);
+                lock(&self.shared.inner).send(&frame)
+            }
+            Priority::Bulk => {
+                let chunk_size = lock(&self.shared.config).chunk_size.max(1);
+                let mut bulk = lock(&self.shared.bulk);
+                let mut chunks = bytes.chunks(chunk_size).peekable();
+                if chunks.peek().is_none() {
+                    bulk.push_back(vec![self.id]);
+                }
+                while let Some(chunk) = chunks.next() {
+                    let header = match chunks.peek() {
+                        Some(_) => self.id | MORE_FRAGMENTS,
+                        None => self.id,
+                    };
+                    let mut frame = Vec::with_capacity(1 + chunk.len());
+                    frame.push(header);
+                    frame.extend_from_slice(chunk);
+                    bulk.push_back(frame);
+                }
+                Ok(())
+            }
+        };
+        self.record(&result, bytes.len());
+        result
+    }
+
+    fn set_on_message(&mut self, callback: Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>) {
+        // Held messages are delivered unlocked, so the callback may send (which
+        // records metrics under the same lock)
+        loop {
+            let mut channels = lock(&self.shared.channels);
+            let channel = channels.entry(self.id).or_default();
+            if channel.pending.is_empty() {
+                channel.on_message = Some(Arc::from(callback));
+                return;
+            }
+            let held: Vec<_> = channel.pending.drain(..).collect();
+            drop(channels);
+            for bytes in held {
+                callback(bytes);
+            }
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        !self.closed && lock(&self.shared.inner).is_open()
+    }
+
+    /// Close this channel only; the transport and other channels stay up
+    fn close(&mut self) -> Result<(), TransportError> {
+        if self.closed {
+            return Err(TransportError::AlreadyClosed);
+        }
+        self.release();
+        Ok(())
+    }
+
+    fn status(&self) -> String {
+        format!(
+            "{} (channel {})",
+            lock(&self.shared.inner).status(),
+            self.id
+        )
+    }
+
+    /// This channel's messages (queued bulk messages count once queued)
+    fn metrics(&self) -> TransportMetrics {
+        lock(&self.shared.channels)
+            .get(&self.id)
+            .map(|channel| channel.metrics)
+            .unwrap_or_default()
+    }
+
+    fn set_on_open(&mut self, callback: Box<dyn Fn() + Send + Sync + 'static>) {
+        let mut channels = lock(&self.shared.channels);
+        channels
+            .entry(self.id)
+            .or_default()
+            .lifecycle
+            .set_on_open(callback);
+    }
+
+    /// Fires `Local` when this channel closes, or with the transport's reason
+    /// when the transport does
+    fn set_on_close(&mut self, callback: Box<dyn Fn(CloseReason) + Send + Sync + 'static>) {
+        let mut channels = lock(&self.shared.channels);
+        channels
+            .entry(self.id)
+            .or_default()
+            .lifecycle
+            .set_on_close(callback);
+    }
+}
+
+impl<T: Transport> Drop for MuxChannel<T> {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::LoopbackTransport;
+
+    fn collect(channel: &mut MuxChannel<LoopbackTransport>) -> Arc<Mutex<Vec<Vec<u8>>>> {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        channel.set_on_message(Box::new(move |bytes| sink.lock().unwrap().push(bytes)));
+        received
+    }
+
+    #[test]
+    fn test_channels_stay_separate() {
+        let (left, right) = LoopbackTransport::pair();
+        let left = Multiplexer::new(left, MuxConfig::default());
+        let right = Multiplexer::new(right, MuxConfig::default());
+
+        let input = left.channel(INPUT_CHANNEL, Priority::Immediate).unwrap();
+        let chat = left.channel(CHAT_CHANNEL, Priority::Immediate).unwrap();
+        input.send(b"up").unwrap();
+        chat.send(b"gg").unwrap();
+
+        // Opened after the messages arrived; they were held
+        let mut right_chat = right.channel(CHAT_CHANNEL, Priority::Immediate).unwrap();
+        let mut right_input = right.channel(INPUT_CHANNEL, Priority::Immediate).unwrap();
+        let chat_received = collect(&mut right_chat);
+        let input_received = collect(&mut right_input);
+        assert_eq!(*chat_received.lock().unwrap(), vec![b"gg".to_vec()]);
+        assert_eq!(*input_received.lock().unwrap(), vec![b"up".to_vec()]);
+        assert_eq!(input.metrics().messages_sent, 1);
+        assert_eq!(right_chat.metrics().bytes_received, 2);
+
+        assert!(matches!(
+            left.channel(INPUT_CHANNEL, Priority::Immediate),
+            Err(TransportError::InvalidConfig(_))
+        ));
+        assert!(left.channel(MAX_CHANNEL + 1, Priority::Bulk).is_err());
+
+        // Closing a channel frees its id and leaves the others working
+        let mut chat = chat;
+        let chat_closed = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&chat_closed);
+        chat.set_on_close(Box::new(move |reason| sink.lock().unwrap().push(reason)));
+        chat.close().unwrap();
+        assert_eq!(*chat_closed.lock().unwrap(), vec![CloseReason::Local]);
+        assert_eq!(chat.send(b"x"), Err(TransportError::NotConnected));
+        assert_eq!(chat.close(), Err(TransportError::AlreadyClosed));
+        assert!(left.channel(CHAT_CHANNEL, Priority::Immediate).is_ok());
+        input.send(b"down").unwrap();
+        assert_eq!(input_received.lock().unwrap().len(), 2);
+
+        // The transport closing closes every channel
+        let input_closed = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&input_closed);
+        right_input.set_on_close(Box::new(move |reason| sink.lock().unwrap().push(reason)));
+        left.close().unwrap();
+        assert_eq!(*input_closed.lock().unwrap(), vec![CloseReason::Remote]);
+        assert!(!right_input.is_open());
+    }
+
+    #[test]
+    fn test_bulk_does_not_delay_input() {
+        let (left, right) = LoopbackTransport::pair();
+        let config = MuxConfig {
+            chunk_size: 100,
+            bulk_bytes_per_poll: 250,
+        };
+        let left = Multiplexer::new(left, config);
+        let right = Multiplexer::new(right, MuxConfig::default());
+        let input = left.channel(INPUT_CHANNEL, Priority::Immediate).unwrap();
+        let bulk = left.channel(BULK_CHANNEL, Priority::Bulk).unwrap();
+        let mut right_input = right.channel(INPUT_CHANNEL, Priority::Immediate).unwrap();
+        let mut right_bulk = right.channel(BULK_CHANNEL, Priority::Bulk).unwrap();
+        let inputs = collect(&mut right_input);
+        let snapshots = collect(&mut right_bulk);
+
+        let snapshot: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+        bulk.send(&snapshot).unwrap();
+        bulk.send(&[]).unwrap();
+        assert_eq!(left.queued_bytes(), 1000 + 10 + 1);
+
+        // Inputs go out ahead of the queued snapshot
+        input.send(b"up").unwrap();
+        assert_eq!(inputs.lock().unwrap().len(), 1);
+        assert!(snapshots.lock().unwrap().is_empty());
+
+        // Two 101-byte frames fit the budget per poll
+        left.poll();
+        assert_eq!(left.queued_bytes(), 1011 - 202);
+        for _ in 0..5 {
+            left.poll();
+        }
+        assert_eq!(left.queued_bytes(), 0);
+        assert_eq!(*snapshots.lock().unwrap(), vec![snapshot, Vec::new()]);
+    }
+}
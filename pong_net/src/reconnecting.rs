@@ -0,0 +1,399 @@
+//! Transport wrapper that survives dropped connections.
+//!
+//! `ReconnectingTransport` holds a way to connect rather than a connection.
+//! When the connection closes it retries with exponential backoff, buffering
+//! sends meanwhile, and reports each drop and reconnect from `poll` so the
+//! session on top can resynchronise (see `Lockstep::rejoin`).
+
+use crate::clock::{default_clock, Clock, Instant};
+use crate::transport::{lock, CloseReason, Lifecycle, Transport, TransportError, TransportMetrics};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Default cap on messages held while disconnected
+const DEFAULT_BUFFER_LIMIT: usize = 1024;
+
+type Connect<T> = Box<dyn FnMut() -> Result<T, TransportError> + Send>;
+type MessageCallback = Arc<dyn Fn(Vec<u8>) + Send + Sync>;
+
+/// How long to wait between reconnection attempts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    /// Wait after the first failed attempt; doubled after each further one
+    pub initial: Duration,
+    /// Longest wait between attempts
+    pub max: Duration,
+    /// Give up after this many failed attempts in a row (None retries forever)
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_millis(250),
+            max: Duration::from_secs(8),
+            max_attempts: None,
+        }
+    }
+}
+
+impl Backoff {
+    /// Wait before the attempt after `failures` failed ones
+    fn delay(&self, failures: u32) -> Duration {
+        let doublings = failures.saturating_sub(1).min(16);
+        self.initial.saturating_mul(1 << doublings).min(self.max)
+    }
+}
+
+/// A change in the underlying connection, reported by `poll`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReconnectEvent {
+    /// The connection dropped; reconnection attempts begin
+    Closed,
+    /// A new connection is up after `attempts` tries, with buffered sends flushed
+    Opened { attempts: u32 },
+    /// `Backoff::max_attempts` ran out; the transport is now closed
+    GaveUp { attempts: u32 },
+}
+
+/// Wraps a connect function, replacing the connection whenever it closes.
+///
+/// Nothing runs in the background: call `poll` regularly (e.g. every frame)
+/// to notice drops and make attempts. While reconnecting the transport still
+/// counts as open and `send` queues messages (up to the buffer limit) for the
+/// next connection, so the close callback only fires on `close` or once
+/// attempts run out.
+pub struct ReconnectingTransport<T: Transport> {
+    /// Locked only so the transport is `Sync`; `poll` has it exclusively
+    connect: Mutex<Connect<T>>,
+    /// The live connection; None between a drop and a successful attempt
+    inner: Option<T>,
+    backoff: Backoff,
+    clock: Box<dyn Clock>,
+    on_message: Option<MessageCallback>,
+    /// Sends waiting for the next connection
+    buffer: Mutex<VecDeque<Vec<u8>>>,
+    buffer_limit: usize,
+    /// Failed attempts since the last drop
+    failures: u32,
+    next_attempt: Option<Instant>,
+    gave_up: bool,
+    closed: bool,
+    lifecycle: Lifecycle,
+}
+
+impl<T: Transport> ReconnectingTransport<T> {
+    /// Connect with `connect` now, and again whenever the connection closes
+    pub fn new(
+        mut connect: impl FnMut() -> Result<T, TransportError> + Send + 'static,
+    ) -> Result<Self, TransportError> {
+        let inner = connect()?;
+        Ok(Self {
+            connect: Mutex::new(Box::new(connect)),
+            inner: Some(inner),
+            backoff: Backoff::default(),
+            clock: default_clock(),
+            on_message: None,
+            buffer: Mutex::new(VecDeque::new()),
+            buffer_limit: DEFAULT_BUFFER_LIMIT,
+            failures: 0,
+            next_attempt: None,
+            gave_up: false,
+            closed: false,
+            lifecycle: Lifecycle::open_now(),
+        })
+    }
+
+    /// Change how attempts are spaced
+    pub fn set_backoff(&mut self, backoff: Backoff) {
+        self.backoff = backoff;
+    }
+
+    /// Hold at most `limit` messages while disconnected; sends beyond it fail
+    pub fn set_buffer_limit(&mut self, limit: usize) {
+        self.buffer_limit = limit;
+    }
+
+    /// Read time from `clock` instead of the platform default
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Box::new(clock);
+    }
+
+    /// Whether a connection is up right now
+    pub fn is_connected(&self) -> bool {
+        self.inner.as_ref().is_some_and(|inner| inner.is_open())
+    }
+
+    /// Messages waiting for the next connection
+    pub fn buffered(&self) -> usize {
+        self.buffer().len()
+    }
+
+    /// The live connection, if any
+    pub fn inner(&self) -> Option<&T> {
+        self.inner.as_ref()
+    }
+
+    /// Notice a dropped connection and, when the backoff allows, try to
+    /// reconnect
+    pub fn poll(&mut self) -> Vec<ReconnectEvent> {
+        let mut events = Vec::new();
+        if self.closed || self.gave_up {
+            return events;
+        }
+
+        let now = self.clock.now();
+        if self.inner.as_ref().is_some_and(|inner| !inner.is_open()) {
+            self.inner = None;
+            self.failures = 0;
+            self.next_attempt = Some(now);
+            events.push(ReconnectEvent::Closed);
+        }
+
+        if self.inner.is_none() && self.next_attempt.is_some_and(|at| now >= at) {
+            let connect = self.connect.get_mut().unwrap_or_else(|e| e.into_inner());
+            match connect() {
+                Ok(inner) if inner.is_open() => {
+                    let attempts = self.failures + 1;
+                    self.attach(inner);
+                    events.push(ReconnectEvent::Opened { attempts });
+                }
+                _ => {
+                    self.failures += 1;
+                    if self
+                        .backoff
+                        .max_attempts
+                        .is_some_and(|max| self.failures >= max)
+                    {
+                        self.gave_up = true;
+                        self.buffer().clear();
+                        self.lifecycle.closed(CloseReason::Error(format!(
+                            "Gave up after {} attempts",
+                            self.failures
+                        )));
+                        events.push(ReconnectEvent::GaveUp {
+                            attempts: self.failures,
+                        });
+                    } else {
+                        self.next_attempt = Some(now + self.backoff.delay(self.failures));
+                    }
+                }
+            }
+        }
+        events
+    }
+
+    /// Adopt a new connection and send what queued up without one
+    fn attach(&mut self, mut inner: T) {
+        if let Some(callback) = &self.on_message {
+            let callback = Arc::clone(callback);
+            inner.set_on_message(Box::new(move |bytes| callback(bytes)));
+        }
+
+        let mut buffer = lock(&self.buffer);
+        while let Some(bytes) = buffer.front() {
+            if inner.send(bytes).is_err() {
+                // Dropped again already; the rest waits for the next one
+                break;
+            }
+            buffer.pop_front();
+        }
+        drop(buffer);
+
+        self.inner = Some(inner);
+        self.failures = 0;
+        self.next_attempt = None;
+    }
+
+    fn buffer(&self) -> std::sync::MutexGuard<'_, VecDeque<Vec<u8>>> {
+        lock(&self.buffer)
+    }
+}
+
+impl<T: Transport> Transport for ReconnectingTransport<T> {
+    fn send(&self, bytes: &[u8]) -> Result<(), TransportError> {
+        if !self.is_open() {
+            return Err(TransportError::NotConnected);
+        }
+        if let Some(inner) = self.inner.as_ref().filter(|inner| inner.is_open()) {
+            if inner.send(bytes).is_ok() {
+                return Ok(());
+            }
+            // The connection just failed; `poll` will notice and replace it
+        }
+
+        let mut buffer = self.buffer();
+        if buffer.len() >= self.buffer_limit {
+            return Err(TransportError::SendFailed(
+                "Reconnect buffer full".to_string(),
+            ));
+        }
+        buffer.push_back(bytes.to_vec());
+        Ok(())
+    }
+
+    fn set_on_message(&mut self, callback: Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>) {
+        let callback: MessageCallback = Arc::from(callback);
+        if let Some(inner) = &mut self.inner {
+            let callback = Arc::clone(&callback);
+            inner.set_on_message(Box::new(move |bytes| callback(bytes)));
+        }
+        self.on_message = Some(callback);
+    }
+
+    /// True until closed or given up, including while reconnecting
+    fn is_open(&self) -> bool {
+        !self.closed && !self.gave_up
+    }
+
+    fn close(&mut self) -> Result<(), TransportError> {
+        if self.closed {
+            return Err(TransportError::AlreadyClosed);
+        }
+        self.closed = true;
+        self.buffer().clear();
+        self.lifecycle.closed(CloseReason::Local);
+        if let Some(inner) = &mut self.inner {
+            // Already closed from the other end is fine
+            let _ = inner.close();
+        }
+        Ok(())
+    }
+
+    fn status(&self) -> String {
+        match &self.inner {
+            _ if self.closed => "Disconnected".to_string(),
+            _ if self.gave_up => format!("Gave up after {} attempts", self.failures),
+            Some(inner) if inner.is_open() => inner.status(),
+            _ if self.failures == 0 => "Reconnecting...".to_string(),
+            _ => format!("Reconnecting (attempt {})...", self.failures + 1),
+        }
+    }
+
+    /// The current connection's traffic (zeros while there is none)
+    fn metrics(&self) -> TransportMetrics {
+        self.inner
+            .as_ref()
+            .map(|inner| inner.metrics())
+            .unwrap_or_default()
+    }
+
+    fn set_on_open(&mut self, callback: Box<dyn Fn() + Send + Sync + 'static>) {
+        self.lifecycle.set_on_open(callback);
+    }
+
+    fn set_on_close(&mut self, callback: Box<dyn Fn(CloseReason) + Send + Sync + 'static>) {
+        self.lifecycle.set_on_close(callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::transport::LoopbackTransport;
+
+    /// Connections handed out by the connect function; None makes an attempt fail
+    type Dialer = Arc<Mutex<VecDeque<Option<LoopbackTransport>>>>;
+
+    fn reconnecting(dialer: &Dialer) -> (ReconnectingTransport<LoopbackTransport>, ManualClock) {
+        let dialer = Arc::clone(dialer);
+        let mut transport = ReconnectingTransport::new(move || {
+            dialer
+                .lock()
+                .unwrap()
+                .pop_front()
+                .flatten()
+                .ok_or_else(|| TransportError::ConnectionFailed("refused".to_string()))
+        })
+        .unwrap();
+        let clock = ManualClock::new();
+        transport.set_clock(clock.clone());
+        transport.set_backoff(Backoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_millis(300),
+            max_attempts: Some(5),
+        });
+        (transport, clock)
+    }
+
+    #[test]
+    fn test_reconnects_and_flushes_buffer() {
+        let (first, mut first_peer) = LoopbackTransport::pair();
+        let (second, second_peer) = LoopbackTransport::pair();
+        let dialer: Dialer = Arc::new(Mutex::new(VecDeque::from([
+            Some(first),
+            None,
+            None,
+            Some(second),
+        ])));
+        let (mut transport, clock) = reconnecting(&dialer);
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        transport.set_on_message(Box::new(move |bytes| sink.lock().unwrap().push(bytes)));
+        first_peer.send(b"one").unwrap();
+        transport.send(b"a").unwrap();
+        assert_eq!(first_peer.try_recv().unwrap(), b"a");
+
+        first_peer.close().unwrap();
+        assert_eq!(transport.poll(), vec![ReconnectEvent::Closed]);
+        assert!(transport.is_open() && !transport.is_connected());
+        transport.send(b"b").unwrap();
+        transport.send(b"c").unwrap();
+        assert_eq!(transport.buffered(), 2);
+
+        // Fails at once, then after 100ms; the next try waits a further 200ms
+        clock.advance(Duration::from_millis(100));
+        assert!(transport.poll().is_empty());
+        clock.advance(Duration::from_millis(199));
+        assert!(transport.poll().is_empty());
+        assert_eq!(transport.status(), "Reconnecting (attempt 3)...");
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(
+            transport.poll(),
+            vec![ReconnectEvent::Opened { attempts: 3 }]
+        );
+
+        let flushed: Vec<_> = std::iter::from_fn(|| second_peer.try_recv()).collect();
+        assert_eq!(flushed, vec![b"b".to_vec(), b"c".to_vec()]);
+        second_peer.send(b"two").unwrap();
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![b"one".to_vec(), b"two".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_gives_up_after_max_attempts() {
+        let (first, mut first_peer) = LoopbackTransport::pair();
+        let dialer: Dialer = Arc::new(Mutex::new(VecDeque::from([Some(first)])));
+        let (mut transport, clock) = reconnecting(&dialer);
+        transport.set_buffer_limit(1);
+        let reason = Arc::new(Mutex::new(None));
+        let sink = Arc::clone(&reason);
+        transport.set_on_close(Box::new(move |why| *sink.lock().unwrap() = Some(why)));
+
+        first_peer.close().unwrap();
+        assert_eq!(transport.poll(), vec![ReconnectEvent::Closed]);
+        transport.send(b"kept").unwrap();
+        assert!(matches!(
+            transport.send(b"over"),
+            Err(TransportError::SendFailed(_))
+        ));
+
+        let mut events = Vec::new();
+        for _ in 0..20 {
+            clock.advance(Duration::from_millis(100));
+            events.extend(transport.poll());
+        }
+        assert_eq!(events, vec![ReconnectEvent::GaveUp { attempts: 5 }]);
+        assert!(!transport.is_open());
+        assert_eq!(
+            *reason.lock().unwrap(),
+            Some(CloseReason::Error("Gave up after 5 attempts".to_string()))
+        );
+        assert_eq!(transport.send(b"late"), Err(TransportError::NotConnected));
+    }
+}
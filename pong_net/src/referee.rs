@@ -0,0 +1,387 @@
+//! Server-authoritative match: a headless node owns the game.
+//!
+//! Both players run an ordinary `Lockstep` (neither as timekeeper) whose
+//! transport leads to the `Referee` instead of to each other. The referee
+//! takes each player's own input, steps its game once it holds both, and
+//! broadcasts the confirmed InputPair, which each player's Lockstep reads as the
+//! remote half. Its periodic snapshots keep the players on its state.
+
+use crate::lockstep::{CoreAdapter, LockstepError};
+use crate::transport::Transport;
+use crate::wire_protocol::{
+    capabilities, input_for_peer, negotiate_version, EncodeOptions, WireMsg, WireMsgRef,
+    PROTOCOL_VERSION,
+};
+use pong_engine::types::*;
+use std::collections::HashMap;
+
+/// Inputs buffered per player ahead of the tick we can step
+const MAX_PENDING_INPUTS: usize = 600;
+
+/// Default ticks between the snapshots broadcast to both players
+pub const DEFAULT_REFEREE_SNAPSHOT_INTERVAL: u32 = 300;
+
+/// What the referee advertises: it only relays inputs and state, so nothing
+/// that needs a peer's cooperation (sequencing, chat, resends, pausing, Bye,
+/// series)
+const ADVERTISED_CAPABILITIES: u32 = capabilities::SUPPORTED
+    & !(capabilities::SEQUENCING
+        | capabilities::CHAT
+        | capabilities::INPUT_RESEND
+        | capabilities::PAUSE
+        | capabilities::BYE
+        | capabilities::SERIES
+        | capabilities::ROLLBACK);
+
+/// Events produced while refereeing
+#[derive(Debug, Clone, PartialEq)]
+pub enum RefereeEvent {
+    /// Stepped `tick` with these events
+    GameAdvanced { tick: Tick, events: Vec<Event> },
+    /// A player's handshake; `version` is the negotiated protocol version
+    PlayerHello {
+        side: Side,
+        version: u8,
+        capabilities: u32,
+    },
+}
+
+/// Headless authority stepping the game from both players' inputs
+pub struct Referee<C: CoreAdapter, T: Transport> {
+    /// Authoritative game
+    core: C,
+    /// Transports to the left and right players
+    players: [T; 2],
+    /// Next tick to step
+    current_tick: Tick,
+    /// Each player's inputs for ticks not yet stepped
+    inputs: [HashMap<Tick, Input>; 2],
+    /// Negotiated protocol version per player, once its Hello arrives
+    peer_versions: [Option<u8>; 2],
+    /// Capability bits each player advertised
+    peer_capabilities: [u32; 2],
+    /// Broadcast a snapshot every this many ticks (0 = only on start and request)
+    snapshot_interval: u32,
+    /// Whether the referee is running
+    is_running: bool,
+}
+
+fn index(side: Side) -> usize {
+    match side {
+        Side::Left => 0,
+        Side::Right => 1,
+    }
+}
+
+impl<C: CoreAdapter, T: Transport> Referee<C, T> {
+    /// Create a referee for `core` with a transport to each player
+    pub fn new(core: C, left: T, right: T) -> Self {
+        Self {
+            current_tick: core.current_tick(),
+            core,
+            players: [left, right],
+            inputs: [HashMap::new(), HashMap::new()],
+            peer_versions: [None; 2],
+            peer_capabilities: [0; 2],
+            snapshot_interval: DEFAULT_REFEREE_SNAPSHOT_INTERVAL,
+            is_running: false,
+        }
+    }
+
+    /// Greet both players and send them the starting state
+    pub fn start(&mut self) -> Result<(), LockstepError> {
+        if self.players.iter().any(|player| !player.is_open()) {
+            return Err(LockstepError::Transport(
+                "Transport not connected".to_string(),
+            ));
+        }
+
+        self.is_running = true;
+        self.current_tick = self.core.current_tick();
+        self.inputs.iter_mut().for_each(HashMap::clear);
+
+        let hello = WireMsg::Hello {
+            version: PROTOCOL_VERSION,
+            capabilities: ADVERTISED_CAPABILITIES,
+        };
+        let snapshot = WireMsg::snapshot(&self.core.snapshot());
+        for side in [Side::Left, Side::Right] {
+            self.send_to(side, &hello)?;
+            self.send_to(side, &snapshot)?;
+        }
+        Ok(())
+    }
+
+    /// Stop refereeing
+    pub fn stop(&mut self) {
+        self.is_running = false;
+        self.inputs.iter_mut().for_each(HashMap::clear);
+    }
+
+    /// Broadcast a snapshot every `ticks` ticks (0 = only on start and request)
+    pub fn set_snapshot_interval(&mut self, ticks: u32) {
+        self.snapshot_interval = ticks;
+    }
+
+    /// Process a message from the player on `from`. Players may only speak
+    /// for their own side; everything else they send is theirs alone.
+    pub fn on_net_message(
+        &mut self,
+        from: Side,
+        bytes: &[u8],
+    ) -> Result<Vec<RefereeEvent>, LockstepError> {
+        if !self.is_running {
+            return Ok(vec![]);
+        }
+
+        let mut events = Vec::new();
+        match WireMsg::decode_ref(bytes)? {
+            // The other half of a player's InputPair is a placeholder
+            WireMsgRef::InputPair(pair) | WireMsgRef::TimedInputPair { pair, .. } => {
+                self.accept_inputs(from, pair.tick, [pair.get_input(from)]);
+            }
+            WireMsgRef::SideInput { tick, side, input } => {
+                if side != from {
+                    return Err(LockstepError::InvalidMessage(format!(
+                        "{from:?} player sent input for {side:?}"
+                    )));
+                }
+                self.accept_inputs(from, tick, [input]);
+            }
+            WireMsgRef::InputBatch { start_tick, inputs } => {
+                self.accept_inputs(from, start_tick, inputs);
+            }
+            WireMsgRef::InputHistory {
+                latest_tick,
+                inputs,
+            } => {
+                let start_tick = latest_tick.wrapping_sub(inputs.len().saturating_sub(1) as u32);
+                self.accept_inputs(from, start_tick, inputs);
+            }
+            WireMsgRef::Hello {
+                version,
+                capabilities,
+            } => {
+                let negotiated =
+                    negotiate_version(version).map_err(|_| LockstepError::VersionMismatch {
+                        local: PROTOCOL_VERSION,
+                        remote: version,
+                    })?;
+                self.peer_versions[index(from)] = Some(negotiated);
+                self.peer_capabilities[index(from)] = capabilities;
+                events.push(RefereeEvent::PlayerHello {
+                    side: from,
+                    version: negotiated,
+                    capabilities,
+                });
+            }
+            WireMsgRef::Ping(timestamp) => self.send_to(from, &WireMsg::pong(timestamp))?,
+            WireMsgRef::SnapshotRequest { .. } => {
+                self.send_to(from, &WireMsg::snapshot(&self.core.snapshot()))?;
+            }
+            // The referee's state is authoritative; nothing else concerns it
+            _ => {}
+        }
+
+        self.step(&mut events)?;
+        Ok(events)
+    }
+
+    /// Buffer `from`'s inputs starting at `start_tick`, ignoring stepped ticks.
+    /// Fields the opponent can't read are dropped, so both simulate the same input.
+    fn accept_inputs(
+        &mut self,
+        from: Side,
+        start_tick: Tick,
+        inputs: impl IntoIterator<Item = Input>,
+    ) {
+        let opponent_capabilities = self.peer_capabilities[index(from.opposite())];
+        let pending = &mut self.inputs[index(from)];
+        for (offset, input) in inputs.into_iter().enumerate() {
+            let tick = start_tick.wrapping_add(offset as u32);
+            let input = input_for_peer(input, opponent_capabilities);
+            if tick_diff(tick, self.current_tick) >= 0
+                && (pending.contains_key(&tick) || pending.len() < MAX_PENDING_INPUTS)
+            {
+                pending.insert(tick, input);
+            }
+        }
+    }
+
+    /// Step every tick both players have sent input for, broadcasting the result
+    fn step(&mut self, events: &mut Vec<RefereeEvent>) -> Result<(), LockstepError> {
+        loop {
+            let tick = self.current_tick;
+            let [left, right] = &self.inputs;
+            let (Some(&a), Some(&b)) = (left.get(&tick), right.get(&tick)) else {
+                break;
+            };
+            let pair = InputPair::new(tick, a, b);
+            for pending in &mut self.inputs {
+                pending.remove(&tick);
+            }
+            self.core.step(&pair);
+            let game_events = self.core.drain_events();
+            self.current_tick = self.current_tick.wrapping_add(1);
+
+            let interval = self.snapshot_interval;
+            let snapshot = (interval > 0 && self.current_tick % interval == 0)
+                .then(|| WireMsg::snapshot(&self.core.snapshot()));
+            for side in [Side::Left, Side::Right] {
+                self.send_to(side, &WireMsg::InputPair(pair))?;
+                if let Some(snapshot) = &snapshot {
+                    self.send_to(side, snapshot)?;
+                }
+            }
+
+            events.push(RefereeEvent::GameAdvanced {
+                tick: pair.tick,
+                events: game_events,
+            });
+        }
+        Ok(())
+    }
+
+    /// Encode `msg` for the player on `side` and send it
+    fn send_to(&mut self, side: Side, msg: &WireMsg) -> Result<(), LockstepError> {
+        let options = EncodeOptions {
+            seq: None,
+            peer_capabilities: self.peer_capabilities[index(side)],
+        };
+        let mut bytes = vec![0; msg.encoded_size_with(&options)];
+        let len = msg.encode_with(&options, &mut bytes)?;
+        self.players[index(side)].send(&bytes[..len])?;
+        Ok(())
+    }
+
+    /// Next tick to step
+    pub fn current_tick(&self) -> Tick {
+        self.current_tick
+    }
+
+    /// Get the authoritative game view
+    pub fn view(&self) -> View {
+        self.core.view()
+    }
+
+    /// Negotiated protocol version of the player on `side`, once its Hello arrived
+    pub fn peer_version(&self, side: Side) -> Option<u8> {
+        self.peer_versions[index(side)]
+    }
+
+    /// The transport to the player on `side`
+    pub fn transport(&self, side: Side) -> &T {
+        &self.players[index(side)]
+    }
+
+    /// Mutable access to the transport to the player on `side`
+    pub fn transport_mut(&mut self, side: Side) -> &mut T {
+        &mut self.players[index(side)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockstep::{GameAdapter, Lockstep};
+    use crate::transport::RecordingMockTransport;
+    use pong_engine::{Config, Game};
+
+    type Player = Lockstep<GameAdapter, RecordingMockTransport>;
+
+    fn exchange(
+        referee: &mut Referee<GameAdapter, RecordingMockTransport>,
+        players: &mut [Player; 2],
+    ) {
+        for (side, player) in [Side::Left, Side::Right]
+            .into_iter()
+            .zip(players.iter_mut())
+        {
+            while let Some(bytes) = player.transport_mut().pop_sent_message() {
+                referee.on_net_message(side, &bytes).unwrap();
+            }
+        }
+        for (side, player) in [Side::Left, Side::Right]
+            .into_iter()
+            .zip(players.iter_mut())
+        {
+            while let Some(bytes) = referee.transport_mut(side).pop_sent_message() {
+                player.on_net_message(bytes).unwrap();
+            }
+        }
+    }
+
+    fn new_player(side: Side) -> Player {
+        let mut lockstep = Lockstep::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            60,
+            side,
+            false,
+        );
+        lockstep.start().unwrap();
+        lockstep
+    }
+
+    #[test]
+    fn test_referee_steps_and_broadcasts() {
+        let mut referee = Referee::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            RecordingMockTransport::new(),
+        );
+        referee.set_snapshot_interval(4);
+        referee.start().unwrap();
+        let mut players = [new_player(Side::Left), new_player(Side::Right)];
+        exchange(&mut referee, &mut players);
+        assert_eq!(referee.peer_version(Side::Right), Some(PROTOCOL_VERSION));
+
+        for tick in 0..6i8 {
+            players[0].on_local_input(tick * 10, 0).unwrap();
+            players[1].on_local_input(-tick * 10, 0).unwrap();
+            exchange(&mut referee, &mut players);
+            for player in &mut players {
+                player.tick().unwrap();
+            }
+        }
+
+        assert_eq!(referee.current_tick(), 6);
+        for player in &players {
+            assert_eq!(player.current_tick(), 6);
+            assert_eq!(player.view(), referee.view());
+        }
+    }
+
+    #[test]
+    fn test_referee_waits_for_both_and_rejects_impersonation() {
+        let mut referee = Referee::new(
+            GameAdapter::new(Game::new(Config::default())),
+            RecordingMockTransport::new(),
+            RecordingMockTransport::new(),
+        );
+        referee.start().unwrap();
+
+        let left = WireMsg::SideInput {
+            tick: 0,
+            side: Side::Left,
+            input: Input::new(20, 0),
+        };
+        referee.on_net_message(Side::Left, &left.encode()).unwrap();
+        assert_eq!(referee.current_tick(), 0);
+
+        // The right player can't put words in the left player's mouth
+        assert!(matches!(
+            referee.on_net_message(Side::Right, &left.encode()),
+            Err(LockstepError::InvalidMessage(_))
+        ));
+
+        // An InputPair only counts for the sender's own half
+        let pair = WireMsg::input_pair(0, Input::new(99, 0), Input::new(-20, 0));
+        referee.on_net_message(Side::Right, &pair.encode()).unwrap();
+        assert_eq!(referee.current_tick(), 1);
+
+        let mut expected = Game::new(Config::default());
+        expected.step(&InputPair::new(0, Input::new(20, 0), Input::new(-20, 0)));
+        assert_eq!(referee.view(), expected.view());
+    }
+}
@@ -0,0 +1,453 @@
+//! Relay server and client, the fallback for players whose NATs defeat
+//! WebRTC: both connect out to a public `RelayServer`, which pairs them by
+//! join code and forwards their messages.
+//!
+//! Everything on the wire is a length-prefixed frame (see `framing`), so the
+//! relay never decodes the messages it forwards. A client's first frame is
+//! its join code; the server's first frame back is a one-byte status, sent
+//! once a second player joins the room (or at once, if the join is refused).
+//! After that each frame from one member is forwarded to the other. When
+//! either member leaves, the room closes and the other is disconnected.
+
+use crate::framing::{Framer, WireDecoder};
+use crate::tcp::{TcpTransport, READ_CHUNK};
+use crate::transport::{lock, CloseReason, Lifecycle, Transport, TransportError, TransportMetrics};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Longest join code the server accepts, in bytes
+pub const MAX_JOIN_CODE_LEN: usize = 64;
+
+/// Status: the room's second player has joined, messages now flow
+pub const STATUS_PAIRED: u8 = 0;
+
+/// Status: the room already has two players
+pub const STATUS_ROOM_FULL: u8 = 1;
+
+/// Status: the join code was empty, too long or not UTF-8
+pub const STATUS_BAD_CODE: u8 = 2;
+
+/// How long the server waits for a new connection's join code
+const JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+type MessageCallback = Arc<dyn Fn(Vec<u8>) + Send + Sync>;
+
+fn valid_code(code: &str) -> bool {
+    !code.is_empty() && code.len() <= MAX_JOIN_CODE_LEN
+}
+
+/// One connection in a room
+struct Member {
+    id: u64,
+    /// Locked so forwarded frames can't interleave
+    writer: Arc<Mutex<TcpStream>>,
+    /// For disconnecting the member without waiting on a blocked write
+    socket: TcpStream,
+}
+
+type Rooms = Mutex<HashMap<String, Vec<Member>>>;
+
+/// Pairs clients by join code and forwards frames between them, one thread
+/// per connection
+pub struct RelayServer {
+    listener: TcpListener,
+    rooms: Arc<Rooms>,
+    next_id: AtomicU64,
+}
+
+impl RelayServer {
+    /// Listen on `addr`
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            rooms: Arc::default(),
+            next_id: AtomicU64::new(0),
+        })
+    }
+
+    /// Address the server is listening on (e.g. after binding port 0)
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Rooms with at least one player in them
+    pub fn room_count(&self) -> usize {
+        lock(&self.rooms).len()
+    }
+
+    /// Accept and serve connections forever
+    pub fn run(&self) {
+        for stream in self.listener.incoming() {
+            // A failed accept only loses that one connection
+            let Ok(stream) = stream else { continue };
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            let rooms = Arc::clone(&self.rooms);
+            let _ = thread::Builder::new()
+                .name(format!("relay-{id}"))
+                .spawn(move || {
+                    let _ = serve(stream, id, &rooms);
+                });
+        }
+    }
+}
+
+/// Read the join code, wait in the room, then forward frames to the partner
+/// until either end leaves
+fn serve(stream: TcpStream, id: u64, rooms: &Rooms) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(JOIN_TIMEOUT))?;
+    let mut reader = stream.try_clone()?;
+    let member = Member {
+        id,
+        socket: stream.try_clone()?,
+        writer: Arc::new(Mutex::new(stream)),
+    };
+    let mut decoder = WireDecoder::new();
+
+    let Some(code) = read_frame(&mut reader, &mut decoder) else {
+        return Ok(());
+    };
+    let code = match String::from_utf8(code) {
+        Ok(code) if valid_code(&code) => code,
+        _ => return refuse(&member, STATUS_BAD_CODE),
+    };
+    reader.set_read_timeout(None)?;
+
+    if let Err(member) = join(rooms, &code, member) {
+        return refuse(&member, STATUS_ROOM_FULL);
+    }
+
+    while let Some(frame) = read_frame(&mut reader, &mut decoder) {
+        // Nothing to forward to until the room is paired
+        let Some(partner) = partner_of(rooms, &code, id) else {
+            continue;
+        };
+        if send_frame(&partner, &frame).is_err() {
+            break;
+        }
+    }
+    leave(rooms, &code, id);
+    Ok(())
+}
+
+/// Add `member` to the room, telling both players once it's paired. Hands
+/// the member back if the room is full.
+fn join(rooms: &Rooms, code: &str, member: Member) -> Result<(), Member> {
+    let mut rooms = lock(rooms);
+    let room = rooms.entry(code.to_string()).or_default();
+    if room.len() >= 2 {
+        return Err(member);
+    }
+    room.push(member);
+    if room.len() == 2 {
+        for member in room.iter() {
+            let _ = send_frame(&member.writer, &[STATUS_PAIRED]);
+        }
+    }
+    Ok(())
+}
+
+/// The other member's writer, if the room is paired
+fn partner_of(rooms: &Rooms, code: &str, id: u64) -> Option<Arc<Mutex<TcpStream>>> {
+    lock(rooms)
+        .get(code)?
+        .iter()
+        .find(|member| member.id != id)
+        .map(|member| Arc::clone(&member.writer))
+}
+
+/// Close the room `id` was in, disconnecting the partner
+fn leave(rooms: &Rooms, code: &str, id: u64) {
+    let mut rooms = lock(rooms);
+    // The room may already be gone, and the code taken by a new one
+    let Some(room) = rooms.get(code) else { return };
+    if !room.iter().any(|member| member.id == id) {
+        return;
+    }
+    for member in rooms.remove(code).into_iter().flatten() {
+        let _ = member.socket.shutdown(Shutdown::Both);
+    }
+}
+
+fn refuse(member: &Member, status: u8) -> io::Result<()> {
+    send_frame(&member.writer, &[status])?;
+    member.socket.shutdown(Shutdown::Both)
+}
+
+fn send_frame(writer: &Mutex<TcpStream>, payload: &[u8]) -> io::Result<()> {
+    let frame = Framer::frame(payload).map_err(|e| io::Error::other(format!("{e:?}")))?;
+    lock(writer).write_all(&frame)
+}
+
+/// Next whole frame, or None once the stream ends, times out or corrupts
+fn read_frame(stream: &mut TcpStream, decoder: &mut WireDecoder) -> Option<Vec<u8>> {
+    let mut chunk = [0u8; READ_CHUNK];
+    loop {
+        match decoder.next_frame() {
+            Some(Ok(payload)) => return Some(payload),
+            // A bad length prefix leaves no way back into the stream
+            Some(Err(_)) => return None,
+            None => {}
+        }
+        match stream.read(&mut chunk) {
+            Ok(0) => return None,
+            Ok(read) => decoder.push(&chunk[..read]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => return None,
+        }
+    }
+}
+
+/// Client-side state, updated from the TCP reader thread
+#[derive(Default)]
+struct RelayState {
+    paired: bool,
+    /// Why the server turned the join code away
+    refused: Option<String>,
+    on_message: Option<MessageCallback>,
+    /// Messages that arrived before a callback was set
+    pending: VecDeque<Vec<u8>>,
+    lifecycle: Lifecycle,
+}
+
+/// Transport to a peer through a `RelayServer`.
+///
+/// Connecting only joins the room: `is_open` stays false, and sends fail,
+/// until the other player joins with the same code. Set an open callback to
+/// hear when that happens.
+pub struct RelayTransport {
+    inner: TcpTransport,
+    code: String,
+    state: Arc<Mutex<RelayState>>,
+}
+
+impl RelayTransport {
+    /// Join the room `code` on the relay at `addr`
+    pub fn connect(addr: impl ToSocketAddrs, code: &str) -> Result<Self, TransportError> {
+        if !valid_code(code) {
+            return Err(TransportError::ConnectionFailed(format!(
+                "Join code must be 1-{MAX_JOIN_CODE_LEN} bytes"
+            )));
+        }
+        let mut inner = TcpTransport::connect(addr)?;
+        let state = Arc::new(Mutex::new(RelayState::default()));
+
+        let relay = Arc::clone(&state);
+        inner.set_on_message(Box::new(move |bytes| {
+            let mut state = lock(&relay);
+            if !state.paired {
+                match bytes.as_slice() {
+                    [STATUS_PAIRED] => {
+                        state.paired = true;
+                        state.lifecycle.opened();
+                    }
+                    [STATUS_ROOM_FULL] => refused(&mut state, "Room full"),
+                    [STATUS_BAD_CODE] => refused(&mut state, "Invalid join code"),
+                    _ => refused(&mut state, "Unexpected reply from relay"),
+                }
+                return;
+            }
+            let Some(callback) = state.on_message.clone() else {
+                state.pending.push_back(bytes);
+                return;
+            };
+            // Released first so the callback may send without deadlocking
+            drop(state);
+            callback(bytes);
+        }));
+
+        let ended = Arc::clone(&state);
+        inner.set_on_close(Box::new(move |reason| {
+            // Ignored after a refusal, which already reported its reason
+            lock(&ended).lifecycle.closed(reason);
+        }));
+
+        inner.send(code.as_bytes())?;
+        Ok(Self {
+            inner,
+            code: code.to_string(),
+            state,
+        })
+    }
+
+    /// The room this transport joined
+    pub fn join_code(&self) -> &str {
+        &self.code
+    }
+
+    /// Whether the other player has joined (even if since disconnected)
+    pub fn is_paired(&self) -> bool {
+        lock(&self.state).paired
+    }
+}
+
+fn refused(state: &mut RelayState, reason: &str) {
+    state.refused = Some(reason.to_string());
+    state
+        .lifecycle
+        .closed(CloseReason::Error(reason.to_string()));
+}
+
+impl Transport for RelayTransport {
+    fn send(&self, bytes: &[u8]) -> Result<(), TransportError> {
+        if !self.is_open() {
+            return Err(TransportError::NotConnected);
+        }
+        self.inner.send(bytes)
+    }
+
+    fn set_on_message(&mut self, callback: Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>) {
+        let mut state = lock(&self.state);
+        for bytes in state.pending.drain(..) {
+            callback(bytes);
+        }
+        state.on_message = Some(Arc::from(callback));
+    }
+
+    fn is_open(&self) -> bool {
+        lock(&self.state).paired && self.inner.is_open()
+    }
+
+    fn close(&mut self) -> Result<(), TransportError> {
+        // Leaving before the peer arrives gives up the room
+        self.inner.close()
+    }
+
+    fn status(&self) -> String {
+        let state = lock(&self.state);
+        let open = self.inner.is_open();
+        match (&state.refused, state.paired, open) {
+            (Some(reason), _, _) => format!("Relay refused room {}: {reason}", self.code),
+            (None, false, true) => format!("Waiting for peer in room {} (relay)", self.code),
+            (None, true, true) => format!(
+                "Connected via relay {}, room {}",
+                self.inner.peer_addr(),
+                self.code
+            ),
+            (None, _, false) => "Disconnected (relay)".to_string(),
+        }
+    }
+
+    /// The relay connection's traffic, join code and status frames included
+    fn metrics(&self) -> TransportMetrics {
+        self.inner.metrics()
+    }
+
+    fn set_on_open(&mut self, callback: Box<dyn Fn() + Send + Sync + 'static>) {
+        lock(&self.state).lifecycle.set_on_open(callback);
+    }
+
+    fn set_on_close(&mut self, callback: Box<dyn Fn(CloseReason) + Send + Sync + 'static>) {
+        lock(&self.state).lifecycle.set_on_close(callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn start_server() -> SocketAddr {
+        let server = RelayServer::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+        addr
+    }
+
+    fn inbox(transport: &mut RelayTransport) -> Arc<Mutex<Vec<Vec<u8>>>> {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        transport.set_on_message(Box::new(move |bytes| sink.lock().unwrap().push(bytes)));
+        received
+    }
+
+    fn closes(transport: &mut RelayTransport) -> Arc<Mutex<Vec<CloseReason>>> {
+        let reasons = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reasons);
+        transport.set_on_close(Box::new(move |reason| sink.lock().unwrap().push(reason)));
+        reasons
+    }
+
+    fn wait_for(mut done: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_relay_pairs_by_join_code() {
+        let addr = start_server();
+        let mut left = RelayTransport::connect(addr, "ABCD").unwrap();
+        let opened = Arc::new(AtomicU64::new(0));
+        let count = Arc::clone(&opened);
+        left.set_on_open(Box::new(move || {
+            count.fetch_add(1, Ordering::Relaxed);
+        }));
+        assert!(!left.is_open());
+        assert_eq!(left.send(b"early"), Err(TransportError::NotConnected));
+        assert!(left.status().contains("Waiting for peer in room ABCD"));
+
+        // Another room doesn't pair with this one
+        let other = RelayTransport::connect(addr, "WXYZ").unwrap();
+        let mut right = RelayTransport::connect(addr, "ABCD").unwrap();
+        wait_for(|| left.is_open() && right.is_open());
+        assert_eq!(opened.load(Ordering::Relaxed), 1);
+        assert!(!other.is_open());
+
+        let to_left = inbox(&mut left);
+        let to_right = inbox(&mut right);
+        left.send(b"hello").unwrap();
+        right.send(&[7; 3000]).unwrap();
+        wait_for(|| to_left.lock().unwrap().len() == 1 && to_right.lock().unwrap().len() == 1);
+        assert_eq!(*to_right.lock().unwrap(), vec![b"hello".to_vec()]);
+        assert_eq!(*to_left.lock().unwrap(), vec![vec![7; 3000]]);
+        assert!(left.status().contains("Connected via relay"));
+
+        let mut third = RelayTransport::connect(addr, "ABCD").unwrap();
+        let refused = closes(&mut third);
+        wait_for(|| !refused.lock().unwrap().is_empty());
+        assert_eq!(
+            *refused.lock().unwrap(),
+            vec![CloseReason::Error("Room full".to_string())]
+        );
+        assert!(!third.is_open());
+        assert!(third.status().contains("Room full"));
+        assert!(left.is_open() && right.is_open());
+
+        assert!(RelayTransport::connect(addr, "").is_err());
+    }
+
+    #[test]
+    fn test_relay_peer_leaving_closes_room() {
+        let server = RelayServer::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let server = Arc::new(server);
+        let runner = Arc::clone(&server);
+        thread::spawn(move || runner.run());
+
+        let mut left = RelayTransport::connect(addr, "ROOM").unwrap();
+        let mut right = RelayTransport::connect(addr, "ROOM").unwrap();
+        let reasons = closes(&mut right);
+        wait_for(|| left.is_open() && right.is_open());
+        assert_eq!(server.room_count(), 1);
+
+        left.close().unwrap();
+        wait_for(|| !reasons.lock().unwrap().is_empty());
+        assert_eq!(*reasons.lock().unwrap(), vec![CloseReason::Remote]);
+        assert!(!right.is_open());
+        assert!(right.is_paired());
+        wait_for(|| server.room_count() == 0);
+
+        // The code is free again
+        let again = RelayTransport::connect(addr, "ROOM").unwrap();
+        assert_eq!(again.join_code(), "ROOM");
+        wait_for(|| server.room_count() == 1);
+    }
+}
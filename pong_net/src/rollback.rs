@@ -0,0 +1,676 @@
+//! Rollback netcode: step at once on a predicted remote input, then rewind and
+//! re-simulate when the peer's real input turns out different.
+//!
+//! A sibling to `Lockstep` for internet play, where waiting a full round trip
+//! for every tick feels sluggish. Each tick's state is kept as a snapshot until
+//! the peer's input for it is confirmed, so a misprediction costs one restore
+//! plus a few replayed ticks.
+
+use crate::clock::{default_clock, Clock};
+use crate::lockstep::{CoreAdapter, LockstepError};
+use crate::transport::{Transport, TransportMetrics};
+use crate::wire_protocol::{
+    capabilities, input_for_peer, negotiate_version, WireMsg, WireMsgRef, MAX_BATCH_INPUTS,
+    PROTOCOL_VERSION,
+};
+use pong_engine::types::*;
+use std::collections::{HashMap, VecDeque};
+
+/// Ticks we may run ahead of the peer's last confirmed input by default
+pub const DEFAULT_MAX_PREDICTION: usize = 8;
+
+/// What a rollback peer advertises: no sequencing, chat, input resends,
+/// pausing, Bye or series, which belong to `Lockstep`
+const ADVERTISED_CAPABILITIES: u32 = capabilities::SUPPORTED
+    & !(capabilities::SEQUENCING
+        | capabilities::CHAT
+        | capabilities::INPUT_RESEND
+        | capabilities::PAUSE
+        | capabilities::BYE
+        | capabilities::SERIES);
+
+/// Events that can occur during rollback processing
+#[derive(Debug, Clone, PartialEq)]
+pub enum RollbackEvent {
+    /// Simulated `tick` with these events. Ticks stepped on a prediction may be
+    /// re-simulated, and report their events again, after a `RolledBack`.
+    GameAdvanced { tick: Tick, events: Vec<Event> },
+    /// A misprediction was found: state was rewound to `from_tick` and
+    /// `resimulated` ticks were replayed with the corrected inputs
+    RolledBack { from_tick: Tick, resimulated: u32 },
+    /// Peer handshake accepted; `version` is the negotiated protocol version
+    PeerHello { version: u8, capabilities: u32 },
+    /// Ping response received
+    PongReceived { round_trip_ms: u32 },
+}
+
+/// Counters for how much prediction and correction a session needed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RollbackStats {
+    /// Ticks stepped on a predicted remote input (re-simulations included)
+    pub predicted_ticks: u32,
+    /// Mispredictions corrected
+    pub rollbacks: u32,
+    /// Ticks replayed by those corrections
+    pub resimulated_ticks: u32,
+}
+
+/// One simulated tick that may still need replaying
+#[derive(Debug, Clone)]
+struct Frame {
+    tick: Tick,
+    /// State before stepping this tick
+    before: Snapshot,
+    local: Input,
+    /// Remote input the tick was stepped with, confirmed or predicted
+    remote: Input,
+}
+
+/// Rollback protocol implementation
+pub struct Rollback<C: CoreAdapter, T: Transport> {
+    /// Game engine adapter
+    core: C,
+    /// Network transport
+    transport: T,
+    /// Time source for ping timestamps
+    clock: Box<dyn Clock>,
+    /// Which side we are (Left or Right)
+    local_side: Side,
+    /// Next tick to simulate
+    current_tick: Tick,
+    /// Running state
+    is_running: bool,
+    /// Most ticks we may run ahead of the peer's confirmed input
+    max_prediction: usize,
+    /// Local input for `current_tick`, already sent and waiting for `tick`
+    pending_local: Option<Input>,
+    /// Latest local input submitted after `current_tick`'s went out
+    held_local: Option<Input>,
+    /// Recent local inputs on consecutive ticks, resent with each new one
+    sent_inputs: VecDeque<(Tick, Input)>,
+    /// Simulated ticks from the oldest unconfirmed one on, oldest first
+    frames: VecDeque<Frame>,
+    /// Confirmed remote inputs for ticks from the oldest frame on
+    remote_inputs: HashMap<Tick, Input>,
+    /// Newest confirmed remote input whose frame was already dropped
+    last_confirmed: Input,
+    /// Earliest tick stepped on a wrong prediction, awaiting rollback
+    rollback_from: Option<Tick>,
+    /// Negotiated protocol version, once the peer's Hello arrives
+    peer_version: Option<u8>,
+    /// Capabilities advertised by the peer
+    peer_capabilities: u32,
+    /// Reused for every outgoing message
+    send_buffer: Vec<u8>,
+    /// Prediction and correction counters
+    stats: RollbackStats,
+}
+
+impl<C: CoreAdapter, T: Transport> Rollback<C, T> {
+    /// Create a new rollback protocol instance
+    pub fn new(core: C, transport: T, local_side: Side) -> Self {
+        Self {
+            current_tick: core.current_tick(),
+            core,
+            transport,
+            clock: default_clock(),
+            local_side,
+            is_running: false,
+            max_prediction: DEFAULT_MAX_PREDICTION,
+            pending_local: None,
+            held_local: None,
+            sent_inputs: VecDeque::new(),
+            frames: VecDeque::new(),
+            remote_inputs: HashMap::new(),
+            last_confirmed: Input::zero(),
+            rollback_from: None,
+            peer_version: None,
+            peer_capabilities: 0,
+            send_buffer: Vec::new(),
+            stats: RollbackStats::default(),
+        }
+    }
+
+    /// Start the rollback protocol
+    pub fn start(&mut self) -> Result<(), LockstepError> {
+        if !self.transport.is_open() {
+            return Err(LockstepError::Transport(
+                "Transport not connected".to_string(),
+            ));
+        }
+
+        self.is_running = true;
+        self.current_tick = self.core.current_tick();
+        self.reset_prediction();
+        self.stats = RollbackStats::default();
+
+        self.send_msg(&WireMsg::Hello {
+            version: PROTOCOL_VERSION,
+            capabilities: ADVERTISED_CAPABILITIES,
+        })
+    }
+
+    /// Read time from `clock` instead of the platform default (see `clock`)
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Box::new(clock);
+    }
+
+    /// Stop the rollback protocol
+    pub fn stop(&mut self) {
+        self.is_running = false;
+        self.reset_prediction();
+    }
+
+    fn reset_prediction(&mut self) {
+        self.pending_local = None;
+        self.held_local = None;
+        self.sent_inputs.clear();
+        self.frames.clear();
+        self.remote_inputs.clear();
+        self.last_confirmed = Input::zero();
+        self.rollback_from = None;
+    }
+
+    /// Run at most `ticks` ahead of the peer's confirmed input (at least 1).
+    /// Higher values hide more latency but make corrections longer.
+    pub fn set_max_prediction(&mut self, ticks: usize) {
+        self.max_prediction = ticks.clamp(1, MAX_BATCH_INPUTS);
+        while self.sent_inputs.len() > self.max_prediction {
+            self.sent_inputs.pop_front();
+        }
+    }
+
+    /// Submit local input for the current tick and send it to the peer along
+    /// with its recent predecessors (the peer may have missed them). Once the
+    /// current tick's input has gone out it is fixed: later calls are held,
+    /// the latest replacing the rest, and sent for the next tick.
+    pub fn submit_local_input(&mut self, input: Input) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+
+        if self.pending_local.is_some() {
+            self.held_local = Some(input);
+            return Ok(());
+        }
+        self.send_local_input(input)
+    }
+
+    /// Send the input held while the current tick's was pending, once it's stepped
+    fn flush_held_local(&mut self) -> Result<(), LockstepError> {
+        if self.pending_local.is_none() {
+            if let Some(input) = self.held_local.take() {
+                self.send_local_input(input)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fix our input for the current tick and send it with its predecessors
+    fn send_local_input(&mut self, input: Input) -> Result<(), LockstepError> {
+        let input = input_for_peer(input, self.peer_capabilities);
+        let tick = self.current_tick;
+        self.pending_local = Some(input);
+
+        // A gap restarts the run
+        if let Some(&(last, _)) = self.sent_inputs.back() {
+            if last.wrapping_add(1) != tick {
+                self.sent_inputs.clear();
+            }
+        }
+        self.sent_inputs.push_back((tick, input));
+        while self.sent_inputs.len() > self.max_prediction {
+            self.sent_inputs.pop_front();
+        }
+
+        let inputs = self.sent_inputs.iter().map(|&(_, input)| input).collect();
+        self.send_msg(&WireMsg::input_history(tick, inputs))
+    }
+
+    /// Process incoming network message
+    pub fn on_net_message(&mut self, bytes: Vec<u8>) -> Result<Vec<RollbackEvent>, LockstepError> {
+        if !self.is_running {
+            return Ok(vec![]);
+        }
+
+        let mut events = Vec::new();
+        match WireMsg::decode_ref(&bytes)? {
+            WireMsgRef::InputPair(pair) | WireMsgRef::TimedInputPair { pair, .. } => {
+                let remote = match self.local_side {
+                    Side::Left => pair.b,
+                    Side::Right => pair.a,
+                };
+                self.accept_remote(pair.tick, remote);
+            }
+            WireMsgRef::SideInput { tick, side, input } if side != self.local_side => {
+                self.accept_remote(tick, input);
+            }
+            WireMsgRef::InputBatch { start_tick, inputs } => {
+                for (offset, input) in inputs.into_iter().enumerate() {
+                    self.accept_remote(start_tick.wrapping_add(offset as u32), input);
+                }
+            }
+            WireMsgRef::InputHistory {
+                latest_tick,
+                inputs,
+            } => {
+                let start_tick = latest_tick.wrapping_sub(inputs.len().saturating_sub(1) as u32);
+                for (offset, input) in inputs.into_iter().enumerate() {
+                    self.accept_remote(start_tick.wrapping_add(offset as u32), input);
+                }
+            }
+            WireMsgRef::Hello {
+                version,
+                capabilities,
+            } => {
+                let negotiated =
+                    negotiate_version(version).map_err(|_| LockstepError::VersionMismatch {
+                        local: PROTOCOL_VERSION,
+                        remote: version,
+                    })?;
+                self.peer_version = Some(negotiated);
+                self.peer_capabilities = capabilities;
+
+                events.push(RollbackEvent::PeerHello {
+                    version: negotiated,
+                    capabilities,
+                });
+            }
+            WireMsgRef::Ping(timestamp) => self.send_msg(&WireMsg::pong(timestamp))?,
+            WireMsgRef::Pong { original_timestamp } => {
+                let round_trip_ms = self.clock.wall_ms().wrapping_sub(original_timestamp);
+                events.push(RollbackEvent::PongReceived { round_trip_ms });
+            }
+            // Lobby, chat and verification messages belong to the session layer
+            _ => {}
+        }
+
+        Ok(events)
+    }
+
+    /// Record a confirmed remote input, scheduling a rollback if the tick was
+    /// already stepped on a different prediction
+    fn accept_remote(&mut self, tick: Tick, input: Input) {
+        let oldest = self.confirmed_tick();
+
+        // Older ticks are confirmed already; far-future ones can't be genuine
+        if tick_after(oldest, tick)
+            || tick_diff(tick, self.current_tick) > MAX_BATCH_INPUTS as i32
+            || self.remote_inputs.contains_key(&tick)
+        {
+            return;
+        }
+        self.remote_inputs.insert(tick, input);
+
+        if tick_after(self.current_tick, tick) {
+            let frame = &self.frames[tick_diff(tick, oldest) as usize];
+            if frame.remote != input {
+                self.rollback_from = Some(match self.rollback_from {
+                    Some(from) if tick_after(tick, from) => from,
+                    _ => tick,
+                });
+            }
+        }
+    }
+
+    /// Correct any misprediction, then step the current tick if local input is
+    /// in and we're within the prediction window (call this every frame)
+    pub fn tick(&mut self) -> Result<Vec<RollbackEvent>, LockstepError> {
+        if !self.is_running {
+            return Ok(vec![]);
+        }
+
+        let mut events = Vec::new();
+        self.resolve_rollback(&mut events)?;
+        self.prune_confirmed();
+
+        if self.frames.len() >= self.max_prediction {
+            return Ok(events);
+        }
+        if let Some(local) = self.pending_local.take() {
+            self.simulate(local, &mut events);
+        }
+        self.flush_held_local()?;
+
+        Ok(events)
+    }
+
+    /// Rewind to the earliest mispredicted tick and replay up to the present
+    fn resolve_rollback(&mut self, events: &mut Vec<RollbackEvent>) -> Result<(), LockstepError> {
+        let Some(from) = self.rollback_from.take() else {
+            return Ok(());
+        };
+
+        let index = tick_diff(from, self.confirmed_tick()) as usize;
+        let replay: Vec<Frame> = self.frames.drain(index..).collect();
+        self.core.restore(&replay[0].before)?;
+        self.current_tick = from;
+
+        events.push(RollbackEvent::RolledBack {
+            from_tick: from,
+            resimulated: replay.len() as u32,
+        });
+        for frame in &replay {
+            self.simulate(frame.local, events);
+        }
+
+        self.stats.rollbacks = self.stats.rollbacks.saturating_add(1);
+        self.stats.resimulated_ticks = self
+            .stats
+            .resimulated_ticks
+            .saturating_add(replay.len() as u32);
+        Ok(())
+    }
+
+    /// Step `current_tick`, predicting the remote input if it hasn't arrived
+    fn simulate(&mut self, local: Input, events: &mut Vec<RollbackEvent>) {
+        let tick = self.current_tick;
+        let remote = match self.remote_inputs.get(&tick) {
+            Some(&input) => input,
+            None => {
+                self.stats.predicted_ticks = self.stats.predicted_ticks.saturating_add(1);
+                self.predict(tick)
+            }
+        };
+
+        let before = self.core.snapshot();
+        let pair = match self.local_side {
+            Side::Left => InputPair::new(tick, local, remote),
+            Side::Right => InputPair::new(tick, remote, local),
+        };
+        self.core.step(&pair);
+        let game_events = self.core.drain_events();
+
+        self.frames.push_back(Frame {
+            tick,
+            before,
+            local,
+            remote,
+        });
+        self.current_tick = tick.wrapping_add(1);
+
+        events.push(RollbackEvent::GameAdvanced {
+            tick,
+            events: game_events,
+        });
+    }
+
+    /// Guess the peer's input for `tick`: players tend to hold their input,
+    /// so repeat the newest confirmed one before it
+    fn predict(&self, tick: Tick) -> Input {
+        let oldest = self.confirmed_tick();
+        let mut t = tick;
+        while t != oldest {
+            t = t.wrapping_sub(1);
+            if let Some(&input) = self.remote_inputs.get(&t) {
+                return input;
+            }
+        }
+        self.last_confirmed
+    }
+
+    /// Drop frames whose remote input is confirmed; they can't be rolled back
+    fn prune_confirmed(&mut self) {
+        while let Some(front) = self.frames.front() {
+            let Some(remote) = self.remote_inputs.remove(&front.tick) else {
+                break;
+            };
+            self.last_confirmed = remote;
+            self.frames.pop_front();
+        }
+    }
+
+    /// Encode into the reusable send buffer and hand it to the transport
+    fn send_msg(&mut self, msg: &WireMsg) -> Result<(), LockstepError> {
+        let size = msg.encoded_size();
+        if self.send_buffer.len() < size {
+            self.send_buffer.resize(size, 0);
+        }
+
+        let len = msg.encode_into(&mut self.send_buffer)?;
+        self.transport.send(&self.send_buffer[..len])?;
+        Ok(())
+    }
+
+    /// Send a ping to measure round-trip time
+    pub fn ping(&mut self) -> Result<(), LockstepError> {
+        if !self.is_running {
+            return Err(LockstepError::NotRunning);
+        }
+
+        self.send_msg(&WireMsg::ping(self.clock.wall_ms()))
+    }
+
+    /// Oldest tick whose remote input is still unconfirmed (or the current
+    /// tick when nothing is predicted); state before it is final
+    pub fn confirmed_tick(&self) -> Tick {
+        self.frames
+            .front()
+            .map_or(self.current_tick, |frame| frame.tick)
+    }
+
+    /// Get the current tick
+    pub fn current_tick(&self) -> Tick {
+        self.current_tick
+    }
+
+    /// Whether the prediction window is full, so `tick` waits for the peer
+    pub fn is_waiting_for_remote(&self) -> bool {
+        self.is_running && self.frames.len() >= self.max_prediction
+    }
+
+    /// Prediction and correction counters since `start`
+    pub fn stats(&self) -> RollbackStats {
+        self.stats
+    }
+
+    /// Get the current game view (may include predicted ticks)
+    pub fn view(&self) -> View {
+        self.core.view()
+    }
+
+    /// Negotiated protocol version, or None before the peer's Hello
+    pub fn peer_version(&self) -> Option<u8> {
+        self.peer_version
+    }
+
+    /// Capability bits the peer advertised in its Hello
+    pub fn peer_capabilities(&self) -> u32 {
+        self.peer_capabilities
+    }
+
+    /// Get transport status
+    pub fn transport_status(&self) -> String {
+        self.transport.status()
+    }
+
+    /// Traffic the transport has carried
+    pub fn transport_metrics(&self) -> TransportMetrics {
+        self.transport.metrics()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockstep::GameAdapter;
+    use crate::transport::RecordingMockTransport;
+    use pong_engine::Game;
+
+    fn playing_game() -> Game {
+        let mut game = Game::new(Config::default());
+        game.status = Status::Playing;
+        game
+    }
+
+    fn new_rollback(side: Side) -> Rollback<GameAdapter, RecordingMockTransport> {
+        let mut rollback = Rollback::new(
+            GameAdapter::new(playing_game()),
+            RecordingMockTransport::new(),
+            side,
+        );
+        rollback.start().unwrap();
+        rollback
+    }
+
+    /// The peer's (Right side) real input for `tick`
+    fn remote_input(tick: Tick) -> Input {
+        Input::new(if (3..6).contains(&tick) { 100 } else { -40 }, 0)
+    }
+
+    fn local_input(tick: Tick) -> Input {
+        Input::new(tick as i8 * 10 - 50, 0)
+    }
+
+    #[test]
+    fn test_steps_on_prediction() {
+        let mut rollback = new_rollback(Side::Left);
+
+        for tick in 0..3 {
+            rollback.submit_local_input(local_input(tick)).unwrap();
+            rollback.tick().unwrap();
+        }
+
+        // Nothing confirmed, yet the simulation kept going
+        assert_eq!(rollback.current_tick(), 3);
+        assert_eq!(rollback.confirmed_tick(), 0);
+        assert_eq!(rollback.stats().predicted_ticks, 3);
+
+        // Every message resends the inputs so far
+        let sent = rollback.transport.sent_messages();
+        assert_eq!(
+            WireMsg::decode(sent.last().unwrap()).unwrap(),
+            WireMsg::input_history(2, (0..3).map(local_input).collect())
+        );
+    }
+
+    #[test]
+    fn test_rollback_converges_on_real_inputs() {
+        let mut rollback = new_rollback(Side::Left);
+
+        // Reference run with every input known up front
+        let mut reference = playing_game();
+        for tick in 0..10 {
+            reference.step(&InputPair::new(tick, local_input(tick), remote_input(tick)));
+        }
+
+        // The peer's inputs arrive in late, out-of-order, duplicated bundles
+        let deliveries = [(4, 0..2), (6, 0..5), (7, 2..4), (9, 5..10)];
+        let mut events = Vec::new();
+        for tick in 0..10 {
+            rollback.submit_local_input(local_input(tick)).unwrap();
+            events.extend(rollback.tick().unwrap());
+
+            for (at, range) in deliveries.iter().cloned() {
+                if at == tick {
+                    let end = range.end - 1;
+                    let msg = WireMsg::input_history(end, range.map(remote_input).collect());
+                    events.extend(rollback.on_net_message(msg.encode()).unwrap());
+                }
+            }
+        }
+        events.extend(rollback.tick().unwrap());
+
+        assert_eq!(rollback.current_tick(), 10);
+        assert_eq!(rollback.confirmed_tick(), 10);
+        assert_eq!(rollback.core.snapshot(), reference.snapshot());
+
+        // Each change of the peer's input (to -40, to 100, back to -40) was
+        // mispredicted once and corrected from that tick
+        let rollbacks: Vec<_> = events
+            .iter()
+            .filter(|event| matches!(event, RollbackEvent::RolledBack { .. }))
+            .collect();
+        assert_eq!(
+            rollbacks,
+            vec![
+                &RollbackEvent::RolledBack {
+                    from_tick: 0,
+                    resimulated: 5
+                },
+                &RollbackEvent::RolledBack {
+                    from_tick: 3,
+                    resimulated: 4
+                },
+                &RollbackEvent::RolledBack {
+                    from_tick: 6,
+                    resimulated: 4
+                },
+            ]
+        );
+        assert_eq!(
+            rollback.stats(),
+            RollbackStats {
+                predicted_ticks: 15,
+                rollbacks: 3,
+                resimulated_ticks: 13,
+            }
+        );
+    }
+
+    #[test]
+    fn test_prediction_window_stalls() {
+        let mut rollback = new_rollback(Side::Right);
+        rollback.set_max_prediction(2);
+
+        for tick in 0..4 {
+            rollback.submit_local_input(local_input(tick)).unwrap();
+            rollback.tick().unwrap();
+        }
+        assert_eq!(rollback.current_tick(), 2);
+        assert!(rollback.is_waiting_for_remote());
+
+        // Confirming tick 0 frees one slot
+        let remote = WireMsg::input_pair(0, Input::zero(), Input::zero());
+        rollback.on_net_message(remote.encode()).unwrap();
+        rollback.tick().unwrap();
+        assert_eq!(rollback.current_tick(), 3);
+        assert_eq!(rollback.confirmed_tick(), 1);
+    }
+
+    #[test]
+    fn test_resubmitted_input_stays_in_sync() {
+        let mut left = new_rollback(Side::Left);
+        let mut right = new_rollback(Side::Right);
+        let up = Input::new(-100, 0);
+        let down = Input::new(100, 0);
+
+        // Input sampled twice a tick: the second sample mustn't rewrite a tick
+        // the peer already has
+        for _ in 0..40 {
+            for rollback in [&mut left, &mut right] {
+                rollback.submit_local_input(up).unwrap();
+                rollback.submit_local_input(down).unwrap();
+                rollback.tick().unwrap();
+            }
+            for msg in left.transport.sent_messages() {
+                right.on_net_message(msg).unwrap();
+            }
+            for msg in right.transport.sent_messages() {
+                left.on_net_message(msg).unwrap();
+            }
+            left.transport.clear_sent_messages();
+            right.transport.clear_sent_messages();
+        }
+        left.tick().unwrap();
+        right.tick().unwrap();
+
+        assert_eq!(left.current_tick(), right.current_tick());
+        assert_eq!(left.confirmed_tick(), right.confirmed_tick());
+        assert_eq!(left.core.snapshot(), right.core.snapshot());
+    }
+
+    #[test]
+    fn test_not_running() {
+        let mut rollback = Rollback::new(
+            GameAdapter::new(playing_game()),
+            RecordingMockTransport::new(),
+            Side::Left,
+        );
+        assert_eq!(
+            rollback.submit_local_input(Input::zero()),
+            Err(LockstepError::NotRunning)
+        );
+        assert_eq!(rollback.tick().unwrap(), vec![]);
+    }
+}
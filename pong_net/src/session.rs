@@ -0,0 +1,261 @@
+//! Ready-made lockstep sessions.
+//!
+//! `LockstepSession::host` and `LockstepSession::join` run the whole lobby
+//! handshake over a fresh transport: Hello (version and capabilities), Join,
+//! Welcome (side and rules), ConfigSync and StartMatch. The guest builds its
+//! game from the host's rules, so both peers are guaranteed to simulate the
+//! same match. Feed received bytes to `on_net_message` as usual; `tick` and
+//! `drive` hold until `is_ready`, which `MatchStarting` announces on both sides.
+
+use crate::clock::Instant;
+use crate::lockstep::{GameAdapter, Lockstep, LockstepError, LockstepEvent};
+use crate::transport::Transport;
+use pong_engine::types::*;
+use pong_engine::Game;
+
+/// Lockstep session that negotiates its own side and rules
+pub struct LockstepSession<T: Transport> {
+    /// The session being set up, and then played
+    lockstep: Lockstep<GameAdapter, T>,
+    /// Rules the match is played by; the guest learns them from the Welcome
+    config: Option<Config>,
+    /// StartMatch was sent (host) or received (guest)
+    ready: bool,
+}
+
+impl<T: Transport> LockstepSession<T> {
+    /// Host a match played by `config`. We keep time and play the left side;
+    /// the guest is welcomed as soon as its Join arrives.
+    pub fn host(transport: T, config: Config) -> Result<Self, LockstepError> {
+        let game = GameAdapter::new(Game::new(config));
+        let mut lockstep = Lockstep::new(game, transport, config.tick_hz, Side::Left, true);
+        lockstep.start()?;
+
+        Ok(Self {
+            lockstep,
+            config: Some(config),
+            ready: false,
+        })
+    }
+
+    /// Join the match hosted on the other end of `transport`, taking whatever
+    /// side and rules the host assigns
+    pub fn join(transport: T) -> Result<Self, LockstepError> {
+        // Placeholder until the Welcome brings the real rules and side
+        let config = Config::default();
+        let game = GameAdapter::new(Game::new(config));
+        let mut lockstep = Lockstep::new(game, transport, config.tick_hz, Side::Right, false);
+        lockstep.start()?;
+        lockstep.join()?;
+
+        Ok(Self {
+            lockstep,
+            config: None,
+            ready: false,
+        })
+    }
+
+    /// Process a message from the peer, advancing the handshake where it applies
+    pub fn on_net_message(&mut self, bytes: Vec<u8>) -> Result<Vec<LockstepEvent>, LockstepError> {
+        let mut events = self.lockstep.on_net_message(bytes)?;
+
+        let mut started = None;
+        for event in &events {
+            match event {
+                LockstepEvent::PeerJoined => {
+                    if let Some(config) = self.config {
+                        self.lockstep.welcome(&config)?;
+                        self.lockstep.sync_config(&config)?;
+                    }
+                }
+                LockstepEvent::Welcomed { config, .. } => {
+                    let game = GameAdapter::new(Game::new(*config));
+                    self.lockstep.replace_core(game, config.tick_hz);
+                    self.config = Some(*config);
+                    self.lockstep.sync_config(config)?;
+                }
+                LockstepEvent::ConfigVerified if self.lockstep.is_timekeeper() && !self.ready => {
+                    let start_tick = self.lockstep.current_tick();
+                    self.lockstep.start_match(start_tick)?;
+                    self.ready = true;
+                    started = Some(start_tick);
+                }
+                LockstepEvent::MatchStarting { start_tick } => {
+                    if self.config.is_none() {
+                        return Err(LockstepError::InvalidMessage(
+                            "StartMatch before Welcome".to_string(),
+                        ));
+                    }
+                    if *start_tick != self.lockstep.current_tick() {
+                        return Err(LockstepError::SyncError(format!(
+                            "Match starts at tick {start_tick}, we are at {}",
+                            self.lockstep.current_tick()
+                        )));
+                    }
+                    self.ready = true;
+                }
+                _ => {}
+            }
+        }
+
+        // The host reports its own start like the guest does
+        if let Some(start_tick) = started {
+            events.push(LockstepEvent::MatchStarting { start_tick });
+        }
+        Ok(events)
+    }
+
+    /// Whether the handshake has finished and the match can run
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    /// Rules the match is played by, once known
+    pub fn config(&self) -> Option<&Config> {
+        self.config.as_ref()
+    }
+
+    /// The side we control; the guest's is settled by the Welcome
+    pub fn local_side(&self) -> Side {
+        self.lockstep.local_side()
+    }
+
+    /// The game being played
+    pub fn game(&self) -> &Game {
+        self.lockstep.core().game()
+    }
+
+    /// Advance the simulation (see `Lockstep::tick`); nothing runs until ready
+    pub fn tick(&mut self) -> Result<Vec<LockstepEvent>, LockstepError> {
+        if !self.ready {
+            return Ok(vec![]);
+        }
+        self.lockstep.tick()
+    }
+
+    /// Pace the simulation (see `Lockstep::drive`); nothing runs until ready
+    pub fn drive(
+        &mut self,
+        now: Instant,
+        input: Input,
+    ) -> Result<Vec<LockstepEvent>, LockstepError> {
+        if !self.ready {
+            return Ok(vec![]);
+        }
+        self.lockstep.drive(now, input)
+    }
+
+    /// The underlying lockstep, for input, stats and settings
+    pub fn lockstep(&self) -> &Lockstep<GameAdapter, T> {
+        &self.lockstep
+    }
+
+    /// Mutable access to the underlying lockstep
+    pub fn lockstep_mut(&mut self) -> &mut Lockstep<GameAdapter, T> {
+        &mut self.lockstep
+    }
+
+    /// Hand over the lockstep once the session is running
+    pub fn into_lockstep(self) -> Lockstep<GameAdapter, T> {
+        self.lockstep
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::RecordingMockTransport;
+
+    fn deliver(
+        from: &mut LockstepSession<RecordingMockTransport>,
+        to: &mut LockstepSession<RecordingMockTransport>,
+    ) -> Vec<LockstepEvent> {
+        let mut events = Vec::new();
+        while let Some(bytes) = from.lockstep_mut().transport_mut().pop_sent_message() {
+            events.extend(to.on_net_message(bytes).unwrap());
+        }
+        events
+    }
+
+    #[test]
+    fn test_host_and_join_agree_on_match() {
+        let rules = Config {
+            max_score: 3,
+            tick_hz: 30,
+            ..Config::default()
+        };
+        let mut host = LockstepSession::host(RecordingMockTransport::new(), rules).unwrap();
+        let mut guest = LockstepSession::join(RecordingMockTransport::new()).unwrap();
+        assert!(!host.is_ready() && !guest.is_ready());
+        assert!(guest.config().is_none());
+
+        // Hello + Join, then Hello + Welcome + ConfigSync, then ConfigSync, then StartMatch
+        deliver(&mut guest, &mut host);
+        let events = deliver(&mut host, &mut guest);
+        assert!(events.contains(&LockstepEvent::Welcomed {
+            side: Side::Right,
+            config: rules
+        }));
+        let events = deliver(&mut guest, &mut host);
+        assert!(events.contains(&LockstepEvent::MatchStarting { start_tick: 0 }));
+        assert!(host.is_ready());
+        let events = deliver(&mut host, &mut guest);
+        assert!(events.contains(&LockstepEvent::MatchStarting { start_tick: 0 }));
+        assert!(guest.is_ready());
+
+        assert_eq!(guest.config(), Some(&rules));
+        assert_eq!(guest.game().config, rules);
+        assert_eq!(guest.local_side(), Side::Right);
+        assert_eq!(host.local_side(), Side::Left);
+        assert!(host.lockstep().is_config_verified());
+        assert!(guest.lockstep().is_config_verified());
+        assert_eq!(
+            guest.lockstep().tick_interval(),
+            host.lockstep().tick_interval()
+        );
+
+        // Play a few ticks in lockstep
+        for tick in 0..3 {
+            host.lockstep_mut()
+                .submit_local_input(Input::new(20, 0))
+                .unwrap();
+            guest
+                .lockstep_mut()
+                .submit_local_input(Input::new(-20, 0))
+                .unwrap();
+            deliver(&mut host, &mut guest);
+            deliver(&mut guest, &mut host);
+            host.tick().unwrap();
+            guest.tick().unwrap();
+            assert_eq!(host.lockstep().current_tick(), tick + 1);
+        }
+        assert_eq!(host.lockstep().view(), guest.lockstep().view());
+    }
+
+    #[test]
+    fn test_session_holds_until_ready() {
+        let mut guest = LockstepSession::join(RecordingMockTransport::new()).unwrap();
+        guest
+            .lockstep_mut()
+            .submit_local_input(Input::zero())
+            .unwrap();
+        assert!(guest.tick().unwrap().is_empty());
+        assert_eq!(guest.lockstep().current_tick(), 0);
+
+        // A StartMatch without a Welcome is out of order
+        let mut host =
+            LockstepSession::host(RecordingMockTransport::new(), Config::default()).unwrap();
+        host.lockstep_mut().start_match(0).unwrap();
+        let start = host
+            .lockstep_mut()
+            .transport_mut()
+            .sent_messages()
+            .last()
+            .unwrap()
+            .clone();
+        assert!(matches!(
+            guest.on_net_message(start),
+            Err(LockstepError::InvalidMessage(_))
+        ));
+    }
+}
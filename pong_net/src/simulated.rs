@@ -0,0 +1,434 @@
+//! Simulated bad networks, for testing netcode without leaving the process.
+//!
+//! A `Link` carries one direction of traffic: it delays messages by a latency
+//! plus random jitter, holds some back long enough to be overtaken, sends some
+//! twice and drops others. `SimulatedTransport` puts a link on each side of
+//! any transport. Time comes from a `Clock` (a `ManualClock` in tests) and
+//! randomness from a seeded generator, so a run can be replayed exactly.
+
+use crate::clock::{default_clock, Clock, Instant};
+use crate::transport::{lock, CloseReason, Transport, TransportError, TransportMetrics};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// How one direction of the link mistreats messages
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkConditions {
+    /// Base one-way delay
+    pub latency: Duration,
+    /// Extra delay drawn uniformly from zero up to this
+    pub jitter: Duration,
+    /// Chance a message is held back a further `latency` and overtaken
+    pub reorder: f32,
+    /// Chance a message also arrives a second time, with its own delay
+    pub duplicate: f32,
+    /// Chance a message is dropped
+    pub loss: f32,
+}
+
+impl LinkConditions {
+    /// A link that delivers everything after `latency`
+    pub fn clean(latency: Duration) -> Self {
+        Self {
+            latency,
+            jitter: Duration::ZERO,
+            reorder: 0.0,
+            duplicate: 0.0,
+            loss: 0.0,
+        }
+    }
+}
+
+impl Default for LinkConditions {
+    /// A perfect link: no delay, nothing lost
+    fn default() -> Self {
+        Self::clean(Duration::ZERO)
+    }
+}
+
+/// What a link did to the messages put on it
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LinkStats {
+    /// Messages put on the link
+    pub sent: u32,
+    /// Messages the link dropped
+    pub dropped: u32,
+    /// Extra copies the link delivered
+    pub duplicated: u32,
+    /// Messages delivered after one sent later
+    pub reordered: u32,
+}
+
+/// Deterministic xorshift generator for the link's coin flips
+pub(crate) struct LinkRng(u64);
+
+impl LinkRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        // xorshift never leaves zero
+        Self(seed | 1)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Uniform in [0, 1)
+    fn unit(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// One direction of the simulated network
+pub(crate) struct Link {
+    pub(crate) conditions: LinkConditions,
+    pub(crate) stats: LinkStats,
+    /// Messages in flight with their arrival time and send order
+    in_flight: Vec<(Instant, u32, Vec<u8>)>,
+    next_order: u32,
+    /// Send order of the last message delivered
+    last_delivered: Option<u32>,
+}
+
+impl Link {
+    pub(crate) fn new(conditions: LinkConditions) -> Self {
+        Self {
+            conditions,
+            stats: LinkStats::default(),
+            in_flight: Vec::new(),
+            next_order: 0,
+            last_delivered: None,
+        }
+    }
+
+    /// Put `bytes` on the link at `now`
+    pub(crate) fn send(&mut self, now: Instant, bytes: Vec<u8>, rng: &mut LinkRng) {
+        self.stats.sent += 1;
+        if rng.unit() < self.conditions.loss {
+            self.stats.dropped += 1;
+            return;
+        }
+
+        // Only drawn when enabled, so links without duplication replay as before
+        if self.conditions.duplicate > 0.0 && rng.unit() < self.conditions.duplicate {
+            self.stats.duplicated += 1;
+            let due = now + self.delay(rng);
+            self.in_flight.push((due, self.next_order, bytes.clone()));
+        }
+        let due = now + self.delay(rng);
+        self.in_flight.push((due, self.next_order, bytes));
+        self.next_order += 1;
+    }
+
+    fn delay(&self, rng: &mut LinkRng) -> Duration {
+        let mut delay = self.conditions.latency + self.conditions.jitter.mul_f32(rng.unit());
+        if rng.unit() < self.conditions.reorder {
+            delay += self.conditions.latency.max(Duration::from_millis(1));
+        }
+        delay
+    }
+
+    /// Take the messages due by `now`, earliest first
+    pub(crate) fn receive(&mut self, now: Instant) -> Vec<Vec<u8>> {
+        self.in_flight.sort_by_key(|&(due, order, _)| (due, order));
+        let due = self.in_flight.partition_point(|&(due, _, _)| due <= now);
+
+        let mut delivered = Vec::with_capacity(due);
+        for (_, order, bytes) in self.in_flight.drain(..due) {
+            if self.last_delivered.is_some_and(|last| order < last) {
+                self.stats.reordered += 1;
+            } else {
+                self.last_delivered = Some(order);
+            }
+            delivered.push(bytes);
+        }
+        delivered
+    }
+}
+
+/// Where messages off the inbound link go
+#[derive(Default)]
+struct Delivery {
+    on_message: Option<Arc<dyn Fn(Vec<u8>) + Send + Sync>>,
+    /// Messages that arrived before a callback was set
+    pending: VecDeque<Vec<u8>>,
+}
+
+/// Links and time shared with the inner transport's callback
+struct SimState {
+    outbound: Link,
+    inbound: Link,
+    rng: LinkRng,
+    clock: Box<dyn Clock>,
+}
+
+/// Wraps any transport in a simulated bad network: sends wait on an outbound
+/// link before reaching the inner transport, and received messages wait on an
+/// inbound link before reaching the callback.
+///
+/// Nothing runs in the background; messages move when `poll` is called (and
+/// on every `send`), so call it each frame or after advancing a test clock.
+/// Delayed sends the inner transport then rejects are lost, as on a real network.
+pub struct SimulatedTransport<T: Transport> {
+    inner: T,
+    state: Arc<Mutex<SimState>>,
+    delivery: Mutex<Delivery>,
+}
+
+impl<T: Transport> SimulatedTransport<T> {
+    /// Wrap `inner`, applying `conditions` in both directions and drawing
+    /// randomness from `seed`
+    pub fn new(mut inner: T, conditions: LinkConditions, seed: u64) -> Self {
+        let state = Arc::new(Mutex::new(SimState {
+            outbound: Link::new(conditions),
+            inbound: Link::new(conditions),
+            rng: LinkRng::new(seed),
+            clock: default_clock(),
+        }));
+
+        let arrivals = Arc::clone(&state);
+        inner.set_on_message(Box::new(move |bytes| {
+            let mut state = lock(&arrivals);
+            let now = state.clock.now();
+            let SimState { inbound, rng, .. } = &mut *state;
+            inbound.send(now, bytes, rng);
+        }));
+
+        Self {
+            inner,
+            state,
+            delivery: Mutex::new(Delivery::default()),
+        }
+    }
+
+    /// Take time from `clock` instead of the system clock, e.g. a `ManualClock`
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.state().clock = Box::new(clock);
+    }
+
+    /// Change both directions' conditions; messages already in flight keep
+    /// their arrival times
+    pub fn set_conditions(&mut self, conditions: LinkConditions) {
+        self.set_link_conditions(conditions, conditions);
+    }
+
+    /// Change each direction's conditions separately
+    pub fn set_link_conditions(&mut self, outbound: LinkConditions, inbound: LinkConditions) {
+        let mut state = self.state();
+        state.outbound.conditions = outbound;
+        state.inbound.conditions = inbound;
+    }
+
+    /// The conditions applied to sends and to received messages
+    pub fn link_conditions(&self) -> (LinkConditions, LinkConditions) {
+        let state = self.state();
+        (state.outbound.conditions, state.inbound.conditions)
+    }
+
+    /// What the outbound and inbound links have done so far
+    pub fn link_stats(&self) -> (LinkStats, LinkStats) {
+        let state = self.state();
+        (state.outbound.stats, state.inbound.stats)
+    }
+
+    /// Deliver every message whose delay has passed, in both directions
+    pub fn poll(&self) {
+        let (outgoing, incoming) = {
+            let mut state = self.state();
+            let now = state.clock.now();
+            (state.outbound.receive(now), state.inbound.receive(now))
+        };
+
+        for bytes in outgoing {
+            let _ = self.inner.send(&bytes);
+        }
+        if incoming.is_empty() {
+            return;
+        }
+        let mut delivery = lock(&self.delivery);
+        let Some(callback) = delivery.on_message.clone() else {
+            delivery.pending.extend(incoming);
+            return;
+        };
+        // Released first so the callback may send without deadlocking
+        drop(delivery);
+        for bytes in incoming {
+            callback(bytes);
+        }
+    }
+
+    /// The wrapped transport
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Stop simulating and return the wrapped transport (messages in flight
+    /// are lost, and its message callback stays installed)
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn state(&self) -> MutexGuard<'_, SimState> {
+        lock(&self.state)
+    }
+}
+
+impl<T: Transport> Transport for SimulatedTransport<T> {
+    fn send(&self, bytes: &[u8]) -> Result<(), TransportError> {
+        if !self.inner.is_open() {
+            return Err(TransportError::NotConnected);
+        }
+        {
+            let mut state = self.state();
+            let now = state.clock.now();
+            let SimState { outbound, rng, .. } = &mut *state;
+            outbound.send(now, bytes.to_vec(), rng);
+        }
+        self.poll();
+        Ok(())
+    }
+
+    fn set_on_message(&mut self, callback: Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>) {
+        {
+            let mut delivery = lock(&self.delivery);
+            for bytes in delivery.pending.drain(..) {
+                callback(bytes);
+            }
+            delivery.on_message = Some(Arc::from(callback));
+        }
+        self.poll();
+    }
+
+    fn is_open(&self) -> bool {
+        self.inner.is_open()
+    }
+
+    fn close(&mut self) -> Result<(), TransportError> {
+        self.inner.close()
+    }
+
+    fn status(&self) -> String {
+        format!("{} (simulated)", self.inner.status())
+    }
+
+    /// The inner transport's traffic, i.e. what survived the outbound link
+    /// and what arrived before the inbound one
+    fn metrics(&self) -> TransportMetrics {
+        self.inner.metrics()
+    }
+
+    fn set_on_open(&mut self, callback: Box<dyn Fn() + Send + Sync + 'static>) {
+        self.inner.set_on_open(callback);
+    }
+
+    /// Straight from the inner transport: a close isn't delayed by the link
+    fn set_on_close(&mut self, callback: Box<dyn Fn(CloseReason) + Send + Sync + 'static>) {
+        self.inner.set_on_close(callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::transport::LoopbackTransport;
+
+    /// A simulated left end talking to a plain right end, on a manual clock
+    fn simulated_pair(
+        conditions: LinkConditions,
+    ) -> (
+        SimulatedTransport<LoopbackTransport>,
+        LoopbackTransport,
+        ManualClock,
+    ) {
+        let (left, right) = LoopbackTransport::pair();
+        let clock = ManualClock::new();
+        let mut left = SimulatedTransport::new(left, conditions, 42);
+        left.set_clock(clock.clone());
+        (left, right, clock)
+    }
+
+    fn drain(transport: &LoopbackTransport) -> Vec<Vec<u8>> {
+        std::iter::from_fn(|| transport.try_recv()).collect()
+    }
+
+    #[test]
+    fn test_simulated_latency_both_ways() {
+        let (mut left, right, clock) =
+            simulated_pair(LinkConditions::clean(Duration::from_millis(50)));
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        left.set_on_message(Box::new(move |bytes| sink.lock().unwrap().push(bytes)));
+
+        left.send(b"out").unwrap();
+        right.send(b"in").unwrap();
+        clock.advance(Duration::from_millis(49));
+        left.poll();
+        assert!(drain(&right).is_empty());
+        assert!(received.lock().unwrap().is_empty());
+
+        clock.advance(Duration::from_millis(1));
+        left.poll();
+        assert_eq!(drain(&right), vec![b"out".to_vec()]);
+        assert_eq!(*received.lock().unwrap(), vec![b"in".to_vec()]);
+        assert_eq!(left.link_stats().0.sent, 1);
+        assert_eq!(left.status(), "Connected (Loopback) (simulated)");
+    }
+
+    #[test]
+    fn test_simulated_loss_and_duplication() {
+        let (mut left, right, _clock) = simulated_pair(LinkConditions {
+            loss: 1.0,
+            ..LinkConditions::default()
+        });
+        for i in 0..10u8 {
+            left.send(&[i]).unwrap();
+        }
+        assert!(drain(&right).is_empty());
+        assert_eq!(left.link_stats().0.dropped, 10);
+
+        left.set_conditions(LinkConditions {
+            duplicate: 1.0,
+            ..LinkConditions::default()
+        });
+        left.send(b"twice").unwrap();
+        assert_eq!(drain(&right), vec![b"twice".to_vec(), b"twice".to_vec()]);
+        assert_eq!(left.link_stats().0.duplicated, 1);
+    }
+
+    #[test]
+    fn test_simulated_reordering() {
+        let (left, right, clock) = simulated_pair(LinkConditions {
+            latency: Duration::from_millis(20),
+            reorder: 0.5,
+            ..LinkConditions::default()
+        });
+        for i in 0..50u8 {
+            left.send(&[i]).unwrap();
+        }
+        clock.advance(Duration::from_millis(40));
+        left.poll();
+
+        let mut delivered: Vec<u8> = drain(&right).into_iter().map(|bytes| bytes[0]).collect();
+        assert_eq!(delivered.len(), 50);
+        assert!(delivered.windows(2).any(|pair| pair[0] > pair[1]));
+        assert!(left.link_stats().0.reordered > 0);
+        delivered.sort_unstable();
+        assert_eq!(delivered, (0..50).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_simulated_holds_messages_for_late_callback() {
+        let (mut left, right, _clock) = simulated_pair(LinkConditions::default());
+        right.send(b"early").unwrap();
+        left.poll();
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        left.set_on_message(Box::new(move |bytes| sink.lock().unwrap().push(bytes)));
+        assert_eq!(*received.lock().unwrap(), vec![b"early".to_vec()]);
+    }
+}
@@ -0,0 +1,191 @@
+//! Soak-test harness: two `Lockstep` peers over a simulated bad network.
+//!
+//! Each direction of the link is a `simulated::Link`. Time comes from a shared
+//! `ManualClock` stepped a millisecond at a time, and all randomness from a
+//! seeded generator, so a failing run can be replayed exactly.
+
+use crate::clock::{Clock, ManualClock};
+use crate::lockstep::{GameAdapter, Lockstep, LockstepEvent};
+use crate::simulated::{Link, LinkConditions, LinkRng};
+use crate::transport::LoopbackTransport;
+use pong_engine::types::*;
+use pong_engine::Game;
+use std::time::Duration;
+
+/// What a soak run saw
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SoakReport {
+    /// Ticks each peer simulated, left then right
+    pub ticks: [Tick; 2],
+    /// Messages put on the link in either direction
+    pub sent: u32,
+    /// Messages the link dropped
+    pub dropped: u32,
+    /// Messages the link delivered out of order
+    pub reordered: u32,
+    /// `DesyncDetected` ticks reported by either peer
+    pub desyncs: Vec<Tick>,
+    /// First tick the peers stepped with different inputs
+    pub diverged_at: Option<Tick>,
+}
+
+type Peer = Lockstep<GameAdapter, LoopbackTransport>;
+
+/// Two peers playing through a simulated link
+pub struct SoakHarness {
+    left: Peer,
+    right: Peer,
+    clock: ManualClock,
+    left_to_right: Link,
+    right_to_left: Link,
+    rng: LinkRng,
+    report: SoakReport,
+}
+
+impl SoakHarness {
+    /// Connect two fresh peers under `conditions` (both directions), shuffling
+    /// with `seed`. The handshake runs over a clean link so capabilities are known.
+    pub fn new(conditions: LinkConditions, seed: u64) -> Self {
+        let clock = ManualClock::new();
+        let (left_end, right_end) = LoopbackTransport::pair();
+        let new_peer = |transport: LoopbackTransport, side: Side, is_timekeeper: bool| {
+            let mut lockstep = Lockstep::new(
+                GameAdapter::new(Game::new(Config::default())),
+                transport,
+                60,
+                side,
+                is_timekeeper,
+            );
+            lockstep.set_clock(clock.clone());
+            lockstep.set_hash_interval(30);
+            lockstep.set_input_redundancy(4);
+            lockstep.start().unwrap();
+            lockstep.record_replay();
+            lockstep.set_replay_keyframe_interval(0);
+            lockstep
+        };
+        let mut left = new_peer(left_end, Side::Left, true);
+        let mut right = new_peer(right_end, Side::Right, false);
+
+        while let Some(bytes) = right.transport().try_recv() {
+            right.on_net_message(bytes).unwrap();
+        }
+        while let Some(bytes) = left.transport().try_recv() {
+            left.on_net_message(bytes).unwrap();
+        }
+
+        Self {
+            left,
+            right,
+            clock,
+            left_to_right: Link::new(conditions),
+            right_to_left: Link::new(conditions),
+            rng: LinkRng::new(seed),
+            report: SoakReport::default(),
+        }
+    }
+
+    /// Play until both peers have simulated `ticks` ticks, giving up after
+    /// `deadline` of simulated time
+    pub fn run(mut self, ticks: Tick, deadline: Duration) -> SoakReport {
+        let start = self.clock.now();
+        while self.left.current_tick() < ticks || self.right.current_tick() < ticks {
+            if self.clock.now().saturating_duration_since(start) > deadline {
+                break;
+            }
+            self.step();
+            self.clock.advance(Duration::from_millis(1));
+        }
+
+        self.report.ticks = [self.left.current_tick(), self.right.current_tick()];
+        for link in [&self.left_to_right, &self.right_to_left] {
+            self.report.sent += link.stats.sent;
+            self.report.dropped += link.stats.dropped;
+            self.report.reordered += link.stats.reordered;
+        }
+
+        // Identical inputs from the same start keep deterministic cores identical
+        let (left, right) = (self.left.take_replay(), self.right.take_replay());
+        if let (Some(left), Some(right)) = (left, right) {
+            self.report.diverged_at = left
+                .inputs()
+                .zip(right.inputs())
+                .find(|(left, right)| left != right)
+                .map(|(pair, _)| pair.tick);
+        }
+        self.report
+    }
+
+    /// One millisecond of network and simulation
+    fn step(&mut self) {
+        let now = self.clock.now();
+
+        for bytes in self.left_to_right.receive(now) {
+            let events = self.right.on_net_message(bytes).unwrap();
+            Self::collect(&events, &mut self.report);
+        }
+        for bytes in self.right_to_left.receive(now) {
+            let events = self.left.on_net_message(bytes).unwrap();
+            Self::collect(&events, &mut self.report);
+        }
+
+        // Inputs vary per tick so a dropped one can't go unnoticed
+        for peer in [&mut self.left, &mut self.right] {
+            let axis = ((peer.current_tick() * 37) % 255) as i16 - 127;
+            let events = peer.drive(now, Input::new(axis as i8, 0)).unwrap();
+            Self::collect(&events, &mut self.report);
+        }
+
+        // Whatever reached the other end goes on the simulated link instead
+        while let Some(bytes) = self.right.transport().try_recv() {
+            self.left_to_right.send(now, bytes, &mut self.rng);
+        }
+        while let Some(bytes) = self.left.transport().try_recv() {
+            self.right_to_left.send(now, bytes, &mut self.rng);
+        }
+    }
+
+    fn collect(events: &[LockstepEvent], report: &mut SoakReport) {
+        for event in events {
+            if let LockstepEvent::DesyncDetected { tick, .. } = event {
+                report.desyncs.push(*tick);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_survives(conditions: LinkConditions, seed: u64) -> SoakReport {
+        let report = SoakHarness::new(conditions, seed).run(3000, Duration::from_secs(600));
+        assert!(report.desyncs.is_empty(), "{report:?}");
+        assert_eq!(report.diverged_at, None);
+        assert!(report.ticks.iter().all(|&tick| tick >= 3000), "{report:?}");
+        report
+    }
+
+    #[test]
+    fn test_soak_clean_link() {
+        let report = assert_survives(LinkConditions::clean(Duration::from_millis(30)), 1);
+        assert_eq!(report.dropped, 0);
+        assert_eq!(report.reordered, 0);
+    }
+
+    #[test]
+    fn test_soak_lossy_reordering_link() {
+        let conditions = LinkConditions {
+            latency: Duration::from_millis(40),
+            jitter: Duration::from_millis(30),
+            reorder: 0.05,
+            duplicate: 0.0,
+            loss: 0.1,
+        };
+        for seed in [7, 1234, 0xdead_beef] {
+            let report = assert_survives(conditions, seed);
+            assert!(report.dropped > 0);
+            assert!(report.reordered > 0);
+        }
+    }
+}
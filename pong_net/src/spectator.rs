@@ -0,0 +1,201 @@
+//! Read-only viewer of a live lockstep match.
+//!
+//! A player's `Lockstep` relays each confirmed InputPair and a periodic snapshot
+//! to its attached spectator transports (see `Lockstep::add_spectator`). Feeding
+//! those messages to a `Spectator` replays the match on a local core without
+//! ever sending input back.
+
+use crate::lockstep::{CoreAdapter, LockstepError};
+use crate::wire_protocol::{negotiate_version, WireMsg, WireMsgRef, PROTOCOL_VERSION};
+use pong_engine::types::*;
+use std::collections::HashMap;
+
+/// Confirmed ticks buffered ahead of the one we can step
+const MAX_PENDING_PAIRS: usize = 600;
+
+/// Events produced while following a match
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpectatorEvent {
+    /// Replayed `tick` with these events
+    GameAdvanced { tick: Tick, events: Vec<Event> },
+    /// A snapshot brought us to `tick`, catching up or correcting our state
+    Synced { tick: Tick },
+    /// The relaying player's handshake; `version` is the negotiated protocol version
+    PeerHello { version: u8, capabilities: u32 },
+}
+
+/// Follows a match from the confirmed inputs a player relays
+pub struct Spectator<C: CoreAdapter> {
+    /// Game engine adapter
+    core: C,
+    /// Next tick to replay
+    current_tick: Tick,
+    /// Confirmed inputs that arrived ahead of `current_tick`
+    pending_pairs: HashMap<Tick, InputPair>,
+    /// A snapshot has been applied, so replayed ticks match the players'
+    synced: bool,
+    /// Negotiated protocol version, once the relay's Hello arrives
+    peer_version: Option<u8>,
+}
+
+impl<C: CoreAdapter> Spectator<C> {
+    /// Create a spectator that replays onto `core`
+    pub fn new(core: C) -> Self {
+        let current_tick = core.current_tick();
+        Self {
+            core,
+            current_tick,
+            pending_pairs: HashMap::new(),
+            synced: false,
+            peer_version: None,
+        }
+    }
+
+    /// Process a message relayed by a player
+    pub fn on_net_message(&mut self, bytes: &[u8]) -> Result<Vec<SpectatorEvent>, LockstepError> {
+        let mut events = Vec::new();
+        match WireMsg::decode_ref(bytes)? {
+            WireMsgRef::InputPair(pair) | WireMsgRef::TimedInputPair { pair, .. } => {
+                if tick_diff(pair.tick, self.current_tick) >= 0
+                    && self.pending_pairs.len() < MAX_PENDING_PAIRS
+                {
+                    self.pending_pairs.insert(pair.tick, pair);
+                }
+            }
+            WireMsgRef::Snapshot(snapshot_data) => {
+                let snapshot = Snapshot::decode(&snapshot_data)?;
+                self.core.restore(&snapshot)?;
+                self.current_tick = snapshot.tick;
+                self.synced = true;
+
+                let tick = snapshot.tick;
+                self.pending_pairs.retain(|&t, _| !tick_after(tick, t));
+                events.push(SpectatorEvent::Synced { tick });
+            }
+            WireMsgRef::Hello {
+                version,
+                capabilities,
+            } => {
+                let negotiated =
+                    negotiate_version(version).map_err(|_| LockstepError::VersionMismatch {
+                        local: PROTOCOL_VERSION,
+                        remote: version,
+                    })?;
+                self.peer_version = Some(negotiated);
+                events.push(SpectatorEvent::PeerHello {
+                    version: negotiated,
+                    capabilities,
+                });
+            }
+            // Everything else is between the players
+            _ => {}
+        }
+
+        if self.synced {
+            self.replay(&mut events);
+        }
+        Ok(events)
+    }
+
+    /// Step through every consecutive confirmed tick we hold
+    fn replay(&mut self, events: &mut Vec<SpectatorEvent>) {
+        while let Some(pair) = self.pending_pairs.remove(&self.current_tick) {
+            self.core.step(&pair);
+            let tick = self.current_tick;
+            self.current_tick = self.current_tick.wrapping_add(1);
+
+            events.push(SpectatorEvent::GameAdvanced {
+                tick,
+                events: self.core.drain_events(),
+            });
+        }
+    }
+
+    /// Whether a snapshot has arrived yet; until then nothing is replayed
+    pub fn is_synced(&self) -> bool {
+        self.synced
+    }
+
+    /// Next tick to replay
+    pub fn current_tick(&self) -> Tick {
+        self.current_tick
+    }
+
+    /// Get the current game view
+    pub fn view(&self) -> View {
+        self.core.view()
+    }
+
+    /// Negotiated protocol version, once the relay's Hello has arrived
+    pub fn peer_version(&self) -> Option<u8> {
+        self.peer_version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockstep::GameAdapter;
+    use pong_engine::{Config, Game};
+
+    #[test]
+    fn test_waits_for_snapshot_then_replays_in_order() {
+        let mut player = Game::new(Config::default());
+        let snapshot = player.snapshot();
+        let mut spectator = Spectator::new(GameAdapter::new(Game::new(Config::default())));
+
+        let pairs: Vec<InputPair> = (0..3)
+            .map(|tick| InputPair::new(tick, Input::new(40, 0), Input::new(-40, 0)))
+            .collect();
+        for pair in &pairs {
+            player.step(pair);
+        }
+
+        // Pairs before the snapshot are held, and may arrive out of order
+        spectator
+            .on_net_message(&WireMsg::InputPair(pairs[1]).encode())
+            .unwrap();
+        spectator
+            .on_net_message(&WireMsg::InputPair(pairs[0]).encode())
+            .unwrap();
+        assert_eq!(spectator.current_tick(), 0);
+        assert!(!spectator.is_synced());
+
+        let events = spectator
+            .on_net_message(&WireMsg::snapshot(&snapshot).encode())
+            .unwrap();
+        let advanced = |tick| SpectatorEvent::GameAdvanced {
+            tick,
+            events: vec![],
+        };
+        assert_eq!(
+            events,
+            vec![SpectatorEvent::Synced { tick: 0 }, advanced(0), advanced(1)]
+        );
+        assert_eq!(spectator.current_tick(), 2);
+
+        spectator
+            .on_net_message(&WireMsg::InputPair(pairs[2]).encode())
+            .unwrap();
+        assert_eq!(spectator.current_tick(), 3);
+        assert_eq!(spectator.view(), player.view());
+    }
+
+    #[test]
+    fn test_ignores_player_only_traffic() {
+        let mut spectator = Spectator::new(GameAdapter::new(Game::new(Config::default())));
+
+        let events = spectator
+            .on_net_message(&WireMsg::hello().encode())
+            .unwrap();
+        assert!(matches!(events[..], [SpectatorEvent::PeerHello { .. }]));
+        assert_eq!(spectator.peer_version(), Some(PROTOCOL_VERSION));
+
+        let chat = WireMsg::chat("hi");
+        assert!(spectator.on_net_message(&chat.encode()).unwrap().is_empty());
+        assert!(spectator
+            .on_net_message(&WireMsg::ping(5).encode())
+            .unwrap()
+            .is_empty());
+    }
+}
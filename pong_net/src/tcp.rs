@@ -0,0 +1,463 @@
+//! Native TCP transport, so two machines on a LAN can play without a browser
+//! or signaling server.
+//!
+//! Messages travel as length-prefixed frames (see `framing`). A reader thread
+//! splits the stream back into messages and hands each to the `on_message`
+//! callback; messages that arrive before a callback is set are kept for it.
+//!
+//! With the `async` feature, `AsyncTcpTransport` speaks the same framing on
+//! tokio sockets, for servers that shouldn't spend a thread per connection.
+
+use crate::framing::{Framer, WireDecoder};
+use crate::transport::{
+    lock, wall_ms, CloseReason, Lifecycle, Transport, TransportError, TransportMetrics,
+};
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Bytes the reader thread asks the socket for at a time
+pub(crate) const READ_CHUNK: usize = 4096;
+
+/// State shared with the reader thread (also used by `UnixSocketTransport`)
+#[derive(Default)]
+pub(crate) struct StreamShared {
+    pub(crate) open: bool,
+    pub(crate) on_message: Option<Box<dyn Fn(Vec<u8>) + Send + Sync>>,
+    /// Messages received before a callback was set
+    pub(crate) pending: VecDeque<Vec<u8>>,
+    /// Why the connection ended, if not by `close`
+    pub(crate) error: Option<String>,
+    pub(crate) metrics: TransportMetrics,
+    pub(crate) lifecycle: Lifecycle,
+}
+
+/// Transport over one TCP connection
+pub struct TcpTransport {
+    /// Write half; locked so concurrent sends can't interleave frames
+    writer: Mutex<TcpStream>,
+    peer_addr: SocketAddr,
+    shared: Arc<Mutex<StreamShared>>,
+}
+
+impl TcpTransport {
+    /// Connect to a peer listening at `addr`
+    pub fn connect(addr: impl ToSocketAddrs) -> Result<Self, TransportError> {
+        let stream = TcpStream::connect(addr)
+            .map_err(|e| TransportError::ConnectionFailed(format!("Connect failed: {e}")))?;
+        Self::from_stream(stream)
+    }
+
+    /// Listen on `addr` and wait for one peer to connect
+    pub fn listen(addr: impl ToSocketAddrs) -> Result<Self, TransportError> {
+        let listener = TcpListener::bind(addr)
+            .map_err(|e| TransportError::ConnectionFailed(format!("Bind failed: {e}")))?;
+        Self::accept(&listener)
+    }
+
+    /// Wait for the next peer on an already bound `listener` (e.g. one bound to
+    /// port 0, whose address has been shared)
+    pub fn accept(listener: &TcpListener) -> Result<Self, TransportError> {
+        let (stream, _) = listener
+            .accept()
+            .map_err(|e| TransportError::ConnectionFailed(format!("Accept failed: {e}")))?;
+        Self::from_stream(stream)
+    }
+
+    /// Take over a connected stream and start reading from it
+    pub fn from_stream(stream: TcpStream) -> Result<Self, TransportError> {
+        let connection_failed = |e: std::io::Error| TransportError::ConnectionFailed(e.to_string());
+        // Inputs are tiny and latency-bound; don't let Nagle hold them back
+        stream.set_nodelay(true).map_err(connection_failed)?;
+        let peer_addr = stream.peer_addr().map_err(connection_failed)?;
+        let reader = stream.try_clone().map_err(connection_failed)?;
+
+        let shared = Arc::new(Mutex::new(StreamShared {
+            open: true,
+            lifecycle: Lifecycle::open_now(),
+            ..StreamShared::default()
+        }));
+        let thread_shared = Arc::clone(&shared);
+        thread::Builder::new()
+            .name(format!("tcp-reader-{peer_addr}"))
+            .spawn(move || {
+                read_loop(reader, &thread_shared, |stream| {
+                    let _ = stream.shutdown(Shutdown::Both);
+                })
+            })
+            .map_err(connection_failed)?;
+
+        Ok(Self {
+            writer: Mutex::new(stream),
+            peer_addr,
+            shared,
+        })
+    }
+
+    /// Address of the other end of the connection
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    fn shared(&self) -> std::sync::MutexGuard<'_, StreamShared> {
+        lock(&self.shared)
+    }
+}
+
+/// Read frames until the stream ends, delivering each message; `shutdown`
+/// cuts the connection after a corrupt frame
+pub(crate) fn read_loop<S: Read>(
+    mut stream: S,
+    shared: &Mutex<StreamShared>,
+    shutdown: impl FnOnce(&S),
+) {
+    let mut decoder = WireDecoder::new();
+    let mut chunk = [0u8; READ_CHUNK];
+
+    let error = loop {
+        let read = match stream.read(&mut chunk) {
+            Ok(0) => break None,
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => break Some(e.to_string()),
+        };
+        decoder.push(&chunk[..read]);
+
+        let mut failed = None;
+        while let Some(frame) = decoder.next_frame() {
+            let Ok(payload) = frame else {
+                // A bad length prefix leaves no way back into the stream
+                failed = Some("Oversized frame".to_string());
+                break;
+            };
+            let mut shared = lock(shared);
+            shared.metrics.record_received(payload.len(), wall_ms());
+            match &shared.on_message {
+                Some(callback) => callback(payload),
+                None => shared.pending.push_back(payload),
+            }
+        }
+        if failed.is_some() {
+            shutdown(&stream);
+            break failed;
+        }
+    };
+
+    let mut shared = lock(shared);
+    if shared.open {
+        shared.open = false;
+        let reason = match &error {
+            Some(error) => CloseReason::Error(error.clone()),
+            None => CloseReason::Remote,
+        };
+        shared.error = error;
+        shared.lifecycle.closed(reason);
+    }
+}
+
+impl Transport for TcpTransport {
+    fn send(&self, bytes: &[u8]) -> Result<(), TransportError> {
+        if !self.is_open() {
+            return Err(TransportError::NotConnected);
+        }
+
+        let result = Framer::frame(bytes)
+            .map_err(|e| TransportError::SendFailed(format!("{e:?}")))
+            .and_then(|frame| {
+                let mut writer = lock(&self.writer);
+                writer
+                    .write_all(&frame)
+                    .map_err(|e| TransportError::SendFailed(e.to_string()))
+            });
+        let mut shared = self.shared();
+        match &result {
+            Ok(()) => shared.metrics.record_sent(bytes.len(), wall_ms()),
+            Err(_) => shared.metrics.record_send_error(),
+        }
+        result
+    }
+
+    fn set_on_message(&mut self, callback: Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>) {
+        let mut shared = self.shared();
+        for bytes in shared.pending.drain(..) {
+            callback(bytes);
+        }
+        shared.on_message = Some(callback);
+    }
+
+    fn is_open(&self) -> bool {
+        self.shared().open
+    }
+
+    fn close(&mut self) -> Result<(), TransportError> {
+        {
+            let mut shared = self.shared();
+            if !shared.open {
+                return Err(TransportError::AlreadyClosed);
+            }
+            shared.open = false;
+            shared.lifecycle.closed(CloseReason::Local);
+        }
+
+        // Ends the reader thread too
+        let writer = lock(&self.writer);
+        let _ = writer.shutdown(Shutdown::Both);
+        Ok(())
+    }
+
+    fn status(&self) -> String {
+        let shared = self.shared();
+        match (&shared.error, shared.open) {
+            (_, true) => format!("Connected to {} (TCP)", self.peer_addr),
+            (Some(error), false) => format!("Disconnected (TCP): {error}"),
+            (None, false) => "Disconnected (TCP)".to_string(),
+        }
+    }
+
+    fn metrics(&self) -> TransportMetrics {
+        self.shared().metrics
+    }
+
+    fn set_on_open(&mut self, callback: Box<dyn Fn() + Send + Sync + 'static>) {
+        self.shared().lifecycle.set_on_open(callback);
+    }
+
+    fn set_on_close(&mut self, callback: Box<dyn Fn(CloseReason) + Send + Sync + 'static>) {
+        self.shared().lifecycle.set_on_close(callback);
+    }
+}
+
+impl Drop for TcpTransport {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+/// Async transport over one tokio TCP connection, framed like `TcpTransport`
+/// so either end may be the other kind
+#[cfg(feature = "async")]
+pub struct AsyncTcpTransport {
+    reader: tokio::sync::Mutex<(tokio::net::tcp::OwnedReadHalf, WireDecoder)>,
+    writer: tokio::sync::Mutex<tokio::net::tcp::OwnedWriteHalf>,
+    peer_addr: SocketAddr,
+    open: std::sync::atomic::AtomicBool,
+}
+
+#[cfg(feature = "async")]
+impl AsyncTcpTransport {
+    /// Connect to a peer listening at `addr`
+    pub async fn connect(addr: impl tokio::net::ToSocketAddrs) -> Result<Self, TransportError> {
+        let stream = tokio::net::TcpStream::connect(addr)
+            .await
+            .map_err(|e| TransportError::ConnectionFailed(format!("Connect failed: {e}")))?;
+        Self::from_stream(stream)
+    }
+
+    /// Wait for the next peer on `listener`
+    pub async fn accept(listener: &tokio::net::TcpListener) -> Result<Self, TransportError> {
+        let (stream, _) = listener
+            .accept()
+            .await
+            .map_err(|e| TransportError::ConnectionFailed(format!("Accept failed: {e}")))?;
+        Self::from_stream(stream)
+    }
+
+    /// Take over a connected stream
+    pub fn from_stream(stream: tokio::net::TcpStream) -> Result<Self, TransportError> {
+        let connection_failed = |e: std::io::Error| TransportError::ConnectionFailed(e.to_string());
+        stream.set_nodelay(true).map_err(connection_failed)?;
+        let peer_addr = stream.peer_addr().map_err(connection_failed)?;
+        let (reader, writer) = stream.into_split();
+
+        Ok(Self {
+            reader: tokio::sync::Mutex::new((reader, WireDecoder::new())),
+            writer: tokio::sync::Mutex::new(writer),
+            peer_addr,
+            open: std::sync::atomic::AtomicBool::new(true),
+        })
+    }
+
+    /// Address of the other end of the connection
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    fn mark_closed(&self) {
+        self.open.store(false, std::sync::atomic::Ordering::Release);
+    }
+}
+
+#[cfg(feature = "async")]
+impl crate::async_transport::AsyncTransport for AsyncTcpTransport {
+    async fn send(&self, bytes: &[u8]) -> Result<(), TransportError> {
+        use tokio::io::AsyncWriteExt;
+
+        if !self.is_open() {
+            return Err(TransportError::NotConnected);
+        }
+        let frame =
+            Framer::frame(bytes).map_err(|e| TransportError::SendFailed(format!("{e:?}")))?;
+        let mut writer = self.writer.lock().await;
+        writer
+            .write_all(&frame)
+            .await
+            .map_err(|e| TransportError::SendFailed(e.to_string()))
+    }
+
+    async fn recv(&self) -> Option<Vec<u8>> {
+        use tokio::io::AsyncReadExt;
+
+        let mut reader = self.reader.lock().await;
+        let (stream, decoder) = &mut *reader;
+        let mut chunk = [0u8; READ_CHUNK];
+        loop {
+            match decoder.next_frame() {
+                Some(Ok(payload)) => return Some(payload),
+                // A bad length prefix leaves no way back into the stream
+                Some(Err(_)) => break,
+                None => {}
+            }
+            match stream.read(&mut chunk).await {
+                Ok(0) | Err(_) => break,
+                Ok(read) => decoder.push(&chunk[..read]),
+            }
+        }
+        self.mark_closed();
+        None
+    }
+
+    fn is_open(&self) -> bool {
+        self.open.load(std::sync::atomic::Ordering::Acquire)
+    }
+
+    async fn close(&self) -> Result<(), TransportError> {
+        use tokio::io::AsyncWriteExt;
+
+        if !self.open.swap(false, std::sync::atomic::Ordering::AcqRel) {
+            return Err(TransportError::AlreadyClosed);
+        }
+        // Ends the peer's reads; ours end when the peer closes in turn
+        let _ = self.writer.lock().await.shutdown().await;
+        Ok(())
+    }
+
+    fn status(&self) -> String {
+        if self.is_open() {
+            format!("Connected to {} (TCP)", self.peer_addr)
+        } else {
+            "Disconnected (TCP)".to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    /// A connected pair over loopback: (listening side, connecting side)
+    fn connected_pair() -> (TcpTransport, TcpTransport) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = thread::spawn(move || TcpTransport::connect(addr).unwrap());
+        let host = TcpTransport::accept(&listener).unwrap();
+        (host, client.join().unwrap())
+    }
+
+    /// Collect messages from `transport` into a shared list
+    fn inbox(transport: &mut TcpTransport) -> Arc<Mutex<Vec<Vec<u8>>>> {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        transport.set_on_message(Box::new(move |bytes| sink.lock().unwrap().push(bytes)));
+        received
+    }
+
+    fn wait_for(mut done: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_tcp_round_trip() {
+        let (mut host, mut client) = connected_pair();
+        assert!(host.is_open() && client.is_open());
+        assert_eq!(
+            client.peer_addr(),
+            host.writer.lock().unwrap().local_addr().unwrap()
+        );
+
+        // Sent before the host listens for messages, and kept for it
+        client.send(b"early").unwrap();
+        client.send(&[7; 3000]).unwrap();
+        wait_for(|| host.shared().pending.len() == 2);
+        let to_host = inbox(&mut host);
+        let to_client = inbox(&mut client);
+        host.send(b"hello").unwrap();
+        wait_for(|| to_client.lock().unwrap().len() == 1);
+
+        assert_eq!(
+            *to_host.lock().unwrap(),
+            vec![b"early".to_vec(), vec![7; 3000]]
+        );
+        assert_eq!(*to_client.lock().unwrap(), vec![b"hello".to_vec()]);
+        assert_eq!(client.metrics().bytes_sent, 3005);
+        assert_eq!(host.metrics().messages_received, 2);
+    }
+
+    #[test]
+    fn test_tcp_close() {
+        let (mut host, mut client) = connected_pair();
+        let reason = Arc::new(Mutex::new(None));
+        let sink = Arc::clone(&reason);
+        client.set_on_close(Box::new(move |why| *sink.lock().unwrap() = Some(why)));
+        host.close().unwrap();
+        assert!(!host.is_open());
+        assert_eq!(host.send(b"late"), Err(TransportError::NotConnected));
+        assert_eq!(host.close(), Err(TransportError::AlreadyClosed));
+
+        // The other end notices the connection went away
+        wait_for(|| !client.is_open());
+        assert_eq!(client.status(), "Disconnected (TCP)");
+        assert_eq!(*reason.lock().unwrap(), Some(CloseReason::Remote));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_async_tcp_with_sync_peer() {
+        use crate::async_transport::AsyncTransport;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let peer = thread::spawn(move || {
+            let mut host = TcpTransport::accept(&listener).unwrap();
+            let to_host = inbox(&mut host);
+            wait_for(|| to_host.lock().unwrap().len() == 1);
+            host.send(b"pong").unwrap();
+            let received = to_host.lock().unwrap().clone();
+            host.close().unwrap();
+            received
+        });
+
+        runtime.block_on(async {
+            let client = AsyncTcpTransport::connect(addr).await.unwrap();
+            assert_eq!(client.peer_addr(), addr);
+            client.send(b"ping").await.unwrap();
+            assert_eq!(client.recv().await, Some(b"pong".to_vec()));
+            assert_eq!(client.recv().await, None);
+            assert!(!client.is_open());
+            assert_eq!(client.close().await, Err(TransportError::AlreadyClosed));
+        });
+        assert_eq!(peer.join().unwrap(), vec![b"ping".to_vec()]);
+    }
+}
@@ -0,0 +1,888 @@
+//! Transport abstraction for network communication
+
+use crate::clock::default_clock;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// Errors that can occur during transport operations
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransportError {
+    /// Connection is not open
+    NotConnected,
+    /// Failed to send message
+    SendFailed(String),
+    /// Connection failed
+    ConnectionFailed(String),
+    /// Invalid configuration
+    InvalidConfig(String),
+    /// Transport is already closed
+    AlreadyClosed,
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransportError::NotConnected => write!(f, "Transport not connected"),
+            TransportError::SendFailed(msg) => write!(f, "Send failed: {}", msg),
+            TransportError::ConnectionFailed(msg) => write!(f, "Connection failed: {}", msg),
+            TransportError::InvalidConfig(msg) => write!(f, "Invalid configuration: {}", msg),
+            TransportError::AlreadyClosed => write!(f, "Transport already closed"),
+        }
+    }
+}
+
+impl std::error::Error for TransportError {}
+
+/// Traffic a transport has carried, for bandwidth displays and dead-link checks
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TransportMetrics {
+    /// Messages handed to the peer
+    pub messages_sent: u64,
+    /// Messages received from the peer
+    pub messages_received: u64,
+    /// Payload bytes handed to the peer (excluding framing and headers)
+    pub bytes_sent: u64,
+    /// Payload bytes received from the peer
+    pub bytes_received: u64,
+    /// Sends on an open transport that returned an error
+    pub send_errors: u64,
+    /// `Clock::wall_ms` when a message was last sent or received
+    pub last_activity_ms: Option<u32>,
+}
+
+impl TransportMetrics {
+    /// Count a sent message of `len` bytes at `wall_ms`
+    pub fn record_sent(&mut self, len: usize, wall_ms: u32) {
+        self.messages_sent += 1;
+        self.bytes_sent += len as u64;
+        self.last_activity_ms = Some(wall_ms);
+    }
+
+    /// Count a received message of `len` bytes at `wall_ms`
+    pub fn record_received(&mut self, len: usize, wall_ms: u32) {
+        self.messages_received += 1;
+        self.bytes_received += len as u64;
+        self.last_activity_ms = Some(wall_ms);
+    }
+
+    /// Count a failed send
+    pub fn record_send_error(&mut self) {
+        self.send_errors += 1;
+    }
+
+    /// Milliseconds from the last activity to `wall_ms`, or None if there
+    /// hasn't been any
+    pub fn idle_ms(&self, wall_ms: u32) -> Option<u32> {
+        self.last_activity_ms.map(|last| wall_ms.wrapping_sub(last))
+    }
+}
+
+/// Why a transport stopped being open, as passed to its close callback
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CloseReason {
+    /// `close` was called on this end
+    Local,
+    /// The peer closed the connection
+    Remote,
+    /// Nothing arrived from the peer for too long
+    TimedOut,
+    /// The connection failed
+    Error(String),
+}
+
+impl fmt::Display for CloseReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloseReason::Local => write!(f, "Closed locally"),
+            CloseReason::Remote => write!(f, "Closed by peer"),
+            CloseReason::TimedOut => write!(f, "Peer timed out"),
+            CloseReason::Error(msg) => write!(f, "Connection failed: {msg}"),
+        }
+    }
+}
+
+pub type OpenCallback = Box<dyn Fn() + Send + Sync>;
+pub type CloseCallback = Box<dyn Fn(CloseReason) + Send + Sync>;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+enum LifecycleState {
+    #[default]
+    Connecting,
+    Open,
+    Closed(CloseReason),
+}
+
+/// Open and close callbacks and the connection state they follow, for
+/// implementing `Transport::set_on_open` and `Transport::set_on_close`.
+/// Callbacks run wherever the state changes, under whatever lock guards the
+/// `Lifecycle`, so they shouldn't call back into the transport.
+#[derive(Default)]
+pub struct Lifecycle {
+    on_open: Option<OpenCallback>,
+    on_close: Option<CloseCallback>,
+    state: LifecycleState,
+}
+
+impl Lifecycle {
+    /// State for a transport that is connected from the start
+    pub fn open_now() -> Self {
+        Self {
+            state: LifecycleState::Open,
+            ..Self::default()
+        }
+    }
+
+    /// Keep `callback` for the next open, calling it now if already open
+    pub fn set_on_open(&mut self, callback: OpenCallback) {
+        if self.state == LifecycleState::Open {
+            callback();
+        }
+        self.on_open = Some(callback);
+    }
+
+    /// Keep `callback` for the next close, calling it now if already closed
+    pub fn set_on_close(&mut self, callback: CloseCallback) {
+        if let LifecycleState::Closed(reason) = &self.state {
+            callback(reason.clone());
+        }
+        self.on_close = Some(callback);
+    }
+
+    /// The connection is up; calls the open callback unless it already was
+    pub fn opened(&mut self) {
+        if self.state == LifecycleState::Open {
+            return;
+        }
+        self.state = LifecycleState::Open;
+        if let Some(callback) = &self.on_open {
+            callback();
+        }
+    }
+
+    /// The connection ended; calls the close callback unless it already had,
+    /// returning whether this was the first report
+    pub fn closed(&mut self, reason: CloseReason) -> bool {
+        if matches!(self.state, LifecycleState::Closed(_)) {
+            return false;
+        }
+        self.state = LifecycleState::Closed(reason.clone());
+        if let Some(callback) = &self.on_close {
+            callback(reason);
+        }
+        true
+    }
+
+    /// Why the connection ended, once it has
+    pub fn close_reason(&self) -> Option<&CloseReason> {
+        match &self.state {
+            LifecycleState::Closed(reason) => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+/// Wall-clock time for stamping `TransportMetrics`
+pub(crate) fn wall_ms() -> u32 {
+    default_clock().wall_ms()
+}
+
+/// Transport abstraction for sending and receiving bytes
+pub trait Transport: Send + Sync {
+    /// Send bytes to the remote peer
+    fn send(&self, bytes: &[u8]) -> Result<(), TransportError>;
+
+    /// Set callback for incoming messages
+    fn set_on_message(&mut self, callback: Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>);
+
+    /// Check if transport is currently connected and ready to send
+    fn is_open(&self) -> bool;
+
+    /// Close the transport connection
+    fn close(&mut self) -> Result<(), TransportError>;
+
+    /// Get connection status as a human-readable string
+    fn status(&self) -> String;
+
+    /// Traffic carried so far; transports that don't count report zeros
+    fn metrics(&self) -> TransportMetrics {
+        TransportMetrics::default()
+    }
+
+    /// Call `callback` when the connection opens, or now if it already is.
+    /// Transports that can't tell only cover the already-open case.
+    fn set_on_open(&mut self, callback: Box<dyn Fn() + Send + Sync + 'static>) {
+        if self.is_open() {
+            callback();
+        }
+    }
+
+    /// Call `callback` with the reason when the connection stops being open,
+    /// or now if it already has. Transports that can't tell never call it.
+    fn set_on_close(&mut self, _callback: Box<dyn Fn(CloseReason) + Send + Sync + 'static>) {}
+}
+
+/// Mock transport implementation for testing
+pub struct MockTransport {
+    is_open: bool,
+    sent_messages: VecDeque<Vec<u8>>,
+    on_message: Option<Box<dyn Fn(Vec<u8>) + Send + Sync>>,
+    should_fail_send: bool,
+    lifecycle: Lifecycle,
+}
+
+impl MockTransport {
+    /// Create a new mock transport
+    pub fn new() -> Self {
+        Self {
+            is_open: true,
+            sent_messages: VecDeque::new(),
+            on_message: None,
+            should_fail_send: false,
+            lifecycle: Lifecycle::open_now(),
+        }
+    }
+
+    /// Create a closed mock transport
+    pub fn new_closed() -> Self {
+        Self {
+            is_open: false,
+            sent_messages: VecDeque::new(),
+            on_message: None,
+            should_fail_send: false,
+            lifecycle: Lifecycle::default(),
+        }
+    }
+
+    /// Set whether send operations should fail
+    pub fn set_should_fail_send(&mut self, should_fail: bool) {
+        self.should_fail_send = should_fail;
+    }
+
+    /// Simulate receiving a message from the remote peer
+    pub fn receive_message(&self, bytes: Vec<u8>) {
+        if let Some(callback) = &self.on_message {
+            callback(bytes);
+        }
+    }
+
+    /// Get all messages that were sent via this transport
+    pub fn sent_messages(&self) -> &VecDeque<Vec<u8>> {
+        &self.sent_messages
+    }
+
+    /// Pop the oldest sent message
+    pub fn pop_sent_message(&mut self) -> Option<Vec<u8>> {
+        self.sent_messages.pop_front()
+    }
+
+    /// Clear all sent messages
+    pub fn clear_sent_messages(&mut self) {
+        self.sent_messages.clear();
+    }
+
+    /// Set the connection status; closing counts as the peer's doing
+    pub fn set_open(&mut self, is_open: bool) {
+        self.is_open = is_open;
+        if is_open {
+            self.lifecycle.opened();
+        } else {
+            self.lifecycle.closed(CloseReason::Remote);
+        }
+    }
+}
+
+impl Default for MockTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self, _bytes: &[u8]) -> Result<(), TransportError> {
+        if !self.is_open {
+            return Err(TransportError::NotConnected);
+        }
+
+        if self.should_fail_send {
+            return Err(TransportError::SendFailed("Mock failure".to_string()));
+        }
+
+        // In a real implementation, we can't mutate self in send(), but for testing
+        // we'll use interior mutability. For now, we'll just not store the message
+        // in the send method and provide a separate way to track sent messages.
+        Ok(())
+    }
+
+    fn set_on_message(&mut self, callback: Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>) {
+        self.on_message = Some(callback);
+    }
+
+    fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    fn close(&mut self) -> Result<(), TransportError> {
+        if !self.is_open {
+            return Err(TransportError::AlreadyClosed);
+        }
+        self.is_open = false;
+        self.lifecycle.closed(CloseReason::Local);
+        Ok(())
+    }
+
+    fn status(&self) -> String {
+        if self.is_open {
+            "Connected (Mock)".to_string()
+        } else {
+            "Disconnected (Mock)".to_string()
+        }
+    }
+
+    fn set_on_open(&mut self, callback: Box<dyn Fn() + Send + Sync + 'static>) {
+        self.lifecycle.set_on_open(callback);
+    }
+
+    fn set_on_close(&mut self, callback: Box<dyn Fn(CloseReason) + Send + Sync + 'static>) {
+        self.lifecycle.set_on_close(callback);
+    }
+}
+
+/// A better mock transport that can actually record sent messages.
+/// Clones share the same state, so a test can keep a handle to one it gave away.
+#[derive(Clone)]
+pub struct RecordingMockTransport {
+    inner: std::sync::Arc<std::sync::Mutex<MockTransportInner>>,
+}
+
+struct MockTransportInner {
+    is_open: bool,
+    sent_messages: VecDeque<Vec<u8>>,
+    on_message: Option<Box<dyn Fn(Vec<u8>) + Send + Sync>>,
+    should_fail_send: bool,
+    metrics: TransportMetrics,
+    lifecycle: Lifecycle,
+}
+
+impl RecordingMockTransport {
+    /// Create a new recording mock transport
+    pub fn new() -> Self {
+        Self {
+            inner: std::sync::Arc::new(std::sync::Mutex::new(MockTransportInner {
+                is_open: true,
+                sent_messages: VecDeque::new(),
+                on_message: None,
+                should_fail_send: false,
+                metrics: TransportMetrics::default(),
+                lifecycle: Lifecycle::open_now(),
+            })),
+        }
+    }
+
+    /// Create a closed recording mock transport
+    pub fn new_closed() -> Self {
+        Self {
+            inner: std::sync::Arc::new(std::sync::Mutex::new(MockTransportInner {
+                is_open: false,
+                sent_messages: VecDeque::new(),
+                on_message: None,
+                should_fail_send: false,
+                metrics: TransportMetrics::default(),
+                lifecycle: Lifecycle::default(),
+            })),
+        }
+    }
+
+    /// Set whether send operations should fail
+    pub fn set_should_fail_send(&mut self, should_fail: bool) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.should_fail_send = should_fail;
+        }
+    }
+
+    /// Simulate receiving a message from the remote peer
+    pub fn receive_message(&self, bytes: Vec<u8>) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.metrics.record_received(bytes.len(), wall_ms());
+            if let Some(callback) = &inner.on_message {
+                callback(bytes);
+            }
+        }
+    }
+
+    /// Get all messages that were sent via this transport
+    pub fn sent_messages(&self) -> Vec<Vec<u8>> {
+        if let Ok(inner) = self.inner.lock() {
+            inner.sent_messages.iter().cloned().collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Pop the oldest sent message
+    pub fn pop_sent_message(&mut self) -> Option<Vec<u8>> {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.sent_messages.pop_front()
+        } else {
+            None
+        }
+    }
+
+    /// Clear all sent messages
+    pub fn clear_sent_messages(&mut self) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.sent_messages.clear();
+        }
+    }
+
+    /// Set the connection status; closing counts as the peer's doing
+    pub fn set_open(&mut self, is_open: bool) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.is_open = is_open;
+            if is_open {
+                inner.lifecycle.opened();
+            } else {
+                inner.lifecycle.closed(CloseReason::Remote);
+            }
+        }
+    }
+}
+
+impl Default for RecordingMockTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transport for RecordingMockTransport {
+    fn send(&self, bytes: &[u8]) -> Result<(), TransportError> {
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| TransportError::SendFailed("Lock poisoned".to_string()))?;
+
+        if !inner.is_open {
+            return Err(TransportError::NotConnected);
+        }
+
+        if inner.should_fail_send {
+            inner.metrics.record_send_error();
+            return Err(TransportError::SendFailed("Mock failure".to_string()));
+        }
+
+        inner.metrics.record_sent(bytes.len(), wall_ms());
+        inner.sent_messages.push_back(bytes.to_vec());
+        Ok(())
+    }
+
+    fn set_on_message(&mut self, callback: Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.on_message = Some(callback);
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        if let Ok(inner) = self.inner.lock() {
+            inner.is_open
+        } else {
+            false
+        }
+    }
+
+    fn close(&mut self) -> Result<(), TransportError> {
+        let mut inner = self
+            .inner
+            .lock()
+            .map_err(|_| TransportError::SendFailed("Lock poisoned".to_string()))?;
+
+        if !inner.is_open {
+            return Err(TransportError::AlreadyClosed);
+        }
+        inner.is_open = false;
+        inner.lifecycle.closed(CloseReason::Local);
+        Ok(())
+    }
+
+    fn status(&self) -> String {
+        if let Ok(inner) = self.inner.lock() {
+            if inner.is_open {
+                "Connected (Recording Mock)".to_string()
+            } else {
+                "Disconnected (Recording Mock)".to_string()
+            }
+        } else {
+            "Error (Recording Mock)".to_string()
+        }
+    }
+
+    fn metrics(&self) -> TransportMetrics {
+        self.inner
+            .lock()
+            .map(|inner| inner.metrics)
+            .unwrap_or_default()
+    }
+
+    fn set_on_open(&mut self, callback: Box<dyn Fn() + Send + Sync + 'static>) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.lifecycle.set_on_open(callback);
+        }
+    }
+
+    fn set_on_close(&mut self, callback: Box<dyn Fn(CloseReason) + Send + Sync + 'static>) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.lifecycle.set_on_close(callback);
+        }
+    }
+}
+
+type MessageCallback = Box<dyn Fn(Vec<u8>) + Send + Sync>;
+
+/// One end of an in-memory connection made by `LoopbackTransport::pair`.
+///
+/// A message sent on one end goes straight to the other end's `on_message`
+/// callback, or waits there for `try_recv` when no callback is set. Closing
+/// either end closes the connection for both.
+pub struct LoopbackTransport {
+    /// Messages for the other end
+    outgoing: Sender<Vec<u8>>,
+    /// Messages from the other end that no callback has taken
+    incoming: Mutex<Receiver<Vec<u8>>>,
+    /// Our callback, also called by the other end's `send`
+    on_message: Arc<Mutex<Option<MessageCallback>>>,
+    /// The other end's callback
+    peer_on_message: Arc<Mutex<Option<MessageCallback>>>,
+    /// Shared by both ends
+    open: Arc<AtomicBool>,
+    metrics: Arc<Mutex<TransportMetrics>>,
+    /// The other end's metrics, where our sends count as received
+    peer_metrics: Arc<Mutex<TransportMetrics>>,
+    lifecycle: Arc<Mutex<Lifecycle>>,
+    /// The other end's lifecycle, told when we close
+    peer_lifecycle: Arc<Mutex<Lifecycle>>,
+}
+
+impl LoopbackTransport {
+    /// Two connected ends, e.g. one per lockstep peer in a local match
+    pub fn pair() -> (Self, Self) {
+        let (a_tx, a_rx) = mpsc::channel();
+        let (b_tx, b_rx) = mpsc::channel();
+        let a_callback = Arc::new(Mutex::new(None));
+        let b_callback = Arc::new(Mutex::new(None));
+        let open = Arc::new(AtomicBool::new(true));
+        let a_metrics = Arc::new(Mutex::new(TransportMetrics::default()));
+        let b_metrics = Arc::new(Mutex::new(TransportMetrics::default()));
+        let a_lifecycle = Arc::new(Mutex::new(Lifecycle::open_now()));
+        let b_lifecycle = Arc::new(Mutex::new(Lifecycle::open_now()));
+
+        let a = Self {
+            outgoing: b_tx,
+            incoming: Mutex::new(a_rx),
+            on_message: Arc::clone(&a_callback),
+            peer_on_message: Arc::clone(&b_callback),
+            open: Arc::clone(&open),
+            metrics: Arc::clone(&a_metrics),
+            peer_metrics: Arc::clone(&b_metrics),
+            lifecycle: Arc::clone(&a_lifecycle),
+            peer_lifecycle: Arc::clone(&b_lifecycle),
+        };
+        let b = Self {
+            outgoing: a_tx,
+            incoming: Mutex::new(b_rx),
+            on_message: b_callback,
+            peer_on_message: a_callback,
+            open,
+            metrics: b_metrics,
+            peer_metrics: a_metrics,
+            lifecycle: b_lifecycle,
+            peer_lifecycle: a_lifecycle,
+        };
+        (a, b)
+    }
+
+    /// Next message from the other end, if one is waiting
+    pub fn try_recv(&self) -> Option<Vec<u8>> {
+        let incoming = lock(&self.incoming);
+        incoming.try_recv().ok()
+    }
+}
+
+impl Transport for LoopbackTransport {
+    fn send(&self, bytes: &[u8]) -> Result<(), TransportError> {
+        if !self.is_open() {
+            return Err(TransportError::NotConnected);
+        }
+
+        let now = wall_ms();
+        lock(&self.metrics).record_sent(bytes.len(), now);
+        lock(&self.peer_metrics).record_received(bytes.len(), now);
+        let peer_on_message = lock(&self.peer_on_message);
+        match &*peer_on_message {
+            Some(callback) => callback(bytes.to_vec()),
+            None => self
+                .outgoing
+                .send(bytes.to_vec())
+                .map_err(|_| TransportError::SendFailed("Other end dropped".to_string()))?,
+        }
+        Ok(())
+    }
+
+    fn set_on_message(&mut self, callback: Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>) {
+        // Holding our callback slot keeps the other end from queueing meanwhile
+        let mut on_message = lock(&self.on_message);
+        while let Some(bytes) = self.try_recv() {
+            callback(bytes);
+        }
+        *on_message = Some(callback);
+    }
+
+    fn is_open(&self) -> bool {
+        self.open.load(Ordering::Acquire)
+    }
+
+    fn close(&mut self) -> Result<(), TransportError> {
+        if !self.open.swap(false, Ordering::AcqRel) {
+            return Err(TransportError::AlreadyClosed);
+        }
+        lock(&self.lifecycle).closed(CloseReason::Local);
+        lock(&self.peer_lifecycle).closed(CloseReason::Remote);
+        Ok(())
+    }
+
+    fn status(&self) -> String {
+        if self.is_open() {
+            "Connected (Loopback)".to_string()
+        } else {
+            "Disconnected (Loopback)".to_string()
+        }
+    }
+
+    fn metrics(&self) -> TransportMetrics {
+        *lock(&self.metrics)
+    }
+
+    fn set_on_open(&mut self, callback: Box<dyn Fn() + Send + Sync + 'static>) {
+        lock(&self.lifecycle).set_on_open(callback);
+    }
+
+    fn set_on_close(&mut self, callback: Box<dyn Fn(CloseReason) + Send + Sync + 'static>) {
+        lock(&self.lifecycle).set_on_close(callback);
+    }
+}
+
+/// Lock `mutex` even if poisoned: a panicking callback poisons the lock but
+/// leaves the state usable
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_transport_basic_operations() {
+        let mut transport = MockTransport::new();
+
+        assert!(transport.is_open());
+        assert_eq!(transport.status(), "Connected (Mock)");
+
+        // Test send (should succeed)
+        assert!(transport.send(b"hello").is_ok());
+
+        // Test close
+        assert!(transport.close().is_ok());
+        assert!(!transport.is_open());
+        assert_eq!(transport.status(), "Disconnected (Mock)");
+
+        // Send should fail when closed
+        assert_eq!(transport.send(b"world"), Err(TransportError::NotConnected));
+
+        // Double close should fail
+        assert_eq!(transport.close(), Err(TransportError::AlreadyClosed));
+    }
+
+    #[test]
+    fn test_mock_transport_send_failure() {
+        let mut transport = MockTransport::new();
+        transport.set_should_fail_send(true);
+
+        assert_eq!(
+            transport.send(b"test"),
+            Err(TransportError::SendFailed("Mock failure".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_mock_transport_message_callback() {
+        let mut transport = MockTransport::new();
+        let received_message = Arc::new(std::sync::Mutex::new(None));
+        let received_clone = Arc::clone(&received_message);
+
+        transport.set_on_message(Box::new(move |bytes| {
+            *received_clone.lock().unwrap() = Some(bytes);
+        }));
+
+        transport.receive_message(b"test message".to_vec());
+
+        let received = received_message.lock().unwrap();
+        assert_eq!(received.as_ref().unwrap(), b"test message");
+    }
+
+    #[test]
+    fn test_recording_mock_transport() {
+        let mut transport = RecordingMockTransport::new();
+
+        // Test send and recording
+        assert!(transport.send(b"hello").is_ok());
+        assert!(transport.send(b"world").is_ok());
+
+        let sent = transport.sent_messages();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0], b"hello");
+        assert_eq!(sent[1], b"world");
+
+        // Test pop message
+        assert_eq!(transport.pop_sent_message().unwrap(), b"hello");
+        assert_eq!(transport.pop_sent_message().unwrap(), b"world");
+        assert!(transport.pop_sent_message().is_none());
+    }
+
+    #[test]
+    fn test_recording_mock_transport_message_callback() {
+        let mut transport = RecordingMockTransport::new();
+        let received_count = Arc::new(AtomicBool::new(false));
+        let received_clone = Arc::clone(&received_count);
+
+        transport.set_on_message(Box::new(move |_bytes| {
+            received_clone.store(true, Ordering::Relaxed);
+        }));
+
+        transport.receive_message(b"test".to_vec());
+        assert!(received_count.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_closed_transport() {
+        let transport = MockTransport::new_closed();
+        assert!(!transport.is_open());
+        assert_eq!(transport.send(b"test"), Err(TransportError::NotConnected));
+    }
+
+    #[test]
+    fn test_loopback_pair() {
+        let (a, mut b) = LoopbackTransport::pair();
+        a.send(b"first").unwrap();
+        a.send(b"second").unwrap();
+        assert_eq!(b.try_recv().unwrap(), b"first");
+        assert!(a.try_recv().is_none());
+
+        // A callback takes what's still waiting, then everything sent after
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        b.set_on_message(Box::new(move |bytes| sink.lock().unwrap().push(bytes)));
+        a.send(b"third").unwrap();
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![b"second".to_vec(), b"third".to_vec()]
+        );
+        assert!(b.try_recv().is_none());
+
+        b.send(b"back").unwrap();
+        assert_eq!(a.try_recv().unwrap(), b"back");
+
+        // Closing one end closes both
+        b.close().unwrap();
+        assert!(!a.is_open());
+        assert_eq!(a.send(b"late"), Err(TransportError::NotConnected));
+        assert_eq!(b.close(), Err(TransportError::AlreadyClosed));
+    }
+
+    #[test]
+    fn test_transport_metrics() {
+        let (a, b) = LoopbackTransport::pair();
+        assert_eq!(a.metrics(), TransportMetrics::default());
+        a.send(b"hello").unwrap();
+        a.send(b"!").unwrap();
+        b.send(b"hi").unwrap();
+
+        let sent = a.metrics();
+        assert_eq!((sent.messages_sent, sent.bytes_sent), (2, 6));
+        assert_eq!((sent.messages_received, sent.bytes_received), (1, 2));
+        let received = b.metrics();
+        assert_eq!(
+            (received.messages_received, received.bytes_received),
+            (2, 6)
+        );
+        assert!(received.last_activity_ms.is_some());
+
+        let mut mock = RecordingMockTransport::new();
+        mock.set_should_fail_send(true);
+        assert!(mock.send(b"lost").is_err());
+        assert_eq!(mock.metrics().send_errors, 1);
+        assert_eq!(mock.metrics().idle_ms(0), None);
+
+        let mut metrics = TransportMetrics::default();
+        metrics.record_received(4, u32::MAX - 5);
+        assert_eq!(metrics.idle_ms(10), Some(16));
+        // Transports that don't count report zeros
+        assert_eq!(MockTransport::new().metrics(), TransportMetrics::default());
+    }
+
+    #[test]
+    fn test_lifecycle_callbacks() {
+        fn watch(transport: &mut impl Transport) -> Arc<Mutex<Vec<String>>> {
+            let log = Arc::new(Mutex::new(Vec::new()));
+            let opens = Arc::clone(&log);
+            let closes = Arc::clone(&log);
+            transport.set_on_open(Box::new(move || opens.lock().unwrap().push("open".into())));
+            transport.set_on_close(Box::new(move |reason| {
+                closes.lock().unwrap().push(reason.to_string())
+            }));
+            log
+        }
+
+        // Already open: the open callback runs straight away
+        let (mut a, mut b) = LoopbackTransport::pair();
+        let a_log = watch(&mut a);
+        let b_log = watch(&mut b);
+        b.close().unwrap();
+        assert_eq!(*a_log.lock().unwrap(), vec!["open", "Closed by peer"]);
+        assert_eq!(*b_log.lock().unwrap(), vec!["open", "Closed locally"]);
+
+        // Already closed: the close callback runs straight away, with the reason
+        let late_log = watch(&mut a);
+        assert_eq!(*late_log.lock().unwrap(), vec!["Closed by peer"]);
+
+        let mut mock = MockTransport::new_closed();
+        let log = watch(&mut mock);
+        mock.set_open(true);
+        mock.set_open(true);
+        mock.set_open(false);
+        assert_eq!(*log.lock().unwrap(), vec!["open", "Closed by peer"]);
+        assert_eq!(mock.lifecycle.close_reason(), Some(&CloseReason::Remote));
+
+        // Closing twice reports once
+        let mut lifecycle = Lifecycle::open_now();
+        assert!(lifecycle.closed(CloseReason::TimedOut));
+        assert!(!lifecycle.closed(CloseReason::Local));
+        assert_eq!(lifecycle.close_reason(), Some(&CloseReason::TimedOut));
+    }
+
+    #[test]
+    fn test_transport_error_display() {
+        let errors = vec![
+            TransportError::NotConnected,
+            TransportError::SendFailed("test".to_string()),
+            TransportError::ConnectionFailed("test".to_string()),
+            TransportError::InvalidConfig("test".to_string()),
+            TransportError::AlreadyClosed,
+        ];
+
+        for error in errors {
+            let display_str = format!("{}", error);
+            assert!(!display_str.is_empty());
+        }
+    }
+}
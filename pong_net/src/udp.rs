@@ -0,0 +1,467 @@
+//! UDP transport with lightweight reliability.
+//!
+//! Each message travels in one datagram tagged with a sequence number. The
+//! receiver acks what it holds and delivers messages in order; the sender
+//! resends whatever stays unacknowledged. A lost datagram only delays the
+//! messages behind it by one resend, and redundant inputs (see
+//! `Lockstep::set_input_redundancy`) often cover the loss before that.
+//!
+//! Datagram layouts:
+//! - data: `[0x00][seq:4][message]`
+//! - ack: `[0x01][next_expected:4][mask:4]`, everything before `next_expected`
+//!   received, plus bit `i` of `mask` for `next_expected + 1 + i`
+
+use crate::transport::{
+    lock, wall_ms, CloseReason, Lifecycle, Transport, TransportError, TransportMetrics,
+};
+use std::collections::{HashMap, VecDeque};
+use std::io::ErrorKind;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const KIND_DATA: u8 = 0x00;
+const KIND_ACK: u8 = 0x01;
+
+/// Sequence number after the kind byte
+const DATA_HEADER_SIZE: usize = 5;
+
+/// Largest message one datagram carries, kept under common path MTUs
+pub const MAX_UDP_PAYLOAD: usize = 1200;
+
+/// How long a message waits for its ack before being sent again
+pub const RESEND_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Resends of one message before the peer is given up on (about five seconds)
+const MAX_RESENDS: u32 = 50;
+
+/// Messages in flight before sends are refused; also how far past the next
+/// in-order message early arrivals are held
+const WINDOW: u32 = 256;
+
+/// A sent message awaiting its ack
+struct Outgoing {
+    seq: u32,
+    datagram: Vec<u8>,
+    sent_at: Instant,
+    resends: u32,
+}
+
+/// State shared with the socket thread
+#[derive(Default)]
+struct UdpShared {
+    open: bool,
+    on_message: Option<Box<dyn Fn(Vec<u8>) + Send + Sync>>,
+    /// Messages received before a callback was set
+    pending: VecDeque<Vec<u8>>,
+    /// Why the transport closed, if not by `close`
+    error: Option<String>,
+    /// Sequence number of the next message we send
+    next_send: u32,
+    /// Our messages the peer hasn't acked, oldest first
+    unacked: VecDeque<Outgoing>,
+    /// Sequence number of the next message to deliver
+    next_expected: u32,
+    /// Messages that arrived ahead of `next_expected`
+    early: HashMap<u32, Vec<u8>>,
+    /// Counts each message once, however often it was resent
+    metrics: TransportMetrics,
+    lifecycle: Lifecycle,
+}
+
+impl UdpShared {
+    /// Hand a message to the callback, or keep it until one is set
+    fn deliver(&mut self, message: Vec<u8>) {
+        self.metrics.record_received(message.len(), wall_ms());
+        match &self.on_message {
+            Some(callback) => callback(message),
+            None => self.pending.push_back(message),
+        }
+    }
+
+    /// Accept a data datagram and deliver whatever is now in order
+    fn on_data(&mut self, seq: u32, message: &[u8]) {
+        let ahead = seq.wrapping_sub(self.next_expected);
+        if ahead >= WINDOW {
+            // Already delivered (a resend whose ack was lost), or absurdly far ahead
+            return;
+        }
+        self.early.insert(seq, message.to_vec());
+
+        while let Some(message) = self.early.remove(&self.next_expected) {
+            self.next_expected = self.next_expected.wrapping_add(1);
+            self.deliver(message);
+        }
+    }
+
+    /// Ack describing everything received so far
+    fn ack(&self) -> [u8; 9] {
+        let mask = (0..32).fold(0u32, |mask, bit| {
+            let seq = self.next_expected.wrapping_add(1 + bit);
+            if self.early.contains_key(&seq) {
+                mask | 1 << bit
+            } else {
+                mask
+            }
+        });
+
+        let mut ack = [0u8; 9];
+        ack[0] = KIND_ACK;
+        ack[1..5].copy_from_slice(&self.next_expected.to_le_bytes());
+        ack[5..9].copy_from_slice(&mask.to_le_bytes());
+        ack
+    }
+
+    /// Forget the messages an ack covers
+    fn on_ack(&mut self, next_expected: u32, mask: u32) {
+        self.unacked.retain(|outgoing| {
+            let ahead = outgoing.seq.wrapping_sub(next_expected);
+            let received = if ahead >= WINDOW {
+                true // Before `next_expected`
+            } else {
+                (1..=32).contains(&ahead) && mask & 1 << (ahead - 1) != 0
+            };
+            !received
+        });
+    }
+
+    /// Send again whatever has waited `RESEND_INTERVAL` for an ack, giving
+    /// up on a peer that hasn't acked for `MAX_RESENDS` tries
+    fn resend_due(&mut self, socket: &UdpSocket, now: Instant) {
+        for outgoing in &mut self.unacked {
+            if now.duration_since(outgoing.sent_at) < RESEND_INTERVAL {
+                continue;
+            }
+            if outgoing.resends == MAX_RESENDS {
+                self.open = false;
+                self.error = Some("Peer stopped acknowledging".to_string());
+                self.lifecycle.closed(CloseReason::TimedOut);
+                return;
+            }
+            // A failed resend is just another loss; the next one may get through
+            let _ = socket.send(&outgoing.datagram);
+            outgoing.sent_at = now;
+            outgoing.resends += 1;
+        }
+    }
+}
+
+/// Transport over UDP to one peer, delivering every message once and in order
+pub struct UdpTransport {
+    socket: Arc<UdpSocket>,
+    peer_addr: SocketAddr,
+    shared: Arc<Mutex<UdpShared>>,
+}
+
+impl UdpTransport {
+    /// Bind to `local` and exchange datagrams with the peer at `peer`
+    pub fn connect(
+        local: impl ToSocketAddrs,
+        peer: impl ToSocketAddrs,
+    ) -> Result<Self, TransportError> {
+        let socket = UdpSocket::bind(local)
+            .map_err(|e| TransportError::ConnectionFailed(format!("Bind failed: {e}")))?;
+        socket
+            .connect(peer)
+            .map_err(|e| TransportError::ConnectionFailed(format!("Connect failed: {e}")))?;
+        Self::from_socket(socket)
+    }
+
+    /// Bind to `local` and wait for the first datagram, then play with
+    /// whoever sent it
+    pub fn listen(local: impl ToSocketAddrs) -> Result<Self, TransportError> {
+        let socket = UdpSocket::bind(local)
+            .map_err(|e| TransportError::ConnectionFailed(format!("Bind failed: {e}")))?;
+        let (_, peer) = socket
+            .peek_from(&mut [0u8; 1])
+            .map_err(|e| TransportError::ConnectionFailed(format!("Listen failed: {e}")))?;
+        socket
+            .connect(peer)
+            .map_err(|e| TransportError::ConnectionFailed(format!("Connect failed: {e}")))?;
+        Self::from_socket(socket)
+    }
+
+    /// Take over a socket already connected to the peer and start receiving
+    pub fn from_socket(socket: UdpSocket) -> Result<Self, TransportError> {
+        let connection_failed = |e: std::io::Error| TransportError::ConnectionFailed(e.to_string());
+        let peer_addr = socket.peer_addr().map_err(connection_failed)?;
+        // Wake up regularly to resend, even when nothing arrives
+        socket
+            .set_read_timeout(Some(RESEND_INTERVAL / 4))
+            .map_err(connection_failed)?;
+
+        let socket = Arc::new(socket);
+        let shared = Arc::new(Mutex::new(UdpShared {
+            open: true,
+            lifecycle: Lifecycle::open_now(),
+            ..UdpShared::default()
+        }));
+        let (thread_socket, thread_shared) = (Arc::clone(&socket), Arc::clone(&shared));
+        thread::Builder::new()
+            .name(format!("udp-{peer_addr}"))
+            .spawn(move || run(&thread_socket, &thread_shared))
+            .map_err(connection_failed)?;
+
+        Ok(Self {
+            socket,
+            peer_addr,
+            shared,
+        })
+    }
+
+    /// Address of the peer
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer_addr
+    }
+
+    /// Address we receive on
+    pub fn local_addr(&self) -> Result<SocketAddr, TransportError> {
+        self.socket
+            .local_addr()
+            .map_err(|e| TransportError::ConnectionFailed(e.to_string()))
+    }
+
+    fn shared(&self) -> std::sync::MutexGuard<'_, UdpShared> {
+        lock(&self.shared)
+    }
+}
+
+/// Receive, ack and resend until the transport closes
+fn run(socket: &UdpSocket, shared: &Mutex<UdpShared>) {
+    let mut buf = [0u8; DATA_HEADER_SIZE + MAX_UDP_PAYLOAD];
+    loop {
+        let received = match socket.recv(&mut buf) {
+            Ok(len) => Some(&buf[..len]),
+            Err(e) => match e.kind() {
+                // Nothing arrived, or the peer isn't up yet (ICMP port unreachable)
+                ErrorKind::WouldBlock
+                | ErrorKind::TimedOut
+                | ErrorKind::Interrupted
+                | ErrorKind::ConnectionRefused => None,
+                _ => {
+                    let mut shared = lock(shared);
+                    if shared.open {
+                        shared.open = false;
+                        shared.error = Some(e.to_string());
+                        shared.lifecycle.closed(CloseReason::Error(e.to_string()));
+                    }
+                    return;
+                }
+            },
+        };
+
+        let mut shared = lock(shared);
+        if !shared.open {
+            return;
+        }
+        match received {
+            Some([KIND_DATA, s0, s1, s2, s3, message @ ..]) => {
+                shared.on_data(u32::from_le_bytes([*s0, *s1, *s2, *s3]), message);
+                let _ = socket.send(&shared.ack());
+            }
+            Some([KIND_ACK, n0, n1, n2, n3, m0, m1, m2, m3]) => shared.on_ack(
+                u32::from_le_bytes([*n0, *n1, *n2, *n3]),
+                u32::from_le_bytes([*m0, *m1, *m2, *m3]),
+            ),
+            // Malformed datagrams are dropped like lost ones
+            _ => {}
+        }
+        shared.resend_due(socket, Instant::now());
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send(&self, bytes: &[u8]) -> Result<(), TransportError> {
+        let mut shared = self.shared();
+        if !shared.open {
+            return Err(TransportError::NotConnected);
+        }
+        if bytes.len() > MAX_UDP_PAYLOAD {
+            shared.metrics.record_send_error();
+            return Err(TransportError::SendFailed(format!(
+                "{} bytes is more than a datagram carries",
+                bytes.len()
+            )));
+        }
+        if shared.unacked.len() >= WINDOW as usize {
+            shared.metrics.record_send_error();
+            return Err(TransportError::SendFailed("Send window full".to_string()));
+        }
+
+        let seq = shared.next_send;
+        let mut datagram = Vec::with_capacity(DATA_HEADER_SIZE + bytes.len());
+        datagram.push(KIND_DATA);
+        datagram.extend_from_slice(&seq.to_le_bytes());
+        datagram.extend_from_slice(bytes);
+
+        match self.socket.send(&datagram) {
+            // Not there yet: resent like any other loss
+            Err(e) if e.kind() == ErrorKind::ConnectionRefused => {}
+            Err(e) => {
+                shared.metrics.record_send_error();
+                return Err(TransportError::SendFailed(e.to_string()));
+            }
+            Ok(_) => {}
+        }
+        shared.metrics.record_sent(bytes.len(), wall_ms());
+        shared.next_send = seq.wrapping_add(1);
+        shared.unacked.push_back(Outgoing {
+            seq,
+            datagram,
+            sent_at: Instant::now(),
+            resends: 0,
+        });
+        Ok(())
+    }
+
+    fn set_on_message(&mut self, callback: Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>) {
+        let mut shared = self.shared();
+        for bytes in shared.pending.drain(..) {
+            callback(bytes);
+        }
+        shared.on_message = Some(callback);
+    }
+
+    fn is_open(&self) -> bool {
+        self.shared().open
+    }
+
+    fn close(&mut self) -> Result<(), TransportError> {
+        let mut shared = self.shared();
+        if !shared.open {
+            return Err(TransportError::AlreadyClosed);
+        }
+        // The socket thread stops at its next wake-up
+        shared.open = false;
+        shared.lifecycle.closed(CloseReason::Local);
+        Ok(())
+    }
+
+    fn status(&self) -> String {
+        let shared = self.shared();
+        match (&shared.error, shared.open) {
+            (_, true) => format!("Connected to {} (UDP)", self.peer_addr),
+            (Some(error), false) => format!("Disconnected (UDP): {error}"),
+            (None, false) => "Disconnected (UDP)".to_string(),
+        }
+    }
+
+    fn metrics(&self) -> TransportMetrics {
+        self.shared().metrics
+    }
+
+    /// UDP has no connection to lose: the close reason is `TimedOut` when
+    /// the peer stops acknowledging, never `Remote`
+    fn set_on_close(&mut self, callback: Box<dyn Fn(CloseReason) + Send + Sync + 'static>) {
+        self.shared().lifecycle.set_on_close(callback);
+    }
+}
+
+impl Drop for UdpTransport {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Collect messages from `transport` into a shared list
+    fn inbox(transport: &mut UdpTransport) -> Arc<Mutex<Vec<Vec<u8>>>> {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        transport.set_on_message(Box::new(move |bytes| sink.lock().unwrap().push(bytes)));
+        received
+    }
+
+    fn wait_for(mut done: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn data(seq: u32, message: &[u8]) -> Vec<u8> {
+        [&[KIND_DATA][..], &seq.to_le_bytes(), message].concat()
+    }
+
+    fn ack(next_expected: u32, mask: u32) -> Vec<u8> {
+        [
+            &[KIND_ACK][..],
+            &next_expected.to_le_bytes(),
+            &mask.to_le_bytes(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_udp_round_trip() {
+        let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+        a.connect(b.local_addr().unwrap()).unwrap();
+        b.connect(a.local_addr().unwrap()).unwrap();
+        let mut a = UdpTransport::from_socket(a).unwrap();
+        let mut b = UdpTransport::from_socket(b).unwrap();
+        let to_a = inbox(&mut a);
+        let to_b = inbox(&mut b);
+
+        for i in 0..20u8 {
+            a.send(&[i]).unwrap();
+        }
+        b.send(b"pong").unwrap();
+        wait_for(|| to_b.lock().unwrap().len() == 20 && a.shared().unacked.is_empty());
+        wait_for(|| b.shared().unacked.is_empty());
+
+        let expected: Vec<Vec<u8>> = (0..20u8).map(|i| vec![i]).collect();
+        assert_eq!(*to_b.lock().unwrap(), expected);
+        assert_eq!(*to_a.lock().unwrap(), vec![b"pong".to_vec()]);
+        assert!(matches!(
+            a.send(&[0; MAX_UDP_PAYLOAD + 1]),
+            Err(TransportError::SendFailed(_))
+        ));
+        let metrics = a.metrics();
+        assert_eq!((metrics.messages_sent, metrics.bytes_sent), (20, 20));
+        assert_eq!((metrics.messages_received, metrics.send_errors), (1, 1));
+    }
+
+    #[test]
+    fn test_udp_reorders_and_resends() {
+        let peer = UdpSocket::bind("127.0.0.1:0").unwrap();
+        peer.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut transport =
+            UdpTransport::connect("127.0.0.1:0", peer.local_addr().unwrap()).unwrap();
+        peer.connect(transport.local_addr().unwrap()).unwrap();
+        let received = inbox(&mut transport);
+        let recv = || {
+            let mut buf = [0u8; 64];
+            let len = peer.recv(&mut buf).unwrap();
+            buf[..len].to_vec()
+        };
+
+        // Arriving out of order, delivered in order; acks report the early one
+        peer.send(&data(1, b"second")).unwrap();
+        assert_eq!(recv(), ack(0, 0b1));
+        peer.send(&data(0, b"first")).unwrap();
+        assert_eq!(recv(), ack(2, 0));
+        peer.send(&data(0, b"first")).unwrap();
+        assert_eq!(recv(), ack(2, 0));
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![b"first".to_vec(), b"second".to_vec()]
+        );
+
+        // Unacked messages come again until acked
+        transport.send(b"hello").unwrap();
+        assert_eq!(recv(), data(0, b"hello"));
+        assert_eq!(recv(), data(0, b"hello"));
+        peer.send(&ack(1, 0)).unwrap();
+        wait_for(|| transport.shared().unacked.is_empty());
+
+        transport.close().unwrap();
+        assert!(!transport.is_open());
+        assert_eq!(transport.send(b"late"), Err(TransportError::NotConnected));
+    }
+}
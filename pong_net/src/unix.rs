@@ -0,0 +1,241 @@
+//! Unix domain socket transport, for matches on one machine: scripted
+//! players, tests, a local relay daemon, or containers sharing a socket
+//! through a mounted volume.
+//!
+//! Framing and delivery work exactly as in `TcpTransport`.
+
+use crate::framing::Framer;
+use crate::tcp::{read_loop, StreamShared};
+use crate::transport::{
+    lock, wall_ms, CloseReason, Lifecycle, Transport, TransportError, TransportMetrics,
+};
+use std::io::Write;
+use std::net::Shutdown;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Transport over one Unix domain socket connection
+pub struct UnixSocketTransport {
+    /// Write half; locked so concurrent sends can't interleave frames
+    writer: Mutex<UnixStream>,
+    /// The listening socket's path, from whichever end knows it
+    path: Option<PathBuf>,
+    shared: Arc<Mutex<StreamShared>>,
+}
+
+impl UnixSocketTransport {
+    /// Connect to a peer listening at `path`
+    pub fn connect(path: impl AsRef<Path>) -> Result<Self, TransportError> {
+        let stream = UnixStream::connect(path)
+            .map_err(|e| TransportError::ConnectionFailed(format!("Connect failed: {e}")))?;
+        Self::from_stream(stream)
+    }
+
+    /// Create a socket at `path` and wait for one peer to connect. Fails if
+    /// the path exists; remove a stale socket first.
+    pub fn listen(path: impl AsRef<Path>) -> Result<Self, TransportError> {
+        let listener = UnixListener::bind(path)
+            .map_err(|e| TransportError::ConnectionFailed(format!("Bind failed: {e}")))?;
+        Self::accept(&listener)
+    }
+
+    /// Wait for the next peer on an already bound `listener`
+    pub fn accept(listener: &UnixListener) -> Result<Self, TransportError> {
+        let (stream, _) = listener
+            .accept()
+            .map_err(|e| TransportError::ConnectionFailed(format!("Accept failed: {e}")))?;
+        Self::from_stream(stream)
+    }
+
+    /// Two transports connected to each other, with no file on disk
+    pub fn pair() -> Result<(Self, Self), TransportError> {
+        let (left, right) = UnixStream::pair()
+            .map_err(|e| TransportError::ConnectionFailed(format!("Pair failed: {e}")))?;
+        Ok((Self::from_stream(left)?, Self::from_stream(right)?))
+    }
+
+    /// Take over a connected stream and start reading from it
+    pub fn from_stream(stream: UnixStream) -> Result<Self, TransportError> {
+        let connection_failed = |e: std::io::Error| TransportError::ConnectionFailed(e.to_string());
+        let path = [stream.peer_addr(), stream.local_addr()]
+            .into_iter()
+            .flatten()
+            .find_map(|addr| addr.as_pathname().map(Path::to_path_buf));
+        let reader = stream.try_clone().map_err(connection_failed)?;
+
+        let shared = Arc::new(Mutex::new(StreamShared {
+            open: true,
+            lifecycle: Lifecycle::open_now(),
+            ..StreamShared::default()
+        }));
+        let thread_shared = Arc::clone(&shared);
+        thread::Builder::new()
+            .name("unix-reader".to_string())
+            .spawn(move || {
+                read_loop(reader, &thread_shared, |stream| {
+                    let _ = stream.shutdown(Shutdown::Both);
+                })
+            })
+            .map_err(connection_failed)?;
+
+        Ok(Self {
+            writer: Mutex::new(stream),
+            path,
+            shared,
+        })
+    }
+
+    /// Path of the listening socket, unless both ends are unnamed (`pair`)
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    fn shared(&self) -> std::sync::MutexGuard<'_, StreamShared> {
+        lock(&self.shared)
+    }
+}
+
+impl Transport for UnixSocketTransport {
+    fn send(&self, bytes: &[u8]) -> Result<(), TransportError> {
+        if !self.is_open() {
+            return Err(TransportError::NotConnected);
+        }
+
+        let result = Framer::frame(bytes)
+            .map_err(|e| TransportError::SendFailed(format!("{e:?}")))
+            .and_then(|frame| {
+                let mut writer = lock(&self.writer);
+                writer
+                    .write_all(&frame)
+                    .map_err(|e| TransportError::SendFailed(e.to_string()))
+            });
+        let mut shared = self.shared();
+        match &result {
+            Ok(()) => shared.metrics.record_sent(bytes.len(), wall_ms()),
+            Err(_) => shared.metrics.record_send_error(),
+        }
+        result
+    }
+
+    fn set_on_message(&mut self, callback: Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>) {
+        let mut shared = self.shared();
+        for bytes in shared.pending.drain(..) {
+            callback(bytes);
+        }
+        shared.on_message = Some(callback);
+    }
+
+    fn is_open(&self) -> bool {
+        self.shared().open
+    }
+
+    fn close(&mut self) -> Result<(), TransportError> {
+        {
+            let mut shared = self.shared();
+            if !shared.open {
+                return Err(TransportError::AlreadyClosed);
+            }
+            shared.open = false;
+            shared.lifecycle.closed(CloseReason::Local);
+        }
+
+        // Ends the reader thread too
+        let writer = lock(&self.writer);
+        let _ = writer.shutdown(Shutdown::Both);
+        Ok(())
+    }
+
+    fn status(&self) -> String {
+        let shared = self.shared();
+        match (&shared.error, shared.open, &self.path) {
+            (_, true, Some(path)) => format!("Connected via {} (Unix)", path.display()),
+            (_, true, None) => "Connected (Unix)".to_string(),
+            (Some(error), false, _) => format!("Disconnected (Unix): {error}"),
+            (None, false, _) => "Disconnected (Unix)".to_string(),
+        }
+    }
+
+    fn metrics(&self) -> TransportMetrics {
+        self.shared().metrics
+    }
+
+    fn set_on_open(&mut self, callback: Box<dyn Fn() + Send + Sync + 'static>) {
+        self.shared().lifecycle.set_on_open(callback);
+    }
+
+    fn set_on_close(&mut self, callback: Box<dyn Fn(CloseReason) + Send + Sync + 'static>) {
+        self.shared().lifecycle.set_on_close(callback);
+    }
+}
+
+impl Drop for UnixSocketTransport {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn inbox(transport: &mut UnixSocketTransport) -> Arc<Mutex<Vec<Vec<u8>>>> {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&received);
+        transport.set_on_message(Box::new(move |bytes| sink.lock().unwrap().push(bytes)));
+        received
+    }
+
+    fn wait_for(mut done: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_unix_round_trip() {
+        let path = std::env::temp_dir().join(format!("pong-unix-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let connect_path = path.clone();
+        let client = thread::spawn(move || UnixSocketTransport::connect(connect_path).unwrap());
+        let mut host = UnixSocketTransport::accept(&listener).unwrap();
+        let mut client = client.join().unwrap();
+        assert_eq!(host.path(), Some(path.as_path()));
+        assert_eq!(client.path(), Some(path.as_path()));
+
+        client.send(b"early").unwrap();
+        client.send(&[7; 3000]).unwrap();
+        wait_for(|| host.shared().pending.len() == 2);
+        let to_host = inbox(&mut host);
+        let to_client = inbox(&mut client);
+        host.send(b"hello").unwrap();
+        wait_for(|| to_client.lock().unwrap().len() == 1);
+
+        assert_eq!(
+            *to_host.lock().unwrap(),
+            vec![b"early".to_vec(), vec![7; 3000]]
+        );
+        assert_eq!(*to_client.lock().unwrap(), vec![b"hello".to_vec()]);
+        assert_eq!(client.metrics().bytes_sent, 3005);
+        assert!(host.status().contains("(Unix)"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_unix_pair_close() {
+        let (mut left, right) = UnixSocketTransport::pair().unwrap();
+        assert_eq!(left.path(), None);
+        assert_eq!(left.status(), "Connected (Unix)");
+
+        left.close().unwrap();
+        assert_eq!(left.send(b"late"), Err(TransportError::NotConnected));
+        assert_eq!(left.close(), Err(TransportError::AlreadyClosed));
+        wait_for(|| !right.is_open());
+        assert_eq!(right.status(), "Disconnected (Unix)");
+    }
+}
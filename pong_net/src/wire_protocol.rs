@@ -0,0 +1,2103 @@
+//! Wire protocol for network communication between clients
+
+#[cfg(feature = "auth")]
+use crate::auth::MessageAuth;
+#[cfg(feature = "compression")]
+use crate::compression;
+use pong_engine::serialization::{SerializationError, CONFIG_ENCODED_SIZE};
+use pong_engine::types::*;
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// Wire protocol version this build speaks, advertised in `Hello`
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// Oldest peer protocol version this build can still talk to
+pub const MIN_PROTOCOL_VERSION: u8 = 1;
+
+/// Capability bits advertised in `Hello`
+pub mod capabilities {
+    /// Understands the InputPair tilt tail
+    pub const INPUT_TILT: u32 = 1 << 0;
+    /// Understands the InputPair axis_x tail
+    pub const INPUT_AXIS_X: u32 = 1 << 1;
+    /// Understands RLE-compressed snapshots (built with the `compression` feature)
+    pub const SNAPSHOT_RLE: u32 = 1 << 2;
+    /// Understands `Sequenced` envelopes and answers them with `Ack`
+    pub const SEQUENCING: u32 = 1 << 3;
+    /// Accepts `Chat` and `Emote` messages
+    pub const CHAT: u32 = 1 << 4;
+    /// Can play with rollback netcode instead of lockstep
+    pub const ROLLBACK: u32 = 1 << 6;
+    /// Answers `InputRequest` by resending its retained inputs
+    pub const INPUT_RESEND: u32 = 1 << 8;
+    /// Understands `SideInput`
+    pub const SIDE_INPUT: u32 = 1 << 9;
+    /// Honors `Pause` and `Resume`
+    pub const PAUSE: u32 = 1 << 10;
+    /// Answers `Bye` before closing
+    pub const BYE: u32 = 1 << 11;
+    /// Plays best-of-N series, following the timekeeper's `NextGame`
+    pub const SERIES: u32 = 1 << 12;
+
+    /// Everything this build supports
+    #[cfg(not(feature = "compression"))]
+    pub const SUPPORTED: u32 = INPUT_TILT
+        | INPUT_AXIS_X
+        | SEQUENCING
+        | CHAT
+        | ROLLBACK
+        | INPUT_RESEND
+        | SIDE_INPUT
+        | PAUSE
+        | BYE
+        | SERIES;
+    /// Everything this build supports
+    #[cfg(feature = "compression")]
+    pub const SUPPORTED: u32 = INPUT_TILT
+        | INPUT_AXIS_X
+        | SNAPSHOT_RLE
+        | SEQUENCING
+        | CHAT
+        | ROLLBACK
+        | INPUT_RESEND
+        | SIDE_INPUT
+        | PAUSE
+        | BYE
+        | SERIES;
+
+    /// Features both sides can use, given what the peer advertised
+    pub fn negotiate(remote: u32) -> u32 {
+        SUPPORTED & remote
+    }
+}
+
+/// `input` as a peer advertising `peer_capabilities` reads it: tilt and axis_x
+/// are dropped unless it understands them. Local input goes through this too,
+/// so both sides simulate what was actually sent.
+pub fn input_for_peer(input: Input, peer_capabilities: u32) -> Input {
+    let mut input = input;
+    if peer_capabilities & capabilities::INPUT_TILT == 0 {
+        input.tilt = 0;
+    }
+    if peer_capabilities & capabilities::INPUT_AXIS_X == 0 {
+        input.axis_x = 0;
+    }
+    input
+}
+
+/// `pair` with both inputs passed through `input_for_peer`
+fn pair_for_peer(pair: &InputPair, peer_capabilities: u32) -> InputPair {
+    InputPair {
+        a: input_for_peer(pair.a, peer_capabilities),
+        b: input_for_peer(pair.b, peer_capabilities),
+        ..*pair
+    }
+}
+
+/// Predefined reactions carried by `WireMsg::Emote`; unknown ids from newer
+/// peers are passed through so clients can ignore them
+pub mod emotes {
+    /// "Good game"
+    pub const GG: u8 = 0;
+    /// "Nice shot!"
+    pub const NICE_SHOT: u8 = 1;
+    /// Laughing face
+    pub const LAUGH: u8 = 2;
+    /// "Oops"
+    pub const OOPS: u8 = 3;
+}
+
+/// Pick the protocol version to use with a peer advertising `remote`:
+/// the older of the two, or `UnsupportedVersion` if the peer is too old
+pub fn negotiate_version(remote: u8) -> Result<u8, SerializationError> {
+    if remote < MIN_PROTOCOL_VERSION {
+        return Err(SerializationError::UnsupportedVersion);
+    }
+    Ok(remote.min(PROTOCOL_VERSION))
+}
+
+/// Type header bit marking a compressed payload (`0x82` = RLE snapshot)
+pub const HEADER_FLAG_COMPRESSED: u8 = 0x80;
+
+/// Size of the CRC32 trailer appended to every encoded message
+pub const CHECKSUM_SIZE: usize = 4;
+
+/// CRC32 (IEEE 802.3, reflected) lookup table, built at compile time
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC32 (IEEE) of a byte slice, as used for the wire message trailer
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Most inputs a single `InputBatch` can carry (count is one byte)
+pub const MAX_BATCH_INPUTS: usize = u8::MAX as usize;
+
+/// Longest chat message in UTF-8 bytes; longer text is cut at a char boundary
+pub const MAX_CHAT_BYTES: usize = 256;
+
+/// InputBatch flag: every input carries [tilt: i8, axis_x: i8] after its two base bytes
+const BATCH_FLAG_EXTENDED: u8 = 1 << 0;
+
+/// Little-endian cursor over an output buffer already checked against `encoded_size`
+struct Writer<'a> {
+    bytes: &'a mut [u8],
+    offset: usize,
+    peer_capabilities: u32,
+}
+
+impl Writer<'_> {
+    fn put(&mut self, field: &[u8]) {
+        self.bytes[self.offset..self.offset + field.len()].copy_from_slice(field);
+        self.offset += field.len();
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.put(&[value]);
+    }
+}
+
+/// Whether `input` needs the tilt and axis_x bytes
+fn input_extended(input: &Input) -> bool {
+    input.tilt != 0 || input.axis_x != 0
+}
+
+/// Inputs that fit in one run, and whether they need the 4-byte stride
+fn input_run_parts(inputs: &[Input], peer_capabilities: u32) -> (&[Input], bool) {
+    let inputs = &inputs[..inputs.len().min(MAX_BATCH_INPUTS)];
+    let extended = inputs
+        .iter()
+        .any(|&input| input_extended(&input_for_peer(input, peer_capabilities)));
+    (inputs, extended)
+}
+
+/// Encoded size of `encode_input_run(inputs)`
+fn input_run_len(inputs: &[Input], peer_capabilities: u32) -> usize {
+    let (inputs, extended) = input_run_parts(inputs, peer_capabilities);
+    2 + inputs.len() * if extended { 4 } else { 2 }
+}
+
+/// Write `[count:1][flags:1]` and 2 (or 4) bytes per input, truncating to `MAX_BATCH_INPUTS`
+fn encode_input_run(w: &mut Writer, inputs: &[Input]) {
+    let (inputs, extended) = input_run_parts(inputs, w.peer_capabilities);
+
+    w.u8(inputs.len() as u8);
+    w.u8(if extended { BATCH_FLAG_EXTENDED } else { 0 });
+    for &input in inputs {
+        let input = input_for_peer(input, w.peer_capabilities);
+        w.u8(input.axis_y as u8);
+        w.u8(input.buttons.bits());
+        if extended {
+            w.u8(input.tilt as u8);
+            w.u8(input.axis_x as u8);
+        }
+    }
+}
+
+/// Bytes of `text` that go on the wire: at most `MAX_CHAT_BYTES`, ending on a char boundary
+fn chat_len(text: &str) -> usize {
+    let mut len = text.len().min(MAX_CHAT_BYTES);
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+    len
+}
+
+/// Whether a snapshot payload goes out compressed, and its size on the wire.
+/// Only builds with the `compression` feature compress, only for peers that
+/// can read it, and only when it helps.
+fn snapshot_payload(data: &[u8], peer_capabilities: u32) -> (bool, usize) {
+    #[cfg(feature = "compression")]
+    if peer_capabilities & capabilities::SNAPSHOT_RLE != 0 {
+        let compressed = compression::compressed_len(data);
+        if compressed < data.len() {
+            return (true, compressed);
+        }
+    }
+    #[cfg(not(feature = "compression"))]
+    let _ = peer_capabilities;
+    (false, data.len())
+}
+
+/// How to encode a message for a particular peer (see `WireMsg::encode_with`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeOptions {
+    /// Wrap the message in a `Sequenced` envelope with this number
+    pub seq: Option<u32>,
+    /// What the peer advertised; optional encodings it lacks aren't used
+    pub peer_capabilities: u32,
+}
+
+impl Default for EncodeOptions {
+    /// No envelope, every optional encoding this build has
+    fn default() -> Self {
+        Self {
+            seq: None,
+            peer_capabilities: capabilities::SUPPORTED,
+        }
+    }
+}
+
+/// Quote and escape `text` as a JSON string
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_input(input: &Input) -> String {
+    format!(
+        "{{\"axis_y\":{},\"axis_x\":{},\"buttons\":{},\"tilt\":{}}}",
+        input.axis_y,
+        input.axis_x,
+        input.buttons.bits(),
+        input.tilt
+    )
+}
+
+fn json_inputs(inputs: &[Input]) -> String {
+    let inputs: Vec<String> = inputs.iter().map(json_input).collect();
+    format!("[{}]", inputs.join(","))
+}
+
+/// The fields that usually explain a config mismatch, not the whole struct
+fn json_config(config: &Config) -> String {
+    format!(
+        "{{\"seed\":{},\"tick_hz\":{},\"max_score\":{}}}",
+        config.seed, config.tick_hz, config.max_score
+    )
+}
+
+/// Read a run written by `encode_input_run`
+fn decode_input_run(bytes: &[u8]) -> Result<Vec<Input>, SerializationError> {
+    if bytes.len() < 2 {
+        return Err(SerializationError::UnexpectedEnd);
+    }
+    let count = bytes[0] as usize;
+    let stride = if bytes[1] & BATCH_FLAG_EXTENDED != 0 {
+        4
+    } else {
+        2
+    };
+
+    let body = bytes
+        .get(2..2 + count * stride)
+        .ok_or(SerializationError::UnexpectedEnd)?;
+    Ok(body
+        .chunks_exact(stride)
+        .map(|chunk| Input {
+            axis_y: chunk[0] as i8,
+            axis_x: chunk.get(3).map_or(0, |&b| b as i8),
+            buttons: Buttons::from_bits_retain(chunk[1]),
+            tilt: chunk.get(2).map_or(0, |&b| b as i8),
+        })
+        .collect())
+}
+
+/// Check the CRC32 trailer and return everything before it
+fn verify_checksum(bytes: &[u8]) -> Result<&[u8], SerializationError> {
+    if bytes.len() <= CHECKSUM_SIZE {
+        return Err(SerializationError::UnexpectedEnd);
+    }
+
+    let (bytes, trailer) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+    let checksum = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    if crc32(bytes) != checksum {
+        return Err(SerializationError::ChecksumMismatch);
+    }
+    Ok(bytes)
+}
+
+/// Network messages that can be sent between clients
+#[derive(Debug, Clone, PartialEq)]
+pub enum WireMsg {
+    /// Input pair for a specific tick
+    InputPair(InputPair),
+    /// Game state snapshot for synchronization
+    Snapshot(Vec<u8>),
+    /// Ping message with client timestamp
+    Ping(u32),
+    /// Connection handshake. Its layout never changes so peers of any
+    /// version can read it before agreeing on a protocol version.
+    Hello { version: u8, capabilities: u32 },
+    /// One side's inputs for consecutive ticks starting at `start_tick`
+    InputBatch {
+        start_tick: Tick,
+        inputs: Vec<Input>,
+    },
+    /// One side's most recent inputs, oldest first and ending at `latest_tick`.
+    /// Resent every tick so a single lost datagram doesn't stall the peer.
+    InputHistory {
+        latest_tick: Tick,
+        inputs: Vec<Input>,
+    },
+    /// Guest asks the host for a seat in the match
+    Join,
+    /// Host's answer to `Join`: the guest's side and the rules to play by
+    Welcome { assigned_side: Side, config: Config },
+    /// Host announces the tick both peers begin simulating from
+    StartMatch { start_tick: Tick },
+    /// The rules this peer simulates with, for verification before play
+    ConfigSync(Config),
+    /// Free-form text from the other player (at most `MAX_CHAT_BYTES`)
+    Chat(String),
+    /// Predefined reaction (see `emotes`); never touches the simulation
+    Emote(u8),
+    /// Hash of the sender's state after simulating `tick`
+    StateHash { tick: Tick, hash: u64 },
+    /// Reply to `Ping`, echoing its timestamp
+    Pong { original_timestamp: u32 },
+    /// Ask the peer for its state; `since_tick` is the requester's current tick
+    SnapshotRequest { since_tick: Tick },
+    /// InputPair stamped with the sender's millisecond clock. Shares the InputPair
+    /// header; older peers read it as a plain pair.
+    TimedInputPair { pair: InputPair, sent_ms: u32 },
+    /// Any other message tagged with the sender's sequence number, so the
+    /// receiver can spot gaps and duplicates. Envelopes never nest.
+    Sequenced { seq: u32, msg: Box<WireMsg> },
+    /// Sequence numbers received so far: `latest`, plus bit `i` of `mask` set
+    /// if `latest - 1 - i` arrived too. Never sequenced itself.
+    Ack { latest: u32, mask: u32 },
+    /// Ask the peer to resend its inputs from `from_tick` on, which never arrived
+    InputRequest { from_tick: Tick },
+    /// One player's input for `tick`, as exchanged between peers; `InputPair`
+    /// is reserved for the stepped result
+    SideInput {
+        tick: Tick,
+        side: Side,
+        input: Input,
+    },
+    /// Hold the simulation before stepping `at_tick`, on both peers
+    Pause { at_tick: Tick },
+    /// Lift the pause held at `at_tick`
+    Resume { at_tick: Tick },
+    /// The sender is leaving; answered with a Bye of our own before closing
+    Bye,
+    /// Timekeeper starts the next game of a series before stepping `start_tick`,
+    /// served by `server` from `seed`
+    NextGame {
+        start_tick: Tick,
+        seed: u64,
+        server: Side,
+    },
+}
+
+impl WireMsg {
+    /// Encode wire message to bytes with type header and CRC32 trailer
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8; self.encoded_size()];
+        self.encode_into(&mut bytes)
+            .expect("buffer sized by encoded_size");
+        bytes
+    }
+
+    /// Encode into the front of `buf` (e.g. a buffer reused every tick), returning
+    /// the number of bytes written. Fails with `BufferTooSmall` if `buf` is shorter
+    /// than `encoded_size()`.
+    pub fn encode_into(&self, buf: &mut [u8]) -> Result<usize, SerializationError> {
+        self.encode_with(&EncodeOptions::default(), buf)
+    }
+
+    /// Encode as if wrapped in `Sequenced { seq, .. }`, without cloning the message
+    pub fn encode_sequenced_into(
+        &self,
+        seq: u32,
+        buf: &mut [u8],
+    ) -> Result<usize, SerializationError> {
+        let options = EncodeOptions {
+            seq: Some(seq),
+            ..EncodeOptions::default()
+        };
+        self.encode_with(&options, buf)
+    }
+
+    /// Size of `encode_sequenced_into`'s output: the envelope adds 5 bytes
+    pub fn sequenced_size(&self) -> usize {
+        5 + self.encoded_size()
+    }
+
+    /// Encode for a specific peer: optionally inside a `Sequenced` envelope, and
+    /// without encodings its capabilities don't cover (e.g. compressed snapshots)
+    pub fn encode_with(
+        &self,
+        options: &EncodeOptions,
+        buf: &mut [u8],
+    ) -> Result<usize, SerializationError> {
+        let size = self.encoded_size_with(options);
+        if buf.len() < size {
+            return Err(SerializationError::BufferTooSmall);
+        }
+
+        let (body, trailer) = buf[..size].split_at_mut(size - CHECKSUM_SIZE);
+        let mut w = Writer {
+            bytes: body,
+            offset: 0,
+            peer_capabilities: options.peer_capabilities,
+        };
+        if let Some(seq) = options.seq {
+            w.u8(0x10); // Type header for Sequenced
+            w.put(&seq.to_le_bytes());
+        }
+        self.encode_body(&mut w)?;
+        trailer.copy_from_slice(&crc32(body).to_le_bytes());
+        Ok(size)
+    }
+
+    /// Size of `encode_with(options, ..)`'s output, including the checksum
+    pub fn encoded_size_with(&self, options: &EncodeOptions) -> usize {
+        let envelope = if options.seq.is_some() { 5 } else { 0 };
+        envelope + self.body_len(options.peer_capabilities) + CHECKSUM_SIZE
+    }
+
+    /// Encode the header and payload, without the checksum trailer
+    fn encode_body(&self, w: &mut Writer) -> Result<(), SerializationError> {
+        match self {
+            WireMsg::InputPair(pair) => {
+                let pair = pair_for_peer(pair, w.peer_capabilities);
+                w.u8(0x01); // Type header for InputPair
+                w.offset += pair.encode_into(&mut w.bytes[w.offset..])?;
+            }
+            WireMsg::TimedInputPair { pair, sent_ms } => {
+                let pair = pair_for_peer(pair, w.peer_capabilities);
+                w.u8(0x01); // Type header for InputPair; its flags byte marks the timestamp
+                w.offset += pair.encode_timestamped_into(*sent_ms, &mut w.bytes[w.offset..])?;
+            }
+            WireMsg::Snapshot(data) => match snapshot_payload(data, w.peer_capabilities) {
+                #[cfg(feature = "compression")]
+                (true, _) => {
+                    w.u8(0x02 | HEADER_FLAG_COMPRESSED); // Type header for compressed Snapshot
+                    w.offset += compression::compress_into(data, &mut w.bytes[w.offset..])?;
+                }
+                _ => {
+                    w.u8(0x02); // Type header for Snapshot
+                    w.put(data);
+                }
+            },
+            WireMsg::Ping(timestamp) => {
+                w.u8(0x03); // Type header for Ping
+                w.put(&timestamp.to_le_bytes());
+            }
+            WireMsg::Hello {
+                version,
+                capabilities,
+            } => {
+                w.u8(0x04); // Type header for Hello
+                w.u8(*version);
+                w.put(&capabilities.to_le_bytes());
+            }
+            WireMsg::InputBatch { start_tick, inputs } => {
+                // Layout: [0x05][start_tick:4][count:1][flags:1] then 2 (or 4) bytes per input
+                w.u8(0x05); // Type header for InputBatch
+                w.put(&start_tick.to_le_bytes());
+                encode_input_run(w, inputs);
+            }
+            WireMsg::InputHistory {
+                latest_tick,
+                inputs,
+            } => {
+                // Same layout as InputBatch, keyed by the newest tick; keep the newest inputs
+                let skip = inputs.len().saturating_sub(MAX_BATCH_INPUTS);
+                w.u8(0x06); // Type header for InputHistory
+                w.put(&latest_tick.to_le_bytes());
+                encode_input_run(w, &inputs[skip..]);
+            }
+            WireMsg::Join => w.u8(0x07), // Type header for Join
+            WireMsg::Welcome {
+                assigned_side,
+                config,
+            } => {
+                w.u8(0x08); // Type header for Welcome
+                w.u8(assigned_side.index() as u8);
+                w.offset += config.encode_into(&mut w.bytes[w.offset..])?;
+            }
+            WireMsg::StartMatch { start_tick } => {
+                w.u8(0x09); // Type header for StartMatch
+                w.put(&start_tick.to_le_bytes());
+            }
+            WireMsg::ConfigSync(config) => {
+                w.u8(0x0A); // Type header for ConfigSync
+                w.offset += config.encode_into(&mut w.bytes[w.offset..])?;
+            }
+            WireMsg::Chat(text) => {
+                // Layout: [0x0B][len:2][utf8 bytes]
+                let len = chat_len(text);
+                w.u8(0x0B); // Type header for Chat
+                w.put(&(len as u16).to_le_bytes());
+                w.put(&text.as_bytes()[..len]);
+            }
+            WireMsg::Emote(id) => {
+                w.u8(0x0C); // Type header for Emote
+                w.u8(*id);
+            }
+            WireMsg::SnapshotRequest { since_tick } => {
+                w.u8(0x0F); // Type header for SnapshotRequest
+                w.put(&since_tick.to_le_bytes());
+            }
+            WireMsg::Pong { original_timestamp } => {
+                w.u8(0x0E); // Type header for Pong
+                w.put(&original_timestamp.to_le_bytes());
+            }
+            WireMsg::StateHash { tick, hash } => {
+                w.u8(0x0D); // Type header for StateHash
+                w.put(&tick.to_le_bytes());
+                w.put(&hash.to_le_bytes());
+            }
+            WireMsg::Sequenced { seq, msg } => {
+                // Layout: [0x10][seq:4] then the inner message's header and payload
+                if matches!(**msg, WireMsg::Sequenced { .. }) {
+                    return Err(SerializationError::InvalidData);
+                }
+                w.u8(0x10); // Type header for Sequenced
+                w.put(&seq.to_le_bytes());
+                msg.encode_body(w)?;
+            }
+            WireMsg::Ack { latest, mask } => {
+                w.u8(0x11); // Type header for Ack
+                w.put(&latest.to_le_bytes());
+                w.put(&mask.to_le_bytes());
+            }
+            WireMsg::InputRequest { from_tick } => {
+                w.u8(0x12); // Type header for InputRequest
+                w.put(&from_tick.to_le_bytes());
+            }
+            WireMsg::SideInput { tick, side, input } => {
+                // Layout: [0x13][tick:4][side:1][flags:1][axis_y][buttons] then [tilt][axis_x] if flagged
+                let input = &input_for_peer(*input, w.peer_capabilities);
+                let extended = input_extended(input);
+                w.u8(0x13); // Type header for SideInput
+                w.put(&tick.to_le_bytes());
+                w.u8(side.index() as u8);
+                w.u8(if extended { BATCH_FLAG_EXTENDED } else { 0 });
+                w.u8(input.axis_y as u8);
+                w.u8(input.buttons.bits());
+                if extended {
+                    w.u8(input.tilt as u8);
+                    w.u8(input.axis_x as u8);
+                }
+            }
+            WireMsg::Pause { at_tick } => {
+                w.u8(0x14); // Type header for Pause
+                w.put(&at_tick.to_le_bytes());
+            }
+            WireMsg::Resume { at_tick } => {
+                w.u8(0x15); // Type header for Resume
+                w.put(&at_tick.to_le_bytes());
+            }
+            WireMsg::Bye => w.u8(0x16), // Type header for Bye
+            WireMsg::NextGame {
+                start_tick,
+                seed,
+                server,
+            } => {
+                w.u8(0x17); // Type header for NextGame
+                w.put(&start_tick.to_le_bytes());
+                w.put(&seed.to_le_bytes());
+                w.u8(server.index() as u8);
+            }
+        }
+        Ok(())
+    }
+
+    /// Decode wire message from bytes, verifying the CRC32 trailer
+    pub fn decode(bytes: &[u8]) -> Result<Self, SerializationError> {
+        WireMsg::decode_ref(bytes).map(WireMsgRef::into_owned)
+    }
+
+    /// Decode without copying: snapshot and chat payloads borrow from `bytes`
+    pub fn decode_ref(bytes: &[u8]) -> Result<WireMsgRef<'_>, SerializationError> {
+        WireMsgRef::decode_body(verify_checksum(bytes)?)
+    }
+
+    /// Decode a frame sealed by `MessageAuth::seal`, rejecting it unless its tag
+    /// was made with the same key
+    #[cfg(feature = "auth")]
+    pub fn decode_authenticated(
+        bytes: &[u8],
+        auth: &MessageAuth,
+    ) -> Result<Self, SerializationError> {
+        WireMsg::decode_authenticated_ref(bytes, auth).map(WireMsgRef::into_owned)
+    }
+
+    /// `decode_authenticated` without copying payloads
+    #[cfg(feature = "auth")]
+    pub fn decode_authenticated_ref<'a>(
+        bytes: &'a [u8],
+        auth: &MessageAuth,
+    ) -> Result<WireMsgRef<'a>, SerializationError> {
+        let body = auth.open(verify_checksum(bytes)?)?;
+        WireMsgRef::decode_body(body)
+    }
+
+    /// Get the message type as a string for debugging
+    pub fn message_type(&self) -> &'static str {
+        match self {
+            WireMsg::InputPair(_) => "InputPair",
+            WireMsg::TimedInputPair { .. } => "TimedInputPair",
+            WireMsg::Snapshot(_) => "Snapshot",
+            WireMsg::Ping(_) => "Ping",
+            WireMsg::Hello { .. } => "Hello",
+            WireMsg::InputBatch { .. } => "InputBatch",
+            WireMsg::InputHistory { .. } => "InputHistory",
+            WireMsg::Join => "Join",
+            WireMsg::Welcome { .. } => "Welcome",
+            WireMsg::StartMatch { .. } => "StartMatch",
+            WireMsg::ConfigSync(_) => "ConfigSync",
+            WireMsg::Chat(_) => "Chat",
+            WireMsg::Emote(_) => "Emote",
+            WireMsg::StateHash { .. } => "StateHash",
+            WireMsg::Pong { .. } => "Pong",
+            WireMsg::SnapshotRequest { .. } => "SnapshotRequest",
+            WireMsg::Sequenced { .. } => "Sequenced",
+            WireMsg::Ack { .. } => "Ack",
+            WireMsg::InputRequest { .. } => "InputRequest",
+            WireMsg::SideInput { .. } => "SideInput",
+            WireMsg::Pause { .. } => "Pause",
+            WireMsg::Resume { .. } => "Resume",
+            WireMsg::Bye => "Bye",
+            WireMsg::NextGame { .. } => "NextGame",
+        }
+    }
+
+    /// One-line JSON description for logs and captures, e.g.
+    /// `{"type":"Ping","timestamp":42}`. Hashes are hex strings (too wide for JS numbers).
+    pub fn to_debug_json(&self) -> String {
+        let fields = match self {
+            WireMsg::InputPair(pair) => format!(
+                ",\"tick\":{},\"a\":{},\"b\":{}",
+                pair.tick,
+                json_input(&pair.a),
+                json_input(&pair.b)
+            ),
+            WireMsg::TimedInputPair { pair, sent_ms } => format!(
+                ",\"tick\":{},\"a\":{},\"b\":{},\"sent_ms\":{sent_ms}",
+                pair.tick,
+                json_input(&pair.a),
+                json_input(&pair.b)
+            ),
+            WireMsg::Snapshot(data) => match Snapshot::decode(data) {
+                Ok(snapshot) => format!(
+                    ",\"len\":{},\"tick\":{},\"status\":{},\"score\":[{},{}]",
+                    data.len(),
+                    snapshot.tick,
+                    json_string(&format!("{:?}", snapshot.status)),
+                    snapshot.score[0],
+                    snapshot.score[1]
+                ),
+                Err(error) => format!(
+                    ",\"len\":{},\"error\":{}",
+                    data.len(),
+                    json_string(&format!("{error:?}"))
+                ),
+            },
+            WireMsg::Ping(timestamp) => format!(",\"timestamp\":{timestamp}"),
+            WireMsg::Hello {
+                version,
+                capabilities,
+            } => format!(",\"version\":{version},\"capabilities\":{capabilities}"),
+            WireMsg::InputBatch { start_tick, inputs } => format!(
+                ",\"start_tick\":{},\"inputs\":{}",
+                start_tick,
+                json_inputs(inputs)
+            ),
+            WireMsg::InputHistory {
+                latest_tick,
+                inputs,
+            } => format!(
+                ",\"latest_tick\":{},\"inputs\":{}",
+                latest_tick,
+                json_inputs(inputs)
+            ),
+            WireMsg::Join | WireMsg::Bye => String::new(),
+            WireMsg::Welcome {
+                assigned_side,
+                config,
+            } => format!(
+                ",\"assigned_side\":\"{:?}\",\"config\":{}",
+                assigned_side,
+                json_config(config)
+            ),
+            WireMsg::StartMatch { start_tick } => format!(",\"start_tick\":{start_tick}"),
+            WireMsg::ConfigSync(config) => format!(",\"config\":{}", json_config(config)),
+            WireMsg::Chat(text) => format!(",\"text\":{}", json_string(text)),
+            WireMsg::Emote(emote) => format!(",\"emote\":{emote}"),
+            WireMsg::StateHash { tick, hash } => {
+                format!(",\"tick\":{tick},\"hash\":\"{hash:016x}\"")
+            }
+            WireMsg::Pong { original_timestamp } => {
+                format!(",\"original_timestamp\":{original_timestamp}")
+            }
+            WireMsg::SnapshotRequest { since_tick } => format!(",\"since_tick\":{since_tick}"),
+            WireMsg::Sequenced { seq, msg } => {
+                format!(",\"seq\":{seq},\"msg\":{}", msg.to_debug_json())
+            }
+            WireMsg::Ack { latest, mask } => format!(",\"latest\":{latest},\"mask\":{mask}"),
+            WireMsg::InputRequest { from_tick } => format!(",\"from_tick\":{from_tick}"),
+            WireMsg::SideInput { tick, side, input } => format!(
+                ",\"tick\":{tick},\"side\":\"{side:?}\",\"input\":{}",
+                json_input(input)
+            ),
+            WireMsg::Pause { at_tick } | WireMsg::Resume { at_tick } => {
+                format!(",\"at_tick\":{at_tick}")
+            }
+            WireMsg::NextGame {
+                start_tick,
+                seed,
+                server,
+            } => format!(
+                ",\"start_tick\":{start_tick},\"seed\":\"{seed:016x}\",\"server\":\"{server:?}\""
+            ),
+        };
+        format!("{{\"type\":\"{}\"{}}}", self.message_type(), fields)
+    }
+
+    /// Get the size of the encoded message in bytes, including the checksum
+    pub fn encoded_size(&self) -> usize {
+        self.encoded_size_with(&EncodeOptions::default())
+    }
+
+    /// Size of the header and payload for a peer with `peer_capabilities`
+    fn body_len(&self, peer_capabilities: u32) -> usize {
+        match self {
+            WireMsg::InputPair(pair) => 1 + pair_for_peer(pair, peer_capabilities).encoded_len(), // 1 byte header + 9 (or more) bytes InputPair
+            WireMsg::TimedInputPair { pair, .. } => {
+                1 + pair_for_peer(pair, peer_capabilities).timestamped_len() // InputPair + 4 bytes timestamp
+            }
+            WireMsg::Snapshot(data) => 1 + snapshot_payload(data, peer_capabilities).1, // 1 byte header + snapshot data
+            WireMsg::Ping(_) => 5,      // 1 byte header + 4 bytes timestamp
+            WireMsg::Hello { .. } => 6, // 1 byte header + version + 4 bytes capabilities
+            WireMsg::InputBatch { inputs, .. } | WireMsg::InputHistory { inputs, .. } => {
+                5 + input_run_len(inputs, peer_capabilities) // 1 byte header + 4 bytes tick + run
+            }
+            WireMsg::Chat(text) => 3 + chat_len(text), // 1 byte header + 2 bytes length + text
+            WireMsg::Join => 1,                        // 1 byte header
+            WireMsg::Welcome { .. } => 2 + CONFIG_ENCODED_SIZE, // 1 byte header + side + config
+            WireMsg::StartMatch { .. } => 5,           // 1 byte header + 4 bytes tick
+            WireMsg::ConfigSync(_) => 1 + CONFIG_ENCODED_SIZE, // 1 byte header + config
+            WireMsg::Emote(_) => 2,                    // 1 byte header + id
+            WireMsg::StateHash { .. } => 13, // 1 byte header + 4 bytes tick + 8 bytes hash
+            WireMsg::Pong { .. } => 5,       // 1 byte header + 4 bytes timestamp
+            WireMsg::SnapshotRequest { .. } => 5, // 1 byte header + 4 bytes tick
+            WireMsg::Sequenced { msg, .. } => 5 + msg.body_len(peer_capabilities), // 1 byte header + 4 bytes seq + inner
+            WireMsg::Ack { .. } => 9, // 1 byte header + 4 bytes latest + 4 bytes mask
+            WireMsg::InputRequest { .. } => 5, // 1 byte header + 4 bytes tick
+            WireMsg::SideInput { input, .. } => {
+                // 1 byte header + 4 bytes tick + side + flags + 2 (or 4) bytes input
+                if input_extended(&input_for_peer(*input, peer_capabilities)) {
+                    11
+                } else {
+                    9
+                }
+            }
+            WireMsg::Pause { .. } | WireMsg::Resume { .. } => 5, // 1 byte header + 4 bytes tick
+            WireMsg::Bye => 1,                                   // 1 byte header
+            WireMsg::NextGame { .. } => 14, // 1 byte header + 4 bytes tick + 8 bytes seed + server
+        }
+    }
+}
+
+/// Borrowed view of a decoded wire message, from `WireMsg::decode_ref`.
+/// Variable-size payloads point into the received buffer instead of being copied.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WireMsgRef<'a> {
+    InputPair(InputPair),
+    TimedInputPair {
+        pair: InputPair,
+        sent_ms: u32,
+    },
+    /// Encoded snapshot, ready for `Snapshot::decode`; only owned if it arrived compressed
+    Snapshot(Cow<'a, [u8]>),
+    Ping(u32),
+    Hello {
+        version: u8,
+        capabilities: u32,
+    },
+    InputBatch {
+        start_tick: Tick,
+        inputs: Vec<Input>,
+    },
+    InputHistory {
+        latest_tick: Tick,
+        inputs: Vec<Input>,
+    },
+    Join,
+    Welcome {
+        assigned_side: Side,
+        config: Config,
+    },
+    StartMatch {
+        start_tick: Tick,
+    },
+    ConfigSync(Config),
+    Chat(&'a str),
+    Emote(u8),
+    StateHash {
+        tick: Tick,
+        hash: u64,
+    },
+    Pong {
+        original_timestamp: u32,
+    },
+    SnapshotRequest {
+        since_tick: Tick,
+    },
+    Sequenced {
+        seq: u32,
+        msg: Box<WireMsgRef<'a>>,
+    },
+    Ack {
+        latest: u32,
+        mask: u32,
+    },
+    InputRequest {
+        from_tick: Tick,
+    },
+    SideInput {
+        tick: Tick,
+        side: Side,
+        input: Input,
+    },
+    Pause {
+        at_tick: Tick,
+    },
+    Resume {
+        at_tick: Tick,
+    },
+    Bye,
+    NextGame {
+        start_tick: Tick,
+        seed: u64,
+        server: Side,
+    },
+}
+
+impl<'a> WireMsgRef<'a> {
+    /// Decode the header and payload once the checksum has been stripped
+    fn decode_body(bytes: &'a [u8]) -> Result<Self, SerializationError> {
+        match bytes[0] {
+            0x01 => {
+                // InputPair message
+                if bytes.len() < 10 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                let pair = InputPair::decode(&bytes[1..])?;
+                match InputPair::decode_timestamp(&bytes[1..])? {
+                    Some(sent_ms) => Ok(WireMsgRef::TimedInputPair { pair, sent_ms }),
+                    None => Ok(WireMsgRef::InputPair(pair)),
+                }
+            }
+            0x02 => {
+                // Snapshot message
+                if bytes.len() < 2 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                Ok(WireMsgRef::Snapshot(Cow::Borrowed(&bytes[1..])))
+            }
+            #[cfg(feature = "compression")]
+            0x82 => {
+                // Compressed Snapshot message
+                if bytes.len() < 2 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                let data = compression::decompress(&bytes[1..])?;
+                Ok(WireMsgRef::Snapshot(Cow::Owned(data)))
+            }
+            0x03 => {
+                // Ping message
+                if bytes.len() < 5 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                let timestamp = u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]);
+                Ok(WireMsgRef::Ping(timestamp))
+            }
+            0x04 => {
+                // Hello message; trailing bytes from newer versions are ignored
+                if bytes.len() < 6 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                Ok(WireMsgRef::Hello {
+                    version: bytes[1],
+                    capabilities: u32::from_le_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]),
+                })
+            }
+            0x05 | 0x06 => {
+                // InputBatch / InputHistory messages
+                if bytes.len() < 7 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                let tick = u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]);
+                let inputs = decode_input_run(&bytes[5..])?;
+
+                if bytes[0] == 0x05 {
+                    Ok(WireMsgRef::InputBatch {
+                        start_tick: tick,
+                        inputs,
+                    })
+                } else {
+                    Ok(WireMsgRef::InputHistory {
+                        latest_tick: tick,
+                        inputs,
+                    })
+                }
+            }
+            0x07 => Ok(WireMsgRef::Join),
+            0x08 => {
+                // Welcome message
+                if bytes.len() < 2 + CONFIG_ENCODED_SIZE {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                let assigned_side =
+                    Side::from_index(bytes[1] as usize).ok_or(SerializationError::InvalidData)?;
+                let config = Config::decode(&bytes[2..])?;
+                Ok(WireMsgRef::Welcome {
+                    assigned_side,
+                    config,
+                })
+            }
+            0x09 => {
+                // StartMatch message
+                if bytes.len() < 5 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                let start_tick = u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]);
+                Ok(WireMsgRef::StartMatch { start_tick })
+            }
+            0x0A => {
+                // ConfigSync message
+                let config = Config::decode(&bytes[1..])?;
+                Ok(WireMsgRef::ConfigSync(config))
+            }
+            0x0B => {
+                // Chat message
+                if bytes.len() < 3 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                let len = u16::from_le_bytes([bytes[1], bytes[2]]) as usize;
+                if len > MAX_CHAT_BYTES {
+                    return Err(SerializationError::InvalidData);
+                }
+                let text = bytes
+                    .get(3..3 + len)
+                    .ok_or(SerializationError::UnexpectedEnd)?;
+                let text =
+                    std::str::from_utf8(text).map_err(|_| SerializationError::InvalidData)?;
+                Ok(WireMsgRef::Chat(text))
+            }
+            0x0C => {
+                // Emote message
+                if bytes.len() < 2 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                Ok(WireMsgRef::Emote(bytes[1]))
+            }
+            0x0D => {
+                // StateHash message
+                if bytes.len() < 13 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                let tick = u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]);
+                let mut hash = [0u8; 8];
+                hash.copy_from_slice(&bytes[5..13]);
+                Ok(WireMsgRef::StateHash {
+                    tick,
+                    hash: u64::from_le_bytes(hash),
+                })
+            }
+            0x0E => {
+                // Pong message
+                if bytes.len() < 5 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                let original_timestamp =
+                    u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]);
+                Ok(WireMsgRef::Pong { original_timestamp })
+            }
+            0x0F => {
+                // SnapshotRequest message
+                if bytes.len() < 5 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                let since_tick = u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]);
+                Ok(WireMsgRef::SnapshotRequest { since_tick })
+            }
+            0x10 => {
+                // Sequenced envelope around another message
+                if bytes.len() < 6 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                let seq = u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]);
+                let msg = WireMsgRef::decode_body(&bytes[5..])?;
+                if matches!(msg, WireMsgRef::Sequenced { .. }) {
+                    return Err(SerializationError::InvalidData);
+                }
+                Ok(WireMsgRef::Sequenced {
+                    seq,
+                    msg: Box::new(msg),
+                })
+            }
+            0x11 => {
+                // Ack message
+                if bytes.len() < 9 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                Ok(WireMsgRef::Ack {
+                    latest: u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]),
+                    mask: u32::from_le_bytes([bytes[5], bytes[6], bytes[7], bytes[8]]),
+                })
+            }
+            0x12 => {
+                // InputRequest message
+                if bytes.len() < 5 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                let from_tick = u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]);
+                Ok(WireMsgRef::InputRequest { from_tick })
+            }
+            0x13 => {
+                // SideInput message
+                if bytes.len() < 9 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                let tick = u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]);
+                let side =
+                    Side::from_index(bytes[5] as usize).ok_or(SerializationError::InvalidData)?;
+                let (tilt, axis_x) = if bytes[6] & BATCH_FLAG_EXTENDED != 0 {
+                    match bytes.get(9..11) {
+                        Some(tail) => (tail[0] as i8, tail[1] as i8),
+                        None => return Err(SerializationError::UnexpectedEnd),
+                    }
+                } else {
+                    (0, 0)
+                };
+                let input = Input {
+                    axis_y: bytes[7] as i8,
+                    axis_x,
+                    buttons: Buttons::from_bits_retain(bytes[8]),
+                    tilt,
+                };
+                Ok(WireMsgRef::SideInput { tick, side, input })
+            }
+            0x14 | 0x15 => {
+                // Pause / Resume message
+                if bytes.len() < 5 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                let at_tick = u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]);
+                Ok(if bytes[0] == 0x14 {
+                    WireMsgRef::Pause { at_tick }
+                } else {
+                    WireMsgRef::Resume { at_tick }
+                })
+            }
+            0x16 => Ok(WireMsgRef::Bye),
+            0x17 => {
+                // NextGame message
+                if bytes.len() < 14 {
+                    return Err(SerializationError::UnexpectedEnd);
+                }
+                let start_tick = u32::from_le_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]);
+                let mut seed = [0u8; 8];
+                seed.copy_from_slice(&bytes[5..13]);
+                let server =
+                    Side::from_index(bytes[13] as usize).ok_or(SerializationError::InvalidData)?;
+                Ok(WireMsgRef::NextGame {
+                    start_tick,
+                    seed: u64::from_le_bytes(seed),
+                    server,
+                })
+            }
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+
+    /// Copy borrowed payloads into an owned `WireMsg`
+    pub fn into_owned(self) -> WireMsg {
+        match self {
+            WireMsgRef::InputPair(pair) => WireMsg::InputPair(pair),
+            WireMsgRef::TimedInputPair { pair, sent_ms } => {
+                WireMsg::TimedInputPair { pair, sent_ms }
+            }
+            WireMsgRef::Snapshot(data) => WireMsg::Snapshot(data.into_owned()),
+            WireMsgRef::Ping(timestamp) => WireMsg::Ping(timestamp),
+            WireMsgRef::Hello {
+                version,
+                capabilities,
+            } => WireMsg::Hello {
+                version,
+                capabilities,
+            },
+            WireMsgRef::InputBatch { start_tick, inputs } => {
+                WireMsg::InputBatch { start_tick, inputs }
+            }
+            WireMsgRef::InputHistory {
+                latest_tick,
+                inputs,
+            } => WireMsg::InputHistory {
+                latest_tick,
+                inputs,
+            },
+            WireMsgRef::Join => WireMsg::Join,
+            WireMsgRef::Welcome {
+                assigned_side,
+                config,
+            } => WireMsg::Welcome {
+                assigned_side,
+                config,
+            },
+            WireMsgRef::StartMatch { start_tick } => WireMsg::StartMatch { start_tick },
+            WireMsgRef::ConfigSync(config) => WireMsg::ConfigSync(config),
+            WireMsgRef::Chat(text) => WireMsg::Chat(text.to_string()),
+            WireMsgRef::Emote(emote) => WireMsg::Emote(emote),
+            WireMsgRef::StateHash { tick, hash } => WireMsg::StateHash { tick, hash },
+            WireMsgRef::Pong { original_timestamp } => WireMsg::Pong { original_timestamp },
+            WireMsgRef::SnapshotRequest { since_tick } => WireMsg::SnapshotRequest { since_tick },
+            WireMsgRef::Sequenced { seq, msg } => WireMsg::Sequenced {
+                seq,
+                msg: Box::new(msg.into_owned()),
+            },
+            WireMsgRef::Ack { latest, mask } => WireMsg::Ack { latest, mask },
+            WireMsgRef::InputRequest { from_tick } => WireMsg::InputRequest { from_tick },
+            WireMsgRef::SideInput { tick, side, input } => WireMsg::SideInput { tick, side, input },
+            WireMsgRef::Pause { at_tick } => WireMsg::Pause { at_tick },
+            WireMsgRef::Resume { at_tick } => WireMsg::Resume { at_tick },
+            WireMsgRef::Bye => WireMsg::Bye,
+            WireMsgRef::NextGame {
+                start_tick,
+                seed,
+                server,
+            } => WireMsg::NextGame {
+                start_tick,
+                seed,
+                server,
+            },
+        }
+    }
+}
+
+/// Utility functions for working with wire messages
+impl WireMsg {
+    /// Create an InputPair message
+    pub fn input_pair(tick: Tick, a: Input, b: Input) -> Self {
+        WireMsg::InputPair(InputPair::new(tick, a, b))
+    }
+
+    /// Create a Snapshot message from a snapshot
+    pub fn snapshot(snapshot: &Snapshot) -> Self {
+        WireMsg::Snapshot(snapshot.encode())
+    }
+
+    /// Create a Ping message with current timestamp (in milliseconds)
+    pub fn ping(timestamp_ms: u32) -> Self {
+        WireMsg::Ping(timestamp_ms)
+    }
+
+    /// Create an InputBatch of consecutive-tick inputs (at most `MAX_BATCH_INPUTS` are sent)
+    pub fn input_batch(start_tick: Tick, inputs: Vec<Input>) -> Self {
+        WireMsg::InputBatch { start_tick, inputs }
+    }
+
+    /// Create an InputHistory ending at `latest_tick` (only the newest `MAX_BATCH_INPUTS` are sent)
+    pub fn input_history(latest_tick: Tick, inputs: Vec<Input>) -> Self {
+        WireMsg::InputHistory {
+            latest_tick,
+            inputs,
+        }
+    }
+
+    /// Create a Chat message (text beyond `MAX_CHAT_BYTES` is dropped when encoding)
+    pub fn chat(text: impl Into<String>) -> Self {
+        WireMsg::Chat(text.into())
+    }
+
+    /// Create the Pong answering a Ping that carried `timestamp_ms`
+    pub fn pong(timestamp_ms: u32) -> Self {
+        WireMsg::Pong {
+            original_timestamp: timestamp_ms,
+        }
+    }
+
+    /// Wrap `msg` with sequence number `seq`
+    pub fn sequenced(seq: u32, msg: WireMsg) -> Self {
+        WireMsg::Sequenced {
+            seq,
+            msg: Box::new(msg),
+        }
+    }
+
+    /// Create a Hello advertising this build's version and capabilities
+    pub fn hello() -> Self {
+        WireMsg::Hello {
+            version: PROTOCOL_VERSION,
+            capabilities: capabilities::SUPPORTED,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_pair_message() {
+        let input_a = Input::new(-50, 1);
+        let input_b = Input::new(75, 2);
+        let pair = InputPair::new(12345, input_a, input_b);
+        let msg = WireMsg::InputPair(pair);
+
+        let encoded = msg.encode();
+        let decoded = WireMsg::decode(&encoded).unwrap();
+
+        assert_eq!(msg, decoded);
+        assert_eq!(msg.message_type(), "InputPair");
+        assert_eq!(msg.encoded_size(), 14);
+        assert_eq!(encoded[0], 0x01); // Check type header
+        assert_eq!(encoded.len(), 14); // 1 byte header + 9 bytes pair + 4 bytes CRC
+    }
+
+    #[test]
+    fn test_snapshot_message() {
+        let snapshot = Snapshot {
+            tick: 1000,
+            status: Status::Playing,
+            paddles: [
+                Paddle {
+                    y: FX_ONE / 2,
+                    vy: FX_ONE / 4,
+                    tilt: 0,
+                },
+                Paddle {
+                    y: FX_ONE / 3,
+                    vy: -FX_ONE / 8,
+                    tilt: 0,
+                },
+            ],
+            ball: Ball {
+                pos: Vec2::new(FX_ONE / 2, FX_ONE / 4),
+                vel: Vec2::new(FX_ONE / 8, -FX_ONE / 16),
+            },
+            score: [3, 2],
+            rng: 0xDEADBEEF_CAFEBABE,
+            obstacle_phases: Vec::new(),
+            rally_hits: 0,
+            stall_ticks: 0,
+            config_hash: 0,
+            extra_balls: Vec::new(),
+        };
+
+        let msg = WireMsg::snapshot(&snapshot);
+        let encoded = msg.encode();
+        let decoded = WireMsg::decode(&encoded).unwrap();
+
+        assert_eq!(msg, decoded);
+        assert_eq!(msg.message_type(), "Snapshot");
+        assert_eq!(encoded[0], 0x02); // Check type header
+        assert_eq!(encoded.len(), 54); // 1 byte header + 49 bytes snapshot + 4 bytes CRC
+
+        // Verify we can decode the snapshot data
+        if let WireMsg::Snapshot(data) = decoded {
+            let decoded_snapshot = Snapshot::decode(&data).unwrap();
+            assert_eq!(snapshot.tick, decoded_snapshot.tick);
+            assert_eq!(snapshot.status, decoded_snapshot.status);
+        } else {
+            panic!("Expected Snapshot message");
+        }
+    }
+
+    #[test]
+    fn test_ping_message() {
+        let timestamp = 0x12345678;
+        let msg = WireMsg::ping(timestamp);
+
+        let encoded = msg.encode();
+        let decoded = WireMsg::decode(&encoded).unwrap();
+
+        assert_eq!(msg, decoded);
+        assert_eq!(msg.message_type(), "Ping");
+        assert_eq!(msg.encoded_size(), 9);
+        assert_eq!(encoded[0], 0x03); // Check type header
+        assert_eq!(encoded.len(), 9);
+
+        if let WireMsg::Ping(decoded_timestamp) = decoded {
+            assert_eq!(timestamp, decoded_timestamp);
+        } else {
+            panic!("Expected Ping message");
+        }
+    }
+
+    #[test]
+    fn test_pong_message() {
+        let msg = WireMsg::pong(0x12345678);
+        let encoded = msg.encode();
+        assert_eq!(encoded[0], 0x0E);
+        assert_eq!(msg.encoded_size(), encoded.len());
+        assert_eq!(msg.message_type(), "Pong");
+
+        // Distinct from the Ping it answers
+        let decoded = WireMsg::decode(&encoded).unwrap();
+        assert_eq!(decoded, msg);
+        assert_ne!(decoded, WireMsg::ping(0x12345678));
+    }
+
+    #[test]
+    fn test_hello_message() {
+        let msg = WireMsg::hello();
+        let encoded = msg.encode();
+        assert_eq!(encoded[0], 0x04);
+        assert_eq!(encoded.len(), msg.encoded_size());
+        assert_eq!(WireMsg::decode(&encoded).unwrap(), msg);
+
+        // A newer peer's Hello may carry extra bytes; the fixed prefix still decodes
+        let mut future = vec![0x04, 9, 0xFF, 0xFF, 0xFF, 0xFF, 1, 2, 3];
+        future.extend_from_slice(&crc32(&future).to_le_bytes());
+        assert_eq!(
+            WireMsg::decode(&future).unwrap(),
+            WireMsg::Hello {
+                version: 9,
+                capabilities: u32::MAX
+            }
+        );
+
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0x04, 1, 0])),
+            Err(SerializationError::UnexpectedEnd)
+        );
+    }
+
+    /// Append a valid checksum to a hand-built message body
+    fn with_crc(body: &[u8]) -> Vec<u8> {
+        let mut bytes = body.to_vec();
+        bytes.extend_from_slice(&crc32(body).to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_crc32_known_value() {
+        // Standard CRC-32/ISO-HDLC check value
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(&[]), 0);
+    }
+
+    #[test]
+    fn test_corrupted_message_rejected() {
+        let msg = WireMsg::input_pair(77, Input::new(-20, 1), Input::new(40, 2));
+        let encoded = msg.encode();
+
+        // Flipping any single bit anywhere in the frame is caught
+        for i in 0..encoded.len() {
+            let mut corrupted = encoded.clone();
+            corrupted[i] ^= 0x10;
+            assert_eq!(
+                WireMsg::decode(&corrupted),
+                Err(SerializationError::ChecksumMismatch)
+            );
+        }
+
+        // Truncated frames are rejected too
+        assert!(WireMsg::decode(&encoded[..encoded.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_input_batch_message() {
+        let inputs: Vec<Input> = (0..10).map(|i| Input::new(i * 10, i as u8 & 1)).collect();
+        let msg = WireMsg::input_batch(u32::MAX - 3, inputs);
+
+        let encoded = msg.encode();
+        assert_eq!(encoded[0], 0x05);
+        assert_eq!(encoded.len(), 7 + 10 * 2 + CHECKSUM_SIZE);
+        assert_eq!(msg.encoded_size(), encoded.len());
+        assert_eq!(WireMsg::decode(&encoded).unwrap(), msg);
+        assert_eq!(msg.message_type(), "InputBatch");
+
+        // Tilt or X axis anywhere widens every entry
+        let extended = WireMsg::input_batch(
+            3,
+            vec![
+                Input::new(1, 0),
+                Input::new(2, 0).with_tilt(-40).with_axis_x(9),
+            ],
+        );
+        let encoded = extended.encode();
+        assert_eq!(encoded.len(), 7 + 2 * 4 + CHECKSUM_SIZE);
+        assert_eq!(WireMsg::decode(&encoded).unwrap(), extended);
+
+        // Empty batches are legal
+        let empty = WireMsg::input_batch(0, Vec::new());
+        assert_eq!(WireMsg::decode(&empty.encode()).unwrap(), empty);
+    }
+
+    #[test]
+    fn test_input_batch_limits() {
+        // Oversized batches are truncated to what the count byte can express
+        let msg = WireMsg::input_batch(0, vec![Input::new(1, 0); MAX_BATCH_INPUTS + 5]);
+        match WireMsg::decode(&msg.encode()).unwrap() {
+            WireMsg::InputBatch { inputs, .. } => assert_eq!(inputs.len(), MAX_BATCH_INPUTS),
+            other => panic!("Expected InputBatch, got {other:?}"),
+        }
+
+        // A count promising more inputs than the frame holds is rejected
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0x05, 0, 0, 0, 0, 3, 0, 1, 0])),
+            Err(SerializationError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_input_history_message() {
+        let inputs = vec![
+            Input::new(-3, 0),
+            Input::new(0, 1),
+            Input::new(3, 0).with_tilt(2),
+        ];
+        let msg = WireMsg::input_history(100, inputs);
+        let encoded = msg.encode();
+        assert_eq!(encoded[0], 0x06);
+        assert_eq!(msg.encoded_size(), encoded.len());
+        assert_eq!(WireMsg::decode(&encoded).unwrap(), msg);
+
+        // Oversized histories keep the newest entries
+        let mut long: Vec<Input> = vec![Input::new(1, 0); MAX_BATCH_INPUTS];
+        long.push(Input::new(99, 0));
+        match WireMsg::decode(&WireMsg::input_history(500, long).encode()).unwrap() {
+            WireMsg::InputHistory {
+                latest_tick,
+                inputs,
+            } => {
+                assert_eq!(latest_tick, 500);
+                assert_eq!(inputs.len(), MAX_BATCH_INPUTS);
+                assert_eq!(inputs.last(), Some(&Input::new(99, 0)));
+            }
+            other => panic!("Expected InputHistory, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_lobby_messages() {
+        let config = Config {
+            max_score: 5,
+            seed: 42,
+            ..Config::default()
+        };
+        let welcome = WireMsg::Welcome {
+            assigned_side: Side::Left,
+            config,
+        };
+        let encoded = welcome.encode();
+        assert_eq!(encoded[0], 0x08);
+        assert_eq!(encoded.len(), welcome.encoded_size());
+        assert_eq!(WireMsg::decode(&encoded).unwrap(), welcome);
+
+        assert_eq!(WireMsg::Join.encode().len(), 1 + CHECKSUM_SIZE);
+        assert_eq!(
+            WireMsg::StartMatch { start_tick: 7 }.encoded_size(),
+            WireMsg::StartMatch { start_tick: 7 }.encode().len()
+        );
+
+        let sync = WireMsg::ConfigSync(config);
+        assert_eq!(sync.encode().len(), sync.encoded_size());
+        assert_eq!(sync.encoded_size(), 1 + 97 + CHECKSUM_SIZE);
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0x0A, 1, 2, 3])),
+            Err(SerializationError::UnexpectedEnd)
+        );
+
+        // Side byte must be 0 or 1
+        let mut body = vec![0x08, 2];
+        body.extend_from_slice(&config.encode());
+        assert_eq!(
+            WireMsg::decode(&with_crc(&body)),
+            Err(SerializationError::InvalidData)
+        );
+    }
+
+    #[test]
+    fn test_chat_message() {
+        let msg = WireMsg::chat("nice shot 🏓");
+        let encoded = msg.encode();
+        assert_eq!(encoded[0], 0x0B);
+        assert_eq!(encoded.len(), 3 + "nice shot 🏓".len() + CHECKSUM_SIZE);
+        assert_eq!(msg.encoded_size(), encoded.len());
+        assert_eq!(WireMsg::decode(&encoded).unwrap(), msg);
+
+        // Long text is cut at the limit without splitting a character
+        let long = "é".repeat(MAX_CHAT_BYTES); // 2 bytes each
+        match WireMsg::decode(&WireMsg::chat(long).encode()).unwrap() {
+            WireMsg::Chat(text) => assert_eq!(text, "é".repeat(MAX_CHAT_BYTES / 2)),
+            other => panic!("Expected Chat, got {other:?}"),
+        }
+        let odd = format!("a{}", "é".repeat(MAX_CHAT_BYTES));
+        match WireMsg::decode(&WireMsg::chat(odd).encode()).unwrap() {
+            WireMsg::Chat(text) => assert_eq!(text.len(), MAX_CHAT_BYTES - 1),
+            other => panic!("Expected Chat, got {other:?}"),
+        }
+
+        // Oversized lengths and invalid UTF-8 are rejected
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0x0B, 0xFF, 0xFF])),
+            Err(SerializationError::InvalidData)
+        );
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0x0B, 2, 0, 0xC3, 0x28])),
+            Err(SerializationError::InvalidData)
+        );
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0x0B, 5, 0, b'h'])),
+            Err(SerializationError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_emote_message() {
+        let msg = WireMsg::Emote(emotes::GG);
+        let encoded = msg.encode();
+        assert_eq!(encoded[..2], [0x0C, emotes::GG]);
+        assert_eq!(msg.encoded_size(), encoded.len());
+
+        // Ids this build doesn't know still decode
+        assert_eq!(
+            WireMsg::decode(&WireMsg::Emote(200).encode()).unwrap(),
+            WireMsg::Emote(200)
+        );
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0x0C])),
+            Err(SerializationError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_state_hash_message() {
+        let msg = WireMsg::StateHash {
+            tick: 42,
+            hash: u64::MAX,
+        };
+        let encoded = msg.encode();
+        assert_eq!(encoded[0], 0x0D);
+        assert_eq!(encoded.len(), 13 + CHECKSUM_SIZE);
+        assert_eq!(msg.encoded_size(), encoded.len());
+        assert_eq!(WireMsg::decode(&encoded).unwrap(), msg);
+
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0x0D, 1, 0, 0, 0, 9])),
+            Err(SerializationError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_encode_into_reuses_buffer() {
+        let messages = [
+            WireMsg::input_pair(3, Input::new(10, 1), Input::new(-10, 0).with_tilt(2)),
+            WireMsg::ping(77),
+            WireMsg::input_history(8, vec![Input::new(1, 0); 300]),
+            WireMsg::ConfigSync(Config::default()),
+            WireMsg::chat("é".repeat(200)),
+            WireMsg::StateHash { tick: 4, hash: 5 },
+        ];
+
+        // One buffer serves every message; the encoding matches `encode()`
+        let mut buf = [0u8; 1100];
+        for msg in &messages {
+            let len = msg.encode_into(&mut buf).unwrap();
+            assert_eq!(len, msg.encoded_size());
+            assert_eq!(&buf[..len], &msg.encode()[..]);
+            assert_eq!(WireMsg::decode(&buf[..len]), WireMsg::decode(&msg.encode()));
+        }
+
+        let ping = WireMsg::ping(1);
+        assert_eq!(
+            ping.encode_into(&mut buf[..ping.encoded_size() - 1]),
+            Err(SerializationError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn test_decode_ref_borrows_payloads() {
+        let snapshot = Snapshot {
+            tick: 5,
+            status: Status::Playing,
+            paddles: [Paddle::new(0), Paddle::new(FX_ONE)],
+            ball: Ball::new(Vec2::zero(), Vec2::zero()),
+            score: [1, 2],
+            rng: 99,
+            obstacle_phases: Vec::new(),
+            rally_hits: 0,
+            stall_ticks: 0,
+            config_hash: 0,
+            extra_balls: Vec::new(),
+        };
+        let encoded = WireMsg::snapshot(&snapshot).encode();
+
+        match WireMsg::decode_ref(&encoded).unwrap() {
+            WireMsgRef::Snapshot(data) => {
+                // Uncompressed payloads point straight into the received frame
+                if encoded[0] == 0x02 {
+                    assert!(matches!(data, Cow::Borrowed(_)));
+                    assert_eq!(data.as_ptr(), encoded[1..].as_ptr());
+                }
+                assert_eq!(Snapshot::decode(&data).unwrap(), snapshot);
+            }
+            other => panic!("Expected Snapshot, got {other:?}"),
+        }
+
+        let chat = WireMsg::chat("borrowed").encode();
+        assert_eq!(
+            WireMsg::decode_ref(&chat).unwrap(),
+            WireMsgRef::Chat("borrowed")
+        );
+        assert_eq!(
+            WireMsg::decode_ref(&chat).unwrap().into_owned(),
+            WireMsg::chat("borrowed")
+        );
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_compressed_snapshot() {
+        let mut snapshot = pong_engine::Game::new(Config::default()).snapshot();
+        snapshot.obstacle_phases = vec![0; 20];
+        let msg = WireMsg::snapshot(&snapshot);
+        let encoded = msg.encode();
+
+        assert_eq!(encoded[0], 0x02 | HEADER_FLAG_COMPRESSED);
+        assert!(encoded.len() < 1 + snapshot.encode().len() + CHECKSUM_SIZE);
+        assert_eq!(encoded.len(), msg.encoded_size());
+        assert_ne!(capabilities::SUPPORTED & capabilities::SNAPSHOT_RLE, 0);
+
+        match WireMsg::decode_ref(&encoded).unwrap() {
+            WireMsgRef::Snapshot(Cow::Owned(data)) => {
+                assert_eq!(Snapshot::decode(&data).unwrap(), snapshot);
+            }
+            other => panic!("Expected owned Snapshot, got {other:?}"),
+        }
+        assert_eq!(WireMsg::decode(&encoded).unwrap(), msg);
+
+        // Payloads RLE can't shrink go out as plain snapshots
+        let noise = WireMsg::Snapshot((0..=255).collect());
+        assert_eq!(noise.encode()[0], 0x02);
+        assert_eq!(WireMsg::decode(&noise.encode()).unwrap(), noise);
+    }
+
+    #[test]
+    fn test_timed_input_pair() {
+        let pair = InputPair::new(21, Input::new(-7, 1), Input::new(7, 0));
+        let msg = WireMsg::TimedInputPair {
+            pair,
+            sent_ms: 123_456,
+        };
+        let encoded = msg.encode();
+        assert_eq!(encoded.len(), 1 + 15 + CHECKSUM_SIZE);
+        assert_eq!(encoded.len(), msg.encoded_size());
+        assert_eq!(encoded[0], 0x01); // Same header as InputPair
+        assert_eq!(WireMsg::decode(&encoded).unwrap(), msg);
+        assert_eq!(
+            msg.to_debug_json(),
+            r#"{"type":"TimedInputPair","tick":21,"a":{"axis_y":-7,"axis_x":0,"buttons":1,"tilt":0},"b":{"axis_y":7,"axis_x":0,"buttons":0,"tilt":0},"sent_ms":123456}"#
+        );
+
+        // Unstamped pairs still decode as plain InputPair
+        assert_eq!(
+            WireMsg::decode(&WireMsg::InputPair(pair).encode()).unwrap(),
+            WireMsg::InputPair(pair)
+        );
+    }
+
+    #[test]
+    fn test_to_debug_json() {
+        let pair = WireMsg::input_pair(7, Input::new(-5, 1), Input::new(3, 0).with_tilt(2));
+        assert_eq!(
+            pair.to_debug_json(),
+            r#"{"type":"InputPair","tick":7,"a":{"axis_y":-5,"axis_x":0,"buttons":1,"tilt":0},"b":{"axis_y":3,"axis_x":0,"buttons":0,"tilt":2}}"#
+        );
+        assert_eq!(WireMsg::Join.to_debug_json(), r#"{"type":"Join"}"#);
+        assert_eq!(
+            WireMsg::StateHash {
+                tick: 60,
+                hash: 0xABCD
+            }
+            .to_debug_json(),
+            r#"{"type":"StateHash","tick":60,"hash":"000000000000abcd"}"#
+        );
+        assert_eq!(
+            WireMsg::chat("tab\there \u{1}").to_debug_json(),
+            r#"{"type":"Chat","text":"tab\there \u0001"}"#
+        );
+
+        let snapshot = pong_engine::Game::new(Config::default()).snapshot();
+        assert_eq!(
+            WireMsg::snapshot(&snapshot).to_debug_json(),
+            r#"{"type":"Snapshot","len":62,"tick":0,"status":"Lobby","score":[0,0]}"#
+        );
+        assert_eq!(
+            WireMsg::Snapshot(vec![1, 2]).to_debug_json(),
+            r#"{"type":"Snapshot","len":2,"error":"UnexpectedEnd"}"#
+        );
+    }
+
+    #[test]
+    fn test_sequenced_envelope() {
+        let inner = WireMsg::input_pair(12, Input::new(-3, 1), Input::zero());
+        let msg = WireMsg::sequenced(0xDEAD_BEEF, inner.clone());
+        let encoded = msg.encode();
+
+        assert_eq!(encoded[0], 0x10);
+        assert_eq!(&encoded[1..5], &0xDEAD_BEEFu32.to_le_bytes());
+        assert_eq!(encoded[5], 0x01); // Inner header follows the seq
+        assert_eq!(encoded.len(), inner.encoded_size() + 5);
+        assert_eq!(msg.encoded_size(), encoded.len());
+        assert_eq!(WireMsg::decode(&encoded).unwrap(), msg);
+
+        // Wrapping in place produces the same bytes
+        let mut buf = [0u8; 64];
+        let len = inner.encode_sequenced_into(0xDEAD_BEEF, &mut buf).unwrap();
+        assert_eq!(&buf[..len], &encoded[..]);
+        assert_eq!(
+            inner.encode_sequenced_into(1, &mut buf[..len - 1]),
+            Err(SerializationError::BufferTooSmall)
+        );
+
+        // Envelopes don't nest, in either direction
+        let nested = WireMsg::sequenced(1, msg.clone());
+        assert_eq!(
+            nested.encode_into(&mut buf),
+            Err(SerializationError::InvalidData)
+        );
+        let mut body = vec![0x10, 1, 0, 0, 0];
+        body.extend_from_slice(&encoded[..encoded.len() - CHECKSUM_SIZE]);
+        assert_eq!(
+            WireMsg::decode(&with_crc(&body)),
+            Err(SerializationError::InvalidData)
+        );
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0x10, 1, 0, 0, 0])),
+            Err(SerializationError::UnexpectedEnd)
+        );
+
+        assert_eq!(
+            WireMsg::sequenced(2, WireMsg::ping(7)).to_debug_json(),
+            r#"{"type":"Sequenced","seq":2,"msg":{"type":"Ping","timestamp":7}}"#
+        );
+    }
+
+    #[test]
+    fn test_ack_message() {
+        let msg = WireMsg::Ack {
+            latest: 41,
+            mask: 0x8000_0001,
+        };
+        let encoded = msg.encode();
+        assert_eq!(encoded[0], 0x11);
+        assert_eq!(encoded.len(), 13);
+        assert_eq!(msg.encoded_size(), encoded.len());
+        assert_eq!(WireMsg::decode(&encoded).unwrap(), msg);
+        assert_eq!(msg.message_type(), "Ack");
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0x11, 1, 0, 0, 0])),
+            Err(SerializationError::UnexpectedEnd)
+        );
+        assert_ne!(capabilities::SUPPORTED & capabilities::SEQUENCING, 0);
+    }
+
+    #[test]
+    fn test_side_input_message() {
+        let msg = WireMsg::SideInput {
+            tick: 300,
+            side: Side::Left,
+            input: Input::new(64, 1),
+        };
+        let encoded = msg.encode();
+        assert_eq!(encoded[0], 0x13);
+        assert_eq!(encoded.len(), 13);
+        assert_eq!(msg.encoded_size(), encoded.len());
+        assert_eq!(WireMsg::decode(&encoded).unwrap(), msg);
+        assert_eq!(msg.message_type(), "SideInput");
+
+        // Tilt and axis_x only travel when set
+        let mut input = Input::new(-1, 0);
+        input.tilt = 20;
+        input.axis_x = -7;
+        let extended = WireMsg::SideInput {
+            tick: 301,
+            side: Side::Right,
+            input,
+        };
+        let encoded = extended.encode();
+        assert_eq!(encoded.len(), 15);
+        assert_eq!(extended.encoded_size(), encoded.len());
+        assert_eq!(WireMsg::decode(&encoded).unwrap(), extended);
+
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0x13, 0, 0, 0, 0, 2, 0, 0, 0])),
+            Err(SerializationError::InvalidData)
+        );
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0x13, 0, 0, 0, 0, 0, 1, 0, 0])),
+            Err(SerializationError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_input_tails_need_peer_capabilities() {
+        let tilted = Input::new(3, 1).with_tilt(-20).with_axis_x(6);
+        let msgs = [
+            WireMsg::input_pair(4, tilted, Input::zero()),
+            WireMsg::input_batch(4, vec![Input::zero(), tilted]),
+            WireMsg::SideInput {
+                tick: 4,
+                side: Side::Right,
+                input: tilted,
+            },
+        ];
+        let mut buf = [0u8; 64];
+        for (peer_capabilities, expected) in [
+            (capabilities::INPUT_TILT, Input::new(3, 1).with_tilt(-20)),
+            (capabilities::INPUT_AXIS_X, Input::new(3, 1).with_axis_x(6)),
+            (0, Input::new(3, 1)),
+        ] {
+            let options = EncodeOptions {
+                seq: None,
+                peer_capabilities,
+            };
+            for msg in &msgs {
+                let len = msg.encode_with(&options, &mut buf).unwrap();
+                assert_eq!(len, msg.encoded_size_with(&options));
+                let inputs = match WireMsg::decode(&buf[..len]).unwrap() {
+                    WireMsg::InputPair(pair) => vec![pair.a],
+                    WireMsg::InputBatch { inputs, .. } => inputs[1..].to_vec(),
+                    WireMsg::SideInput { input, .. } => vec![input],
+                    other => panic!("unexpected {other:?}"),
+                };
+                assert_eq!(inputs, vec![expected]);
+            }
+        }
+
+        // Without either bit the tail isn't sent at all
+        let plain = EncodeOptions {
+            seq: None,
+            peer_capabilities: 0,
+        };
+        assert_eq!(
+            msgs[0].encoded_size_with(&plain),
+            WireMsg::input_pair(4, Input::new(3, 1), Input::zero()).encoded_size()
+        );
+    }
+
+    #[test]
+    fn test_encode_with_peer_capabilities() {
+        let mut snapshot = pong_engine::Game::new(Config::default()).snapshot();
+        snapshot.obstacle_phases = vec![0; 20];
+        let msg = WireMsg::snapshot(&snapshot);
+
+        // A peer without SNAPSHOT_RLE always gets the plain payload
+        let plain = EncodeOptions {
+            seq: None,
+            peer_capabilities: capabilities::negotiate(0),
+        };
+        let mut buf = [0u8; 256];
+        let len = msg.encode_with(&plain, &mut buf).unwrap();
+        assert_eq!(len, msg.encoded_size_with(&plain));
+        assert_eq!(buf[0], 0x02);
+        assert_eq!(len, 1 + snapshot.encode().len() + CHECKSUM_SIZE);
+        assert_eq!(WireMsg::decode(&buf[..len]).unwrap(), msg);
+
+        // The options' envelope matches encode_sequenced_into
+        let sequenced = EncodeOptions {
+            seq: Some(4),
+            ..EncodeOptions::default()
+        };
+        let len = msg.encode_with(&sequenced, &mut buf).unwrap();
+        assert_eq!(
+            &buf[..len],
+            &WireMsg::sequenced(4, msg.clone()).encode()[..]
+        );
+
+        assert_eq!(capabilities::negotiate(u32::MAX), capabilities::SUPPORTED);
+        assert_eq!(
+            capabilities::negotiate(capabilities::CHAT | 1 << 31),
+            capabilities::CHAT
+        );
+    }
+
+    #[test]
+    fn test_negotiate_version() {
+        assert_eq!(negotiate_version(PROTOCOL_VERSION), Ok(PROTOCOL_VERSION));
+        assert_eq!(negotiate_version(u8::MAX), Ok(PROTOCOL_VERSION)); // Downgrade to ours
+        assert_eq!(
+            negotiate_version(MIN_PROTOCOL_VERSION - 1),
+            Err(SerializationError::UnsupportedVersion)
+        );
+    }
+
+    #[test]
+    fn test_utility_constructors() {
+        let input_a = Input::new(-100, 3);
+        let input_b = Input::new(50, 7);
+        let tick = 999;
+
+        let msg = WireMsg::input_pair(tick, input_a, input_b);
+        if let WireMsg::InputPair(pair) = msg {
+            assert_eq!(pair.tick, tick);
+            assert_eq!(pair.a, input_a);
+            assert_eq!(pair.b, input_b);
+        } else {
+            panic!("Expected InputPair message");
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        // Empty buffer, or nothing but a checksum
+        assert_eq!(WireMsg::decode(&[]), Err(SerializationError::UnexpectedEnd));
+        assert_eq!(
+            WireMsg::decode(&[0, 0, 0, 0]),
+            Err(SerializationError::UnexpectedEnd)
+        );
+
+        // Invalid type header
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0xFF])),
+            Err(SerializationError::InvalidData)
+        );
+
+        // InputPair too short
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0x01, 1, 2, 3])),
+            Err(SerializationError::UnexpectedEnd)
+        );
+
+        // Snapshot too short
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0x02])),
+            Err(SerializationError::UnexpectedEnd)
+        );
+
+        // Ping too short
+        assert_eq!(
+            WireMsg::decode(&with_crc(&[0x03, 1, 2])),
+            Err(SerializationError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_message_roundtrip_all_types() {
+        let messages = vec![
+            WireMsg::input_pair(42, Input::new(-127, 255), Input::new(127, 0)),
+            WireMsg::snapshot(&Snapshot {
+                tick: 0,
+                status: Status::Lobby,
+                paddles: [Paddle::new(0), Paddle::new(0)],
+                ball: Ball::new(Vec2::zero(), Vec2::zero()),
+                score: [0, 0],
+                rng: 0,
+                obstacle_phases: Vec::new(),
+                rally_hits: 0,
+                stall_ticks: 0,
+                config_hash: 0,
+                extra_balls: Vec::new(),
+            }),
+            WireMsg::ping(0xFFFFFFFF),
+            WireMsg::pong(0xFFFFFFFF),
+            WireMsg::SnapshotRequest { since_tick: 77 },
+            WireMsg::hello(),
+            WireMsg::input_batch(7, vec![Input::new(5, 1), Input::new(-5, 0)]),
+            WireMsg::input_history(9, vec![Input::new(5, 1), Input::new(-5, 0)]),
+            WireMsg::Join,
+            WireMsg::Welcome {
+                assigned_side: Side::Right,
+                config: Config::default(),
+            },
+            WireMsg::StartMatch { start_tick: 120 },
+            WireMsg::ConfigSync(Config::default()),
+            WireMsg::chat("gg"),
+            WireMsg::Emote(emotes::NICE_SHOT),
+            WireMsg::StateHash {
+                tick: 600,
+                hash: 0x0123_4567_89AB_CDEF,
+            },
+            WireMsg::sequenced(3, WireMsg::chat("late")),
+            WireMsg::Ack {
+                latest: 70,
+                mask: 0b1011,
+            },
+            WireMsg::InputRequest { from_tick: 31 },
+            WireMsg::SideInput {
+                tick: 12,
+                side: Side::Right,
+                input: Input::new(-3, 1),
+            },
+            WireMsg::Pause { at_tick: 44 },
+            WireMsg::Resume { at_tick: 44 },
+            WireMsg::Bye,
+            WireMsg::NextGame {
+                start_tick: 900,
+                seed: 0xFEED_FACE_CAFE_BEEF,
+                server: Side::Right,
+            },
+        ];
+
+        for msg in messages {
+            let encoded = msg.encode();
+            let decoded = WireMsg::decode(&encoded).unwrap();
+            assert_eq!(msg, decoded);
+        }
+    }
+
+    #[test]
+    fn test_encoded_sizes() {
+        let input_msg = WireMsg::input_pair(0, Input::zero(), Input::zero());
+        assert_eq!(input_msg.encoded_size(), input_msg.encode().len());
+
+        let snapshot = Snapshot {
+            tick: 0,
+            status: Status::Lobby,
+            paddles: [Paddle::new(0), Paddle::new(0)],
+            ball: Ball::new(Vec2::zero(), Vec2::zero()),
+            score: [0, 0],
+            rng: 0,
+            obstacle_phases: Vec::new(),
+            rally_hits: 0,
+            stall_ticks: 0,
+            config_hash: 0,
+            extra_balls: Vec::new(),
+        };
+        let snapshot_msg = WireMsg::snapshot(&snapshot);
+        assert_eq!(snapshot_msg.encoded_size(), snapshot_msg.encode().len());
+
+        let ping_msg = WireMsg::ping(123);
+        assert_eq!(ping_msg.encoded_size(), ping_msg.encode().len());
+
+        let request_msg = WireMsg::SnapshotRequest { since_tick: 9 };
+        assert_eq!(request_msg.encoded_size(), request_msg.encode().len());
+    }
+}
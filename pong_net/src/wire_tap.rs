@@ -0,0 +1,169 @@
+//! Transport wrapper that logs wire traffic as JSON lines
+
+use crate::transport::{CloseReason, Transport, TransportError, TransportMetrics};
+use crate::wire_protocol::WireMsg;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+type Sink = Arc<Mutex<Box<dyn Write + Send>>>;
+
+/// Format one captured message: `{"dir":"send","len":17,"msg":{...}}`, with an
+/// `"error"` in place of `"msg"` when the bytes don't decode
+pub fn tap_line(direction: &str, bytes: &[u8]) -> String {
+    match WireMsg::decode(bytes) {
+        Ok(msg) => format!(
+            "{{\"dir\":\"{direction}\",\"len\":{},\"msg\":{}}}",
+            bytes.len(),
+            msg.to_debug_json()
+        ),
+        Err(error) => format!(
+            "{{\"dir\":\"{direction}\",\"len\":{},\"error\":\"{error:?}\"}}",
+            bytes.len()
+        ),
+    }
+}
+
+fn log(sink: &Sink, direction: &str, bytes: &[u8]) {
+    // Logging is best-effort; a full disk shouldn't take the connection down
+    if let Ok(mut sink) = sink.lock() {
+        let _ = writeln!(sink, "{}", tap_line(direction, bytes));
+    }
+}
+
+/// Wraps any transport and writes every sent and received message to a sink,
+/// one JSON object per line, so a captured session can be read with `jq`
+pub struct WireTap<T: Transport> {
+    inner: T,
+    sink: Sink,
+}
+
+impl<T: Transport> WireTap<T> {
+    /// Tap `inner`, logging to `sink` (a file, `std::io::stderr()`, a `Vec<u8>`...)
+    pub fn new(inner: T, sink: impl Write + Send + 'static) -> Self {
+        Self {
+            inner,
+            sink: Arc::new(Mutex::new(Box::new(sink))),
+        }
+    }
+
+    /// The wrapped transport
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// The wrapped transport, mutably
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Stop tapping and return the wrapped transport
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Transport> Transport for WireTap<T> {
+    fn send(&self, bytes: &[u8]) -> Result<(), TransportError> {
+        self.inner.send(bytes)?;
+        log(&self.sink, "send", bytes);
+        Ok(())
+    }
+
+    fn set_on_message(&mut self, callback: Box<dyn Fn(Vec<u8>) + Send + Sync + 'static>) {
+        let sink = Arc::clone(&self.sink);
+        self.inner.set_on_message(Box::new(move |bytes| {
+            log(&sink, "recv", &bytes);
+            callback(bytes);
+        }));
+    }
+
+    fn is_open(&self) -> bool {
+        self.inner.is_open()
+    }
+
+    fn close(&mut self) -> Result<(), TransportError> {
+        self.inner.close()
+    }
+
+    fn status(&self) -> String {
+        format!("{} (tapped)", self.inner.status())
+    }
+
+    fn metrics(&self) -> TransportMetrics {
+        self.inner.metrics()
+    }
+
+    fn set_on_open(&mut self, callback: Box<dyn Fn() + Send + Sync + 'static>) {
+        self.inner.set_on_open(callback);
+    }
+
+    fn set_on_close(&mut self, callback: Box<dyn Fn(CloseReason) + Send + Sync + 'static>) {
+        self.inner.set_on_close(callback);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::RecordingMockTransport;
+
+    /// Sink the test can read back after handing a clone to the tap
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_wire_tap_logs_both_directions() {
+        let buf = SharedBuf::default();
+        let mut tap = WireTap::new(RecordingMockTransport::new(), buf.clone());
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_clone = Arc::clone(&received);
+        tap.set_on_message(Box::new(move |bytes| {
+            received_clone.lock().unwrap().push(bytes);
+        }));
+
+        tap.send(&WireMsg::ping(42).encode()).unwrap();
+        tap.inner()
+            .receive_message(WireMsg::chat("say \"hi\"\n").encode());
+        tap.inner().receive_message(vec![0xFF, 1, 2, 3, 4]);
+
+        // Traffic still flows through untouched
+        assert_eq!(tap.inner().sent_messages().len(), 1);
+        assert_eq!(received.lock().unwrap().len(), 2);
+        assert!(tap.status().ends_with("(tapped)"));
+
+        let log = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"dir":"send","len":9,"msg":{"type":"Ping","timestamp":42}}"#,
+                r#"{"dir":"recv","len":16,"msg":{"type":"Chat","text":"say \"hi\"\n"}}"#,
+                r#"{"dir":"recv","len":5,"error":"ChecksumMismatch"}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failed_send_is_not_logged() {
+        let buf = SharedBuf::default();
+        let tap = WireTap::new(RecordingMockTransport::new_closed(), buf.clone());
+
+        assert_eq!(
+            tap.send(&WireMsg::ping(1).encode()),
+            Err(TransportError::NotConnected)
+        );
+        assert!(buf.0.lock().unwrap().is_empty());
+    }
+}
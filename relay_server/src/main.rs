@@ -0,0 +1,26 @@
+//! Relay server for players who can't connect directly.
+//!
+//! Usage: `relay_server [--bind <addr>]` (default `0.0.0.0:7878`). Clients join
+//! with `RelayTransport::connect(addr, code)`; two clients with the same code
+//! are paired and their messages forwarded.
+
+use pong_net::relay::RelayServer;
+
+const DEFAULT_BIND: &str = "0.0.0.0:7878";
+
+/// The address given with `--bind <addr>`, or the default
+fn bind_addr() -> String {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .position(|arg| arg == "--bind")
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+        .unwrap_or_else(|| DEFAULT_BIND.to_string())
+}
+
+fn main() -> std::io::Result<()> {
+    let server = RelayServer::bind(bind_addr())?;
+    println!("Relay listening on {}", server.local_addr()?);
+    server.run();
+    Ok(())
+}